@@ -0,0 +1,14 @@
+//! Breathing and swaying for a crowd of characters. Run with `cargo bench --bench sway`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+fn breathe_and_sway(c: &mut Criterion) {
+    let mut app = energy::sway_crowd(1000);
+
+    c.bench_function("breathe and sway 1000", |bencher| {
+        bencher.iter(|| app.update())
+    });
+}
+
+criterion_group!(benches, breathe_and_sway);
+criterion_main!(benches);
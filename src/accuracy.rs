@@ -0,0 +1,162 @@
+//! How wide a player's rounds go.
+//!
+//! Every player weapon has an [`Accuracy`], recomputed each tick from its player: deep breaths,
+//! moving fast and being off the ground all open the cone of fire up, as does firing partway
+//! through bringing the sights up or down, while aiming down the sights and getting low tighten
+//! it. Rounds leave at a random angle inside the cone, and the
+//! crosshair spreads out to match.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::movement::{Grounded, Stance};
+use crate::{AdsAlpha, Breath, Player, PlayerWeapon, SwayTarget};
+
+pub struct AccuracyPlugin;
+
+impl Plugin for AccuracyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, update_accuracy.before(crate::player_shoot));
+    }
+}
+
+/// The cone a weapon's rounds leave in.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Accuracy {
+    /// Degrees from the centre of the cone to its edge
+    pub spread: f32,
+}
+
+impl Accuracy {
+    /// Degrees of spread from the hip, standing still and breathing easy
+    const BASE: f32 = 0.5;
+    /// Extra degrees for each unit of breath depth
+    const PER_BREATH_DEPTH: f32 = 0.4;
+    /// Extra degrees for each metre per second of horizontal speed
+    const PER_SPEED: f32 = 0.3;
+    /// Extra degrees while off the ground
+    const AIRBORNE: f32 = 3.0;
+    /// Multiplier on spread fully aimed down the sights
+    const AIMED: f32 = 0.2;
+    /// Extra multiplier on spread halfway between the hip and the sights
+    const MID_AIM: f32 = 2.0;
+    const MAX: f32 = 10.0;
+
+    /// The spread for a player breathing `breath`, moving at `velocity` and aimed in
+    /// `ads_alpha` of the way
+    pub fn new(
+        breath: &Breath,
+        velocity: Vec3,
+        grounded: bool,
+        stance: Stance,
+        ads_alpha: f32,
+    ) -> Self {
+        let breathing = breath.strained_depth() * breath.hold_sway * Self::PER_BREATH_DEPTH;
+        let moving = velocity.xz().length() * Self::PER_SPEED;
+        let airborne = if grounded { 0.0 } else { Self::AIRBORNE };
+
+        let ads_alpha = ads_alpha.clamp(0.0, 1.0);
+        // nothing at either end, all of it halfway
+        let mid_aim = 4.0 * ads_alpha * (1.0 - ads_alpha);
+
+        let spread = (Self::BASE + breathing + moving + airborne)
+            * 1.0_f32.lerp(Self::AIMED, ads_alpha)
+            * (1.0 + Self::MID_AIM * mid_aim)
+            * stance_scale(stance);
+
+        Self {
+            spread: spread.min(Self::MAX),
+        }
+    }
+
+    /// A random turn off the centre of the cone, for a round leaving down -Z
+    pub fn deviation(&self, rng: &mut impl Rng) -> Quat {
+        // the square root spreads rounds evenly over the cone's face rather than bunching them
+        // in the middle
+        let angle = self.spread.to_radians() * rng.random::<f32>().sqrt();
+        let around = rng.random_range(0.0..std::f32::consts::TAU);
+
+        Quat::from_rotation_z(around) * Quat::from_rotation_x(angle)
+    }
+}
+
+/// Multiplier on spread for how low a character's got
+fn stance_scale(stance: Stance) -> f32 {
+    match stance {
+        Stance::Standing => 1.0,
+        Stance::Crouched => 0.75,
+        Stance::Prone => 0.5,
+    }
+}
+
+fn update_accuracy(
+    players: Query<(&Breath, &LinearVelocity, Has<Grounded>, Option<&Stance>), With<Player>>,
+    weapons: Query<(&SwayTarget, &AdsAlpha, &mut Accuracy), With<PlayerWeapon>>,
+) {
+    for (owner, ads_alpha, mut accuracy) in weapons {
+        let Ok((breath, velocity, grounded, stance)) = players.get(owner.0) else {
+            continue;
+        };
+
+        accuracy.set_if_neq(Accuracy::new(
+            breath,
+            velocity.0,
+            grounded,
+            stance.copied().unwrap_or_default(),
+            ads_alpha.0,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::BreathDirection;
+
+    fn breath(depth: f32, direction: BreathDirection) -> Breath {
+        Breath::new(1.0, depth, direction)
+    }
+
+    #[test]
+    fn rounds_stay_in_the_cone_of_fire() {
+        let calm = breath(0.5, BreathDirection::In);
+        let hip = Accuracy::new(&calm, Vec3::ZERO, true, Stance::Standing, 0.0);
+
+        let aimed = Accuracy::new(&calm, Vec3::ZERO, true, Stance::Standing, 1.0);
+        let aiming = Accuracy::new(&calm, Vec3::ZERO, true, Stance::Standing, 0.5);
+        let prone = Accuracy::new(&calm, Vec3::ZERO, true, Stance::Prone, 0.0);
+        let running = Accuracy::new(&calm, Vec3::new(6.0, 0.0, 0.0), true, Stance::Standing, 0.0);
+        let falling = Accuracy::new(
+            &calm,
+            Vec3::new(0.0, -8.0, 0.0),
+            false,
+            Stance::Standing,
+            0.0,
+        );
+        let panting = Accuracy::new(
+            &breath(3.0, BreathDirection::In),
+            Vec3::ZERO,
+            true,
+            Stance::Standing,
+            0.0,
+        );
+
+        assert!(aimed.spread < hip.spread && prone.spread < hip.spread);
+        // mid-transition is worse than either end
+        assert!(aiming.spread > hip.spread);
+        assert!(running.spread > hip.spread && falling.spread > hip.spread);
+        assert!(panting.spread > hip.spread);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for accuracy in [hip, running, falling] {
+            for _ in 0..200 {
+                let direction = accuracy.deviation(&mut rng) * Vec3::NEG_Z;
+                let off = direction.angle_between(Vec3::NEG_Z).to_degrees();
+                assert!(off <= accuracy.spread + 1e-3, "{off} outside {accuracy:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,655 @@
+//! Hostile soldiers.
+//!
+//! A [`Soldier`] fights the nearest player it can see or hear shooting at it. Out in the open it
+//! stands and shoots, but once it's under fire, hit or with rounds passing close by, it runs for
+//! the nearest free [`CoverPoint`] that hides it from whoever's shooting. From cover it ducks for a
+//! moment, then leans out to whichever side it can see from, by the same [`LeanOffsets`] players
+//! lean by, and shoots back before ducking again. Cover that stops hiding it, because its target
+//! has moved round, is given up.
+//!
+//! How well each soldier sees, hears, aims and fights comes from its preset, see `ai_presets`.
+//!
+//! Cover points are generated on the ground around static colliders as levels spawn them, along
+//! each side of anything between chest and roof height.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ai_presets::{AiPreset, Perception};
+use crate::damage::{DamageEvent, Dead};
+use crate::menu::GameState;
+use crate::movement::{LeanOffsets, MovementAction, MovementKind};
+use crate::projectile::ProjectilePool;
+use crate::squad::{FlankTo, HoldFire, SquadRole};
+use crate::{Player, RoundAssets, Shot, ShotFired, fire_round};
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CoverPoints>()
+            // levels are built while loading, before play starts
+            .configure_sets(
+                Update,
+                (AiSystems::Perceive, AiSystems::Act)
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, (forget_cover_points, find_cover_points).chain())
+            .add_systems(Update, notice_threats.in_set(AiSystems::Perceive))
+            .add_systems(
+                Update,
+                (take_cover, move_soldiers, lean_soldiers, soldiers_shoot)
+                    .chain()
+                    .in_set(AiSystems::Act),
+            );
+    }
+}
+
+/// Soldiers noticing players, then acting on what they know. Anything that shares or changes
+/// what they know, like `squad`, goes in between.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AiSystems {
+    Perceive,
+    Act,
+}
+
+/// A hostile character that shoots at players and takes cover from them.
+#[derive(Component, Debug, Default)]
+#[require(AiPreset)]
+pub struct Soldier {
+    state: SoldierState,
+    /// The player it's fighting, and where it last saw or heard them
+    threat: Option<(Entity, Vec3)>,
+    /// It can see its threat right now
+    spotted: bool,
+    /// Seconds it's had its threat in sight, firing once it's had time to react
+    seen_for: f32,
+    /// Seconds left feeling shot at
+    under_fire: f32,
+    /// Seconds until it can fire again
+    reload: f32,
+    /// How far it's leaning out, from -1 to the left to 1 to the right
+    lean: f32,
+    /// Rounds fired, for tracers
+    rounds: u32,
+}
+
+impl Soldier {
+    /// Seconds it feels under fire after the last hit or near miss
+    const UNDER_FIRE: f32 = 3.0;
+    /// Metres a round can pass by and still count as shooting at it
+    const NEAR_MISS: f32 = 2.0;
+    /// Metres it will run for cover
+    const COVER_RANGE: f32 = 20.0;
+    /// Seconds spent ducked behind cover, then leaning out of it, by a soldier with no aggression
+    const DUCK: f32 = 1.5;
+    const PEEK: f32 = 1.2;
+    /// Leans per second, all the way out taking a second
+    const LEAN_SPEED: f32 = 4.0;
+    /// Seconds between shots
+    const FIRE_INTERVAL: f32 = 0.7;
+    /// Seconds between shots keeping a player's head down
+    const SUPPRESSING_INTERVAL: f32 = 0.3;
+    const DAMAGE: f32 = 12.0;
+    const MUZZLE_VELOCITY: f32 = 70.0;
+    const ROUND_MASS: f32 = 0.008;
+
+    /// The player it can see right now, and where
+    pub fn spotted(&self) -> Option<(Entity, Vec3)> {
+        self.threat.filter(|_| self.spotted)
+    }
+
+    /// Tells it where a player was last seen, unless it can see for itself
+    pub fn alert(&mut self, threat: (Entity, Vec3)) {
+        if !self.spotted {
+            self.threat = Some(threat);
+        }
+    }
+
+    /// Where it's running to or hiding behind, if anywhere
+    fn cover(&self) -> Option<&CoverPoint> {
+        match &self.state {
+            SoldierState::Engaging => None,
+            SoldierState::TakingCover(point) | SoldierState::InCover { point, .. } => Some(point),
+        }
+    }
+
+    /// Leaning far enough out of cover to shoot, or out in the open
+    fn exposed(&self) -> bool {
+        match self.state {
+            SoldierState::Engaging => true,
+            SoldierState::TakingCover(_) => false,
+            SoldierState::InCover { peeking, .. } => peeking && self.lean.abs() > 0.8,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+enum SoldierState {
+    /// Out in the open
+    #[default]
+    Engaging,
+    TakingCover(CoverPoint),
+    InCover {
+        point: CoverPoint,
+        peeking: bool,
+        /// Seconds until it ducks or peeks again
+        timer: f32,
+        /// The way it leans out, -1 or 1
+        side: f32,
+    },
+}
+
+/// A soldier's head, which it sees and shoots from and which leans out of cover.
+#[derive(Component, Debug)]
+pub struct SoldierHead {
+    /// Where it sits upright
+    pub rest: Vec3,
+}
+
+/// A place on the ground where something stands between a soldier and one side of the arena.
+#[derive(Debug, Clone, Copy)]
+pub struct CoverPoint {
+    pub position: Vec3,
+    /// Towards the collider it's behind
+    pub towards: Vec3,
+    /// The collider it's behind
+    source: Entity,
+}
+
+impl CoverPoint {
+    /// Height of a crouched soldier's chest above the point
+    const CHEST: f32 = 1.0;
+
+    /// Whether it hides a soldier from something at `from`
+    fn hides_from(&self, spatial_query: &SpatialQuery, from: Vec3) -> bool {
+        self.towards.dot(from - self.position) > 0.0
+            && !clear_line(spatial_query, self.position + Vec3::Y * Self::CHEST, from)
+    }
+}
+
+/// Every cover point in the level.
+#[derive(Resource, Debug, Default)]
+pub struct CoverPoints(Vec<CoverPoint>);
+
+impl CoverPoints {
+    /// Metres between points along a side
+    const SPACING: f32 = 2.5;
+    /// Metres out from the side a point sits
+    const STANDOFF: f32 = 0.7;
+    /// Heights of collider worth hiding behind, lower is a step and higher a building
+    const HEIGHTS: std::ops::RangeInclusive<f32> = 1.0..=8.0;
+    /// Most points along one side of anything
+    const MAX_PER_SIDE: usize = 16;
+
+    /// Points along each side of a collider's bounds
+    fn around(source: Entity, min: Vec3, max: Vec3) -> impl Iterator<Item = CoverPoint> {
+        let size = max - min;
+        let centre = (min + max) / 2.0;
+
+        [Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z]
+            .into_iter()
+            .flat_map(move |normal| {
+                let along = if normal.x != 0.0 { Vec3::Z } else { Vec3::X };
+                let length = size.dot(along);
+                let count =
+                    ((length / Self::SPACING).floor() as usize).clamp(1, Self::MAX_PER_SIDE);
+                let out = size.dot(normal.abs()) / 2.0 + Self::STANDOFF;
+
+                (0..count).map(move |i| {
+                    let t = ((i as f32 + 0.5) / count as f32 - 0.5) * length;
+
+                    CoverPoint {
+                        position: (centre + normal * out + along * t).with_y(min.y),
+                        towards: -normal,
+                        source,
+                    }
+                })
+            })
+    }
+}
+
+/// Whether nothing stands between `from` and `to`, stopping short of whatever's standing at each
+/// end.
+fn clear_line(spatial_query: &SpatialQuery, from: Vec3, to: Vec3) -> bool {
+    /// Metres left clear at each end, for the looker's and the looked at's own colliders
+    const MARGIN: f32 = 0.6;
+
+    let Ok((direction, distance)) = Dir3::new_and_length(to - from) else {
+        return true;
+    };
+
+    distance <= MARGIN * 2.0
+        || spatial_query
+            .cast_ray(
+                from + direction * MARGIN,
+                direction,
+                distance - MARGIN * 2.0,
+                true,
+                &SpatialQueryFilter::default(),
+            )
+            .is_none()
+}
+
+fn find_cover_points(
+    mut cover_points: ResMut<CoverPoints>,
+    colliders: Query<
+        (Entity, &Collider, &RigidBody, &Transform),
+        (Added<Collider>, Without<ChildOf>),
+    >,
+) {
+    for (entity, collider, body, transform) in colliders {
+        if *body != RigidBody::Static {
+            continue;
+        }
+
+        let aabb = collider.aabb(transform.translation, transform.rotation);
+
+        if CoverPoints::HEIGHTS.contains(&(aabb.max.y - aabb.min.y)) {
+            cover_points
+                .0
+                .extend(CoverPoints::around(entity, aabb.min, aabb.max));
+        }
+    }
+}
+
+fn forget_cover_points(
+    mut cover_points: ResMut<CoverPoints>,
+    mut removed: RemovedComponents<Collider>,
+) {
+    let removed: Vec<_> = removed.read().collect();
+
+    if !removed.is_empty() {
+        cover_points
+            .0
+            .retain(|point| !removed.contains(&point.source));
+    }
+}
+
+/// Soldiers spot players in sight and notice being shot at.
+fn notice_threats(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut damage_reader: MessageReader<DamageEvent>,
+    mut shot_reader: MessageReader<ShotFired>,
+    mut soldiers: Query<(Entity, &mut Soldier, &Transform, &Perception), Without<Dead>>,
+    players: Query<(Entity, &GlobalTransform), (With<Player>, Without<Dead>)>,
+) {
+    for (_, mut soldier, transform, perception) in &mut soldiers {
+        soldier.under_fire = (soldier.under_fire - time.delta_secs()).max(0.0);
+
+        let half_cone = perception.vision_cone.to_radians() / 2.0;
+        let seen = players
+            .iter()
+            .map(|(player, player_transform)| (player, player_transform.translation()))
+            .filter(|(_, position)| {
+                let to = *position - transform.translation;
+
+                to.length() <= perception.vision_range
+                    && transform.forward().angle_between(to.with_y(0.0)) <= half_cone
+                    && clear_line(&spatial_query, transform.translation, *position)
+            })
+            .min_by(|(_, a), (_, b)| {
+                let a = a.distance_squared(transform.translation);
+                let b = b.distance_squared(transform.translation);
+                a.total_cmp(&b)
+            });
+
+        soldier.spotted = seen.is_some();
+        if seen.is_some() {
+            soldier.threat = seen;
+            soldier.seen_for += time.delta_secs();
+        } else {
+            soldier.seen_for = 0.0;
+        }
+    }
+
+    for event in damage_reader.read() {
+        if let Ok((_, mut soldier, ..)) = soldiers.get_mut(event.target)
+            && let Ok((player, player_transform)) = players.get(event.source)
+        {
+            soldier.under_fire = Soldier::UNDER_FIRE;
+            soldier.threat = Some((player, player_transform.translation()));
+        }
+    }
+
+    for shot in shot_reader.read() {
+        if !players.contains(shot.shooter) {
+            continue;
+        }
+
+        for (_, mut soldier, transform, perception) in &mut soldiers {
+            let to_soldier = transform.translation - shot.origin;
+            let along = to_soldier.dot(shot.direction);
+
+            if along > 0.0 && (to_soldier - shot.direction * along).length() <= Soldier::NEAR_MISS {
+                soldier.under_fire = Soldier::UNDER_FIRE;
+                soldier.threat = Some((shot.shooter, shot.origin));
+            } else if to_soldier.length() <= perception.hearing_radius {
+                // turns to where it heard the shot from, to see for itself
+                soldier.alert((shot.shooter, shot.origin));
+            }
+        }
+    }
+}
+
+/// Sends soldiers under fire off to cover, and brings them out of cover that doesn't hide them.
+fn take_cover(
+    spatial_query: SpatialQuery,
+    cover_points: Res<CoverPoints>,
+    // kept between frames so it isn't allocated every one
+    mut taken: Local<Vec<(Entity, Vec3)>>,
+    mut soldiers: Query<(Entity, &mut Soldier, &Transform, &Perception), Without<Dead>>,
+) {
+    taken.clear();
+    taken.extend(
+        soldiers
+            .iter()
+            .filter_map(|(entity, soldier, ..)| Some((entity, soldier.cover()?.position))),
+    );
+
+    for (entity, mut soldier, transform, perception) in &mut soldiers {
+        let Some((_, threat)) = soldier.threat else {
+            continue;
+        };
+
+        if let Some(point) = soldier.cover()
+            && !point.hides_from(&spatial_query, threat)
+        {
+            debug!("{entity} left cover that stopped hiding it");
+            soldier.state = SoldierState::Engaging;
+        }
+
+        if soldier.under_fire <= 0.0 || soldier.cover().is_some() || perception.aggression >= 1.0 {
+            continue;
+        }
+
+        let free = |point: &&CoverPoint| {
+            !taken.iter().any(|(other, position)| {
+                *other != entity && position.distance(point.position) < CoverPoints::SPACING / 2.0
+            })
+        };
+
+        let nearest = cover_points
+            .0
+            .iter()
+            .filter(|point| point.position.distance(transform.translation) <= Soldier::COVER_RANGE)
+            .filter(free)
+            .filter(|point| point.hides_from(&spatial_query, threat))
+            .min_by(|a, b| {
+                let a = a.position.distance_squared(transform.translation);
+                let b = b.position.distance_squared(transform.translation);
+                a.total_cmp(&b)
+            });
+
+        if let Some(point) = nearest {
+            debug!("{entity} taking cover at {}", point.position);
+            soldier.state = SoldierState::TakingCover(*point);
+        }
+    }
+}
+
+/// Turns soldiers to face their threat, runs them to cover and ducks and peeks once there.
+fn move_soldiers(
+    mut commands: Commands,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    lean_offsets: Res<LeanOffsets>,
+    mut movement_writer: MessageWriter<MovementAction>,
+    mut soldiers: Query<
+        (
+            Entity,
+            &mut Soldier,
+            &mut Transform,
+            &Children,
+            &Perception,
+            Option<&FlankTo>,
+        ),
+        Without<Dead>,
+    >,
+    heads: Query<&SoldierHead>,
+) {
+    /// Metres from a cover point that count as there
+    const ARRIVED: f32 = 0.4;
+    /// Metres from a flanking position that count as there
+    const FLANKED: f32 = 2.0;
+
+    for (controller, mut soldier, mut transform, children, perception, flank) in &mut soldiers {
+        // the more aggressive, the less time ducked and the more leaning out shooting
+        let duck = Soldier::DUCK * (1.0 - perception.aggression * 0.8);
+        let peek = Soldier::PEEK * (1.0 + perception.aggression);
+
+        let Some((_, threat)) = soldier.threat else {
+            continue;
+        };
+
+        let facing = (threat - transform.translation).with_y(0.0);
+        if facing != Vec3::ZERO {
+            transform.look_to(facing, Vec3::Y);
+        }
+
+        match soldier.state {
+            SoldierState::Engaging => {
+                let Some(FlankTo(flank)) = flank else {
+                    continue;
+                };
+                let to = (*flank - transform.translation).with_y(0.0);
+
+                if to.length() <= FLANKED {
+                    commands.entity(controller).remove::<FlankTo>();
+                    continue;
+                }
+
+                walk(&mut movement_writer, controller, &transform, to);
+            }
+            SoldierState::TakingCover(point) => {
+                let to = (point.position - transform.translation).with_y(0.0);
+
+                if to.length() <= ARRIVED {
+                    soldier.state = SoldierState::InCover {
+                        point,
+                        peeking: false,
+                        timer: duck,
+                        side: 1.0,
+                    };
+                    continue;
+                }
+
+                walk(&mut movement_writer, controller, &transform, to);
+            }
+            SoldierState::InCover {
+                point,
+                peeking,
+                timer,
+                side,
+            } => {
+                let timer = timer - time.delta_secs();
+
+                soldier.state = if timer > 0.0 {
+                    SoldierState::InCover {
+                        point,
+                        peeking,
+                        timer,
+                        side,
+                    }
+                } else if peeking {
+                    SoldierState::InCover {
+                        point,
+                        peeking: false,
+                        timer: duck,
+                        side,
+                    }
+                } else {
+                    // out whichever side there's a line of fire from
+                    let head = children
+                        .iter()
+                        .find_map(|child| heads.get(child).ok())
+                        .map_or(Vec3::Y, |head| head.rest);
+                    let side = [1.0, -1.0]
+                        .into_iter()
+                        .find(|side| {
+                            let leaned = transform
+                                .transform_point(head + lean_offsets.at(*side).translation);
+                            clear_line(&spatial_query, leaned, threat)
+                        })
+                        .unwrap_or(side);
+
+                    SoldierState::InCover {
+                        point,
+                        peeking: true,
+                        timer: peek,
+                        side,
+                    }
+                };
+            }
+        }
+    }
+}
+
+/// Walks a soldier towards `to`, whichever way they're facing.
+fn walk(
+    movement_writer: &mut MessageWriter<MovementAction>,
+    controller: Entity,
+    transform: &Transform,
+    to: Vec3,
+) {
+    // movement is relative to the way the character faces
+    let local = transform.rotation.inverse() * to.normalize_or_zero();
+    movement_writer.write(MovementAction {
+        controller,
+        kind: MovementKind::Move(Vector2::new(local.x, -local.z)),
+    });
+}
+
+/// Eases soldiers' heads out of and back into cover.
+fn lean_soldiers(
+    time: Res<Time>,
+    lean_offsets: Res<LeanOffsets>,
+    mut soldiers: Query<(&mut Soldier, &Children)>,
+    mut heads: Query<(&SoldierHead, &mut Transform)>,
+) {
+    for (mut soldier, children) in &mut soldiers {
+        let target = match soldier.state {
+            SoldierState::InCover {
+                peeking: true,
+                side,
+                ..
+            } => side,
+            _ => 0.0,
+        };
+
+        let step = Soldier::LEAN_SPEED * time.delta_secs();
+        soldier.lean += (target - soldier.lean).clamp(-step, step);
+
+        for child in children {
+            if let Ok((head, mut transform)) = heads.get_mut(*child) {
+                let lean = lean_offsets.at(soldier.lean);
+                transform.translation = head.rest + lean.translation;
+                transform.rotation = lean.rotation;
+            }
+        }
+    }
+}
+
+fn soldiers_shoot(
+    mut commands: Commands,
+    round_assets: Res<RoundAssets>,
+    mut pool: ResMut<ProjectilePool>,
+    mut shot_writer: MessageWriter<ShotFired>,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut soldiers: Query<
+        (
+            Entity,
+            &mut Soldier,
+            &Transform,
+            &Children,
+            &Perception,
+            Option<&SquadRole>,
+            Has<HoldFire>,
+        ),
+        Without<Dead>,
+    >,
+    heads: Query<&Transform, With<SoldierHead>>,
+    players: Query<&GlobalTransform, With<Player>>,
+) {
+    /// Metres ahead of the head rounds leave from, clear of the soldier's own colliders
+    const MUZZLE: f32 = 0.6;
+
+    let mut rng = rand::rng();
+
+    for (soldier_entity, mut soldier, transform, children, perception, role, hold_fire) in
+        &mut soldiers
+    {
+        let _span = debug_span!("soldier_shoot", soldier = %soldier_entity).entered();
+        soldier.reload -= time.delta_secs();
+
+        let Some((player, last_known)) = soldier.threat else {
+            continue;
+        };
+
+        let Ok(target) = players.get(player).map(GlobalTransform::translation) else {
+            soldier.threat = None;
+            continue;
+        };
+
+        // suppressing fire goes where they were last seen, whether they can be seen or not
+        let suppressing = role == Some(&SquadRole::Suppress);
+        let target = if suppressing { last_known } else { target };
+
+        let Some(head) = children.iter().find_map(|child| heads.get(child).ok()) else {
+            continue;
+        };
+        let head = transform.transform_point(head.translation);
+
+        // those firing at what they can see take a moment to react first
+        let aimed = suppressing
+            || (soldier.seen_for >= perception.reaction_time
+                && clear_line(&spatial_query, head, target));
+
+        if hold_fire || soldier.reload > 0.0 || !soldier.exposed() || !aimed {
+            continue;
+        }
+
+        let Ok(aim) = Dir3::new(target - head) else {
+            continue;
+        };
+
+        let aim_error = perception.aim_error.to_radians();
+        let error = Quat::from_euler(
+            EulerRot::YXZ,
+            rng.random_range(-aim_error..=aim_error),
+            rng.random_range(-aim_error..=aim_error),
+            0.0,
+        );
+        let muzzle = Transform::from_translation(head + aim * MUZZLE)
+            .looking_to(aim, Vec3::Y)
+            .mul_transform(Transform::from_rotation(error));
+
+        let mut rounds = soldier.rounds;
+        fire_round(
+            &mut commands,
+            &round_assets,
+            &mut pool,
+            &mut shot_writer,
+            &mut rounds,
+            &muzzle.into(),
+            Shot {
+                shooter: soldier_entity,
+                weapon: soldier_entity,
+                damage: Soldier::DAMAGE,
+                muzzle_velocity: Soldier::MUZZLE_VELOCITY,
+                round_mass: Soldier::ROUND_MASS,
+            },
+        );
+
+        soldier.rounds = rounds;
+        soldier.reload = if suppressing {
+            Soldier::SUPPRESSING_INTERVAL
+        } else {
+            Soldier::FIRE_INTERVAL
+        };
+    }
+}
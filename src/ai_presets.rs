@@ -0,0 +1,133 @@
+//! How well soldiers see, hear, aim and fight, loaded from `*.ai.ron` assets.
+//!
+//! `assets/ai/presets.ai.ron` names a set of [`Perception`]s, like `recruit` and `veteran`, and
+//! each soldier picks one by its [`AiPreset`]. Like player tuning, the file is watched, so saving
+//! it while the game is running retunes every soldier straight away.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use crate::ron_asset::RonLoader;
+
+pub struct AiPresetsPlugin;
+
+impl Plugin for AiPresetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AiPresets>()
+            .register_asset_loader(RonLoader::<AiPresets>::new(&["ai.ron"]))
+            .add_systems(Startup, load_ai_presets)
+            .add_systems(Update, apply_ai_presets);
+    }
+}
+
+/// Named soldier perceptions, see `assets/ai/presets.ai.ron`.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct AiPresets(HashMap<String, Perception>);
+
+impl AiPresets {
+    pub fn get(&self, name: &str) -> Option<&Perception> {
+        self.0.get(name)
+    }
+}
+
+/// How well a soldier sees, hears, aims and fights.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Perception {
+    /// Seconds from first seeing a player to opening fire on them
+    pub reaction_time: f32,
+    /// Degrees a shot can stray from where it's aimed
+    pub aim_error: f32,
+    /// Metres it can see players from
+    pub vision_range: f32,
+    /// Degrees across its field of view. Anything shooting at it is noticed wherever it is
+    pub vision_cone: f32,
+    /// Metres it hears players' shots from, turning towards them
+    pub hearing_radius: f32,
+    /// From 0, taking cover whenever it's shot at and keeping its head down, to 1, never taking
+    /// cover
+    pub aggression: f32,
+}
+
+impl Default for Perception {
+    /// A `regular` soldier, until the presets have loaded
+    fn default() -> Self {
+        Self {
+            reaction_time: 0.5,
+            aim_error: 2.5,
+            vision_range: 40.0,
+            vision_cone: 120.0,
+            hearing_radius: 30.0,
+            aggression: 0.3,
+        }
+    }
+}
+
+/// The name of the preset a soldier's [`Perception`] comes from.
+#[derive(Component, Debug, Clone)]
+#[require(Perception)]
+pub struct AiPreset(pub String);
+
+impl Default for AiPreset {
+    fn default() -> Self {
+        Self("regular".to_string())
+    }
+}
+
+/// The presets asset, kept loaded so edits to it are picked up.
+#[derive(Resource)]
+struct AiPresetsHandle(Handle<AiPresets>);
+
+fn load_ai_presets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AiPresetsHandle(asset_server.load("ai/presets.ai.ron")));
+}
+
+fn apply_ai_presets(
+    mut asset_events: MessageReader<AssetEvent<AiPresets>>,
+    presets: Res<Assets<AiPresets>>,
+    handle: Res<AiPresetsHandle>,
+    mut soldiers: Query<(Entity, Ref<AiPreset>, &mut Perception)>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if *id == handle.0.id()
+        )
+    });
+
+    let Some(presets) = presets.get(&handle.0) else {
+        return;
+    };
+
+    if changed {
+        info!("applying AI presets");
+    }
+
+    for (soldier, preset, mut perception) in &mut soldiers {
+        if !changed && !preset.is_changed() {
+            continue;
+        }
+
+        match presets.get(&preset.0) {
+            Some(preset) => *perception = preset.clone(),
+            None => warn!("{soldier} has no AI preset called '{}'", preset.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_ai_presets_parse() {
+        let presets: AiPresets =
+            ron::from_str(include_str!("../assets/ai/presets.ai.ron")).unwrap();
+
+        // soldiers without a preset, and those the waves timeline spawns, all find theirs
+        for name in [AiPreset::default().0.as_str(), "recruit", "veteran"] {
+            assert!(presets.get(name).is_some(), "no AI preset called '{name}'");
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Character attributes that grow with use.
+//!
+//! Endurance improves by sprinting and raises stamina capacity, strength improves by moving under
+//! load and raises carrying capacity, and weapon handling improves by shooting and aiming and
+//! speeds up aiming down sights and reloading. Progress is slow: each attribute goes from 1 to
+//! [`Attributes::MAX_LEVEL`] over many hours of play.
+//!
+//! The player's attributes are stored in their [`Profile`](crate::profile::Profile) and given to
+//! them when play starts. The stats screen is toggled with F3.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::encumbrance::Encumbrance;
+use crate::energy::Stamina;
+use crate::movement::Sprinting;
+use crate::split_screen::PrimaryPlayer;
+use crate::{AdsAlpha, PlayerWeapon, ShotFired};
+
+pub struct AttributesPlugin;
+
+impl Plugin for AttributesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_stats_screen)
+            .add_systems(
+                Update,
+                (
+                    (train_endurance, train_strength, train_handling),
+                    apply_attributes,
+                    update_stats_screen,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, toggle_stats_screen);
+    }
+}
+
+/// A character's long term physical and weapon skills.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attributes {
+    pub endurance: f32,
+    pub strength: f32,
+    pub handling: f32,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            endurance: 1.0,
+            strength: 1.0,
+            handling: 1.0,
+        }
+    }
+}
+
+impl Attributes {
+    pub const MAX_LEVEL: f32 = 10.0;
+
+    /// Multiplier on stamina capacity
+    pub fn stamina_scale(&self) -> f32 {
+        1.0 + (self.endurance - 1.0) * 0.05
+    }
+
+    /// Multiplier on carrying capacity
+    pub fn carry_scale(&self) -> f32 {
+        1.0 + (self.strength - 1.0) * 0.06
+    }
+
+    /// Multiplier on how quickly weapons are raised to aim and reloaded
+    pub fn handling_scale(&self) -> f32 {
+        1.0 + (self.handling - 1.0) * 0.04
+    }
+
+    fn train(level: &mut f32, amount: f32) {
+        *level = (*level + amount).min(Self::MAX_LEVEL);
+    }
+}
+
+fn train_endurance(
+    time: Res<Time>,
+    query: Query<(&mut Attributes, &LinearVelocity), With<Sprinting>>,
+) {
+    const PER_SECOND: f32 = 0.0004;
+
+    for (mut attributes, velocity) in query {
+        if velocity.xz().length() > 0.5 {
+            Attributes::train(&mut attributes.endurance, PER_SECOND * time.delta_secs());
+        }
+    }
+}
+
+fn train_strength(time: Res<Time>, query: Query<(&mut Attributes, &Encumbrance, &LinearVelocity)>) {
+    const PER_SECOND: f32 = 0.0003;
+
+    for (mut attributes, encumbrance, velocity) in query {
+        let strain = encumbrance.strain();
+
+        if strain > 0.0 && velocity.xz().length() > 0.5 {
+            Attributes::train(
+                &mut attributes.strength,
+                PER_SECOND * strain * time.delta_secs(),
+            );
+        }
+    }
+}
+
+fn train_handling(
+    time: Res<Time>,
+    mut shot_reader: MessageReader<ShotFired>,
+    mut players_q: Query<&mut Attributes>,
+    weapons_q: Query<&AdsAlpha, With<PlayerWeapon>>,
+    player: Single<Entity, With<PrimaryPlayer>>,
+) {
+    const PER_SHOT: f32 = 0.0005;
+    const PER_SECOND_AIMING: f32 = 0.0002;
+
+    for shot in shot_reader.read() {
+        if let Ok(mut attributes) = players_q.get_mut(shot.shooter) {
+            Attributes::train(&mut attributes.handling, PER_SHOT);
+        }
+    }
+
+    let aiming = weapons_q.iter().any(|ads| ads.0 >= 1.0);
+
+    if aiming && let Ok(mut attributes) = players_q.get_mut(*player) {
+        Attributes::train(
+            &mut attributes.handling,
+            PER_SECOND_AIMING * time.delta_secs(),
+        );
+    }
+}
+
+fn apply_attributes(
+    query: Query<
+        (&Attributes, Option<&mut Stamina>, Option<&mut Encumbrance>),
+        Changed<Attributes>,
+    >,
+) {
+    for (attributes, stamina, encumbrance) in query {
+        if let Some(mut stamina) = stamina {
+            stamina.max_scale = attributes.stamina_scale();
+        }
+
+        if let Some(mut encumbrance) = encumbrance {
+            encumbrance.set_capacity_scale(attributes.carry_scale());
+        }
+    }
+}
+
+#[derive(Component)]
+struct StatsScreen;
+
+fn setup_stats_screen(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(24.0),
+            top: Val::Px(80.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        Text::default(),
+        TextFont::from_font_size(16.0),
+        Visibility::Hidden,
+        StatsScreen,
+    ));
+}
+
+fn toggle_stats_screen(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut screen: Single<&mut Visibility, With<StatsScreen>>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        screen.toggle_visible_hidden();
+    }
+}
+
+fn update_stats_screen(
+    player: Single<&Attributes, (With<PrimaryPlayer>, Changed<Attributes>)>,
+    mut screen: Single<&mut Text, With<StatsScreen>>,
+) {
+    let line = |name: &str, level: f32, scale: f32, effect: &str| {
+        format!(
+            "{name:<10} {level:>5.2}  +{:.0}% {effect}",
+            (scale - 1.0) * 100.0
+        )
+    };
+
+    screen.0 = [
+        line(
+            "Endurance",
+            player.endurance,
+            player.stamina_scale(),
+            "stamina",
+        ),
+        line(
+            "Strength",
+            player.strength,
+            player.carry_scale(),
+            "carry weight",
+        ),
+        line(
+            "Handling",
+            player.handling,
+            player.handling_scale(),
+            "aim speed",
+        ),
+    ]
+    .join("\n");
+}
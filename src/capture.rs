@@ -0,0 +1,204 @@
+//! Screenshots and clips.
+//!
+//! The screenshot key saves the window as a PNG in `captures/`. With
+//! [`GameSettings::record_clips`] on, the last [`ClipBuffer::SECONDS`] of each game are kept in
+//! memory at a low frame rate and half resolution, around 200MB at 1080p, and the clip key dumps
+//! them to a numbered PNG sequence in a `captures/clip-*` directory of its own, ready to be
+//! stitched into a video with something like `ffmpeg -framerate 10 -i frame-%04d.png clip.mp4`.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk},
+    tasks::{AsyncComputeTaskPool, IoTaskPool, Task, futures::check_ready},
+};
+
+use crate::console::console_closed;
+use crate::menu::GameState;
+use crate::settings::{GameSettings, Keybinds};
+
+const CAPTURE_DIRECTORY: &str = "captures";
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClipBuffer>()
+            .add_systems(OnExit(GameState::InGame), clear_clip_buffer)
+            .add_systems(
+                Update,
+                (
+                    (record_clip_frames, collect_clip_frames)
+                        .chain()
+                        .run_if(in_state(GameState::InGame).and(recording_clips)),
+                    (take_screenshot, save_clip).run_if(console_closed),
+                ),
+            );
+    }
+}
+
+/// The most recent frames, oldest first.
+#[derive(Resource)]
+struct ClipBuffer {
+    frames: VecDeque<Image>,
+    /// Frames still being shrunk on another thread, oldest first
+    pending: VecDeque<Task<Option<Image>>>,
+    timer: Timer,
+}
+
+impl ClipBuffer {
+    const SECONDS: usize = 10;
+    const FRAME_RATE: usize = 10;
+
+    fn push(&mut self, frame: Image) {
+        if self.frames.len() == Self::SECONDS * Self::FRAME_RATE {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(frame);
+    }
+}
+
+impl Default for ClipBuffer {
+    fn default() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            pending: VecDeque::new(),
+            timer: Timer::from_seconds(1.0 / Self::FRAME_RATE as f32, TimerMode::Repeating),
+        }
+    }
+}
+
+/// A path in the captures directory, named after the current time
+fn capture_path(prefix: &str, extension: &str) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    Path::new(CAPTURE_DIRECTORY).join(format!("{prefix}-{now}{extension}"))
+}
+
+fn take_screenshot(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+) {
+    if !keys.just_pressed(keybinds.screenshot) {
+        return;
+    }
+
+    if let Err(err) = std::fs::create_dir_all(CAPTURE_DIRECTORY) {
+        error!("could not create {CAPTURE_DIRECTORY}: {err}");
+        return;
+    }
+
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(capture_path("screenshot", ".png")));
+}
+
+fn recording_clips(settings: Res<GameSettings>) -> bool {
+    settings.record_clips
+}
+
+fn record_clip_frames(mut commands: Commands, time: Res<Time>, mut buffer: ResMut<ClipBuffer>) {
+    if !buffer.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(buffer_clip_frame);
+}
+
+/// Shrinks a captured frame to half size on another thread, to be added to the clip buffer by
+/// `collect_clip_frames`.
+fn buffer_clip_frame(captured: On<ScreenshotCaptured>, mut buffer: ResMut<ClipBuffer>) {
+    let image = captured.image.clone();
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let frame = match image.try_into_dynamic() {
+            Ok(frame) => frame,
+            Err(err) => {
+                warn_once!("could not buffer a clip frame: {err}");
+                return None;
+            }
+        };
+
+        let frame = frame.thumbnail(frame.width() / 2, frame.height() / 2);
+        Some(Image::from_dynamic(
+            frame,
+            true,
+            RenderAssetUsages::MAIN_WORLD,
+        ))
+    });
+
+    buffer.pending.push_back(task);
+}
+
+/// Adds shrunk frames to the clip buffer in the order they were captured.
+fn collect_clip_frames(mut buffer: ResMut<ClipBuffer>) {
+    while let Some(task) = buffer.pending.front_mut() {
+        let Some(frame) = check_ready(task) else {
+            break;
+        };
+
+        buffer.pending.pop_front();
+
+        if let Some(frame) = frame {
+            buffer.push(frame);
+        }
+    }
+}
+
+/// Frees the buffered frames, clips only cover the game being played.
+fn clear_clip_buffer(mut buffer: ResMut<ClipBuffer>) {
+    buffer.frames.clear();
+    buffer.pending.clear();
+}
+
+/// Writes the buffered frames out in the background, so the game doesn't hitch.
+fn save_clip(keys: Res<ButtonInput<KeyCode>>, keybinds: Res<Keybinds>, buffer: Res<ClipBuffer>) {
+    if !keys.just_pressed(keybinds.clip) || buffer.frames.is_empty() {
+        return;
+    }
+
+    let directory = capture_path("clip", "");
+    let frames: Vec<Image> = buffer.frames.iter().cloned().collect();
+    info!(
+        "saving {} clip frames to {}",
+        frames.len(),
+        directory.display()
+    );
+
+    IoTaskPool::get()
+        .spawn(async move {
+            if let Err(err) = std::fs::create_dir_all(&directory) {
+                error!("could not create {}: {err}", directory.display());
+                return;
+            }
+
+            for (index, frame) in frames.into_iter().enumerate() {
+                let path = directory.join(format!("frame-{:04}.png", index + 1));
+
+                // the alpha channel holds brightness with HDR on, so it's dropped
+                let saved = frame
+                    .try_into_dynamic()
+                    .map_err(|err| err.to_string())
+                    .and_then(|frame| frame.to_rgb8().save(&path).map_err(|err| err.to_string()));
+
+                if let Err(err) = saved {
+                    error!("could not save {}: {err}", path.display());
+                    return;
+                }
+            }
+
+            info!("saved clip to {}", directory.display());
+        })
+        .detach();
+}
@@ -0,0 +1,379 @@
+//! Challenges, loaded from `assets/challenges/default.challenges.ron`.
+//!
+//! Each [`ChallengeDef`] counts the kills or waves matching its [`Condition`] towards a goal,
+//! with the count kept in the active profile, so adding one is a matter of adding an entry to the
+//! file. Finishing one puts up a toast, and the challenges screen off the main menu shows how far
+//! along each one is.
+//!
+//! There's no sliding to kill from yet, so the nearest thing is a kill mid-roll out of a landing.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::Player;
+use crate::damage::{DamageEvent, HitZone};
+use crate::glide::{Gliding, Rolling};
+use crate::hud::Toast;
+use crate::menu::{self, GameState, MenuScreen};
+use crate::movement::{Grounded, Sprinting};
+use crate::profile::ActiveProfile;
+use crate::ron_asset::RonLoader;
+use crate::split_screen::PrimaryPlayer;
+use crate::timeline::WaveStarted;
+use crate::zipline::Ziplining;
+
+pub struct ChallengesPlugin;
+
+impl Plugin for ChallengesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Challenges>()
+            .init_resource::<WaveHurt>()
+            .register_asset_loader(RonLoader::<Challenges>::new(&["challenges.ron"]))
+            .add_systems(Startup, load_challenges)
+            .add_systems(OnEnter(MenuScreen::Challenges), setup_challenges_screen)
+            .add_systems(OnExit(GameState::InGame), forget_wave)
+            .add_systems(
+                Update,
+                (track_kills, track_flawless_waves)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<ActiveProfile>),
+            );
+    }
+}
+
+/// Every challenge, see `assets/challenges/default.challenges.ron`.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct Challenges(Vec<ChallengeDef>);
+
+impl Challenges {
+    pub fn iter(&self) -> impl Iterator<Item = &ChallengeDef> {
+        self.0.iter()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChallengeDef {
+    /// What a profile keeps its progress under, so it shouldn't change once released
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Matching kills or waves to finish it
+    pub goal: u32,
+    pub condition: Condition,
+}
+
+/// What counts towards a challenge.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// A kill by the player meeting all of these
+    Kill {
+        /// Metres from the player, at least
+        #[serde(default)]
+        distance: f32,
+        #[serde(default)]
+        headshot: bool,
+        /// What the player was doing at the time
+        #[serde(default)]
+        moving: Option<Moving>,
+    },
+    /// A wave got through without the player being hurt, counted as the next one starts
+    FlawlessWave,
+}
+
+/// What a player can be doing when a kill counts.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moving {
+    Sprinting,
+    /// Rolling out of a hard landing
+    Rolling,
+    Gliding,
+    Ziplining,
+    /// Off the ground, however they got there
+    Airborne,
+}
+
+/// A kill by the player, as challenges see it.
+#[derive(Debug, Default)]
+pub struct Kill {
+    /// Metres from the player
+    pub distance: f32,
+    pub headshot: bool,
+    pub moving: Vec<Moving>,
+}
+
+impl Condition {
+    pub fn counts(&self, kill: &Kill) -> bool {
+        match self {
+            Condition::Kill {
+                distance,
+                headshot,
+                moving,
+            } => {
+                kill.distance >= *distance
+                    && (kill.headshot || !headshot)
+                    && moving.is_none_or(|moving| kill.moving.contains(&moving))
+            }
+            Condition::FlawlessWave => false,
+        }
+    }
+}
+
+/// Whether the player has been hurt during the current wave, if one has started this game.
+#[derive(Resource, Debug, Default)]
+struct WaveHurt(Option<bool>);
+
+/// The challenges asset, kept loaded so edits to it are picked up.
+#[derive(Resource)]
+struct ChallengesHandle(Handle<Challenges>);
+
+fn load_challenges(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ChallengesHandle(
+        asset_server.load("challenges/default.challenges.ron"),
+    ));
+}
+
+/// Counts one more towards every challenge `counts` picks out, with a toast for any it finishes.
+fn advance(
+    challenges: &Challenges,
+    progress: &mut BTreeMap<String, u32>,
+    toast_writer: &mut MessageWriter<Toast>,
+    counts: impl Fn(&Condition) -> bool,
+) {
+    for challenge in challenges
+        .iter()
+        .filter(|challenge| counts(&challenge.condition))
+    {
+        let done = progress.entry(challenge.id.clone()).or_default();
+        if *done >= challenge.goal {
+            continue;
+        }
+
+        *done += 1;
+
+        if *done == challenge.goal {
+            info!("challenge '{}' complete", challenge.id);
+            toast_writer.write(Toast(format!("Challenge complete: {}", challenge.name)));
+        }
+    }
+}
+
+fn track_kills(
+    mut damage_reader: MessageReader<DamageEvent>,
+    mut toast_writer: MessageWriter<Toast>,
+    mut profile: ResMut<ActiveProfile>,
+    handle: Res<ChallengesHandle>,
+    challenges: Res<Assets<Challenges>>,
+    players: Query<
+        (
+            &GlobalTransform,
+            Has<Sprinting>,
+            Has<Rolling>,
+            Has<Gliding>,
+            Has<Ziplining>,
+            Has<Grounded>,
+        ),
+        With<Player>,
+    >,
+) {
+    let Some(challenges) = challenges.get(&handle.0) else {
+        return;
+    };
+
+    // the local players share a profile, so any of them can count towards a challenge
+    for damage in damage_reader.read() {
+        if !damage.killed {
+            continue;
+        }
+
+        let Ok((transform, sprinting, rolling, gliding, ziplining, grounded)) =
+            players.get(damage.source)
+        else {
+            continue;
+        };
+
+        let moving = [
+            (sprinting, Moving::Sprinting),
+            (rolling, Moving::Rolling),
+            (gliding, Moving::Gliding),
+            (ziplining, Moving::Ziplining),
+            (!grounded, Moving::Airborne),
+        ];
+        let kill = Kill {
+            distance: transform.translation().distance(damage.point),
+            headshot: damage.zone == HitZone::Head,
+            moving: moving
+                .into_iter()
+                .filter_map(|(doing, moving)| doing.then_some(moving))
+                .collect(),
+        };
+
+        advance(
+            challenges,
+            &mut profile.challenges,
+            &mut toast_writer,
+            |condition| condition.counts(&kill),
+        );
+    }
+}
+
+/// Counts each wave the player wasn't hurt in once the next one starts.
+fn track_flawless_waves(
+    mut wave_hurt: ResMut<WaveHurt>,
+    mut damage_reader: MessageReader<DamageEvent>,
+    mut wave_reader: MessageReader<WaveStarted>,
+    mut toast_writer: MessageWriter<Toast>,
+    mut profile: ResMut<ActiveProfile>,
+    handle: Res<ChallengesHandle>,
+    challenges: Res<Assets<Challenges>>,
+    player: Single<Entity, With<PrimaryPlayer>>,
+) {
+    for damage in damage_reader.read() {
+        if damage.target == *player
+            && let Some(hurt) = wave_hurt.0.as_mut()
+        {
+            *hurt = true;
+        }
+    }
+
+    for _ in wave_reader.read() {
+        if wave_hurt.0 == Some(false)
+            && let Some(challenges) = challenges.get(&handle.0)
+        {
+            advance(
+                challenges,
+                &mut profile.challenges,
+                &mut toast_writer,
+                |condition| *condition == Condition::FlawlessWave,
+            );
+        }
+
+        wave_hurt.0 = Some(false);
+    }
+}
+
+/// Leaving a game mid-wave doesn't get it through.
+fn forget_wave(mut wave_hurt: ResMut<WaveHurt>) {
+    wave_hurt.0 = None;
+}
+
+fn setup_challenges_screen(
+    mut commands: Commands,
+    profile: Res<ActiveProfile>,
+    handle: Res<ChallengesHandle>,
+    challenges: Res<Assets<Challenges>>,
+) {
+    let challenges = challenges.get(&handle.0);
+
+    commands
+        .spawn(menu::menu_root("Challenges", MenuScreen::Challenges))
+        .with_children(|parent| {
+            for challenge in challenges.into_iter().flat_map(Challenges::iter) {
+                let done = profile.challenges.get(&challenge.id).copied().unwrap_or(0);
+                let progress = if done >= challenge.goal {
+                    "Complete".to_owned()
+                } else {
+                    format!("{done}/{}", challenge.goal)
+                };
+
+                parent.spawn((
+                    Node {
+                        width: Val::Px(560.0),
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    children![
+                        (
+                            Text::new(format!("{}: {}", challenge.name, challenge.description)),
+                            TextFont::from_font_size(18.0),
+                        ),
+                        (Text::new(progress), TextFont::from_font_size(18.0)),
+                    ],
+                ));
+            }
+
+            parent.spawn(menu::back_button());
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_challenges_count_matching_kills() {
+        let challenges: Challenges =
+            ron::from_str(include_str!("../assets/challenges/default.challenges.ron")).unwrap();
+
+        let condition = |id: &str| {
+            challenges
+                .iter()
+                .find(|challenge| challenge.id == id)
+                .map(|challenge| challenge.condition.clone())
+                .unwrap()
+        };
+
+        let close_roll = Kill {
+            distance: 10.0,
+            headshot: false,
+            moving: vec![Moving::Rolling, Moving::Airborne],
+        };
+        assert!(condition("tuck_and_roll").counts(&close_roll));
+        assert!(!condition("long_shot").counts(&close_roll));
+        assert!(!condition("head_hunter").counts(&close_roll));
+
+        let far_headshot = Kill {
+            distance: 60.0,
+            headshot: true,
+            ..default()
+        };
+        assert!(condition("long_shot").counts(&far_headshot));
+        assert!(condition("head_hunter").counts(&far_headshot));
+        assert!(!condition("run_and_gun").counts(&far_headshot));
+        assert_eq!(condition("untouchable"), Condition::FlawlessWave);
+    }
+
+    #[test]
+    fn kills_by_any_local_player_count() {
+        use crate::profile::Profile;
+
+        let challenges: Challenges =
+            ron::from_str(include_str!("../assets/challenges/default.challenges.ron")).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<DamageEvent>()
+            .add_message::<Toast>()
+            .init_resource::<Assets<Challenges>>()
+            .insert_resource(ActiveProfile(Profile::new("test".to_owned())))
+            .add_systems(Update, track_kills);
+
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<Challenges>>()
+            .add(challenges);
+        app.insert_resource(ChallengesHandle(handle));
+
+        let world = app.world_mut();
+        world.spawn((Player, PrimaryPlayer, GlobalTransform::default(), Grounded));
+        let two = world
+            .spawn((Player, GlobalTransform::default(), Grounded))
+            .id();
+
+        world.write_message(DamageEvent {
+            target: Entity::PLACEHOLDER,
+            source: two,
+            amount: 100.0,
+            point: Vec3::new(0.0, 0.0, -60.0),
+            zone: HitZone::Body,
+            armor_hit: false,
+            killed: true,
+        });
+        app.update();
+
+        let profile = app.world().resource::<ActiveProfile>();
+        assert_eq!(profile.challenges.get("long_shot"), Some(&1));
+    }
+}
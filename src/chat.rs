@@ -0,0 +1,406 @@
+//! Text chat and quick commands.
+//!
+//! T opens a line to type into and Enter sends it, or closes it again if nothing's been typed.
+//! Holding V opens a wheel of [`QuickCommand`]s instead: flick the mouse towards one and let go to
+//! send it, where "Enemy spotted" also pings whatever the crosshair is on. Everything sent shows in
+//! the chat overlay for a few seconds.
+//!
+//! Only built into the game with the `networked` feature. There's no network layer yet, so a
+//! [`ChatMessage`] only goes as far as this machine and the players sharing its screen.
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+        mouse::AccumulatedMouseMotion,
+    },
+    prelude::*,
+};
+
+use avian3d::prelude::*;
+
+use crate::PlayerCamera;
+use crate::console::console_closed;
+use crate::hud::HudTheme;
+use crate::menu::GameState;
+use crate::ping::{self, PingPlaced};
+use crate::profile::ActiveProfile;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ChatMessage>()
+            .init_resource::<Chat>()
+            .add_systems(Startup, setup_chat)
+            .add_systems(OnExit(GameState::InGame), close_chat)
+            .add_systems(
+                Update,
+                (
+                    (type_chat, quick_command_wheel.run_if(chat_closed))
+                        .run_if(in_state(GameState::InGame))
+                        .run_if(console_closed),
+                    show_chat,
+                    expire_chat_lines,
+                    update_chat_input,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// A line sent to the chat.
+#[derive(Message, Debug, Clone)]
+pub struct ChatMessage {
+    pub from: String,
+    pub text: String,
+}
+
+/// A canned line sent from the quick command wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickCommand {
+    EnemySpotted,
+    NeedAmmo,
+    OnMyWay,
+    FallBack,
+}
+
+impl QuickCommand {
+    /// Clockwise round the wheel from the top
+    const ALL: [QuickCommand; 4] = [
+        QuickCommand::EnemySpotted,
+        QuickCommand::NeedAmmo,
+        QuickCommand::OnMyWay,
+        QuickCommand::FallBack,
+    ];
+
+    fn text(self) -> &'static str {
+        match self {
+            QuickCommand::EnemySpotted => "Enemy spotted",
+            QuickCommand::NeedAmmo => "Need ammo",
+            QuickCommand::OnMyWay => "On my way",
+            QuickCommand::FallBack => "Fall back",
+        }
+    }
+
+    /// Which way from the middle of the wheel it sits, with y up
+    fn direction(self) -> Vec2 {
+        match self {
+            QuickCommand::EnemySpotted => Vec2::Y,
+            QuickCommand::NeedAmmo => Vec2::X,
+            QuickCommand::OnMyWay => Vec2::NEG_Y,
+            QuickCommand::FallBack => Vec2::NEG_X,
+        }
+    }
+
+    /// The command the mouse has been flicked towards, if it's moved far enough to tell
+    fn picked(flick: Vec2) -> Option<Self> {
+        /// Pixels of mouse movement before anything's picked
+        const DEAD_ZONE: f32 = 30.0;
+
+        if flick.length() < DEAD_ZONE {
+            return None;
+        }
+
+        Self::ALL.into_iter().max_by(|a, b| {
+            a.direction()
+                .dot(flick)
+                .total_cmp(&b.direction().dot(flick))
+        })
+    }
+}
+
+/// What's being typed or picked.
+#[derive(Resource, Debug, Default)]
+pub struct Chat {
+    /// The line being typed, while chat is open
+    typing: Option<String>,
+    /// How far the mouse has moved since the quick command wheel opened, while it's held open
+    wheel: Option<Vec2>,
+}
+
+impl Chat {
+    /// Lines shown at once, the oldest going first
+    const MAX_LINES: usize = 6;
+    /// Seconds each line stays up
+    const LINE_LIFETIME: f32 = 10.0;
+    const TYPE_KEY: KeyCode = KeyCode::KeyT;
+    const WHEEL_KEY: KeyCode = KeyCode::KeyV;
+}
+
+/// Run condition for gameplay input that shouldn't fire while typing into the chat.
+pub fn chat_closed(chat: Option<Res<Chat>>) -> bool {
+    chat.is_none_or(|chat| chat.typing.is_none())
+}
+
+/// Run condition for mouse look, which picks from the quick command wheel while it's open.
+pub fn wheel_closed(chat: Option<Res<Chat>>) -> bool {
+    chat.is_none_or(|chat| chat.wheel.is_none())
+}
+
+/// The column chat lines are added to.
+#[derive(Component)]
+struct ChatLines;
+
+#[derive(Component)]
+struct ChatLine(Timer);
+
+#[derive(Component)]
+struct ChatInput;
+
+#[derive(Component)]
+struct QuickCommandWheel;
+
+#[derive(Component)]
+struct QuickCommandOption(QuickCommand);
+
+fn setup_chat(mut commands: Commands, theme: Res<HudTheme>) {
+    /// Pixels from the middle of the wheel to each command
+    const WHEEL_RADIUS: f32 = 120.0;
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            bottom: Val::Px(160.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        ChatLines,
+        children![(
+            Text::default(),
+            TextFont::from_font_size(theme.small_font_size),
+            TextColor(theme.text),
+            Visibility::Hidden,
+            ChatInput,
+            // always after the lines, which are inserted before it
+            ZIndex(1),
+        )],
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                top: Val::Percent(50.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            QuickCommandWheel,
+        ))
+        .with_children(|wheel| {
+            for command in QuickCommand::ALL {
+                let offset = command.direction() * WHEEL_RADIUS;
+
+                wheel.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(offset.x),
+                        top: Val::Px(-offset.y),
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                        ..default()
+                    },
+                    // centred on its spot on the wheel
+                    UiTransform::from_translation(Val2::percent(-50.0, -50.0)),
+                    BackgroundColor(theme.panel),
+                    QuickCommandOption(command),
+                    children![(
+                        Text::new(command.text()),
+                        TextFont::from_font_size(theme.font_size),
+                        TextColor(theme.text),
+                    )],
+                ));
+            }
+        });
+}
+
+fn sender(profile: Option<&ActiveProfile>) -> String {
+    profile.map_or_else(|| "Player".to_owned(), |profile| profile.name.clone())
+}
+
+fn type_chat(
+    mut chat: ResMut<Chat>,
+    mut keyboard_reader: MessageReader<KeyboardInput>,
+    mut chat_writer: MessageWriter<ChatMessage>,
+    profile: Option<Res<ActiveProfile>>,
+) {
+    for event in keyboard_reader.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        let Some(typing) = &mut chat.typing else {
+            if event.key_code == Chat::TYPE_KEY && chat.wheel.is_none() {
+                chat.typing = Some(String::new());
+            }
+            continue;
+        };
+
+        match &event.logical_key {
+            Key::Enter => {
+                let text = std::mem::take(typing);
+                chat.typing = None;
+
+                if !text.trim().is_empty() {
+                    chat_writer.write(ChatMessage {
+                        from: sender(profile.as_deref()),
+                        text,
+                    });
+                }
+            }
+            Key::Backspace => {
+                typing.pop();
+            }
+            Key::Space => typing.push(' '),
+            Key::Character(text) => typing.push_str(text),
+            _ => {}
+        }
+    }
+}
+
+fn quick_command_wheel(
+    mut chat: ResMut<Chat>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    spatial_query: SpatialQuery,
+    mut chat_writer: MessageWriter<ChatMessage>,
+    mut ping_writer: MessageWriter<PingPlaced>,
+    profile: Option<Res<ActiveProfile>>,
+    player: Single<(Entity, &Children), With<PrimaryPlayer>>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    if keys.just_pressed(Chat::WHEEL_KEY) {
+        chat.wheel = Some(Vec2::ZERO);
+    }
+
+    let Some(flick) = &mut chat.wheel else {
+        return;
+    };
+
+    // screen space has y down
+    *flick += mouse_motion.delta * Vec2::new(1.0, -1.0);
+
+    if !keys.just_released(Chat::WHEEL_KEY) {
+        return;
+    }
+
+    let picked = QuickCommand::picked(*flick);
+    chat.wheel = None;
+
+    let Some(command) = picked else {
+        return;
+    };
+
+    chat_writer.write(ChatMessage {
+        from: sender(profile.as_deref()),
+        text: command.text().to_owned(),
+    });
+
+    let (player, children) = *player;
+    let camera = children.iter().find_map(|child| cameras.get(child).ok());
+
+    if command == QuickCommand::EnemySpotted
+        && let Some(camera) = camera
+        && let Some(position) = ping::crosshair_point(&spatial_query, player, camera)
+    {
+        ping_writer.write(PingPlaced {
+            owner: player,
+            position,
+        });
+    }
+}
+
+fn show_chat(
+    mut commands: Commands,
+    theme: Res<HudTheme>,
+    mut chat_reader: MessageReader<ChatMessage>,
+    list: Single<(Entity, &Children), With<ChatLines>>,
+    lines: Query<(), With<ChatLine>>,
+) {
+    let (list, children) = *list;
+    let mut shown = children.iter().filter(|child| lines.contains(*child));
+    let mut count = shown.clone().count();
+
+    for message in chat_reader.read() {
+        info!(target: "chat", "{}: {}", message.from, message.text);
+
+        if count >= Chat::MAX_LINES
+            && let Some(oldest) = shown.next()
+        {
+            commands.entity(oldest).despawn();
+            count -= 1;
+        }
+
+        commands.spawn((
+            Text::new(format!("{}: {}", message.from, message.text)),
+            TextFont::from_font_size(theme.small_font_size),
+            TextColor(theme.text),
+            ChatLine(Timer::from_seconds(Chat::LINE_LIFETIME, TimerMode::Once)),
+            ChildOf(list),
+        ));
+        count += 1;
+    }
+}
+
+fn expire_chat_lines(
+    mut commands: Commands,
+    time: Res<Time>,
+    lines: Query<(Entity, &mut ChatLine)>,
+) {
+    for (line, mut timer) in lines {
+        if timer.0.tick(time.delta()).is_finished() {
+            commands.entity(line).despawn();
+        }
+    }
+}
+
+fn update_chat_input(
+    chat: Res<Chat>,
+    theme: Res<HudTheme>,
+    input: Single<(&mut Text, &mut Visibility), With<ChatInput>>,
+    mut wheel: Single<&mut Visibility, (With<QuickCommandWheel>, Without<ChatInput>)>,
+    options: Query<(&QuickCommandOption, &Children)>,
+    mut colors: Query<&mut TextColor>,
+) {
+    if !chat.is_changed() {
+        return;
+    }
+
+    let (mut text, mut visibility) = input.into_inner();
+    match &chat.typing {
+        Some(typing) => {
+            text.0 = format!("say: {typing}_");
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+
+    **wheel = if chat.wheel.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    let picked = chat.wheel.and_then(QuickCommand::picked);
+    for (option, children) in options {
+        let color = if picked == Some(option.0) {
+            theme.objective
+        } else {
+            theme.text
+        };
+
+        for child in children {
+            if let Ok(mut text_color) = colors.get_mut(*child) {
+                text_color.0 = color;
+            }
+        }
+    }
+}
+
+/// Leaving a game drops anything half typed or picked.
+fn close_chat(mut chat: ResMut<Chat>) {
+    *chat = Chat::default();
+}
@@ -0,0 +1,150 @@
+//! Tidying away the things shooting leaves lying around.
+//!
+//! Spent rounds, grenades, casings and debris get a [`Cleanup`] saying how long they may stay:
+//! a [`CleanupPolicy`] despawns them once they're too old, too far from every player, have fallen
+//! out of the world or have been asleep in the physics engine for long enough. [`Pooled`] rounds
+//! go back to the `projectile` pool instead. What's tracked and what's been cleared is
+//! reported through Bevy's diagnostics under `cleanup/`, and shown in the debug overlay.
+
+use avian3d::prelude::*;
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+
+use crate::Player;
+use crate::projectile::{Pooled, release};
+
+pub struct CleanupPlugin;
+
+impl Plugin for CleanupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CleanupCounts>()
+            .register_diagnostic(Diagnostic::new(CleanupCounts::TRACKED))
+            .register_diagnostic(Diagnostic::new(CleanupCounts::AGED_OUT))
+            .register_diagnostic(Diagnostic::new(CleanupCounts::OUT_OF_RANGE))
+            .register_diagnostic(Diagnostic::new(CleanupCounts::AT_REST))
+            .add_systems(Update, (clean_up, measure_cleanup).chain());
+    }
+}
+
+/// When something left lying around is despawned. Each limit is optional.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CleanupPolicy {
+    /// Seconds since spawning
+    pub max_age: Option<f32>,
+    /// Metres from the nearest player
+    pub max_distance: Option<f32>,
+    /// Height it's fallen out of the world below
+    pub min_height: Option<f32>,
+    /// Seconds spent asleep, come to rest in the physics engine
+    pub max_rest: Option<f32>,
+}
+
+impl CleanupPolicy {
+    /// Rounds fired from any weapon
+    pub const ROUND: Self = Self {
+        max_age: Some(20.0),
+        max_distance: Some(250.0),
+        min_height: Some(-50.0),
+        max_rest: Some(3.0),
+    };
+}
+
+/// Despawns this entity according to its policy.
+#[derive(Component, Debug)]
+pub struct Cleanup {
+    policy: CleanupPolicy,
+    age: f32,
+    rest: f32,
+}
+
+impl Cleanup {
+    pub fn new(policy: CleanupPolicy) -> Self {
+        Self {
+            policy,
+            age: 0.0,
+            rest: 0.0,
+        }
+    }
+}
+
+/// Running totals of what's been cleaned up, and why.
+#[derive(Resource, Debug, Default)]
+pub struct CleanupCounts {
+    pub tracked: usize,
+    pub aged_out: usize,
+    /// Too far from every player, or fallen out of the world
+    pub out_of_range: usize,
+    pub at_rest: usize,
+}
+
+impl CleanupCounts {
+    pub const TRACKED: DiagnosticPath = DiagnosticPath::const_new("cleanup/tracked");
+    pub const AGED_OUT: DiagnosticPath = DiagnosticPath::const_new("cleanup/aged_out");
+    pub const OUT_OF_RANGE: DiagnosticPath = DiagnosticPath::const_new("cleanup/out_of_range");
+    pub const AT_REST: DiagnosticPath = DiagnosticPath::const_new("cleanup/at_rest");
+
+    pub fn despawned(&self) -> usize {
+        self.aged_out + self.out_of_range + self.at_rest
+    }
+}
+
+fn clean_up(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut counts: ResMut<CleanupCounts>,
+    players: Query<&GlobalTransform, With<Player>>,
+    tracked: Query<(
+        Entity,
+        &mut Cleanup,
+        &GlobalTransform,
+        Has<Sleeping>,
+        Has<Pooled>,
+    )>,
+) {
+    let delta = time.delta_secs();
+    let players: Vec<_> = players.iter().map(GlobalTransform::translation).collect();
+
+    counts.tracked = 0;
+
+    for (entity, mut cleanup, transform, sleeping, pooled) in tracked {
+        cleanup.age += delta;
+        cleanup.rest = if sleeping { cleanup.rest + delta } else { 0.0 };
+
+        let policy = cleanup.policy;
+        let nearest = players
+            .iter()
+            .map(|player| player.distance(transform.translation()))
+            .reduce(f32::min);
+
+        if policy.max_age.is_some_and(|max| cleanup.age > max) {
+            counts.aged_out += 1;
+        } else if let (Some(max), Some(nearest)) = (policy.max_distance, nearest)
+            && nearest > max
+        {
+            counts.out_of_range += 1;
+        } else if policy
+            .min_height
+            .is_some_and(|min| transform.translation().y < min)
+        {
+            counts.out_of_range += 1;
+        } else if policy.max_rest.is_some_and(|max| cleanup.rest > max) {
+            counts.at_rest += 1;
+        } else {
+            counts.tracked += 1;
+            continue;
+        }
+
+        if pooled {
+            release(&mut commands, entity);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn measure_cleanup(mut diagnostics: Diagnostics, counts: Res<CleanupCounts>) {
+    diagnostics.add_measurement(&CleanupCounts::TRACKED, || counts.tracked as f64);
+    diagnostics.add_measurement(&CleanupCounts::AGED_OUT, || counts.aged_out as f64);
+    diagnostics.add_measurement(&CleanupCounts::OUT_OF_RANGE, || counts.out_of_range as f64);
+    diagnostics.add_measurement(&CleanupCounts::AT_REST, || counts.at_rest as f64);
+}
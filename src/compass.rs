@@ -0,0 +1,201 @@
+//! A compass strip across the top of the screen.
+//!
+//! The strip shows the half of the horizon the player is facing, with cardinal and intercardinal
+//! markers, the current heading in degrees, and a marker at the bearing of every visible
+//! objective.
+
+use bevy::prelude::*;
+
+use crate::hud::HudTheme;
+use crate::minimap::MinimapIcon;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct CompassPlugin;
+
+impl Plugin for CompassPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_compass)
+            .add_systems(Update, update_compass);
+    }
+}
+
+/// Width of the strip in pixels
+const STRIP_WIDTH: f32 = 480.0;
+/// Degrees either side of the heading shown on the strip
+const HALF_FOV: f32 = 90.0;
+
+#[derive(Component)]
+struct CompassStrip;
+
+/// A cardinal or intercardinal marker at a fixed bearing.
+#[derive(Component)]
+struct CompassMark(f32);
+
+/// An objective marker, pooled and handed to whichever objectives are in view each frame.
+#[derive(Component)]
+struct CompassObjective;
+
+#[derive(Component)]
+struct CompassHeading;
+
+/// Degrees clockwise from north (-Z) of a direction, from 0 to 360
+fn bearing(direction: Vec3) -> f32 {
+    direction
+        .x
+        .atan2(-direction.z)
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// How far along the strip `bearing` sits when facing `heading`, or `None` if it is off the strip
+fn strip_offset(heading: f32, bearing: f32) -> Option<f32> {
+    let relative = (bearing - heading + 180.0).rem_euclid(360.0) - 180.0;
+
+    (relative.abs() <= HALF_FOV).then(|| (relative / HALF_FOV * 0.5 + 0.5) * STRIP_WIDTH)
+}
+
+fn setup_compass(mut commands: Commands, theme: Res<HudTheme>) {
+    const MARKS: [(&str, f32); 8] = [
+        ("N", 0.0),
+        ("NE", 45.0),
+        ("E", 90.0),
+        ("SE", 135.0),
+        ("S", 180.0),
+        ("SW", 225.0),
+        ("W", 270.0),
+        ("NW", 315.0),
+    ];
+
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(STRIP_WIDTH),
+                        height: Val::Px(36.0),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    BackgroundColor(theme.panel),
+                    CompassStrip,
+                ))
+                .with_children(|strip| {
+                    for (label, bearing) in MARKS {
+                        let is_cardinal = label.len() == 1;
+
+                        strip.spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                top: Val::Px(2.0),
+                                ..default()
+                            },
+                            Text::new(label),
+                            TextFont::from_font_size(if is_cardinal {
+                                theme.font_size
+                            } else {
+                                theme.small_font_size
+                            }),
+                            TextColor(if is_cardinal {
+                                theme.text
+                            } else {
+                                theme.dim_text
+                            }),
+                            CompassMark(bearing),
+                        ));
+                    }
+
+                    strip.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            bottom: Val::Px(0.0),
+                            left: Val::Px(STRIP_WIDTH / 2.0 - 14.0),
+                            width: Val::Px(28.0),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        Text::default(),
+                        TextFont::from_font_size(theme.small_font_size - 4.0),
+                        TextColor(theme.dim_text),
+                        CompassHeading,
+                    ));
+                });
+        });
+}
+
+fn update_compass(
+    mut commands: Commands,
+    theme: Res<HudTheme>,
+    player: Single<&GlobalTransform, With<PrimaryPlayer>>,
+    strip: Single<Entity, With<CompassStrip>>,
+    mut marks: Query<(&CompassMark, &mut Node, &mut Visibility, &ComputedNode)>,
+    mut objective_markers: Query<
+        (&mut Node, &mut Visibility),
+        (With<CompassObjective>, Without<CompassMark>),
+    >,
+    mut heading_text: Single<&mut Text, With<CompassHeading>>,
+    icons: Query<(&GlobalTransform, &MinimapIcon, &Visibility), Without<Node>>,
+) {
+    const MARKER_WIDTH: f32 = 4.0;
+
+    let heading = bearing(*player.forward());
+    heading_text.0 = format!("{:03.0}", heading.round() % 360.0);
+
+    for (mark, mut node, mut visibility, computed) in &mut marks {
+        let width = computed.size().x * computed.inverse_scale_factor();
+
+        match strip_offset(heading, mark.0) {
+            Some(offset) => {
+                node.left = Val::Px(offset - width / 2.0);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    let offsets = icons
+        .iter()
+        .filter(|(_, icon, visibility)| {
+            **icon == MinimapIcon::Objective && **visibility != Visibility::Hidden
+        })
+        .filter_map(|(transform, ..)| {
+            let direction = transform.translation() - player.translation();
+            strip_offset(heading, bearing(direction))
+        });
+
+    let mut markers = objective_markers.iter_mut();
+
+    for offset in offsets {
+        let left = Val::Px(offset - MARKER_WIDTH / 2.0);
+
+        if let Some((mut node, mut visibility)) = markers.next() {
+            node.left = left;
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+
+        // grow the pool when there are more objectives in view than markers
+        commands.entity(*strip).with_child((
+            Node {
+                position_type: PositionType::Absolute,
+                left,
+                bottom: Val::Px(0.0),
+                width: Val::Px(MARKER_WIDTH),
+                height: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(theme.objective),
+            CompassObjective,
+        ));
+    }
+
+    for (_, mut visibility) in markers {
+        *visibility = Visibility::Hidden;
+    }
+}
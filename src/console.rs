@@ -0,0 +1,231 @@
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ConsoleCommand>()
+            .add_message::<ConsoleOutput>()
+            .init_resource::<Console>()
+            .init_resource::<ConsoleRegistry>()
+            .add_console_command("help", "list the available commands")
+            .add_systems(Startup, setup_console)
+            .add_systems(
+                Update,
+                (
+                    console_input,
+                    console_help,
+                    console_output,
+                    update_console_text,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// A line submitted through the developer console, split into a command name and arguments.
+#[derive(Message, Debug, Clone)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace().map(str::to_owned);
+        let name = words.next()?.to_lowercase();
+
+        Some(Self {
+            name,
+            args: words.collect(),
+        })
+    }
+
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name
+    }
+
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(String::as_str)
+    }
+}
+
+/// A line of text to print in the console, sent by command handlers to report back.
+#[derive(Message, Debug, Clone)]
+pub struct ConsoleOutput(pub String);
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+}
+
+impl Console {
+    const MAX_HISTORY: usize = 12;
+
+    fn print(&mut self, line: impl Into<String>) {
+        self.history.push(line.into());
+
+        if self.history.len() > Self::MAX_HISTORY {
+            let excess = self.history.len() - Self::MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+/// The commands other plugins have registered, used for `help` and unknown command reporting.
+#[derive(Resource, Default)]
+struct ConsoleRegistry(Vec<(&'static str, &'static str)>);
+
+pub trait ConsoleAppExt {
+    /// Register a command name so the console knows it is handled by some system reading
+    /// [`ConsoleCommand`]s.
+    fn add_console_command(&mut self, name: &'static str, help: &'static str) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command(&mut self, name: &'static str, help: &'static str) -> &mut Self {
+        self.init_resource::<ConsoleRegistry>();
+        self.world_mut()
+            .resource_mut::<ConsoleRegistry>()
+            .0
+            .push((name, help));
+        self
+    }
+}
+
+/// Run condition for gameplay input that shouldn't fire while typing into the console.
+pub fn console_closed(console: Res<Console>) -> bool {
+    !console.open
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn setup_console(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.75)),
+            Visibility::Hidden,
+            GlobalZIndex(100),
+            ConsoleRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::default(),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 1.0, 0.8)),
+                ConsoleText,
+            ));
+        });
+}
+
+fn console_input(
+    mut console: ResMut<Console>,
+    mut keyboard_reader: MessageReader<KeyboardInput>,
+    mut command_writer: MessageWriter<ConsoleCommand>,
+    registry: Res<ConsoleRegistry>,
+    mut root: Single<&mut Visibility, With<ConsoleRoot>>,
+) {
+    for event in keyboard_reader.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if event.key_code == KeyCode::Backquote {
+            console.open = !console.open;
+            **root = if console.open {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+            continue;
+        }
+
+        if !console.open {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.print(format!("> {line}"));
+
+                let Some(command) = ConsoleCommand::parse(&line) else {
+                    continue;
+                };
+
+                if !registry.0.iter().any(|(name, _)| *name == command.name) {
+                    console.print(format!("unknown command '{}'", command.name));
+                    continue;
+                }
+
+                command_writer.write(command);
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+}
+
+fn console_help(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    registry: Res<ConsoleRegistry>,
+) {
+    for command in command_reader.read() {
+        if !command.is("help") {
+            continue;
+        }
+
+        for (name, help) in &registry.0 {
+            output_writer.write(ConsoleOutput(format!("{name} - {help}")));
+        }
+    }
+}
+
+fn console_output(mut console: ResMut<Console>, mut output_reader: MessageReader<ConsoleOutput>) {
+    for output in output_reader.read() {
+        info!(target: "console", "{}", output.0);
+        console.print(output.0.clone());
+    }
+}
+
+fn update_console_text(console: Res<Console>, mut text: Single<&mut Text, With<ConsoleText>>) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let mut lines = console.history.join("\n");
+    if !lines.is_empty() {
+        lines.push('\n');
+    }
+    lines.push_str(&format!("> {}_", console.input));
+
+    text.0 = lines;
+}
@@ -0,0 +1,199 @@
+//! Session reports for when the game panics.
+//!
+//! The last [`SessionLog::EVENTS`] gameplay events and a snapshot of the session, refreshed every
+//! [`SNAPSHOT_INTERVAL`] seconds, are kept to hand. If anything panics they're written to a
+//! report in `crashes/` along with the panic and a backtrace, so a crash deep in something like
+//! the breath sampling still leaves enough behind to reproduce it from. The player is told where to
+//! find it the next time the game starts, as the panicking thread could be any thread and in any
+//! state, which is no place to be putting up a dialog.
+
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write as _};
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::damage::DamageEvent;
+use crate::difficulty::Difficulty;
+use crate::energy::Stamina;
+use crate::level::{Level, RunFinished};
+use crate::menu::GameState;
+use crate::settings::GameSettings;
+use crate::weapon::ReloadStarted;
+use crate::{Breath, BreathPhaseChanged, Player};
+
+const CRASH_DIRECTORY: &str = "crashes";
+/// Holds the path of a report the player hasn't been told about yet
+const UNSEEN_REPORT: &str = "crashes/unseen";
+/// Seconds between snapshots of the session
+const SNAPSHOT_INTERVAL: f32 = 0.5;
+
+static SESSION: Mutex<SessionLog> = Mutex::new(SessionLog::new());
+
+pub struct CrashReportPlugin;
+
+impl Plugin for CrashReportPlugin {
+    fn build(&self, app: &mut App) {
+        // plugins are built on the main thread, where dialogs need to be shown from
+        show_unseen_report();
+
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            report_crash(info);
+        }));
+
+        app.add_systems(
+            Update,
+            (
+                record_messages::<StateTransitionEvent<GameState>>,
+                record_messages::<DamageEvent>,
+                record_messages::<BreathPhaseChanged>,
+                record_messages::<ReloadStarted>,
+                record_messages::<RunFinished>,
+                snapshot_session,
+            ),
+        );
+    }
+}
+
+/// What's happened recently, for the report.
+struct SessionLog {
+    /// Most recent last
+    events: VecDeque<String>,
+    snapshot: String,
+}
+
+impl SessionLog {
+    const EVENTS: usize = 64;
+
+    const fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+            snapshot: String::new(),
+        }
+    }
+
+    fn record(&mut self, event: String) {
+        if self.events.len() == Self::EVENTS {
+            self.events.pop_front();
+        }
+
+        self.events.push_back(event);
+    }
+}
+
+fn record_messages<M: Message + Debug>(time: Res<Time>, mut reader: MessageReader<M>) {
+    // a panic elsewhere while this was held is no reason to stop recording
+    let mut session = SESSION
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    for message in reader.read() {
+        session.record(format!("[{:.2}] {message:?}", time.elapsed_secs()));
+    }
+}
+
+fn snapshot_session(
+    time: Res<Time>,
+    mut timer: Local<Option<Timer>>,
+    state: Res<State<GameState>>,
+    level: Res<Level>,
+    difficulty: Res<Difficulty>,
+    settings: Res<GameSettings>,
+    players: Query<(Entity, &Transform, &Breath, Option<&Stamina>), With<Player>>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(SNAPSHOT_INTERVAL, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut snapshot = format!(
+        "elapsed: {:.2}s\nstate: {:?}\nlevel: {}\ndifficulty: {:?}\nsettings: {:?}\n",
+        time.elapsed_secs(),
+        state.get(),
+        *level,
+        *difficulty,
+        *settings,
+    );
+
+    for (player, transform, breath, stamina) in players {
+        let _ = writeln!(
+            snapshot,
+            "player {player} at {}\n  {breath:?}\n  {stamina:?}",
+            transform.translation
+        );
+    }
+
+    SESSION
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .snapshot = snapshot;
+}
+
+/// Writes the report, to be pointed out by `show_unseen_report` on the next launch.
+fn report_crash(info: &PanicHookInfo) {
+    let mut report = format!("{info}\n\n{}\n", Backtrace::force_capture());
+
+    // whatever panicked may have been holding the log, so don't wait on it
+    match SESSION.try_lock() {
+        Ok(session) => {
+            let _ = write!(report, "\nsession:\n{}\nrecent events:\n", session.snapshot);
+            for event in &session.events {
+                let _ = writeln!(report, "{event}");
+            }
+        }
+        Err(_) => report.push_str("\nthe session log was unavailable\n"),
+    }
+
+    let saved = write_report(&report).and_then(|path| {
+        std::fs::write(UNSEEN_REPORT, path.to_string_lossy().as_bytes())?;
+        Ok(path)
+    });
+
+    match saved {
+        Ok(path) => error!("saved a crash report to {}", path.display()),
+        Err(err) => error!("could not save a crash report: {err}"),
+    }
+}
+
+/// Lets the player know where the report from the last crash went, if they haven't been told.
+fn show_unseen_report() {
+    let Ok(path) = std::fs::read_to_string(UNSEEN_REPORT) else {
+        return;
+    };
+
+    // cleared before it's shown, so quitting from the dialog doesn't bring it back next time
+    if let Err(err) = std::fs::remove_file(UNSEEN_REPORT) {
+        warn!("could not clear {UNSEEN_REPORT}: {err}");
+    }
+
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("Energy crashed")
+        .set_description(format!(
+            "Something went wrong and the game had to close last time. Sorry about that.\n\n\
+             A report was saved to {path}. Attaching it to a bug report helps get this fixed."
+        ))
+        .set_buttons(rfd::MessageButtons::Ok)
+        .show();
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(CRASH_DIRECTORY)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    let path = PathBuf::from(CRASH_DIRECTORY).join(format!("crash-{now}.txt"));
+    std::fs::write(&path, report)?;
+
+    Ok(path)
+}
@@ -0,0 +1,226 @@
+//! The daily challenge.
+//!
+//! [`GameMode::Daily`] is a time trial on a generated level that's the same for everyone on the
+//! same (UTC) day. The [`DailyChallenge`] for the date picks the seed, and so the obstacles and
+//! targets, along with the weather, a climate laid over the whole level.
+//!
+//! Runs are played at [`Difficulty::Standard`] with the player tuning as shipped and untrained
+//! [`Attributes`], whatever the profile says, and the profile's own are put back afterwards. They
+//! go on a leaderboard of their own in `saves/daily_leaderboard.ron`, so a daily time is only ever
+//! compared with runs on the same conditions.
+
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::attributes::Attributes;
+use crate::difficulty::Difficulty;
+use crate::environment::{Climate, EnvironmentZone};
+use crate::leaderboard::Leaderboard;
+use crate::level::{GameMode, Level, RunFinished};
+use crate::menu::GameState;
+use crate::profile::ActiveProfile;
+use crate::settings::GameSettings;
+use crate::split_screen::PrimaryPlayer;
+use crate::tuning::{PlayerTuning, Tuning};
+
+const DAILY_LEADERBOARD_PATH: &str = "saves/daily_leaderboard.ron";
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyLeaderboard(Leaderboard::load(Path::new(
+            DAILY_LEADERBOARD_PATH,
+        ))))
+        .add_systems(
+            Update,
+            (
+                pick_daily_level
+                    .run_if(resource_changed::<GameMode>.or(resource_changed::<Level>))
+                    .run_if(is_daily),
+                record_daily_runs,
+            ),
+        )
+        .add_systems(
+            OnEnter(GameState::Loading),
+            start_daily_run.run_if(is_daily),
+        )
+        .add_systems(
+            OnExit(GameState::InGame),
+            end_daily_run.run_if(resource_exists::<DailyRun>),
+        );
+    }
+}
+
+/// Everything about a day's challenge that comes from the date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyChallenge {
+    /// Days since 1970-01-01
+    pub day: u64,
+    /// What the level is generated from
+    pub seed: u64,
+    pub climate: Climate,
+}
+
+impl DailyChallenge {
+    /// How far the weather reaches from the middle of the level, past its border
+    const WEATHER_EXTENT: f32 = 60.0;
+
+    pub fn for_day(day: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(day);
+
+        Self {
+            day,
+            seed: rng.random(),
+            climate: [Climate::Temperate, Climate::Cold, Climate::Hot][rng.random_range(0..3)],
+        }
+    }
+
+    /// Today's challenge, going by the clock in UTC
+    pub fn today() -> Self {
+        // the system clock isn't there to read in a browser, so ask the page
+        #[cfg(target_arch = "wasm32")]
+        let seconds = (web_sys::js_sys::Date::now() / 1000.0) as u64;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self::for_day(seconds / (24 * 60 * 60))
+    }
+
+    pub fn level(&self) -> Level {
+        Level::Generated { seed: self.seed }
+    }
+}
+
+/// Finished daily runs, kept apart from the other modes' [`Leaderboard`].
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct DailyLeaderboard(pub Leaderboard);
+
+/// A daily run being played, holding what it replaced to put back afterwards.
+#[derive(Resource, Debug)]
+pub struct DailyRun {
+    difficulty: Difficulty,
+    settings: GameSettings,
+    tuning: Handle<Tuning>,
+    attributes: Option<Attributes>,
+}
+
+fn is_daily(mode: Res<GameMode>) -> bool {
+    *mode == GameMode::Daily
+}
+
+/// Keeps the level on today's while the daily challenge is picked.
+fn pick_daily_level(mut level: ResMut<Level>) {
+    level.set_if_neq(DailyChallenge::today().level());
+}
+
+fn start_daily_run(
+    mut commands: Commands,
+    mut difficulty: ResMut<Difficulty>,
+    settings: Res<GameSettings>,
+    mut player_tuning: ResMut<PlayerTuning>,
+    mut tunings: ResMut<Assets<Tuning>>,
+    player: Option<Single<(Entity, Option<&Attributes>), With<PrimaryPlayer>>>,
+) {
+    let challenge = DailyChallenge::today();
+    let (player, attributes) = player.map(|player| *player).unzip();
+
+    info!(
+        "daily challenge for day {}: seed {}, {:?}",
+        challenge.day, challenge.seed, challenge.climate
+    );
+
+    commands.insert_resource(DailyRun {
+        difficulty: *difficulty,
+        settings: settings.clone(),
+        tuning: std::mem::replace(&mut player_tuning.0, tunings.add(Tuning::standard())),
+        attributes: attributes.flatten().cloned(),
+    });
+
+    difficulty.set_if_neq(Difficulty::Standard);
+    if let Some(player) = player {
+        commands.entity(player).insert(Attributes::default());
+    }
+
+    commands.spawn((
+        EnvironmentZone {
+            climate: challenge.climate,
+            half_extents: Vec3::splat(DailyChallenge::WEATHER_EXTENT),
+        },
+        DespawnOnExit(GameState::InGame),
+    ));
+}
+
+fn end_daily_run(
+    mut commands: Commands,
+    run: Res<DailyRun>,
+    mut difficulty: ResMut<Difficulty>,
+    mut settings: ResMut<GameSettings>,
+    mut player_tuning: ResMut<PlayerTuning>,
+    player: Option<Single<Entity, With<PrimaryPlayer>>>,
+) {
+    // everything else is put back even with no player to give their attributes back to
+    commands.remove_resource::<DailyRun>();
+
+    // the settings already reflect the difficulty, as when a profile is applied
+    *difficulty.bypass_change_detection() = run.difficulty;
+    *settings = run.settings.clone();
+    player_tuning.0 = run.tuning.clone();
+
+    let Some(player) = player else {
+        return;
+    };
+
+    match &run.attributes {
+        Some(attributes) => commands.entity(*player).insert(attributes.clone()),
+        None => commands.entity(*player).remove::<Attributes>(),
+    };
+}
+
+fn record_daily_runs(
+    mut finished_reader: MessageReader<RunFinished>,
+    mut leaderboard: ResMut<DailyLeaderboard>,
+    profile: Option<Res<ActiveProfile>>,
+) {
+    for run in finished_reader.read() {
+        if run.mode != GameMode::Daily {
+            continue;
+        }
+
+        leaderboard.record(
+            run,
+            profile.as_deref().map(|profile| &profile.0),
+            Path::new(DAILY_LEADERBOARD_PATH),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_challenges_follow_the_date() {
+        let today = DailyChallenge::for_day(20_000);
+
+        assert_eq!(
+            today,
+            DailyChallenge::for_day(20_000),
+            "the same for everyone"
+        );
+        assert_ne!(today.seed, DailyChallenge::for_day(20_001).seed);
+        assert_eq!(
+            today.level(),
+            crate::level::Level::Generated { seed: today.seed }
+        );
+        assert!(crate::level::GameMode::Daily.is_timed());
+    }
+}
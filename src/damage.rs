@@ -0,0 +1,618 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::Player;
+use crate::difficulty::{Difficulty, DifficultyPreset};
+use crate::particles::{ParticleEffect, SpawnParticles};
+use crate::scene::SpawnPoint;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<DamageEvent>()
+            .add_message::<HitEvent>()
+            .add_systems(
+                Update,
+                (
+                    projectile_hits,
+                    hitscan_hits,
+                    blast_hits,
+                    log_damage,
+                    despawn_dead,
+                    respawn_players,
+                    regenerate_health,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Hit points of anything that can be damaged and killed.
+#[derive(Component, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// How lost [`Health`] comes back once a body stops taking damage.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RegenSettings {
+    /// Seconds without taking damage before health starts coming back
+    pub delay: f32,
+    /// Health regained per second
+    pub rate: f32,
+    /// Health is split into segments this big, and only a partly emptied segment refills.
+    /// Damage that empties a whole segment is permanent.
+    pub segment: f32,
+}
+
+impl RegenSettings {
+    pub const NONE: Self = Self {
+        delay: 0.0,
+        rate: 0.0,
+        segment: 0.0,
+    };
+
+    /// The most health can regenerate to from `current`
+    pub fn cap(&self, current: f32, max: f32) -> f32 {
+        if self.rate <= 0.0 || self.segment <= 0.0 || current <= 0.0 {
+            return current;
+        }
+
+        ((current / self.segment).ceil() * self.segment).min(max)
+    }
+}
+
+impl Default for RegenSettings {
+    fn default() -> Self {
+        Self {
+            delay: 5.0,
+            rate: 10.0,
+            segment: 25.0,
+        }
+    }
+}
+
+/// Regenerates the body's [`Health`] with its own settings, unless the [`Difficulty`] overrides
+/// them.
+#[derive(Component, Debug)]
+pub struct HealthRegen {
+    pub settings: RegenSettings,
+    since_damage: f32,
+    last_health: f32,
+}
+
+impl HealthRegen {
+    pub fn new(settings: RegenSettings) -> Self {
+        Self {
+            settings,
+            since_damage: 0.0,
+            last_health: 0.0,
+        }
+    }
+
+    /// The settings in use on this difficulty
+    pub fn effective(&self, difficulty: &Difficulty) -> RegenSettings {
+        difficulty.preset().health_regen.unwrap_or(self.settings)
+    }
+}
+
+/// Where on a body a hit landed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitZone {
+    Body,
+    Head,
+}
+
+impl HitZone {
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            HitZone::Body => 1.0,
+            HitZone::Head => 2.5,
+        }
+    }
+}
+
+/// A collider that can take damage on behalf of the body it is attached to.
+///
+/// The [`Health`] lives on the rigid body, so a body can have several hitboxes (head, torso) as
+/// child colliders.
+#[derive(Component, Debug)]
+pub struct Hitbox(pub HitZone);
+
+/// Plating on a hitbox that soaks up a share of incoming damage until its durability runs out.
+#[derive(Component, Debug)]
+pub struct Armor {
+    pub durability: f32,
+    pub absorption: f32,
+}
+
+impl Armor {
+    pub fn new(durability: f32, absorption: f32) -> Self {
+        Self {
+            durability,
+            absorption: absorption.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Absorb part of the damage, returning what gets through to the body
+    fn absorb(&mut self, damage: f32) -> f32 {
+        let absorbed = (damage * self.absorption).min(self.durability);
+        self.durability -= absorbed;
+        damage - absorbed
+    }
+}
+
+/// A round in flight that deals damage to the first hitbox it touches.
+#[derive(Component, Debug)]
+pub struct Projectile {
+    pub damage: f32,
+    pub shooter: Entity,
+}
+
+/// A round that bursts on the first thing it touches, hurting every body within `radius` metres
+/// less the further out it is. The shooter's own blasts don't hurt them.
+#[derive(Component, Debug)]
+pub struct Blast {
+    pub damage: f32,
+    pub radius: f32,
+    pub shooter: Entity,
+}
+
+/// Marker for bodies whose [`Health`] has run out.
+#[derive(Component)]
+pub struct Dead;
+
+/// An event sent whenever a hitbox takes damage.
+#[derive(Message, Debug, Clone)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: f32,
+    pub point: Vec3,
+    pub zone: HitZone,
+    /// The hit was (at least partially) stopped by [`Armor`]
+    pub armor_hit: bool,
+    /// This hit took the target's [`Health`] to zero
+    pub killed: bool,
+}
+
+/// An event sent when a hitscan round strikes a collider, dealt with like a [`Projectile`]
+/// touching it by `hitscan_hits`.
+#[derive(Message, Debug, Clone)]
+pub struct HitEvent {
+    /// The collider struck
+    pub entity: Entity,
+    pub point: Vec3,
+    /// Of the surface struck, at `point`
+    pub normal: Vec3,
+    pub shooter: Entity,
+    pub damage: f32,
+    /// The momentum handed to the body struck, as a physical round hands over its own on contact
+    pub impulse: Vec3,
+}
+
+/// A round landing on a hitbox.
+struct Hit {
+    /// The body the hitbox belongs to
+    body: Entity,
+    zone: HitZone,
+    shooter: Entity,
+    damage: f32,
+    point: Vec3,
+}
+
+/// Deals a hit's damage to `health`, scaled by where it landed and the difficulty and less
+/// whatever `armor` soaks up.
+fn hurt(
+    commands: &mut Commands,
+    damage_writer: &mut MessageWriter<DamageEvent>,
+    preset: &DifficultyPreset,
+    players: &Query<(), With<Player>>,
+    hit: Hit,
+    health: &mut Health,
+    armor: Option<Mut<Armor>>,
+) {
+    let mut amount = hit.damage * hit.zone.multiplier();
+
+    if players.contains(hit.shooter) {
+        amount *= preset.damage_dealt;
+    }
+
+    if players.contains(hit.body) {
+        amount *= preset.damage_taken;
+    }
+    let mut armor_hit = false;
+
+    if let Some(mut armor) = armor
+        && armor.durability > 0.0
+    {
+        amount = armor.absorb(amount);
+        armor_hit = true;
+    }
+
+    health.current = (health.current - amount).max(0.0);
+    let killed = health.is_dead();
+
+    debug!("{} health {}/{}", hit.body, health.current, health.max);
+
+    if killed {
+        commands.entity(hit.body).insert(Dead);
+    }
+
+    damage_writer.write(DamageEvent {
+        target: hit.body,
+        source: hit.shooter,
+        amount,
+        point: hit.point,
+        zone: hit.zone,
+        armor_hit,
+        killed,
+    });
+}
+
+fn projectile_hits(
+    mut commands: Commands,
+    mut collisions: MessageReader<CollisionStart>,
+    mut damage_writer: MessageWriter<DamageEvent>,
+    projectiles: Query<(&Projectile, &Transform)>,
+    mut hitboxes: Query<(&Hitbox, &ColliderOf, Option<&mut Armor>)>,
+    mut bodies: Query<&mut Health, Without<Dead>>,
+    players: Query<(), With<Player>>,
+    difficulty: Res<Difficulty>,
+) {
+    let preset = difficulty.preset();
+
+    for collision in collisions.read() {
+        let pair = [
+            (collision.collider1, collision.collider2),
+            (collision.collider2, collision.collider1),
+        ];
+
+        for (projectile_entity, hitbox_entity) in pair {
+            let Ok((projectile, projectile_transform)) = projectiles.get(projectile_entity) else {
+                continue;
+            };
+
+            let Ok((hitbox, collider_of, armor)) = hitboxes.get_mut(hitbox_entity) else {
+                continue;
+            };
+
+            // rounds leave through the shooter's own hitboxes
+            if collider_of.body == projectile.shooter {
+                continue;
+            }
+
+            let Ok(mut health) = bodies.get_mut(collider_of.body) else {
+                continue;
+            };
+
+            hurt(
+                &mut commands,
+                &mut damage_writer,
+                &preset,
+                &players,
+                Hit {
+                    body: collider_of.body,
+                    zone: hitbox.0,
+                    shooter: projectile.shooter,
+                    damage: projectile.damage,
+                    point: projectile_transform.translation,
+                },
+                &mut health,
+                armor,
+            );
+
+            // spent rounds stay in the world as plain physics bodies, until `cleanup` clears them
+            commands.entity(projectile_entity).remove::<Projectile>();
+        }
+    }
+}
+
+/// Deals damage and knockback for hitscan rounds, which have already found what they hit.
+fn hitscan_hits(
+    mut commands: Commands,
+    mut hit_reader: MessageReader<HitEvent>,
+    mut damage_writer: MessageWriter<DamageEvent>,
+    mut particle_writer: MessageWriter<SpawnParticles>,
+    colliders: Query<&ColliderOf>,
+    mut hitboxes: Query<(&Hitbox, Option<&mut Armor>)>,
+    mut bodies: Query<&mut Health, Without<Dead>>,
+    mut rigid_bodies: Query<(&RigidBody, Forces)>,
+    players: Query<(), With<Player>>,
+    difficulty: Res<Difficulty>,
+) {
+    let preset = difficulty.preset();
+
+    for hit in hit_reader.read() {
+        // with no round to see, a puff off the surface shows where it landed
+        particle_writer.write(SpawnParticles {
+            effect: ParticleEffect::Dust,
+            position: hit.point,
+            direction: hit.normal,
+            count: 4,
+        });
+
+        let Ok(collider_of) = colliders.get(hit.entity) else {
+            continue;
+        };
+
+        if let Ok((rigid_body, mut forces)) = rigid_bodies.get_mut(collider_of.body)
+            && *rigid_body == RigidBody::Dynamic
+        {
+            forces.apply_linear_impulse_at_point(hit.impulse, hit.point);
+        }
+
+        let (Ok((hitbox, armor)), Ok(mut health)) = (
+            hitboxes.get_mut(hit.entity),
+            bodies.get_mut(collider_of.body),
+        ) else {
+            continue;
+        };
+
+        hurt(
+            &mut commands,
+            &mut damage_writer,
+            &preset,
+            &players,
+            Hit {
+                body: collider_of.body,
+                zone: hitbox.0,
+                shooter: hit.shooter,
+                damage: hit.damage,
+                point: hit.point,
+            },
+            &mut health,
+            armor,
+        );
+    }
+}
+
+fn blast_hits(
+    mut commands: Commands,
+    mut collisions: MessageReader<CollisionStart>,
+    mut damage_writer: MessageWriter<DamageEvent>,
+    blasts: Query<(&Blast, &Transform)>,
+    colliders: Query<&ColliderOf>,
+    mut bodies: Query<(Entity, &GlobalTransform, &mut Health), Without<Dead>>,
+    players: Query<(), With<Player>>,
+    difficulty: Res<Difficulty>,
+) {
+    let preset = difficulty.preset();
+    let mut burst = Vec::new();
+
+    for collision in collisions.read() {
+        let pair = [
+            (collision.collider1, collision.collider2),
+            (collision.collider2, collision.collider1),
+        ];
+
+        for (round, other) in pair {
+            let Ok((blast, transform)) = blasts.get(round) else {
+                continue;
+            };
+
+            // clearing the shooter on the way out of the barrel
+            if burst.contains(&round)
+                || colliders
+                    .get(other)
+                    .is_ok_and(|collider_of| collider_of.body == blast.shooter)
+            {
+                continue;
+            }
+
+            burst.push(round);
+            commands.entity(round).try_despawn();
+
+            let point = transform.translation;
+
+            for (body, body_transform, mut health) in &mut bodies {
+                let distance = body_transform.translation().distance(point);
+
+                if body == blast.shooter || distance >= blast.radius {
+                    continue;
+                }
+
+                let mut amount = blast.damage * (1.0 - distance / blast.radius);
+
+                if players.contains(blast.shooter) {
+                    amount *= preset.damage_dealt;
+                }
+
+                if players.contains(body) {
+                    amount *= preset.damage_taken;
+                }
+
+                health.current = (health.current - amount).max(0.0);
+                let killed = health.is_dead();
+
+                if killed {
+                    commands.entity(body).insert(Dead);
+                }
+
+                damage_writer.write(DamageEvent {
+                    target: body,
+                    source: blast.shooter,
+                    amount,
+                    point,
+                    zone: HitZone::Body,
+                    armor_hit: false,
+                    killed,
+                });
+            }
+        }
+    }
+}
+
+fn log_damage(mut damage_reader: MessageReader<DamageEvent>) {
+    for event in damage_reader.read() {
+        debug!(
+            "{} hit {} for {:.1} ({:?}) at {}",
+            event.source, event.target, event.amount, event.zone, event.point
+        );
+    }
+}
+
+fn despawn_dead(mut commands: Commands, dead_q: Query<Entity, (Added<Dead>, Without<Player>)>) {
+    for entity in dead_q {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Players who die go back to the start with full health, rather than being despawned.
+fn respawn_players(
+    mut commands: Commands,
+    spawn_points: Query<&Transform, (With<SpawnPoint>, Without<Player>)>,
+    players: Query<
+        (Entity, &mut Transform, &mut LinearVelocity, &mut Health),
+        (Added<Dead>, With<Player>),
+    >,
+) {
+    for (player, mut transform, mut velocity, mut health) in players {
+        let start = spawn_points
+            .iter()
+            .next()
+            .map_or(Vec3::new(0.5, 0.5, 0.5), |point| point.translation);
+
+        transform.translation = start + Vec3::Y;
+        velocity.0 = Vec3::ZERO;
+        health.current = health.max;
+        commands.entity(player).remove::<Dead>();
+        info!("{player} died and respawned");
+    }
+}
+
+fn regenerate_health(
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
+    bodies: Query<(&mut Health, &mut HealthRegen), Without<Dead>>,
+) {
+    for (mut health, mut regen) in bodies {
+        // anything that lowered health counts as damage, whatever caused it
+        if health.current < regen.last_health {
+            regen.since_damage = 0.0;
+        } else {
+            regen.since_damage += time.delta_secs();
+        }
+
+        let settings = regen.effective(&difficulty);
+        let cap = settings.cap(health.current, health.max);
+
+        if regen.since_damage >= settings.delay && health.current < cap {
+            health.current = (health.current + settings.rate * time.delta_secs()).min(cap);
+        }
+
+        regen.last_health = health.current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_players_respawn_at_the_start() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_systems(Update, respawn_players);
+
+        app.world_mut()
+            .spawn((SpawnPoint, Transform::from_xyz(4.0, 0.0, -2.0)));
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                Dead,
+                Transform::from_xyz(30.0, 1.0, 30.0),
+                LinearVelocity(Vec3::X * 5.0),
+                Health {
+                    current: 0.0,
+                    max: 100.0,
+                },
+            ))
+            .id();
+        app.update();
+
+        let player = app.world().entity(player);
+        assert!(!player.contains::<Dead>());
+        assert_eq!(player.get::<Health>().unwrap().current, 100.0);
+        assert_eq!(
+            player.get::<Transform>().unwrap().translation,
+            Vec3::new(4.0, 1.0, -2.0)
+        );
+        assert_eq!(player.get::<LinearVelocity>().unwrap().0, Vec3::ZERO);
+    }
+
+    #[test]
+    fn hitscan_hits_hurt_the_body_behind_the_hitbox() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Difficulty>()
+            .add_message::<HitEvent>()
+            .add_message::<DamageEvent>()
+            .add_message::<SpawnParticles>()
+            .add_systems(Update, hitscan_hits);
+
+        let shooter = app.world_mut().spawn_empty().id();
+        let target = app.world_mut().spawn(Health::new(100.0)).id();
+        let head = app
+            .world_mut()
+            .spawn((Hitbox(HitZone::Head), ColliderOf { body: target }))
+            .id();
+
+        app.world_mut().write_message(HitEvent {
+            entity: head,
+            point: Vec3::new(0.0, 1.7, -20.0),
+            normal: Vec3::Z,
+            shooter,
+            damage: 10.0,
+            impulse: Vec3::NEG_Z,
+        });
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(target).unwrap().current, 75.0);
+
+        let damage = app
+            .world_mut()
+            .resource_mut::<Messages<DamageEvent>>()
+            .drain()
+            .next()
+            .unwrap();
+        assert_eq!(damage.target, target);
+        assert_eq!(damage.source, shooter);
+        assert_eq!(damage.zone, HitZone::Head);
+        assert!(!damage.killed);
+    }
+
+    #[test]
+    fn health_only_regenerates_the_damaged_segment() {
+        let settings = RegenSettings {
+            delay: 5.0,
+            rate: 10.0,
+            segment: 25.0,
+        };
+
+        assert_eq!(settings.cap(60.0, 100.0), 75.0);
+        assert_eq!(settings.cap(75.0, 100.0), 75.0);
+        assert_eq!(settings.cap(90.0, 95.0), 95.0);
+        assert_eq!(RegenSettings::NONE.cap(60.0, 100.0), 60.0);
+
+        let casual = crate::difficulty::Difficulty::Casual
+            .preset()
+            .health_regen
+            .unwrap();
+        assert_eq!(casual.cap(10.0, 100.0), 100.0);
+    }
+}
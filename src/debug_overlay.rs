@@ -0,0 +1,105 @@
+//! Live movement numbers for player one, under the FPS counter.
+//!
+//! Toggled with F4. Shows what the controller is actually working with after stamina, load and
+//! tuning have had their say, which is easier than reading it back out of the logs, along with
+//! how many spent rounds and the like are lying around waiting to be cleaned up, and how many
+//! are parked for reuse.
+
+use bevy::prelude::*;
+
+use crate::Player;
+use crate::cleanup::CleanupCounts;
+use crate::encumbrance::Encumbrance;
+use crate::energy::Stamina;
+use crate::hud::HudTheme;
+use crate::movement::JumpImpulse;
+use crate::projectile::ProjectilePool;
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_debug_overlay)
+            .add_systems(Update, (toggle_debug_overlay, update_debug_overlay).chain());
+    }
+}
+
+#[derive(Component)]
+struct DebugOverlay;
+
+fn setup_debug_overlay(mut commands: Commands, theme: Res<HudTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // clear of the FPS counter
+            top: Val::Px(32.0),
+            left: Val::Px(4.0),
+            ..default()
+        },
+        Text::default(),
+        TextFont::from_font_size(theme.small_font_size),
+        TextColor(theme.dim_text),
+        Visibility::Hidden,
+        DebugOverlay,
+    ));
+}
+
+fn toggle_debug_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut overlay: Single<&mut Visibility, With<DebugOverlay>>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        overlay.toggle_visible_hidden();
+    }
+}
+
+fn update_debug_overlay(
+    overlay: Single<(&mut Text, &Visibility), With<DebugOverlay>>,
+    players: Query<(&JumpImpulse, Option<&Stamina>, Option<&Encumbrance>), With<Player>>,
+    cleanup: Res<CleanupCounts>,
+    pool: Res<ProjectilePool>,
+) {
+    let (mut text, visibility) = overlay.into_inner();
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let Some((jump_impulse, stamina, encumbrance)) = players.iter().next() else {
+        text.set_if_neq(Text::new("no player"));
+        return;
+    };
+
+    let stamina_scale = stamina.map_or(1.0, Stamina::jump_scale);
+    let load_scale = encumbrance.map_or(1.0, Encumbrance::jump_scale);
+    let jump = jump_impulse.0 * stamina_scale * load_scale;
+
+    let stamina = stamina.map_or_else(
+        || "none".to_string(),
+        |stamina| {
+            format!(
+                "{:.0}/{:.0}{}",
+                stamina.current,
+                stamina.capacity(),
+                if stamina.is_exhausted() {
+                    " exhausted"
+                } else {
+                    ""
+                }
+            )
+        },
+    );
+
+    text.set_if_neq(Text(format!(
+        "stamina {stamina}\njump {jump:.1} m/s (stamina x{stamina_scale:.2}, load x{load_scale:.2})\n\
+         debris {} (cleared {}: {} aged, {} far, {} resting)\n\
+         round pool {} spare ({} reused)",
+        cleanup.tracked,
+        cleanup.despawned(),
+        cleanup.aged_out,
+        cleanup.out_of_range,
+        cleanup.at_rest,
+        pool.spare(),
+        pool.reused
+    )));
+}
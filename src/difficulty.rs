@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::PlayerCamera;
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::damage::{Hitbox, RegenSettings};
+use crate::settings::GameSettings;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Difficulty::from_args())
+            .init_resource::<AimAssist>()
+            .add_console_command("difficulty", "difficulty <casual|standard|hardcore>")
+            .add_systems(
+                Update,
+                (
+                    difficulty_command,
+                    apply_difficulty.run_if(resource_changed::<Difficulty>),
+                    aim_assist,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The selected difficulty preset.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Casual,
+    #[default]
+    Standard,
+    Hardcore,
+}
+
+/// The gameplay values a [`Difficulty`] scales.
+#[derive(Debug, Clone, Copy)]
+pub struct DifficultyPreset {
+    pub show_hud: bool,
+    pub hit_confirmation: bool,
+    /// Scales damage dealt by the player
+    pub damage_dealt: f32,
+    /// Scales damage dealt to the player
+    pub damage_taken: f32,
+    pub sway_scale: f32,
+    /// Seconds at the bottom of each exhale that an aimed weapon holds almost still
+    pub respiratory_pause: f32,
+    /// How much look sensitivity is reduced while the crosshair is over a hitbox (0 = off)
+    pub aim_assist: f32,
+    /// Replaces the player's tuned health regeneration
+    pub health_regen: Option<RegenSettings>,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [
+        Difficulty::Casual,
+        Difficulty::Standard,
+        Difficulty::Hardcore,
+    ];
+
+    pub fn preset(&self) -> DifficultyPreset {
+        match self {
+            Difficulty::Casual => DifficultyPreset {
+                show_hud: true,
+                hit_confirmation: true,
+                damage_dealt: 1.25,
+                damage_taken: 0.5,
+                sway_scale: 0.5,
+                respiratory_pause: 0.8,
+                aim_assist: 0.4,
+                health_regen: Some(RegenSettings {
+                    delay: 3.0,
+                    rate: 20.0,
+                    // a single segment, so everything comes back
+                    segment: f32::MAX,
+                }),
+            },
+            Difficulty::Standard => DifficultyPreset {
+                show_hud: true,
+                hit_confirmation: true,
+                damage_dealt: 1.0,
+                damage_taken: 1.0,
+                sway_scale: 1.0,
+                respiratory_pause: 0.5,
+                aim_assist: 0.0,
+                health_regen: None,
+            },
+            Difficulty::Hardcore => DifficultyPreset {
+                show_hud: false,
+                hit_confirmation: false,
+                damage_dealt: 1.0,
+                damage_taken: 2.0,
+                sway_scale: 1.5,
+                respiratory_pause: 0.3,
+                aim_assist: 0.0,
+                health_regen: Some(RegenSettings::NONE),
+            },
+        }
+    }
+
+    /// Read `--difficulty <preset>` from the command line, falling back to the default
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+
+        args.windows(2)
+            .find(|pair| pair[0] == "--difficulty")
+            .and_then(|pair| match pair[1].parse() {
+                Ok(difficulty) => Some(difficulty),
+                Err(err) => {
+                    warn!("{err}");
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "casual" => Ok(Difficulty::Casual),
+            "standard" => Ok(Difficulty::Standard),
+            "hardcore" => Ok(Difficulty::Hardcore),
+            other => Err(format!("unknown difficulty '{other}'")),
+        }
+    }
+}
+
+/// How much look input is currently being slowed by aim assist.
+#[derive(Resource, Default)]
+pub struct AimAssist {
+    pub friction: f32,
+}
+
+impl AimAssist {
+    pub fn scale(&self) -> f32 {
+        1.0 - self.friction
+    }
+}
+
+fn difficulty_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    for command in command_reader.read() {
+        if !command.is("difficulty") {
+            continue;
+        }
+
+        let Some(arg) = command.arg(0) else {
+            output_writer.write(ConsoleOutput(format!("difficulty: {:?}", *difficulty)));
+            continue;
+        };
+
+        match arg.parse() {
+            Ok(new_difficulty) => {
+                *difficulty = new_difficulty;
+                output_writer.write(ConsoleOutput(format!(
+                    "difficulty set to {new_difficulty:?}"
+                )));
+            }
+            Err(err) => {
+                output_writer.write(ConsoleOutput(err));
+            }
+        }
+    }
+}
+
+fn apply_difficulty(difficulty: Res<Difficulty>, mut settings: ResMut<GameSettings>) {
+    let preset = difficulty.preset();
+    settings.hardcore = !preset.hit_confirmation;
+    info!("difficulty: {:?}", *difficulty);
+}
+
+fn aim_assist(
+    difficulty: Res<Difficulty>,
+    mut assist: ResMut<AimAssist>,
+    spatial_query: SpatialQuery,
+    player: Single<(Entity, &Children), With<PrimaryPlayer>>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    hitboxes: Query<(), With<Hitbox>>,
+) {
+    const RANGE: f32 = 100.0;
+
+    let strength = difficulty.preset().aim_assist;
+
+    if strength <= 0.0 {
+        assist.friction = 0.0;
+        return;
+    }
+
+    let (player, children) = *player;
+    let Some(camera) = children.iter().find_map(|child| cameras.get(child).ok()) else {
+        return;
+    };
+
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player]);
+    let on_target = spatial_query
+        .cast_ray(camera.translation(), camera.forward(), RANGE, true, &filter)
+        .is_some_and(|hit| hitboxes.contains(hit.entity));
+
+    assist.friction = if on_target { strength } else { 0.0 };
+}
@@ -0,0 +1,152 @@
+//! Doppler shift for moving sounds.
+//!
+//! Anything playing a sound with a [`DopplerEmitter`] has its playback speed, and so its pitch,
+//! raised while it closes on the [`DopplerListener`] and lowered once it has gone past. Velocities
+//! are worked out from how far each entity moved since the last frame, so it works the same for
+//! physics bodies, kinematic vehicles and entities carried along by their parent.
+//!
+//! Rounds in flight whine as they go, so near misses are heard passing. The shift can be turned
+//! off in the settings menu.
+
+use bevy::prelude::*;
+
+use crate::damage::Projectile;
+use crate::settings::GameSettings;
+
+pub struct DopplerPlugin;
+
+impl Plugin for DopplerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_doppler_sounds).add_systems(
+            PostUpdate,
+            (add_projectile_whine, track_motion, apply_doppler)
+                .chain()
+                .after(TransformSystems::Propagate),
+        );
+    }
+}
+
+/// Metres per second, the shift is measured against this
+const SPEED_OF_SOUND: f32 = 343.0;
+
+/// A sound source whose pitch shifts with its speed towards or away from the listener.
+#[derive(Component, Debug, Default)]
+#[require(Motion)]
+pub struct DopplerEmitter;
+
+/// The ears the shift is heard from, alongside the [`SpatialListener`].
+#[derive(Component, Debug, Default)]
+#[require(Motion)]
+pub struct DopplerListener;
+
+/// World space velocity measured between frames.
+#[derive(Component, Debug, Default)]
+struct Motion {
+    previous: Option<Vec3>,
+    velocity: Vec3,
+}
+
+#[derive(Resource)]
+struct DopplerSounds {
+    whine: Handle<Pitch>,
+}
+
+/// The playback speed a sound is heard at, for a source and listener at these positions and
+/// velocities.
+pub fn doppler_factor(
+    source: Vec3,
+    source_velocity: Vec3,
+    listener: Vec3,
+    listener_velocity: Vec3,
+) -> f32 {
+    let direction = (listener - source).normalize_or_zero();
+
+    // speeds along the line from source to listener, held under the speed of sound so a
+    // supersonic round can't divide by zero
+    let limit = SPEED_OF_SOUND * 0.9;
+    let source_speed = source_velocity.dot(direction).clamp(-limit, limit);
+    let listener_speed = listener_velocity.dot(direction).clamp(-limit, limit);
+
+    ((SPEED_OF_SOUND - listener_speed) / (SPEED_OF_SOUND - source_speed)).clamp(0.5, 2.0)
+}
+
+fn setup_doppler_sounds(mut commands: Commands, mut pitches: ResMut<Assets<Pitch>>) {
+    commands.insert_resource(DopplerSounds {
+        whine: pitches.add(Pitch::new(1200.0, std::time::Duration::from_secs(1))),
+    });
+}
+
+fn add_projectile_whine(
+    mut commands: Commands,
+    sounds: Res<DopplerSounds>,
+    projectiles: Query<Entity, Added<Projectile>>,
+) {
+    for projectile in projectiles {
+        commands.entity(projectile).insert((
+            AudioPlayer(sounds.whine.clone()),
+            PlaybackSettings::LOOP
+                .with_spatial(true)
+                .with_volume(bevy::audio::Volume::Linear(0.15)),
+            DopplerEmitter,
+        ));
+    }
+}
+
+fn track_motion(time: Res<Time>, movers: Query<(&GlobalTransform, &mut Motion)>) {
+    let delta = time.delta_secs();
+
+    if delta <= 0.0 {
+        return;
+    }
+
+    for (transform, mut motion) in movers {
+        let position = transform.translation();
+
+        if let Some(previous) = motion.previous {
+            motion.velocity = (position - previous) / delta;
+        }
+
+        motion.previous = Some(position);
+    }
+}
+
+fn apply_doppler(
+    settings: Res<GameSettings>,
+    listener: Option<Single<(&GlobalTransform, &Motion), With<DopplerListener>>>,
+    emitters: Query<(&GlobalTransform, &Motion, &SpatialAudioSink), With<DopplerEmitter>>,
+) {
+    let listener = listener.map(|listener| (listener.0.translation(), listener.1.velocity));
+
+    for (transform, motion, sink) in emitters {
+        let speed = match listener {
+            Some((position, velocity)) if settings.doppler => {
+                doppler_factor(transform.translation(), motion.velocity, position, velocity)
+            }
+            _ => 1.0,
+        };
+
+        sink.set_speed(speed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doppler_raises_pitch_approaching_and_lowers_it_receding() {
+        let listener = Vec3::ZERO;
+        let source = Vec3::new(0.0, 0.0, -50.0);
+
+        let approaching = doppler_factor(source, Vec3::Z * 100.0, listener, Vec3::ZERO);
+        let receding = doppler_factor(source, Vec3::NEG_Z * 100.0, listener, Vec3::ZERO);
+        let passing = doppler_factor(source, Vec3::X * 100.0, listener, Vec3::ZERO);
+
+        assert!(approaching > 1.0);
+        assert!(receding < 1.0);
+        assert_eq!(passing, 1.0);
+
+        // clamped however fast the round is going
+        assert!(doppler_factor(source, Vec3::Z * 900.0, listener, Vec3::ZERO) <= 2.0);
+    }
+}
@@ -0,0 +1,151 @@
+//! Dual wielding.
+//!
+//! `dual` in the console puts a second copy of each player's one-handed weapon in their left hand,
+//! or takes it away again. The left weapon sits mirrored across the screen and fires with the aim
+//! button while the right fires with the fire button, so neither aims down the sights. Both sway
+//! with the same breath, mirrored, and each has its own magazine; run both dry and they reload one
+//! after the other, so there's always one nearly ready.
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::input_buffer::Action;
+use crate::particles::{MuzzleHeat, ParticleEffect, ParticleEmitter};
+use crate::sockets::{Socket, SocketAnchor, WeaponSockets};
+use crate::sway::SwayProfile;
+use crate::weapon::{WeaponDef, WeaponDefHandle, WeaponStats};
+use crate::{
+    AdsAlpha, PlayerWeapon, PlayerWeaponTransformConfig, SprintAlpha, SwayTarget,
+    TransformPipeline, WeaponActive,
+};
+
+pub struct DualWieldPlugin;
+
+impl Plugin for DualWieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_console_command("dual", "dual - toggle dual wielding one-handed weapons")
+            .add_systems(Update, dual_command);
+    }
+}
+
+/// Which hand a dual wielded weapon is in. Weapons held in both hands don't have one.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponHand {
+    Right,
+    Left,
+}
+
+impl WeaponHand {
+    /// The action that fires the weapon in this hand
+    pub fn trigger(self) -> Action {
+        match self {
+            WeaponHand::Right => Action::Fire,
+            WeaponHand::Left => Action::Aim,
+        }
+    }
+
+    /// `offset` from the right hand's point of view, moved to this hand
+    pub fn mirror(self, offset: Vec3) -> Vec3 {
+        match self {
+            WeaponHand::Right => offset,
+            WeaponHand::Left => offset * Vec3::new(-1.0, 1.0, 1.0),
+        }
+    }
+}
+
+fn dual_command(
+    mut commands: Commands,
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    defs: Res<Assets<WeaponDef>>,
+    weapons: Query<
+        (
+            Entity,
+            &SwayTarget,
+            &ChildOf,
+            &WeaponDefHandle,
+            &PlayerWeaponTransformConfig,
+            &WeaponStats,
+            &SwayProfile,
+            &SceneRoot,
+            Option<&WeaponHand>,
+            Has<WeaponActive>,
+        ),
+        With<PlayerWeapon>,
+    >,
+) {
+    for command in command_reader.read() {
+        if !command.is("dual") {
+            continue;
+        }
+
+        let mut armed = 0;
+        let dual_wielding: HashSet<_> = weapons
+            .iter()
+            .filter(|(.., hand, _)| *hand == Some(&WeaponHand::Left))
+            .map(|(_, owner, ..)| owner.0)
+            .collect();
+
+        for (weapon, owner, camera, handle, config, stats, sway, scene, hand, active) in &weapons {
+            match hand {
+                Some(WeaponHand::Left) => {
+                    commands.entity(weapon).despawn();
+                }
+                Some(WeaponHand::Right) => {
+                    commands.entity(weapon).remove::<WeaponHand>();
+                }
+                None if dual_wielding.contains(&owner.0) || !active => {}
+                None => {
+                    let Some(def) = defs.get(&handle.0) else {
+                        continue;
+                    };
+
+                    if !def.one_handed {
+                        output_writer
+                            .write(ConsoleOutput(format!("the {} takes both hands", def.name)));
+                        continue;
+                    }
+
+                    let config = config.mirrored();
+
+                    let mut left = commands.spawn((
+                        scene.clone(),
+                        Transform::from_translation(config.hip.translation),
+                        PlayerWeapon,
+                        WeaponActive,
+                        SwayTarget(owner.0),
+                        TransformPipeline::new(config.hip.translation),
+                        config,
+                        (AdsAlpha(0.0), SprintAlpha::default()),
+                        WeaponDefHandle(handle.0.clone()),
+                        (stats.clone(), def.fire_mode.clone(), sway.clone()),
+                        WeaponSockets::default(),
+                        WeaponHand::Left,
+                        ChildOf(camera.parent()),
+                        children![(
+                            SocketAnchor(Socket::Muzzle),
+                            MuzzleHeat::default(),
+                            ParticleEmitter::new(ParticleEffect::MuzzleSmoke),
+                        )],
+                    ));
+
+                    if let Some(magazine) = def.full_magazine() {
+                        left.insert(magazine);
+                    }
+
+                    commands.entity(weapon).insert(WeaponHand::Right);
+                    armed += 1;
+                }
+            }
+        }
+
+        if !dual_wielding.is_empty() {
+            output_writer.write(ConsoleOutput("back to one weapon".to_string()));
+        }
+
+        if armed > 0 {
+            output_writer.write(ConsoleOutput("dual wielding".to_string()));
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Short lived lights from flares and tracers.
+//!
+//! A [`DynamicLight`] flickers and fades its entity's [`PointLight`] over its lifetime, then
+//! removes it (or the whole entity, for flares). Shadows are expensive, so the [`LightBudget`]
+//! only lets the brightest few cast them, and once there are too many lights the dimmest are put
+//! out early to make room.
+//!
+//! Players throw flares with the flare key (or d-pad down), which uses a `flare` from their
+//! inventory and stamina like any other throw.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::energy::{EnergyAction, EnergyCosts, Stamina};
+use crate::inventory::Inventory;
+use crate::menu::GameState;
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::turret::Manning;
+use crate::vehicle::Driving;
+use crate::{Player, PlayerCamera};
+
+pub struct DynamicLightsPlugin;
+
+impl Plugin for DynamicLightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightBudget>()
+            .add_systems(Startup, setup_flare_assets)
+            .add_systems(
+                Update,
+                (
+                    throw_flares.run_if(in_state(GameState::InGame)),
+                    update_dynamic_lights,
+                    enforce_light_budget,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// How many dynamic lights can be alive at once.
+#[derive(Resource, Debug)]
+pub struct LightBudget {
+    /// The brightest lights up to this many cast shadows
+    pub max_shadowed: usize,
+    /// Past this the dimmest lights are put out
+    pub max_total: usize,
+}
+
+impl Default for LightBudget {
+    fn default() -> Self {
+        Self {
+            max_shadowed: 4,
+            max_total: 16,
+        }
+    }
+}
+
+/// Drives the entity's [`PointLight`] for `lifetime` seconds.
+#[derive(Component, Debug)]
+#[require(PointLight)]
+pub struct DynamicLight {
+    /// Intensity before flicker and fading
+    pub intensity: f32,
+    pub lifetime: f32,
+    /// 0 for a steady light, up to 1 for one that gutters almost out
+    pub flicker: f32,
+    /// Despawn the whole entity when the light goes out, rather than just the light
+    pub despawn: bool,
+    age: f32,
+    /// Offsets the flicker so lights spawned together don't pulse in step
+    phase: f32,
+}
+
+impl DynamicLight {
+    /// Fraction of the lifetime spent fading out at the end
+    const FADE: f32 = 0.3;
+
+    pub fn new(intensity: f32, lifetime: f32, flicker: f32) -> Self {
+        Self {
+            intensity,
+            lifetime,
+            flicker,
+            despawn: false,
+            age: 0.0,
+            phase: rand::rng().random_range(0.0..std::f32::consts::TAU),
+        }
+    }
+
+    pub fn despawning(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    /// A tracer round's glow
+    pub fn tracer() -> (PointLight, Self) {
+        (
+            PointLight {
+                color: Color::srgb(1.0, 0.6, 0.25),
+                range: 6.0,
+                ..default()
+            },
+            Self::new(40_000.0, 0.5, 0.2),
+        )
+    }
+
+    /// A burning road flare
+    pub fn flare() -> (PointLight, Self) {
+        (
+            PointLight {
+                color: Color::srgb(1.0, 0.15, 0.1),
+                range: 25.0,
+                ..default()
+            },
+            Self::new(600_000.0, 25.0, 0.6).despawning(),
+        )
+    }
+
+    fn is_out(&self) -> bool {
+        self.age >= self.lifetime
+    }
+
+    /// The light's intensity `age` seconds in
+    fn current_intensity(&self) -> f32 {
+        let life = self.age / self.lifetime;
+        let fade = ((1.0 - life) / Self::FADE).clamp(0.0, 1.0);
+
+        // two sines at unrelated rates make an irregular flicker without per frame noise
+        let t = self.age + self.phase;
+        let noise = 0.5 + 0.5 * (t * 13.0).sin() * (t * 7.3 + self.phase).sin();
+
+        self.intensity * fade * (1.0 - self.flicker * noise)
+    }
+}
+
+/// A thrown flare, see [`DynamicLight::flare`].
+#[derive(Component)]
+pub struct Flare;
+
+#[derive(Resource)]
+struct FlareAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_flare_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(FlareAssets {
+        mesh: meshes.add(Cylinder::new(0.03, 0.25)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.9, 0.1, 0.05),
+            emissive: LinearRgba::rgb(20.0, 2.0, 1.0),
+            ..default()
+        }),
+    });
+}
+
+fn throw_flares(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    costs: Res<EnergyCosts>,
+    assets: Res<FlareAssets>,
+    gamepads: Query<&Gamepad>,
+    players: Query<
+        (
+            Entity,
+            &PlayerInput,
+            &mut Inventory,
+            Option<&mut Stamina>,
+            &Children,
+        ),
+        (With<Player>, Without<Driving>, Without<Manning>),
+    >,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    /// Metres per second the flare leaves the hand at
+    const THROW_SPEED: f32 = 12.0;
+
+    for (entity, input, mut inventory, stamina, children) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.flare);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::DPadDown));
+
+        if !keyboard && !gamepad {
+            continue;
+        }
+
+        let Some(eyes) = children.iter().find_map(|child| cameras.get(child).ok()) else {
+            continue;
+        };
+
+        if inventory.count("flare") == 0 {
+            debug!("{entity} has no flares");
+            continue;
+        }
+
+        if let Some(mut stamina) = stamina
+            && !stamina.try_spend(costs.get(EnergyAction::Throw))
+        {
+            continue;
+        }
+
+        inventory.take("flare", 1);
+
+        // lobbed a little upwards, from just in front of the face
+        let direction = (eyes.forward().as_vec3() + Vec3::Y * 0.3).normalize();
+
+        commands.spawn((
+            Flare,
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(assets.material.clone()),
+            Transform::from_translation(eyes.translation() + eyes.forward().as_vec3() * 0.6),
+            RigidBody::Dynamic,
+            Collider::cylinder(0.03, 0.25),
+            LinearVelocity(direction * THROW_SPEED),
+            AngularVelocity(Vec3::new(4.0, 0.0, 1.0)),
+            DynamicLight::flare(),
+            DespawnOnExit(GameState::InGame),
+        ));
+    }
+}
+
+fn update_dynamic_lights(
+    mut commands: Commands,
+    time: Res<Time>,
+    lights: Query<(Entity, &mut DynamicLight, &mut PointLight)>,
+) {
+    for (entity, mut light, mut point_light) in lights {
+        light.age += time.delta_secs();
+
+        if light.is_out() {
+            put_out(&mut commands, entity, &light);
+            continue;
+        }
+
+        point_light.intensity = light.current_intensity();
+    }
+}
+
+fn put_out(commands: &mut Commands, entity: Entity, light: &DynamicLight) {
+    if light.despawn {
+        commands.entity(entity).try_despawn();
+    } else {
+        commands
+            .entity(entity)
+            .try_remove::<(DynamicLight, PointLight)>();
+    }
+}
+
+fn enforce_light_budget(
+    mut commands: Commands,
+    budget: Res<LightBudget>,
+    lights: Query<(Entity, &DynamicLight, &mut PointLight)>,
+) {
+    let mut lights: Vec<_> = lights
+        .into_iter()
+        .filter(|(_, light, _)| !light.is_out())
+        .collect();
+
+    // brightest first
+    lights.sort_by(|(.., a), (.., b)| b.intensity.total_cmp(&a.intensity));
+
+    for (index, (entity, light, mut point_light)) in lights.into_iter().enumerate() {
+        if index >= budget.max_total {
+            debug!("light budget full, putting out {entity}");
+            put_out(&mut commands, entity, light);
+            continue;
+        }
+
+        let shadows = index < budget.max_shadowed;
+
+        if point_light.shadows_enabled != shadows {
+            point_light.shadows_enabled = shadows;
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! Trading resolution for frame rate.
+//!
+//! With [`GameSettings::dynamic_resolution`] on, [`RenderScale`] watches how long frames take and
+//! steps the share of full resolution players' views are drawn at down while frames run slower
+//! than [`GameSettings::target_fps`], and back up once there's room again, within
+//! [`GameSettings::render_scale_bounds`]. Each view is drawn to an image that much smaller than
+//! its part of the window and stretched over it by a presenting camera, which draws the menus
+//! and the shared HUD on top at full resolution. The particle and light budgets shrink with the
+//! scale too.
+//!
+//! Turned off, the views go back to drawing straight to the window and the budgets to full.
+
+use bevy::{
+    camera::{RenderTarget, visibility::RenderLayers},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureFormat},
+    window::{PrimaryWindow, WindowRef},
+};
+
+use crate::dynamic_lights::LightBudget;
+use crate::particles::ParticleBudget;
+use crate::settings::GameSettings;
+use crate::split_screen::{self, LocalPlayers, PlayerView};
+
+pub struct DynamicResolutionPlugin;
+
+impl Plugin for DynamicResolutionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenderScale>().add_systems(
+            Update,
+            (
+                track_frame_time,
+                scale_views,
+                scale_budgets.run_if(resource_changed::<RenderScale>),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The share of full resolution players' views are drawn at, and how long frames have been
+/// taking.
+#[derive(Resource, Debug)]
+pub struct RenderScale {
+    pub scale: f32,
+    /// Seconds a frame has taken lately, smoothed over the last few
+    frame_time: f32,
+    /// Seconds since the scale last stepped
+    since_step: f32,
+}
+
+impl RenderScale {
+    /// How far the scale moves at a time
+    const STEP: f32 = 0.05;
+    /// Seconds between steps, long enough for the last one to show in the frame time
+    const INTERVAL: f32 = 0.5;
+    /// Share of each frame's time taken into the smoothed frame time
+    const SMOOTHING: f32 = 0.1;
+    /// Frames quicker than this share of the target leave room to step back up
+    const HEADROOM: f32 = 0.85;
+
+    /// Takes in a frame that took `delta` seconds, stepping the scale to hold `target_fps`
+    /// between `bounds`. Returns whether the scale moved
+    pub fn track(&mut self, delta: f32, target_fps: f32, bounds: (f32, f32)) -> bool {
+        let (lowest, highest) = (bounds.0, bounds.1.max(bounds.0));
+
+        self.frame_time = if self.frame_time == 0.0 {
+            delta
+        } else {
+            self.frame_time.lerp(delta, Self::SMOOTHING)
+        };
+        self.since_step += delta;
+
+        let target = 1.0 / target_fps.max(1.0);
+        let step = if self.since_step < Self::INTERVAL {
+            0.0
+        } else if self.frame_time > target {
+            -Self::STEP
+        } else if self.frame_time < target * Self::HEADROOM {
+            Self::STEP
+        } else {
+            0.0
+        };
+
+        let scale = (self.scale + step).clamp(lowest, highest);
+        if scale == self.scale {
+            return false;
+        }
+
+        self.scale = scale;
+        self.since_step = 0.0;
+        true
+    }
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            frame_time: 0.0,
+            since_step: 0.0,
+        }
+    }
+}
+
+/// A player's camera drawing to an image at the [`RenderScale`] rather than to the window.
+#[derive(Component, Debug)]
+pub struct ScaledView {
+    image: Handle<Image>,
+    /// The UI node the image is stretched over
+    node: Entity,
+}
+
+/// The camera the scaled views are shown through.
+#[derive(Component, Debug)]
+struct Presenter;
+
+fn track_frame_time(
+    time: Res<Time<Real>>,
+    settings: Res<GameSettings>,
+    mut render_scale: ResMut<RenderScale>,
+) {
+    if !settings.dynamic_resolution {
+        if render_scale.scale != 1.0 {
+            render_scale.scale = 1.0;
+        }
+        return;
+    }
+
+    // the smoothed frame time changes every frame, only a new scale is worth reacting to
+    let stepped = render_scale.bypass_change_detection().track(
+        time.delta_secs(),
+        settings.target_fps,
+        settings.render_scale_bounds,
+    );
+    if stepped {
+        debug!("render scale now {:.2}", render_scale.scale);
+        render_scale.set_changed();
+    }
+}
+
+/// Moves players' views between the window and images sized by the [`RenderScale`], keeping the
+/// images the right size as the window and scale change.
+fn scale_views(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    render_scale: Res<RenderScale>,
+    players: Res<LocalPlayers>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    presenters: Query<Entity, With<Presenter>>,
+    cameras: Query<(Entity, &mut Camera, &PlayerView, Option<&ScaledView>)>,
+    mut nodes: Query<&mut Node>,
+) {
+    if !settings.dynamic_resolution {
+        if presenters.is_empty() {
+            return;
+        }
+
+        for (entity, mut camera, view, scaled) in cameras {
+            let Some(scaled) = scaled else {
+                continue;
+            };
+
+            camera.target = RenderTarget::Window(WindowRef::Primary);
+            // split screen puts the viewport back
+            camera.viewport = None;
+            images.remove(&scaled.image);
+            commands.entity(scaled.node).despawn();
+
+            let mut entity = commands.entity(entity);
+            entity.remove::<ScaledView>();
+            if view.0 == 0 {
+                entity.insert(IsDefaultUiCamera);
+            }
+        }
+
+        for presenter in presenters {
+            commands.entity(presenter).despawn();
+        }
+        return;
+    }
+
+    let presenter = presenters.iter().next().unwrap_or_else(|| {
+        commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    // after every player's view
+                    order: LocalPlayers::MAX as isize,
+                    ..default()
+                },
+                // nothing in the world is drawn twice
+                RenderLayers::none(),
+                IsDefaultUiCamera,
+                Presenter,
+            ))
+            .id()
+    });
+
+    let window_size = window.physical_size();
+
+    for (entity, mut camera, view, scaled) in cameras {
+        let rect = split_screen::viewport_rect(view.0, players.0, window_size);
+        let size = (rect.size().as_vec2() * render_scale.scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+
+        // stretched over the view's part of the window, which UI lays out in logical pixels
+        let placed = Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(rect.min.x as f32 / window.scale_factor()),
+            top: Val::Px(rect.min.y as f32 / window.scale_factor()),
+            width: Val::Px(rect.width() as f32 / window.scale_factor()),
+            height: Val::Px(rect.height() as f32 / window.scale_factor()),
+            ..default()
+        };
+
+        let Some(scaled) = scaled else {
+            let image = images.add(Image::new_target_texture(
+                size.x,
+                size.y,
+                TextureFormat::bevy_default(),
+            ));
+            let node = commands
+                .spawn((
+                    placed,
+                    ImageNode::new(image.clone()),
+                    UiTargetCamera(presenter),
+                    // under the menus and the rest of the HUD
+                    GlobalZIndex(i32::MIN),
+                ))
+                .id();
+
+            camera.target = RenderTarget::Image(image.clone().into());
+            camera.viewport = None;
+            commands
+                .entity(entity)
+                .remove::<IsDefaultUiCamera>()
+                .insert(ScaledView { image, node });
+            continue;
+        };
+
+        if let Some(image) = images.get_mut(&scaled.image)
+            && image.size() != size
+        {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            });
+        }
+
+        if let Ok(mut node) = nodes.get_mut(scaled.node)
+            && *node != placed
+        {
+            *node = placed;
+        }
+    }
+}
+
+/// Cuts the particle and light budgets down in step with the [`RenderScale`].
+fn scale_budgets(
+    render_scale: Res<RenderScale>,
+    mut particles: ResMut<ParticleBudget>,
+    mut lights: ResMut<LightBudget>,
+) {
+    let scaled = |full: usize| ((full as f32 * render_scale.scale).round() as usize).max(1);
+    let full_lights = LightBudget::default();
+
+    particles.max = scaled(ParticleBudget::FULL);
+    *lights = LightBudget {
+        max_shadowed: scaled(full_lights.max_shadowed),
+        max_total: scaled(full_lights.max_total),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::simulate;
+
+    #[test]
+    fn render_scale_drops_under_load_and_recovers_within_bounds() {
+        let mut render_scale = RenderScale::default();
+        let bounds = (0.6, 0.9);
+
+        // 40 fps against a 60 fps target
+        simulate(40.0, 10.0, |delta| {
+            render_scale.track(delta, 60.0, bounds);
+        });
+        assert_eq!(render_scale.scale, 0.6);
+
+        // 120 fps, plenty of room
+        simulate(120.0, 10.0, |delta| {
+            render_scale.track(delta, 60.0, bounds);
+        });
+        assert_eq!(render_scale.scale, 0.9);
+    }
+}
@@ -0,0 +1,121 @@
+//! Carried weight slowing characters down.
+//!
+//! The weight of everything in a character's [`Inventory`], and the armour they're wearing, is
+//! compared against their carrying capacity. Past half capacity the load starts to cost sprint
+//! stamina, movement speed, jump height and steadiness of aim, and past full capacity the
+//! character is over-encumbered and heavily slowed. Leaving the over-encumbered state needs the
+//! load to drop a little below capacity, so picking up and dropping a single item at the limit
+//! doesn't make it flicker.
+
+use bevy::prelude::*;
+
+use crate::equipment::Equipment;
+use crate::inventory::{Inventory, ItemWeights};
+
+pub struct EncumbrancePlugin;
+
+impl Plugin for EncumbrancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_encumbrance);
+    }
+}
+
+/// How weighed down a character is by their inventory.
+#[derive(Component, Debug)]
+pub struct Encumbrance {
+    /// Weight that can be carried before being over-encumbered
+    pub capacity: f32,
+    /// Multiplier on `capacity`
+    capacity_scale: f32,
+    weight: f32,
+    over: bool,
+}
+
+impl Encumbrance {
+    /// Fraction of capacity at which carried weight starts to have an effect
+    const PENALTY_START: f32 = 0.5;
+    /// Fraction of capacity above which a character becomes over-encumbered
+    const OVER_ENTER: f32 = 1.0;
+    /// Fraction of capacity an over-encumbered character has to get back under to recover
+    const OVER_EXIT: f32 = 0.9;
+
+    pub fn new(capacity: f32) -> Self {
+        Self {
+            capacity,
+            capacity_scale: 1.0,
+            weight: 0.0,
+            over: false,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    fn load(&self) -> f32 {
+        self.weight / (self.capacity * self.capacity_scale).max(f32::EPSILON)
+    }
+
+    /// 0 when lightly loaded, rising to 1 at capacity
+    pub fn strain(&self) -> f32 {
+        ((self.load() - Self::PENALTY_START) / (1.0 - Self::PENALTY_START)).clamp(0.0, 1.0)
+    }
+
+    pub fn set_capacity_scale(&mut self, scale: f32) {
+        self.capacity_scale = scale;
+        self.set_weight(self.weight);
+    }
+
+    fn set_weight(&mut self, weight: f32) {
+        self.weight = weight;
+
+        let threshold = if self.over {
+            Self::OVER_EXIT
+        } else {
+            Self::OVER_ENTER
+        };
+
+        self.over = self.load() > threshold;
+    }
+
+    /// Multiplier on movement acceleration
+    pub fn speed_scale(&self) -> f32 {
+        let scale = 1.0 - 0.3 * self.strain();
+        if self.over { scale * 0.6 } else { scale }
+    }
+
+    /// Multiplier on jump impulse
+    pub fn jump_scale(&self) -> f32 {
+        let scale = 1.0 - 0.25 * self.strain();
+        if self.over { scale * 0.5 } else { scale }
+    }
+
+    /// Multiplier on stamina spent sprinting
+    pub fn drain_scale(&self) -> f32 {
+        let scale = 1.0 + 0.5 * self.strain();
+        if self.over { scale * 1.5 } else { scale }
+    }
+
+    /// Multiplier on idle weapon sway
+    pub fn sway_scale(&self) -> f32 {
+        1.0 + self.strain()
+    }
+}
+
+fn update_encumbrance(
+    weights: Res<ItemWeights>,
+    query: Query<
+        (Entity, &Inventory, Option<&Equipment>, &mut Encumbrance),
+        Or<(Changed<Inventory>, Changed<Equipment>)>,
+    >,
+) {
+    for (entity, inventory, equipment, mut encumbrance) in query {
+        let was_over = encumbrance.is_over();
+        let worn = equipment.map_or(0.0, |equipment| equipment.weight());
+        encumbrance.set_weight(inventory.weight(&weights) + worn);
+
+        if encumbrance.is_over() != was_over {
+            debug!("{entity} over-encumbered: {}", encumbrance.is_over());
+        }
+    }
+}
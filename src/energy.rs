@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::Breath;
+use crate::encumbrance::Encumbrance;
+use crate::environment::Climate;
+use crate::movement::Sprinting;
+use crate::status::StatusEffects;
+
+pub struct EnergyPlugin;
+
+impl Plugin for EnergyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<StaminaDepleted>()
+            .add_message::<StaminaRecovered>()
+            .init_resource::<EnergyCosts>()
+            .add_systems(
+                Update,
+                (
+                    drain_stamina,
+                    exhaustion,
+                    exert,
+                    strain_breath,
+                    log_exhaustion,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Metres per second of horizontal speed below which a character counts as standing still
+const MOVING_THRESHOLD: f32 = 0.5;
+
+/// The energy a character spends on exertion.
+#[derive(Component, Debug)]
+#[require(Exertion)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    /// Stamina spent per second of sprinting
+    pub sprint_drain: f32,
+    /// Stamina regained per second while not sprinting
+    pub regen: f32,
+    /// Fraction of max stamina needed before an exhausted character can sprint again
+    pub recovery_threshold: f32,
+    /// Fraction of max stamina below which the character starts getting out of breath
+    pub strain_threshold: f32,
+    /// Multiplier on `max`
+    pub max_scale: f32,
+    /// How much lower the character jumps as they tire
+    pub jump_curve: JumpCurve,
+    exhausted: bool,
+    /// Seconds until stamina starts regenerating again
+    regen_blocked: f32,
+}
+
+impl Stamina {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            sprint_drain: 15.0,
+            regen: 10.0,
+            recovery_threshold: 0.3,
+            strain_threshold: 0.5,
+            max_scale: 1.0,
+            jump_curve: JumpCurve::default(),
+            exhausted: false,
+            regen_blocked: 0.0,
+        }
+    }
+
+    /// The most stamina the character can hold
+    pub fn capacity(&self) -> f32 {
+        self.max * self.max_scale
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// How full the character's stamina is, `0..=1`
+    pub fn fraction(&self) -> f32 {
+        let capacity = self.capacity();
+
+        if capacity > 0.0 {
+            (self.current / capacity).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Multiplier on jump impulse
+    pub fn jump_scale(&self) -> f32 {
+        self.jump_curve.scale(self.fraction())
+    }
+
+    /// How out of breath the character is, `0..=1`, rising as stamina falls below
+    /// `strain_threshold` and full until they've recovered from exhaustion
+    pub fn strain(&self) -> f32 {
+        if self.exhausted {
+            return 1.0;
+        }
+
+        if self.strain_threshold <= 0.0 {
+            return 0.0;
+        }
+
+        (1.0 - self.fraction() / self.strain_threshold).clamp(0.0, 1.0)
+    }
+
+    /// Spend stamina on an action, returning `false` without spending anything if there isn't
+    /// enough
+    pub fn try_spend(&mut self, cost: EnergyCost) -> bool {
+        if self.exhausted || self.current < cost.cost {
+            return false;
+        }
+
+        self.current -= cost.cost;
+        self.regen_blocked = self.regen_blocked.max(cost.regen_delay);
+        true
+    }
+}
+
+/// How worked up a character is from sprinting, `0..=1`.
+///
+/// Builds the longer a sprint goes on and fades slowly afterwards, quicker standing still than
+/// walking. It strains the breath like running low on stamina does, so a long sprint leaves the
+/// aim ragged for a while even once stamina is coming back.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Exertion {
+    pub level: f32,
+    pub rates: ExertionRates,
+}
+
+/// How quickly [`Exertion`] builds and fades, per second.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ExertionRates {
+    /// While sprinting
+    pub build: f32,
+    /// While moving without sprinting
+    pub walking_recovery: f32,
+    /// While standing still
+    pub resting_recovery: f32,
+}
+
+impl Default for ExertionRates {
+    fn default() -> Self {
+        Self {
+            build: 0.08,
+            walking_recovery: 0.03,
+            resting_recovery: 0.06,
+        }
+    }
+}
+
+impl Exertion {
+    /// Builds or fades for `delta` seconds of moving or standing still, sprinting or not
+    pub fn exert(&mut self, moving: bool, sprinting: bool, delta: f32) {
+        let change = match (moving, sprinting) {
+            (true, true) => self.rates.build,
+            (true, false) => -self.rates.walking_recovery,
+            (false, _) => -self.rates.resting_recovery,
+        };
+
+        self.level = (self.level + change * delta).clamp(0.0, 1.0);
+    }
+}
+
+/// Jump impulse falling off with stamina: full strength down to `threshold` of max stamina, then
+/// curving down to `min_scale` when empty.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct JumpCurve {
+    /// Fraction of max stamina below which jumps weaken
+    pub threshold: f32,
+    /// Multiplier on a jump with no stamina left
+    pub min_scale: f32,
+    /// Shape of the falloff, 1 for linear, higher to hold on longer before dropping sharply
+    #[serde(default = "JumpCurve::default_exponent")]
+    pub exponent: f32,
+}
+
+impl JumpCurve {
+    fn default_exponent() -> f32 {
+        1.0
+    }
+
+    /// Multiplier on jump impulse with `fraction` of max stamina left
+    pub fn scale(&self, fraction: f32) -> f32 {
+        if self.threshold <= 0.0 || fraction >= self.threshold {
+            return 1.0;
+        }
+
+        let t = (fraction / self.threshold).clamp(0.0, 1.0);
+        let t = 1.0 - (1.0 - t).powf(self.exponent.max(0.01));
+
+        self.min_scale + (1.0 - self.min_scale) * t
+    }
+}
+
+impl Default for JumpCurve {
+    fn default() -> Self {
+        Self {
+            threshold: 0.4,
+            min_scale: 0.6,
+            exponent: Self::default_exponent(),
+        }
+    }
+}
+
+/// Something a character can spend stamina on.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnergyAction {
+    Jump,
+    Dash,
+    Throw,
+    Grapple,
+    Melee,
+}
+
+/// What an action takes out of a character.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct EnergyCost {
+    pub cost: f32,
+    /// Seconds after the action before stamina regenerates again
+    #[serde(default)]
+    pub regen_delay: f32,
+}
+
+/// The stamina cost of every action, set from the `costs` table of the player tuning.
+///
+/// Actions missing from the table are free.
+#[derive(Resource, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct EnergyCosts(HashMap<EnergyAction, EnergyCost>);
+
+impl EnergyCosts {
+    pub fn get(&self, action: EnergyAction) -> EnergyCost {
+        self.0.get(&action).copied().unwrap_or_default()
+    }
+}
+
+impl Default for EnergyCosts {
+    fn default() -> Self {
+        let cost = |cost, regen_delay| EnergyCost { cost, regen_delay };
+
+        Self(HashMap::from([
+            (EnergyAction::Jump, cost(12.0, 0.5)),
+            (EnergyAction::Dash, cost(25.0, 1.25)),
+            (EnergyAction::Throw, cost(15.0, 0.75)),
+            (EnergyAction::Grapple, cost(20.0, 1.0)),
+            (EnergyAction::Melee, cost(10.0, 0.75)),
+        ]))
+    }
+}
+
+/// An event sent when a character runs out of stamina.
+#[derive(Message, Debug)]
+pub struct StaminaDepleted(pub Entity);
+
+/// An event sent when an exhausted character has recovered enough stamina to exert themselves.
+#[derive(Message, Debug)]
+pub struct StaminaRecovered(pub Entity);
+
+fn drain_stamina(
+    time: Res<Time>,
+    query: Query<(
+        &mut Stamina,
+        &LinearVelocity,
+        Has<Sprinting>,
+        Option<&Climate>,
+        Option<&Encumbrance>,
+        Option<&StatusEffects>,
+    )>,
+) {
+    let delta = time.delta_secs();
+
+    for (mut stamina, velocity, sprinting, climate, encumbrance, effects) in query {
+        let climate = climate.copied().unwrap_or_default();
+        let moving = velocity.xz().length() > MOVING_THRESHOLD;
+
+        stamina.regen_blocked = (stamina.regen_blocked - delta).max(0.0);
+
+        let change = if sprinting && moving {
+            -stamina.sprint_drain
+                * climate.drain_scale()
+                * encumbrance.map_or(1.0, Encumbrance::drain_scale)
+        } else if stamina.regen_blocked > 0.0 {
+            0.0
+        } else {
+            stamina.regen
+                * climate.regen_scale()
+                * effects.map_or(1.0, |effects| effects.modifiers().regen)
+        };
+
+        stamina.current = (stamina.current + change * delta).clamp(0.0, stamina.capacity());
+    }
+}
+
+fn exhaustion(
+    mut commands: Commands,
+    query: Query<(Entity, &mut Stamina)>,
+    mut depleted_writer: MessageWriter<StaminaDepleted>,
+    mut recovered_writer: MessageWriter<StaminaRecovered>,
+) {
+    for (entity, mut stamina) in query {
+        if !stamina.exhausted && stamina.current <= 0.0 {
+            stamina.exhausted = true;
+            commands.entity(entity).remove::<Sprinting>();
+            depleted_writer.write(StaminaDepleted(entity));
+        } else if stamina.exhausted
+            && stamina.current >= stamina.capacity() * stamina.recovery_threshold
+        {
+            stamina.exhausted = false;
+            recovered_writer.write(StaminaRecovered(entity));
+        }
+    }
+}
+
+fn exert(time: Res<Time>, query: Query<(&mut Exertion, &LinearVelocity, Has<Sprinting>)>) {
+    for (mut exertion, velocity, sprinting) in query {
+        let moving = velocity.xz().length() > MOVING_THRESHOLD;
+        exertion.exert(moving, sprinting, time.delta_secs());
+    }
+}
+
+/// Tired or worked up characters breathe harder, and so sway more.
+fn strain_breath(query: Query<(&Stamina, &Exertion, &mut Breath)>) {
+    for (stamina, exertion, mut breath) in query {
+        let strain = stamina.strain().max(exertion.level);
+
+        if breath.strain != strain {
+            breath.strain = strain;
+        }
+    }
+}
+
+fn log_exhaustion(
+    mut depleted_reader: MessageReader<StaminaDepleted>,
+    mut recovered_reader: MessageReader<StaminaRecovered>,
+) {
+    for StaminaDepleted(entity) in depleted_reader.read() {
+        debug!("{entity} is exhausted");
+    }
+
+    for StaminaRecovered(entity) in recovered_reader.read() {
+        debug!("{entity} recovered from exhaustion");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::simulate;
+
+    #[test]
+    fn tired_jumps_fall_off_below_the_threshold() {
+        let curve = JumpCurve {
+            threshold: 0.4,
+            min_scale: 0.6,
+            exponent: 1.0,
+        };
+
+        assert_eq!(curve.scale(1.0), 1.0);
+        assert_eq!(curve.scale(0.4), 1.0);
+        assert!((curve.scale(0.2) - 0.8).abs() < 1e-5);
+        assert!((curve.scale(0.0) - 0.6).abs() < 1e-5);
+    }
+
+    #[test]
+    fn long_sprints_wind_the_breath_and_rest_settles_it_quicker_than_walking() {
+        let mut exertion = Exertion::default();
+
+        simulate(60.0, 20.0, |delta| exertion.exert(true, true, delta));
+        assert_eq!(exertion.level, 1.0);
+
+        let (mut walking, mut resting) = (exertion, exertion);
+        simulate(60.0, 5.0, |delta| {
+            walking.exert(true, false, delta);
+            resting.exert(false, false, delta);
+        });
+
+        assert!(resting.level < walking.level && walking.level < 1.0);
+    }
+}
@@ -0,0 +1,121 @@
+//! Climate zones.
+//!
+//! An [`EnvironmentZone`] is a box volume placed by the scene. Characters standing inside one take
+//! on its [`Climate`], which scales how fast their stamina drains and recovers. In the cold every
+//! exhale also puffs out a cloud of breath vapour (see [`crate::particles`]).
+
+use bevy::prelude::*;
+
+use crate::particles::{ParticleEffect, SpawnParticles};
+use crate::{BreathDirection, BreathPhaseChanged, PlayerCamera};
+
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (update_climate, exhale_vapor));
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Climate {
+    #[default]
+    Temperate,
+    Cold,
+    Hot,
+}
+
+impl Climate {
+    /// Multiplier on stamina spent sprinting
+    pub fn drain_scale(&self) -> f32 {
+        match self {
+            Self::Temperate => 1.0,
+            Self::Cold => 1.15,
+            Self::Hot => 1.4,
+        }
+    }
+
+    /// Multiplier on stamina regained while resting
+    pub fn regen_scale(&self) -> f32 {
+        match self {
+            Self::Temperate => 1.0,
+            Self::Cold => 0.8,
+            Self::Hot => 0.6,
+        }
+    }
+
+    fn shows_breath(&self) -> bool {
+        *self == Self::Cold
+    }
+}
+
+/// A box volume, centred on the entity's transform, with its own climate.
+#[derive(Component, Debug)]
+#[require(Transform)]
+pub struct EnvironmentZone {
+    pub climate: Climate,
+    pub half_extents: Vec3,
+}
+
+impl EnvironmentZone {
+    fn contains(&self, zone_transform: &GlobalTransform, point: Vec3) -> bool {
+        let local = zone_transform.affine().inverse().transform_point3(point);
+        local.abs().cmple(self.half_extents).all()
+    }
+}
+
+/// Characters with a climate have it updated from the zone they're standing in.
+fn update_climate(
+    zones_q: Query<(&EnvironmentZone, &GlobalTransform)>,
+    characters_q: Query<(&mut Climate, &GlobalTransform)>,
+) {
+    for (mut climate, transform) in characters_q {
+        let position = transform.translation();
+
+        let current = zones_q
+            .iter()
+            .find(|(zone, zone_transform)| zone.contains(zone_transform, position))
+            .map(|(zone, _)| zone.climate)
+            .unwrap_or_default();
+
+        if *climate != current {
+            debug!("climate is now {current:?}");
+            *climate = current;
+        }
+    }
+}
+
+fn exhale_vapor(
+    mut phase_reader: MessageReader<BreathPhaseChanged>,
+    mut particle_writer: MessageWriter<SpawnParticles>,
+    breathers_q: Query<(&Climate, &Children)>,
+    cameras_q: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    for phase in phase_reader.read() {
+        if phase.direction != BreathDirection::Out {
+            continue;
+        }
+
+        let Ok((climate, children)) = breathers_q.get(phase.entity) else {
+            continue;
+        };
+
+        if !climate.shows_breath() {
+            continue;
+        }
+
+        // breath comes out just below and in front of the eyes
+        let Some(eyes) = children.iter().find_map(|child| cameras_q.get(child).ok()) else {
+            continue;
+        };
+
+        let forward = eyes.forward().as_vec3();
+
+        particle_writer.write(SpawnParticles {
+            effect: ParticleEffect::BreathVapor,
+            position: eyes.translation() + forward * 0.25 - eyes.up().as_vec3() * 0.12,
+            direction: forward,
+            count: 5,
+        });
+    }
+}
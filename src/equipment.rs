@@ -0,0 +1,265 @@
+//! Wearable armour.
+//!
+//! A character's [`Equipment`] has a slot for each [`ArmorPiece`]. Wearing a piece puts [`Armor`]
+//! on the hitbox of the zone it covers, adds its weight to the character's encumbrance and shows
+//! it on their body. Pieces are picked up by walking over an [`ArmorPickup`], and fall apart once
+//! their durability is used up.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::damage::{Armor, HitZone, Hitbox};
+use crate::menu::GameState;
+use crate::trigger::{TriggerEntered, TriggerFilter, TriggerVolume};
+
+pub struct EquipmentPlugin;
+
+impl Plugin for EquipmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_equipment_assets)
+            .add_systems(
+                Update,
+                (
+                    pick_up_armor,
+                    break_armor,
+                    show_equipment,
+                    spin_pickups.run_if(in_state(GameState::InGame)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EquipmentSlot {
+    Head,
+    Torso,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmorPiece {
+    Helmet,
+    Vest,
+}
+
+impl ArmorPiece {
+    pub fn slot(&self) -> EquipmentSlot {
+        match self {
+            ArmorPiece::Helmet => EquipmentSlot::Head,
+            ArmorPiece::Vest => EquipmentSlot::Torso,
+        }
+    }
+
+    /// The hitbox the piece protects
+    fn zone(&self) -> HitZone {
+        match self {
+            ArmorPiece::Helmet => HitZone::Head,
+            ArmorPiece::Vest => HitZone::Body,
+        }
+    }
+
+    /// Fresh [`Armor`] for the piece
+    fn armor(&self) -> Armor {
+        match self {
+            ArmorPiece::Helmet => Armor::new(50.0, 0.5),
+            ArmorPiece::Vest => Armor::new(100.0, 0.6),
+        }
+    }
+
+    /// Kilograms added to the wearer's load
+    pub fn weight(&self) -> f32 {
+        match self {
+            ArmorPiece::Helmet => 1.5,
+            ArmorPiece::Vest => 6.0,
+        }
+    }
+}
+
+/// The armour a character is wearing.
+#[derive(Component, Debug, Default)]
+pub struct Equipment {
+    slots: HashMap<EquipmentSlot, ArmorPiece>,
+}
+
+impl Equipment {
+    /// Wear `piece`, returning whatever was in its slot before
+    pub fn equip(&mut self, piece: ArmorPiece) -> Option<ArmorPiece> {
+        self.slots.insert(piece.slot(), piece)
+    }
+
+    pub fn unequip(&mut self, slot: EquipmentSlot) -> Option<ArmorPiece> {
+        self.slots.remove(&slot)
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = ArmorPiece> + '_ {
+        self.slots.values().copied()
+    }
+
+    /// Total weight of everything worn
+    pub fn weight(&self) -> f32 {
+        self.pieces().map(|piece| piece.weight()).sum()
+    }
+}
+
+/// A piece of armour lying in the world, worn by the first player to walk into it.
+#[derive(Component, Debug)]
+#[require(Transform)]
+pub struct ArmorPickup(pub ArmorPiece);
+
+impl ArmorPickup {
+    pub fn trigger() -> TriggerVolume {
+        TriggerVolume::sphere(0.8).with_filter(TriggerFilter::Player)
+    }
+}
+
+/// A worn piece drawn on the body.
+#[derive(Component)]
+struct EquipmentVisual;
+
+#[derive(Resource)]
+pub struct EquipmentAssets {
+    pub helmet_mesh: Handle<Mesh>,
+    pub vest_mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl EquipmentAssets {
+    pub fn mesh(&self, piece: ArmorPiece) -> Handle<Mesh> {
+        match piece {
+            ArmorPiece::Helmet => self.helmet_mesh.clone(),
+            ArmorPiece::Vest => self.vest_mesh.clone(),
+        }
+    }
+}
+
+fn setup_equipment_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(EquipmentAssets {
+        helmet_mesh: meshes.add(Sphere::new(0.32)),
+        vest_mesh: meshes.add(Cylinder::new(0.56, 0.8)),
+        material: materials.add(Color::srgb_u8(70, 80, 60)),
+    });
+}
+
+fn pick_up_armor(
+    mut commands: Commands,
+    mut entered_reader: MessageReader<TriggerEntered>,
+    pickups: Query<&ArmorPickup>,
+    mut wearers: Query<&mut Equipment>,
+    hitboxes: Query<(Entity, &Hitbox, &ChildOf)>,
+) {
+    for entered in entered_reader.read() {
+        let Ok(pickup) = pickups.get(entered.trigger) else {
+            continue;
+        };
+
+        let Ok(mut equipment) = wearers.get_mut(entered.body) else {
+            continue;
+        };
+
+        let piece = pickup.0;
+        equipment.equip(piece);
+
+        // the body's own collider is its body hitbox, other zones are child colliders
+        let hitbox = if piece.zone() == HitZone::Body {
+            Some(entered.body)
+        } else {
+            hitboxes
+                .iter()
+                .find(|(_, hitbox, child_of)| {
+                    child_of.parent() == entered.body && hitbox.0 == piece.zone()
+                })
+                .map(|(entity, ..)| entity)
+        };
+
+        if let Some(hitbox) = hitbox {
+            commands.entity(hitbox).insert(piece.armor());
+        }
+
+        info!("{} put on a {piece:?}", entered.body);
+        commands.entity(entered.trigger).despawn();
+    }
+}
+
+/// Pieces whose armour is used up come off.
+fn break_armor(
+    mut commands: Commands,
+    armor: Query<(Entity, &Armor, &Hitbox, Option<&ChildOf>), Changed<Armor>>,
+    mut wearers: Query<&mut Equipment>,
+) {
+    for (entity, armor, hitbox, child_of) in armor {
+        if armor.durability > 0.0 {
+            continue;
+        }
+
+        let wearer = child_of.map_or(entity, |child_of| child_of.parent());
+
+        let Ok(mut equipment) = wearers.get_mut(wearer) else {
+            continue;
+        };
+
+        let slot = match hitbox.0 {
+            HitZone::Head => EquipmentSlot::Head,
+            HitZone::Body => EquipmentSlot::Torso,
+        };
+
+        if let Some(piece) = equipment.unequip(slot) {
+            info!("{wearer}'s {piece:?} broke");
+            commands.entity(entity).remove::<Armor>();
+        }
+    }
+}
+
+fn show_equipment(
+    mut commands: Commands,
+    assets: Res<EquipmentAssets>,
+    wearers: Query<(Entity, &Equipment, Option<&Children>), Changed<Equipment>>,
+    visuals: Query<(), With<EquipmentVisual>>,
+) {
+    for (wearer, equipment, children) in wearers {
+        for child in children.into_iter().flatten() {
+            if visuals.contains(*child) {
+                commands.entity(*child).despawn();
+            }
+        }
+
+        for piece in equipment.pieces() {
+            let offset = match piece {
+                ArmorPiece::Helmet => Vec3::new(0.0, 1.05, 0.0),
+                ArmorPiece::Vest => Vec3::new(0.0, 0.3, 0.0),
+            };
+
+            commands.entity(wearer).with_child((
+                Mesh3d(assets.mesh(piece)),
+                MeshMaterial3d(assets.material.clone()),
+                Transform::from_translation(offset),
+                EquipmentVisual,
+            ));
+        }
+    }
+}
+
+fn spin_pickups(time: Res<Time>, pickups: Query<&mut Transform, With<ArmorPickup>>) {
+    for mut transform in pickups {
+        transform.rotate_y(time.delta_secs() * 1.5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equipping_armor_replaces_the_slot_and_adds_weight() {
+        let mut equipment = Equipment::default();
+
+        assert_eq!(equipment.equip(ArmorPiece::Vest), None);
+        assert_eq!(equipment.equip(ArmorPiece::Helmet), None);
+        assert_eq!(equipment.equip(ArmorPiece::Vest), Some(ArmorPiece::Vest));
+
+        let weight = ArmorPiece::Vest.weight() + ArmorPiece::Helmet.weight();
+        assert!((equipment.weight() - weight).abs() < 1e-6);
+    }
+}
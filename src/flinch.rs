@@ -0,0 +1,155 @@
+//! Hit reactions.
+//!
+//! A body that [`Flinches`] jerks away from a hit that doesn't kill it: the hitbox nearest the
+//! point of impact, its head or its torso, is knocked back along the round's path and tipped over
+//! with it, then eases back. The flinch is added on top of wherever anything else has put the
+//! segment that frame, so it layers over movement rather than replacing it, and a body that's just
+//! flinched shrugs off hits for a moment so a stream of fire can't hold it in place.
+
+use bevy::prelude::*;
+
+use crate::damage::{DamageEvent, Hitbox};
+
+pub struct FlinchPlugin;
+
+impl Plugin for FlinchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (start_flinches, flinch)
+                .chain()
+                .before(TransformSystems::Propagate),
+        );
+    }
+}
+
+/// A body that reacts to hits.
+#[derive(Component, Debug, Default)]
+pub struct Flinches {
+    /// Seconds until it flinches again
+    cooldown: f32,
+}
+
+impl Flinches {
+    /// Seconds a flinch takes, from the hit back to rest
+    const DURATION: f32 = 0.35;
+    /// Seconds after a flinch starts before another can
+    const COOLDOWN: f32 = 0.6;
+    /// Metres a segment is knocked back
+    const DISTANCE: f32 = 0.08;
+    /// Radians a segment tips over
+    const ANGLE: f32 = 0.25;
+}
+
+/// A segment partway through a flinch.
+#[derive(Component, Debug)]
+struct Flinch {
+    /// The way it was hit, in its parent's space
+    direction: Vec3,
+    elapsed: f32,
+    /// What was added to its transform last frame, taken off again before adding this frame's
+    applied: Transform,
+}
+
+impl Flinch {
+    /// How far through its knock back the segment is at `elapsed` seconds, snapping out and
+    /// easing back
+    fn weight(elapsed: f32) -> f32 {
+        let t = (elapsed / Flinches::DURATION).clamp(0.0, 1.0);
+        (1.0 - t).powi(2) * (t * 8.0).min(1.0)
+    }
+
+    fn offset(&self) -> Transform {
+        let weight = Self::weight(self.elapsed);
+        let axis = Vec3::Y.cross(self.direction).normalize_or_zero();
+
+        Transform::from_translation(self.direction * Flinches::DISTANCE * weight)
+            .with_rotation(Quat::from_axis_angle(axis, Flinches::ANGLE * weight))
+    }
+}
+
+fn start_flinches(
+    mut commands: Commands,
+    mut damage_reader: MessageReader<DamageEvent>,
+    time: Res<Time>,
+    mut bodies: Query<(&mut Flinches, &GlobalTransform, Option<&Children>)>,
+    segments: Query<(&GlobalTransform, Option<&ChildOf>, Option<&Flinch>), With<Hitbox>>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for (mut flinches, ..) in &mut bodies {
+        flinches.cooldown -= time.delta_secs();
+    }
+
+    for event in damage_reader.read() {
+        if event.killed {
+            continue;
+        }
+
+        let Ok((mut flinches, body_transform, children)) = bodies.get_mut(event.target) else {
+            continue;
+        };
+
+        if flinches.cooldown > 0.0 {
+            continue;
+        }
+
+        let Some((segment, (_, parent, flinch))) = [event.target]
+            .into_iter()
+            .chain(children.into_iter().flatten().copied())
+            .filter_map(|segment| Some((segment, segments.get(segment).ok()?)))
+            .min_by(|(_, (a, ..)), (_, (b, ..))| {
+                let a = a.translation().distance_squared(event.point);
+                let b = b.translation().distance_squared(event.point);
+                a.total_cmp(&b)
+            })
+        else {
+            continue;
+        };
+
+        // along the round's path, or straight out from the body if the shooter's gone
+        let from = transforms
+            .get(event.source)
+            .map_or(body_transform.translation(), GlobalTransform::translation);
+        let Ok(direction) = Dir3::new((event.point - from).with_y(0.0)) else {
+            continue;
+        };
+
+        let parent_rotation = parent
+            .and_then(|parent| transforms.get(parent.parent()).ok())
+            .map_or(Quat::IDENTITY, |parent| parent.rotation());
+
+        commands.entity(segment).insert(Flinch {
+            direction: parent_rotation.inverse() * *direction,
+            elapsed: 0.0,
+            // carry on from wherever a flinch already under way had it
+            applied: flinch.map_or(Transform::IDENTITY, |flinch| flinch.applied),
+        });
+
+        flinches.cooldown = Flinches::COOLDOWN;
+        debug!("{segment} flinched from a hit on {}", event.target);
+    }
+}
+
+fn flinch(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut segments: Query<(Entity, &mut Flinch, &mut Transform)>,
+) {
+    for (segment, mut flinch, mut transform) in &mut segments {
+        // take off last frame's, leaving wherever anything else has put it since
+        transform.translation -= flinch.applied.translation;
+        transform.rotation = flinch.applied.rotation.inverse() * transform.rotation;
+
+        flinch.elapsed += time.delta_secs();
+
+        if flinch.elapsed >= Flinches::DURATION {
+            commands.entity(segment).remove::<Flinch>();
+            continue;
+        }
+
+        let offset = flinch.offset();
+        transform.translation += offset.translation;
+        transform.rotation = offset.rotation * transform.rotation;
+        flinch.applied = offset;
+    }
+}
@@ -0,0 +1,93 @@
+//! Asset files gameplay needs, loaded up front.
+//!
+//! [`GameAssets`] loads every weapon definition at startup, then each weapon's model as its
+//! definition comes in, and holds on to them all. Nothing is loaded the first time it's fired or
+//! switched to, and anywhere else that loads one of these paths gets the same handle back
+//! straight away. The loading screen waits for them before a level starts.
+//!
+//! Sounds and effects don't come from files, they're built at startup by the plugins that play
+//! them, such as `hud`'s hit confirms and `particles`, and kept for the whole game already.
+
+use bevy::{
+    asset::{AssetPath, UntypedAssetId},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::weapon::WeaponDef;
+
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, preload_game_assets)
+            .add_systems(Update, preload_weapon_models);
+    }
+}
+
+/// Strong handles to every asset file gameplay uses, see the module docs.
+#[derive(Resource, Debug, Default)]
+pub struct GameAssets {
+    /// Every weapon definition, by path
+    pub weapons: HashMap<&'static str, Handle<WeaponDef>>,
+    /// The models of the weapons defined so far, by path
+    pub weapon_models: HashMap<AssetPath<'static>, Handle<Scene>>,
+}
+
+impl GameAssets {
+    /// The weapon definitions that ship with the game, the first being the one new profiles use
+    pub const WEAPONS: [&'static str; 2] = [
+        "weapons/mpx/mpx.weapon.ron",
+        "weapons/mpx/mpx_sd.weapon.ron",
+    ];
+
+    /// Every handle held, for the loading screen to wait on
+    pub fn ids(&self) -> impl Iterator<Item = UntypedAssetId> + '_ {
+        self.weapons
+            .values()
+            .map(|handle| handle.id().untyped())
+            .chain(
+                self.weapon_models
+                    .values()
+                    .map(|handle| handle.id().untyped()),
+            )
+    }
+}
+
+fn preload_game_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let weapons = GameAssets::WEAPONS
+        .into_iter()
+        .map(|path| (path, asset_server.load(path)))
+        .collect();
+
+    commands.insert_resource(GameAssets {
+        weapons,
+        ..default()
+    });
+}
+
+/// Loads each weapon's model as soon as its definition has loaded, or been edited to use another.
+fn preload_weapon_models(
+    mut def_events: MessageReader<AssetEvent<WeaponDef>>,
+    defs: Res<Assets<WeaponDef>>,
+    asset_server: Res<AssetServer>,
+    mut game_assets: ResMut<GameAssets>,
+) {
+    for event in def_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        let Some(def) = defs.get(*id) else {
+            continue;
+        };
+
+        let path = def.model_path();
+        if !game_assets.weapon_models.contains_key(&path) {
+            debug!("preloading weapon model {path}");
+            let model = asset_server.load(path.clone());
+            game_assets.weapon_models.insert(path, model);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Structured session log for balancing, written as one JSON object per line.
+//!
+//! Only compiled with the `gameplay_log` feature. Each session writes to
+//! `logs/session-<unix seconds>.jsonl` in the working directory.
+
+use std::{
+    fs::{self, File},
+    io::{LineWriter, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::ShotFired;
+use crate::damage::{DamageEvent, HitZone};
+use crate::energy::StaminaDepleted;
+
+pub struct GameplayLogPlugin;
+
+impl Plugin for GameplayLogPlugin {
+    fn build(&self, app: &mut App) {
+        match GameplayLog::create() {
+            Ok(log) => {
+                app.insert_resource(log)
+                    .add_systems(Update, (log_shots, log_damage, log_exhaustion));
+            }
+            Err(err) => error!("failed to create gameplay log: {err}"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum GameplayEvent {
+    Shot {
+        shooter: u64,
+        origin: [f32; 3],
+        direction: [f32; 3],
+    },
+    Hit {
+        shooter: u64,
+        target: u64,
+        amount: f32,
+        zone: &'static str,
+        armor: bool,
+        point: [f32; 3],
+    },
+    Kill {
+        shooter: u64,
+        target: u64,
+    },
+    Death {
+        entity: u64,
+    },
+    EnergyExhausted {
+        entity: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Wall clock time in milliseconds since the unix epoch
+    timestamp: u128,
+    /// Seconds since the app started
+    elapsed: f32,
+    #[serde(flatten)]
+    event: &'a GameplayEvent,
+}
+
+#[derive(Resource)]
+struct GameplayLog {
+    writer: LineWriter<File>,
+}
+
+impl GameplayLog {
+    const DIRECTORY: &'static str = "logs";
+
+    fn create() -> std::io::Result<Self> {
+        fs::create_dir_all(Self::DIRECTORY)?;
+
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let path = format!("{}/session-{started}.jsonl", Self::DIRECTORY);
+        info!("writing gameplay log to {path}");
+
+        Ok(Self {
+            writer: LineWriter::new(File::create(path)?),
+        })
+    }
+
+    fn record(&mut self, time: &Time, event: GameplayEvent) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let record = Record {
+            timestamp,
+            elapsed: time.elapsed_secs(),
+            event: &event,
+        };
+
+        let result = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+
+        if let Err(err) = result {
+            warn!("failed to write gameplay log: {err}");
+        }
+    }
+}
+
+fn log_shots(
+    time: Res<Time>,
+    mut log: ResMut<GameplayLog>,
+    mut shot_reader: MessageReader<ShotFired>,
+) {
+    for shot in shot_reader.read() {
+        log.record(
+            &time,
+            GameplayEvent::Shot {
+                shooter: shot.shooter.to_bits(),
+                origin: shot.origin.to_array(),
+                direction: shot.direction.to_array(),
+            },
+        );
+    }
+}
+
+fn log_damage(
+    time: Res<Time>,
+    mut log: ResMut<GameplayLog>,
+    mut damage_reader: MessageReader<DamageEvent>,
+) {
+    for damage in damage_reader.read() {
+        let zone = match damage.zone {
+            HitZone::Body => "body",
+            HitZone::Head => "head",
+        };
+
+        log.record(
+            &time,
+            GameplayEvent::Hit {
+                shooter: damage.source.to_bits(),
+                target: damage.target.to_bits(),
+                amount: damage.amount,
+                zone,
+                armor: damage.armor_hit,
+                point: damage.point.to_array(),
+            },
+        );
+
+        if damage.killed {
+            log.record(
+                &time,
+                GameplayEvent::Kill {
+                    shooter: damage.source.to_bits(),
+                    target: damage.target.to_bits(),
+                },
+            );
+            log.record(
+                &time,
+                GameplayEvent::Death {
+                    entity: damage.target.to_bits(),
+                },
+            );
+        }
+    }
+}
+
+fn log_exhaustion(
+    time: Res<Time>,
+    mut log: ResMut<GameplayLog>,
+    mut depleted_reader: MessageReader<StaminaDepleted>,
+) {
+    for StaminaDepleted(entity) in depleted_reader.read() {
+        log.record(
+            &time,
+            GameplayEvent::EnergyExhausted {
+                entity: entity.to_bits(),
+            },
+        );
+    }
+}
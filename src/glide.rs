@@ -0,0 +1,464 @@
+//! Falls, landing rolls and gliding.
+//!
+//! Every character controller off the ground is [`Falling`], and lands hard enough to hurt once
+//! they're coming down faster than [`Falling::SAFE_SPEED`]. Players who press crouch (or the east
+//! face button) within their [`RollWindow`] of a hard landing roll out of it instead, taking no
+//! damage: the camera tumbles forwards and they're carried on for a moment with their movement
+//! locked. Players who have dropped further than
+//! [`Gliding::DEPLOY_HEIGHT`] can jump again to open a glider: it brakes them to a slow sink, carries
+//! them forwards steered by movement, tilts the camera into turns and puts their weapons away.
+//! Jumping again folds it, and landing under it takes a fraction of the damage. Anything hanging
+//! off a zipline or grapple isn't falling.
+
+use std::f32::consts::TAU;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::damage::{Dead, Health};
+use crate::difficulty::Difficulty;
+use crate::menu::GameState;
+use crate::movement::{
+    CharacterController, Grounded, MovementAction, MovementIntent, MovementKind, MovementLocked,
+    RollWindow, Suspended, apply_movement_damping,
+};
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::{Player, PlayerCamera, PlayerWeapon, SwayTarget, holster_weapons};
+
+pub struct GlidePlugin;
+
+impl Plugin for GlidePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(stow_glider)
+            .add_observer(finish_roll)
+            .add_systems(
+                Update,
+                (
+                    (deploy_gliders, brace_for_landing).run_if(in_state(GameState::InGame)),
+                    tilt_glider_cameras,
+                    tumble_roll_cameras,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                // after the controller's own damping, which would otherwise eat the glide and roll
+                (glide, roll, track_falls)
+                    .chain()
+                    .after(apply_movement_damping),
+            );
+    }
+}
+
+/// A character in the air, since they last stood on something.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Falling {
+    /// The highest they've been on the way
+    pub peak: f32,
+    /// How fast they were coming down at the last step
+    pub speed: f32,
+    /// Seconds since they last pressed crouch on the way down
+    since_crouch: f32,
+}
+
+impl Falling {
+    /// Landing slower than this, in metres per second, doesn't hurt
+    const SAFE_SPEED: f32 = 12.0;
+    /// Damage per metre per second over the safe speed
+    const DAMAGE: f32 = 8.0;
+
+    pub fn new(peak: f32) -> Self {
+        Self {
+            peak,
+            speed: 0.0,
+            since_crouch: f32::INFINITY,
+        }
+    }
+
+    /// How far below their peak they are
+    pub fn dropped(&self, height: f32) -> f32 {
+        self.peak - height
+    }
+
+    /// Crouch, ready to roll when they land
+    pub fn brace(&mut self) {
+        self.since_crouch = 0.0;
+    }
+
+    /// Whether landing now would hurt, but they braced for it within `window` seconds
+    pub fn rolls(&self, window: f32) -> bool {
+        Self::damage(self.speed) > 0.0 && self.since_crouch <= window
+    }
+
+    /// The damage a landing at `speed` does, before any glider
+    pub fn damage(speed: f32) -> f32 {
+        (speed - Self::SAFE_SPEED).max(0.0) * Self::DAMAGE
+    }
+}
+
+/// A player under an open glider, see the module docs.
+#[derive(Component, Debug, Default)]
+#[component(storage = "SparseSet")]
+pub struct Gliding {
+    /// Where the glider is carrying them on the level, kept apart from the controller's damping
+    velocity: Vec2,
+}
+
+impl Gliding {
+    /// How far a player has to have fallen before the glider opens
+    const DEPLOY_HEIGHT: f32 = 4.0;
+    /// Metres per second the glider sinks at
+    const SINK: f32 = 3.0;
+    /// Metres per second per second the glider takes off a faster fall
+    const BRAKE: f32 = 20.0;
+    /// Metres per second forwards with no input, and how much pushing or pulling changes it
+    const SPEED: f32 = 9.0;
+    const SPEED_CONTROL: f32 = 5.0;
+    /// Metres per second sideways at full strafe
+    const STRAFE: f32 = 5.0;
+    /// Seconds to settle into a new heading
+    const RESPONSE: f32 = 0.4;
+    /// Radians the camera rolls at full strafe
+    const TILT: f32 = 0.2;
+    /// Share of the landing damage taken under a glider
+    const DAMAGE_SCALE: f32 = 0.25;
+}
+
+/// A player rolling out of a hard landing, see the module docs.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Rolling {
+    /// Which way they're rolling, on the level
+    direction: Vec2,
+    elapsed: f32,
+    /// How far the camera has tumbled so far, in radians
+    tumbled: f32,
+}
+
+impl Rolling {
+    const DURATION: f32 = 0.6;
+    /// Metres per second they're thrown forwards at, slowing to a stop by the end
+    const SPEED: f32 = 7.0;
+
+    /// How far through the roll they are, from 0 to 1
+    fn progress(&self) -> f32 {
+        (self.elapsed / Self::DURATION).clamp(0.0, 1.0)
+    }
+}
+
+fn deploy_gliders(
+    mut commands: Commands,
+    mut movement_reader: MessageReader<MovementAction>,
+    players: Query<(&Transform, &Falling, &LinearVelocity, Has<Gliding>), With<Player>>,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    for action in movement_reader.read() {
+        let MovementKind::Jump = action.kind else {
+            continue;
+        };
+
+        let Ok((transform, falling, velocity, gliding)) = players.get(action.controller) else {
+            continue;
+        };
+
+        // `stow_glider` does the rest
+        if gliding {
+            commands.entity(action.controller).try_remove::<Gliding>();
+            continue;
+        }
+
+        if falling.dropped(transform.translation.y) < Gliding::DEPLOY_HEIGHT {
+            continue;
+        }
+
+        commands.entity(action.controller).insert(Gliding {
+            velocity: velocity.xz(),
+        });
+        holster_weapons(&mut commands, &weapons, action.controller, true);
+
+        debug!("{} opened their glider", action.controller);
+    }
+}
+
+fn stow_glider(
+    remove: On<Remove, Gliding>,
+    mut commands: Commands,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    holster_weapons(&mut commands, &weapons, remove.entity, false);
+
+    debug!("{} stowed their glider", remove.entity);
+}
+
+fn glide(
+    time: Res<Time>,
+    gliders: Query<
+        (
+            &mut Gliding,
+            &MovementIntent,
+            &Transform,
+            &mut LinearVelocity,
+        ),
+        Without<Suspended>,
+    >,
+) {
+    let delta = time.delta_secs();
+    let settle = 1.0 - (-delta / Gliding::RESPONSE).exp();
+
+    for (mut gliding, intent, transform, mut velocity) in gliders {
+        let heading = transform.rotation
+            * Vec3::new(
+                intent.direction.x * Gliding::STRAFE,
+                0.0,
+                -(Gliding::SPEED + intent.direction.y * Gliding::SPEED_CONTROL),
+            );
+
+        gliding.velocity = gliding.velocity.lerp(heading.xz(), settle);
+
+        velocity.x = gliding.velocity.x;
+        velocity.z = gliding.velocity.y;
+
+        if velocity.y < -Gliding::SINK {
+            velocity.y = (velocity.y + Gliding::BRAKE * delta).min(-Gliding::SINK);
+        }
+    }
+}
+
+fn track_falls(
+    mut commands: Commands,
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
+    characters: Query<
+        (
+            Entity,
+            &Transform,
+            &LinearVelocity,
+            Option<&mut Falling>,
+            Has<Grounded>,
+            Has<Suspended>,
+            Has<Gliding>,
+            Option<&mut Health>,
+            Option<&RollWindow>,
+            Has<Player>,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    for (
+        entity,
+        transform,
+        velocity,
+        falling,
+        grounded,
+        suspended,
+        gliding,
+        health,
+        roll_window,
+        player,
+    ) in characters
+    {
+        let height = transform.translation.y;
+
+        // hanging on, they start falling again from wherever they let go
+        if suspended {
+            if falling.is_some() || gliding {
+                commands.entity(entity).remove::<(Falling, Gliding)>();
+            }
+            continue;
+        }
+
+        let Some(mut falling) = falling else {
+            if !grounded {
+                commands.entity(entity).insert(Falling::new(height));
+            }
+            continue;
+        };
+
+        if !grounded {
+            falling.peak = falling.peak.max(height);
+            falling.speed = -velocity.y;
+            falling.since_crouch += time.delta_secs();
+            continue;
+        }
+
+        commands.entity(entity).remove::<(Falling, Gliding)>();
+
+        if let Some(RollWindow(window)) = roll_window
+            && falling.rolls(*window)
+        {
+            // forwards the way they're looking, whichever way they were falling
+            let direction = transform.forward().xz().normalize_or(Vec2::NEG_Y);
+
+            commands.entity(entity).insert((
+                Rolling {
+                    direction,
+                    elapsed: 0.0,
+                    tumbled: 0.0,
+                },
+                MovementLocked,
+            ));
+
+            debug!("{entity} rolled out of a {:.1} m/s landing", falling.speed);
+            continue;
+        }
+
+        let mut damage = Falling::damage(falling.speed);
+
+        if gliding {
+            damage *= Gliding::DAMAGE_SCALE;
+        }
+
+        if player {
+            damage *= difficulty.preset().damage_taken;
+        }
+
+        let Some(mut health) = health.filter(|_| damage > 0.0) else {
+            continue;
+        };
+
+        health.current = (health.current - damage).max(0.0);
+
+        debug!(
+            "{entity} landed at {:.1} m/s from {:.1} m for {damage:.1} damage",
+            falling.speed,
+            falling.dropped(height)
+        );
+
+        if health.is_dead() {
+            commands.entity(entity).insert(Dead);
+        }
+    }
+}
+
+/// Rolls gliding players' cameras into their turns, and back level once they're down.
+fn tilt_glider_cameras(
+    time: Res<Time>,
+    // the tumble of a roll isn't a roll of the camera this could level out
+    players: Query<(&MovementIntent, Has<Gliding>), (With<Player>, Without<Rolling>)>,
+    cameras: Query<(&ChildOf, &mut Transform), With<PlayerCamera>>,
+) {
+    let settle = 1.0 - (-time.delta_secs() / Gliding::RESPONSE).exp();
+
+    for (child_of, mut transform) in cameras {
+        let Ok((intent, gliding)) = players.get(child_of.parent()) else {
+            continue;
+        };
+
+        let target = if gliding {
+            -intent.direction.x * Gliding::TILT
+        } else {
+            0.0
+        };
+
+        // the pitch from mouse look is applied outside this, so the roll comes off cleanly
+        let (.., roll) = transform.rotation.to_euler(EulerRot::XYZ);
+
+        if (roll - target).abs() < 0.001 {
+            continue;
+        }
+
+        transform.rotate_local_z((target - roll) * settle);
+    }
+}
+
+fn brace_for_landing(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(&PlayerInput, &mut Falling), With<Player>>,
+) {
+    for (input, mut falling) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.crouch);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::East));
+
+        if keyboard || gamepad {
+            falling.brace();
+        }
+    }
+}
+
+fn roll(
+    mut commands: Commands,
+    time: Res<Time>,
+    rollers: Query<(Entity, &mut Rolling, &mut LinearVelocity)>,
+) {
+    for (entity, mut rolling, mut velocity) in rollers {
+        rolling.elapsed += time.delta_secs();
+
+        if rolling.elapsed >= Rolling::DURATION {
+            commands.entity(entity).remove::<Rolling>();
+            continue;
+        }
+
+        let speed = Rolling::SPEED * (1.0 - rolling.progress());
+        velocity.x = rolling.direction.x * speed;
+        velocity.z = rolling.direction.y * speed;
+    }
+}
+
+/// Tumbles rolling players' cameras head over heels, once round over the roll.
+fn tumble_roll_cameras(
+    mut players: Query<&mut Rolling, With<Player>>,
+    cameras: Query<(&ChildOf, &mut Transform), With<PlayerCamera>>,
+) {
+    for (child_of, mut transform) in cameras {
+        let Ok(mut rolling) = players.get_mut(child_of.parent()) else {
+            continue;
+        };
+
+        let progress = rolling.progress();
+        let turn = TAU * progress * progress * (3.0 - 2.0 * progress);
+
+        transform.rotate_local_x(rolling.tumbled - turn);
+        rolling.tumbled = turn;
+    }
+}
+
+/// Brings the camera the rest of the way round and hands movement back.
+fn finish_roll(
+    remove: On<Remove, Rolling>,
+    mut commands: Commands,
+    rollers: Query<(&Rolling, &Children)>,
+    mut cameras: Query<&mut Transform, With<PlayerCamera>>,
+) {
+    let Ok((rolling, children)) = rollers.get(remove.entity) else {
+        return;
+    };
+
+    let mut cameras = cameras.iter_many_mut(children);
+    while let Some(mut camera) = cameras.fetch_next() {
+        camera.rotate_local_x(rolling.tumbled - TAU);
+    }
+
+    commands
+        .entity(remove.entity)
+        .try_remove::<MovementLocked>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hard_landings_hurt() {
+        assert_eq!(Falling::damage(0.0), 0.0);
+        assert_eq!(Falling::damage(11.0), 0.0);
+        assert!(Falling::damage(20.0) > Falling::damage(15.0));
+    }
+
+    #[test]
+    fn bracing_just_before_a_hard_landing_rolls() {
+        let mut falling = Falling::new(10.0);
+        falling.speed = 20.0;
+        assert!(!falling.rolls(0.25), "didn't crouch");
+
+        falling.brace();
+        assert!(falling.rolls(0.25));
+
+        falling.speed = 5.0;
+        assert!(!falling.rolls(0.25), "soft landings don't need a roll");
+    }
+}
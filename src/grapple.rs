@@ -0,0 +1,309 @@
+//! The grappling hook.
+//!
+//! Players fire the hook with the grapple key (or the right bumper) along their view, and it bites
+//! into the first thing it hits if that's a [`GrappleSurface`]. Once hooked, the rope reels in
+//! like a stiff spring: it only ever pulls, so with slack in it they fall and swing under the
+//! anchor, steering with movement and letting out rope by pulling back. Firing again or jumping
+//! lets go, keeping whatever speed the swing built up. Each shot costs stamina.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::energy::{EnergyAction, EnergyCosts, Stamina};
+use crate::menu::GameState;
+use crate::movement::{MovementIntent, Suspended};
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::turret::Manning;
+use crate::vehicle::Driving;
+use crate::zipline::Ziplining;
+use crate::{Player, PlayerCamera};
+
+pub struct GrapplePlugin;
+
+impl Plugin for GrapplePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_grapple_assets)
+            .add_observer(release_grapple)
+            .add_systems(Update, fire_grapples.run_if(in_state(GameState::InGame)))
+            .add_systems(FixedUpdate, reel_grapples)
+            .add_systems(PostUpdate, draw_cables.before(TransformSystems::Propagate));
+    }
+}
+
+/// A marker component for colliders the grappling hook can bite into.
+#[derive(Component, Debug)]
+pub struct GrappleSurface;
+
+/// A player hanging off their grappling hook.
+#[derive(Component, Debug)]
+pub struct Grappling {
+    /// What the hook is stuck in
+    pub surface: Entity,
+    /// Where the hook is, in the surface's space so it moves with it
+    anchor: Vec3,
+    /// Rope paid out, in metres
+    pub length: f32,
+    cable: Entity,
+}
+
+impl Grappling {
+    /// How far the hook flies
+    const RANGE: f32 = 40.0;
+    /// Metres of rope reeled in per second
+    const REEL_SPEED: f32 = 6.0;
+    /// Metres of rope let out per second pulling back
+    const PAY_OUT_SPEED: f32 = 4.0;
+    /// Rope left when it's reeled all the way in
+    const MIN_LENGTH: f32 = 2.0;
+    /// Metres per second per second pulled, per metre the rope is stretched
+    const STIFFNESS: f32 = 40.0;
+    /// Per metre per second the rope is stretching
+    const DAMPING: f32 = 4.0;
+    /// Metres per second per second of steering while swinging
+    const SWING_CONTROL: f32 = 6.0;
+    /// Upwards speed added jumping off
+    const JUMP_OFF: f32 = 4.0;
+    /// Height of the rope's end above a player's centre
+    const HANDS: f32 = 0.5;
+}
+
+/// The acceleration a rope of `length` puts on whatever's on the end of it, `offset` from the
+/// anchor to its end and moving at `velocity`. Slack ropes don't pull and ropes never push.
+pub fn rope_pull(offset: Vec3, velocity: Vec3, length: f32) -> Vec3 {
+    let distance = offset.length();
+    let stretch = distance - length;
+
+    if stretch <= 0.0 || distance <= f32::EPSILON {
+        return Vec3::ZERO;
+    }
+
+    let outwards = offset / distance;
+    let stretching = velocity.dot(outwards);
+    let pull = Grappling::STIFFNESS * stretch + Grappling::DAMPING * stretching;
+
+    -outwards * pull.max(0.0)
+}
+
+/// The rope between a player and their hook.
+#[derive(Component)]
+struct GrappleCable;
+
+#[derive(Resource)]
+struct GrappleAssets {
+    cable_mesh: Handle<Mesh>,
+    cable_material: Handle<StandardMaterial>,
+}
+
+fn setup_grapple_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(GrappleAssets {
+        // a metre long, stretched to the rope's length
+        cable_mesh: meshes.add(Cylinder::new(0.015, 1.0)),
+        cable_material: materials.add(Color::srgb_u8(40, 40, 45)),
+    });
+}
+
+fn fire_grapples(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    costs: Res<EnergyCosts>,
+    assets: Res<GrappleAssets>,
+    spatial_query: SpatialQuery,
+    gamepads: Query<&Gamepad>,
+    players: Query<
+        (
+            Entity,
+            &PlayerInput,
+            &Children,
+            Option<&mut Stamina>,
+            Has<Grappling>,
+        ),
+        (
+            With<Player>,
+            Without<Driving>,
+            Without<Manning>,
+            Without<Ziplining>,
+        ),
+    >,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    surfaces: Query<&GlobalTransform, With<GrappleSurface>>,
+) {
+    for (player, input, children, stamina, grappling) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.grapple);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::RightTrigger));
+
+        if !keyboard && !gamepad {
+            continue;
+        }
+
+        // `release_grapple` does the rest
+        if grappling {
+            commands.entity(player).remove::<Grappling>();
+            continue;
+        }
+
+        let Some(eyes) = children.iter().find_map(|child| cameras.get(child).ok()) else {
+            continue;
+        };
+
+        let Some(hit) = spatial_query.cast_ray(
+            eyes.translation(),
+            eyes.forward(),
+            Grappling::RANGE,
+            true,
+            &SpatialQueryFilter::from_excluded_entities([player]),
+        ) else {
+            continue;
+        };
+
+        let Ok(surface) = surfaces.get(hit.entity) else {
+            debug!("{player}'s grapple bounced off {}", hit.entity);
+            continue;
+        };
+
+        if let Some(mut stamina) = stamina
+            && !stamina.try_spend(costs.get(EnergyAction::Grapple))
+        {
+            continue;
+        }
+
+        let point = eyes.translation() + eyes.forward() * hit.distance;
+
+        let cable = commands
+            .spawn((
+                GrappleCable,
+                Mesh3d(assets.cable_mesh.clone()),
+                MeshMaterial3d(assets.cable_material.clone()),
+            ))
+            .id();
+
+        commands.entity(player).insert((
+            Grappling {
+                surface: hit.entity,
+                anchor: surface.affine().inverse().transform_point3(point),
+                // starts taut, and reels in from there
+                length: hit.distance,
+                cable,
+            },
+            Suspended,
+        ));
+
+        debug!("{player} grappled {} at {point:.1}", hit.entity);
+    }
+}
+
+/// Takes the rope away and hands the player back to the character controller.
+fn release_grapple(
+    remove: On<Remove, Grappling>,
+    mut commands: Commands,
+    grapples: Query<&Grappling>,
+) {
+    let Ok(grappling) = grapples.get(remove.entity) else {
+        return;
+    };
+
+    commands.entity(grappling.cable).try_despawn();
+    commands.entity(remove.entity).try_remove::<Suspended>();
+
+    debug!("{} let go of their grapple", remove.entity);
+}
+
+fn reel_grapples(
+    mut commands: Commands,
+    time: Res<Time>,
+    players: Query<(
+        Entity,
+        &mut Grappling,
+        &mut MovementIntent,
+        &Transform,
+        &mut LinearVelocity,
+    )>,
+    surfaces: Query<&GlobalTransform, With<GrappleSurface>>,
+) {
+    let delta = time.delta_secs();
+
+    for (player, mut grappling, mut intent, transform, mut velocity) in players {
+        let Ok(surface) = surfaces.get(grappling.surface) else {
+            commands.entity(player).remove::<Grappling>();
+            continue;
+        };
+
+        // no dashing on the end of a rope
+        intent.dash = false;
+
+        if std::mem::take(&mut intent.jump) {
+            velocity.y += Grappling::JUMP_OFF;
+            commands.entity(player).remove::<Grappling>();
+            continue;
+        }
+
+        let reel = if intent.direction.y < 0.0 {
+            Grappling::PAY_OUT_SPEED * -intent.direction.y
+        } else {
+            -Grappling::REEL_SPEED
+        };
+        grappling.length =
+            (grappling.length + reel * delta).clamp(Grappling::MIN_LENGTH, Grappling::RANGE);
+
+        // steering the swing, on the level
+        let steer =
+            transform.rotation * Vec3::new(intent.direction.x, 0.0, -intent.direction.y.max(0.0));
+        let steer = steer.with_y(0.0).normalize_or_zero() * intent.direction.length();
+
+        let anchor = surface.transform_point(grappling.anchor);
+        let hands = transform.translation + Vec3::Y * Grappling::HANDS;
+        let pull = rope_pull(hands - anchor, velocity.0, grappling.length);
+
+        velocity.0 += (pull + steer * Grappling::SWING_CONTROL) * delta;
+    }
+}
+
+/// Stretches each rope from its player's hands to the hook.
+fn draw_cables(
+    players: Query<(&Grappling, &Transform), Without<GrappleCable>>,
+    surfaces: Query<&GlobalTransform, With<GrappleSurface>>,
+    mut cables: Query<&mut Transform, With<GrappleCable>>,
+) {
+    for (grappling, transform) in players {
+        let (Ok(surface), Ok(mut cable)) = (
+            surfaces.get(grappling.surface),
+            cables.get_mut(grappling.cable),
+        ) else {
+            continue;
+        };
+
+        let anchor = surface.transform_point(grappling.anchor);
+        let hands = transform.translation + Vec3::Y * Grappling::HANDS;
+        let rope = anchor - hands;
+
+        *cable = Transform::from_translation(hands.midpoint(anchor))
+            .with_rotation(Quat::from_rotation_arc(Vec3::Y, rope.normalize_or(Vec3::Y)))
+            .with_scale(Vec3::new(1.0, rope.length(), 1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grapple_rope_only_pulls_when_stretched() {
+        // slack, or taut and still, hangs free or holds on
+        assert_eq!(rope_pull(Vec3::NEG_Y * 3.0, Vec3::ZERO, 5.0), Vec3::ZERO);
+
+        // stretched, it pulls back towards the anchor
+        let pull = rope_pull(Vec3::NEG_Y * 6.0, Vec3::ZERO, 5.0);
+        assert!(pull.y > 0.0 && pull.x == 0.0);
+
+        // and never pushes, even springing back quickly
+        let pull = rope_pull(Vec3::NEG_Y * 5.1, Vec3::Y * 20.0, 5.0);
+        assert_eq!(pull, Vec3::ZERO);
+    }
+}
@@ -0,0 +1,831 @@
+use std::time::Duration;
+
+use bevy::{audio::Pitch, platform::collections::HashMap, prelude::*};
+
+use crate::accuracy::Accuracy;
+use crate::damage::{DamageEvent, Health, HealthRegen, HitZone};
+use crate::difficulty::Difficulty;
+use crate::encumbrance::Encumbrance;
+use crate::level::Level;
+use crate::menu::GameState;
+use crate::settings::GameSettings;
+use crate::split_screen::PrimaryPlayer;
+use crate::sway::RespiratoryPause;
+use crate::weapon::{FireMode, ReloadFinished, ReloadStarted, WeaponRaised, WeaponRaising};
+use crate::{Player, PlayerCamera, PlayerWeapon, SwayTarget, WeaponActive};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudTheme>()
+            .add_message::<Toast>()
+            .add_systems(
+                Startup,
+                (
+                    setup_hit_confirm_sounds,
+                    setup_encumbrance_warning,
+                    setup_health_bar,
+                    setup_toasts,
+                ),
+            )
+            .add_systems(OnEnter(GameState::InGame), setup_seed_label)
+            .add_systems(
+                Update,
+                (
+                    (
+                        setup_crosshairs,
+                        hit_confirm,
+                        fade_hitmarkers,
+                        show_respiratory_pause,
+                        show_weapon_raising,
+                        show_fire_mode,
+                    )
+                        .chain(),
+                    apply_hud_visibility.run_if(resource_changed::<Difficulty>),
+                    show_encumbrance_warning,
+                    update_health_bar,
+                    show_reloads,
+                    (show_toasts, expire_toasts).chain(),
+                ),
+            );
+    }
+}
+
+/// Colours and sizes shared by every HUD element, so they can be restyled in one place.
+#[derive(Resource, Debug, Clone)]
+pub struct HudTheme {
+    pub text: Color,
+    /// Secondary text and markings
+    pub dim_text: Color,
+    pub warning: Color,
+    pub objective: Color,
+    pub enemy: Color,
+    pub health: Color,
+    /// Lost health that will come back
+    pub regenerable: Color,
+    /// Background of HUD panels
+    pub panel: Color,
+    pub panel_border: Color,
+    /// The crosshair while the player's aim is steadied by their breathing
+    pub steady: Color,
+    pub font_size: f32,
+    pub small_font_size: f32,
+}
+
+impl Default for HudTheme {
+    fn default() -> Self {
+        Self {
+            text: Color::WHITE,
+            dim_text: Color::WHITE.with_alpha(0.7),
+            warning: Color::srgb(1.0, 0.55, 0.2),
+            objective: Color::srgb(1.0, 0.8, 0.1),
+            enemy: Color::srgb(1.0, 0.2, 0.2),
+            health: Color::srgb(0.9, 0.95, 0.9),
+            regenerable: Color::srgb(0.8, 0.3, 0.25).with_alpha(0.8),
+            panel: Color::BLACK.with_alpha(0.5),
+            panel_border: Color::WHITE.with_alpha(0.4),
+            steady: Color::srgb(0.75, 1.0, 0.8),
+            font_size: 18.0,
+            small_font_size: 14.0,
+        }
+    }
+}
+
+/// The kind of feedback a hit gives the shooter, ordered by priority so the most important
+/// confirmation of a frame wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HitConfirm {
+    Body,
+    Armor,
+    Headshot,
+    Kill,
+}
+
+impl HitConfirm {
+    pub fn from_damage(event: &DamageEvent) -> Self {
+        if event.killed {
+            HitConfirm::Kill
+        } else if event.zone == HitZone::Head {
+            HitConfirm::Headshot
+        } else if event.armor_hit {
+            HitConfirm::Armor
+        } else {
+            HitConfirm::Body
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            HitConfirm::Body => Color::WHITE,
+            HitConfirm::Armor => Color::srgb(0.45, 0.7, 1.0),
+            HitConfirm::Headshot => Color::srgb(1.0, 0.85, 0.2),
+            HitConfirm::Kill => Color::srgb(1.0, 0.15, 0.15),
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        match self {
+            HitConfirm::Kill => 1.6,
+            HitConfirm::Headshot => 1.25,
+            _ => 1.0,
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        match self {
+            HitConfirm::Kill => 0.5,
+            _ => 0.2,
+        }
+    }
+
+    /// Frequency (Hz) and length of the confirmation tone
+    fn tone(&self) -> (f32, Duration) {
+        match self {
+            HitConfirm::Body => (1400.0, Duration::from_millis(40)),
+            HitConfirm::Armor => (650.0, Duration::from_millis(60)),
+            HitConfirm::Headshot => (2200.0, Duration::from_millis(70)),
+            HitConfirm::Kill => (330.0, Duration::from_millis(220)),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct HitConfirmSounds {
+    body: Handle<Pitch>,
+    armor: Handle<Pitch>,
+    headshot: Handle<Pitch>,
+    kill: Handle<Pitch>,
+}
+
+impl HitConfirmSounds {
+    fn get(&self, confirm: HitConfirm) -> Handle<Pitch> {
+        match confirm {
+            HitConfirm::Body => self.body.clone(),
+            HitConfirm::Armor => self.armor.clone(),
+            HitConfirm::Headshot => self.headshot.clone(),
+            HitConfirm::Kill => self.kill.clone(),
+        }
+    }
+}
+
+/// The crosshair in `player`'s view.
+#[derive(Component)]
+pub struct Crosshair {
+    player: Entity,
+    /// The weapon coming up from a sprint, greying the crosshair out until it can fire
+    raising: Option<Entity>,
+}
+
+#[derive(Component)]
+struct CrosshairArm;
+
+/// Flashes when `player` lands a hit.
+#[derive(Component)]
+struct Hitmarker {
+    player: Entity,
+    timer: Timer,
+    color: Color,
+}
+
+#[derive(Component)]
+struct HitmarkerArm;
+
+/// Fills up under `player`'s crosshair while their weapon reloads.
+#[derive(Component)]
+struct ReloadBar {
+    player: Entity,
+    /// The weapon being reloaded
+    weapon: Option<Entity>,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct ReloadFill;
+
+#[derive(Component)]
+struct EncumbranceWarning;
+
+/// A short note shown at the top of the screen for a few seconds, such as something being
+/// unlocked.
+#[derive(Message, Debug, Clone)]
+pub struct Toast(pub String);
+
+/// The column toasts are stacked in, newest at the bottom.
+#[derive(Component)]
+struct ToastList;
+
+/// A toast on screen, until its timer runs out.
+#[derive(Component)]
+struct ShownToast(Timer);
+
+/// The player's health, drawn as one box per regeneration segment.
+#[derive(Component)]
+struct HealthBar;
+
+/// Part of a [`HealthBar`] segment, filled with either current or regenerable health.
+#[derive(Component)]
+struct HealthFill {
+    segment: usize,
+    regenerable: bool,
+}
+
+fn setup_hit_confirm_sounds(mut commands: Commands, mut pitches: ResMut<Assets<Pitch>>) {
+    let mut tone = |confirm: HitConfirm| {
+        let (frequency, duration) = confirm.tone();
+        pitches.add(Pitch::new(frequency, duration))
+    };
+
+    commands.insert_resource(HitConfirmSounds {
+        body: tone(HitConfirm::Body),
+        armor: tone(HitConfirm::Armor),
+        headshot: tone(HitConfirm::Headshot),
+        kill: tone(HitConfirm::Kill),
+    });
+}
+
+/// Gives each player's view its own crosshair and hitmarker.
+fn setup_crosshairs(
+    mut commands: Commands,
+    theme: Res<HudTheme>,
+    cameras: Query<(Entity, &ChildOf), Added<PlayerCamera>>,
+    difficulty: Res<Difficulty>,
+) {
+    let visibility = hud_visibility(&difficulty);
+
+    for (camera, child_of) in cameras {
+        spawn_crosshair(&mut commands, &theme, visibility, camera, child_of.parent());
+    }
+}
+
+fn spawn_crosshair(
+    commands: &mut Commands,
+    theme: &HudTheme,
+    visibility: Visibility,
+    camera: Entity,
+    player: Entity,
+) {
+    const GAP: f32 = 6.0;
+    const LENGTH: f32 = 8.0;
+    const THICKNESS: f32 = 2.0;
+    const HITMARKER_SIZE: f32 = 22.0;
+    const RELOAD_WIDTH: f32 = 40.0;
+    /// Pixels below the centre of the crosshair
+    const RELOAD_OFFSET: f32 = 28.0;
+
+    let arm = |left: f32, top: f32, width: f32, height: f32| {
+        (
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(left),
+                top: Val::Px(top),
+                width: Val::Px(width),
+                height: Val::Px(height),
+                ..default()
+            },
+            BackgroundColor(theme.text.with_alpha(0.8)),
+            CrosshairArm,
+        )
+    };
+
+    let centre = -THICKNESS / 2.0;
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            UiTargetCamera(camera),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(0.0),
+                        height: Val::Px(0.0),
+                        ..default()
+                    },
+                    visibility,
+                    UiTransform::default(),
+                    Crosshair {
+                        player,
+                        raising: None,
+                    },
+                ))
+                .with_children(|crosshair| {
+                    crosshair.spawn(arm(centre, -GAP - LENGTH, THICKNESS, LENGTH));
+                    crosshair.spawn(arm(centre, GAP, THICKNESS, LENGTH));
+                    crosshair.spawn(arm(-GAP - LENGTH, centre, LENGTH, THICKNESS));
+                    crosshair.spawn(arm(GAP, centre, LENGTH, THICKNESS));
+                });
+
+            parent
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(HITMARKER_SIZE),
+                        height: Val::Px(HITMARKER_SIZE),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    Hitmarker {
+                        player,
+                        timer: Timer::from_seconds(0.0, TimerMode::Once),
+                        color: Color::WHITE,
+                    },
+                    UiTransform::default(),
+                ))
+                .with_children(|hitmarker| {
+                    for degrees in [45.0, -45.0] {
+                        hitmarker.spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(HITMARKER_SIZE),
+                                height: Val::Px(THICKNESS),
+                                ..default()
+                            },
+                            UiTransform {
+                                rotation: Rot2::degrees(degrees),
+                                ..default()
+                            },
+                            BackgroundColor(Color::NONE),
+                            HitmarkerArm,
+                        ));
+                    }
+                });
+
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(RELOAD_WIDTH),
+                    height: Val::Px(THICKNESS * 2.0),
+                    ..default()
+                },
+                UiTransform::from_translation(Val2::px(0.0, RELOAD_OFFSET)),
+                BackgroundColor(theme.panel),
+                Visibility::Hidden,
+                ReloadBar {
+                    player,
+                    weapon: None,
+                    timer: Timer::from_seconds(0.0, TimerMode::Once),
+                },
+                children![(
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(theme.text.with_alpha(0.8)),
+                    ReloadFill,
+                )],
+            ));
+        });
+}
+
+/// Shows the seed of a generated level so a run can be shared and replayed.
+fn setup_seed_label(mut commands: Commands, level: Res<Level>, theme: Res<HudTheme>) {
+    let Level::Generated { seed } = *level else {
+        return;
+    };
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            bottom: Val::Px(12.0),
+            ..default()
+        },
+        Text::new(format!("Seed {seed}")),
+        TextFont::from_font_size(theme.small_font_size),
+        TextColor(theme.dim_text),
+        DespawnOnExit(GameState::InGame),
+    ));
+}
+
+fn setup_health_bar(mut commands: Commands, difficulty: Res<Difficulty>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            bottom: Val::Px(40.0),
+            width: Val::Px(200.0),
+            height: Val::Px(10.0),
+            column_gap: Val::Px(3.0),
+            ..default()
+        },
+        hud_visibility(&difficulty),
+        HealthBar,
+    ));
+}
+
+/// How full segment `index` of the bar is with current health, and with health that will still
+/// regenerate up to `cap`. The last segment may be cut short by the maximum health.
+fn health_segment_fill(index: usize, segment: f32, health: &Health, cap: f32) -> (f32, f32) {
+    let bottom = index as f32 * segment;
+    let size = (health.max - bottom).min(segment);
+
+    let fill = |amount: f32| ((amount - bottom) / size).clamp(0.0, 1.0);
+    let current = fill(health.current);
+
+    (current, fill(cap) - current)
+}
+
+fn update_health_bar(
+    mut commands: Commands,
+    theme: Res<HudTheme>,
+    difficulty: Res<Difficulty>,
+    player: Single<(&Health, &HealthRegen), With<PrimaryPlayer>>,
+    bar: Single<(Entity, Option<&Children>), With<HealthBar>>,
+    fills: Query<(&HealthFill, &mut Node)>,
+) {
+    /// More segments than this are too small to read, so the bar is drawn as one
+    const MAX_SEGMENTS: f32 = 20.0;
+
+    let (health, regen) = *player;
+    let settings = regen.effective(&difficulty);
+    let (bar, segments) = *bar;
+
+    let count = match (health.max / settings.segment).ceil() {
+        count if count.is_finite() && (1.0..=MAX_SEGMENTS).contains(&count) => count as usize,
+        _ => 1,
+    };
+
+    // rebuild the segments when their number changes, then fill them in from the next frame
+    if segments.map_or(0, |segments| segments.len()) != count {
+        commands
+            .entity(bar)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for segment in 0..count {
+                    parent.spawn((
+                        Node {
+                            flex_grow: 1.0,
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(theme.panel),
+                        children![
+                            (
+                                Node::default(),
+                                BackgroundColor(theme.health),
+                                HealthFill {
+                                    segment,
+                                    regenerable: false,
+                                },
+                            ),
+                            (
+                                Node::default(),
+                                BackgroundColor(theme.regenerable),
+                                HealthFill {
+                                    segment,
+                                    regenerable: true,
+                                },
+                            )
+                        ],
+                    ));
+                }
+            });
+        return;
+    }
+
+    let cap = settings.cap(health.current, health.max);
+    let segment = if count == 1 {
+        health.max
+    } else {
+        settings.segment
+    };
+
+    for (fill, mut node) in fills {
+        let (current, regenerable) = health_segment_fill(fill.segment, segment, health, cap);
+        let amount = if fill.regenerable {
+            regenerable
+        } else {
+            current
+        };
+
+        node.width = Val::Percent(amount * 100.0);
+        node.height = Val::Percent(100.0);
+    }
+}
+
+fn setup_encumbrance_warning(mut commands: Commands, theme: Res<HudTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(48.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            Text::new("OVER-ENCUMBERED"),
+            TextFont::from_font_size(theme.font_size),
+            TextColor(theme.warning),
+            Visibility::Hidden,
+            EncumbranceWarning,
+        )],
+    ));
+}
+
+fn setup_toasts(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(64.0),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        ToastList,
+    ));
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    theme: Res<HudTheme>,
+    mut toast_reader: MessageReader<Toast>,
+    list: Single<Entity, With<ToastList>>,
+) {
+    /// Seconds each toast stays up
+    const SHOWN_FOR: f32 = 4.0;
+
+    for toast in toast_reader.read() {
+        commands.spawn((
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(theme.panel),
+            BorderColor::all(theme.panel_border),
+            ShownToast(Timer::from_seconds(SHOWN_FOR, TimerMode::Once)),
+            ChildOf(*list),
+            children![(
+                Text::new(toast.0.clone()),
+                TextFont::from_font_size(theme.font_size),
+                TextColor(theme.objective),
+            )],
+        ));
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    toasts: Query<(Entity, &mut ShownToast)>,
+) {
+    for (toast, mut shown) in toasts {
+        if shown.0.tick(time.delta()).is_finished() {
+            commands.entity(toast).despawn();
+        }
+    }
+}
+
+fn hit_confirm(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    sounds: Res<HitConfirmSounds>,
+    mut damage_reader: MessageReader<DamageEvent>,
+    players_q: Query<(), With<Player>>,
+    hitmarkers: Query<(&mut Hitmarker, &mut UiTransform)>,
+) {
+    // the strongest confirmation of the frame wins, so a kill isn't masked by the hit before it
+    let mut confirms = HashMap::<Entity, HitConfirm>::new();
+
+    for event in damage_reader.read() {
+        if players_q.contains(event.source) {
+            let confirm = HitConfirm::from_damage(event);
+            let best = confirms.entry(event.source).or_insert(confirm);
+            *best = (*best).max(confirm);
+        }
+    }
+
+    if confirms.is_empty() || !settings.hit_confirmation() {
+        return;
+    }
+
+    for (mut hitmarker, mut ui_transform) in hitmarkers {
+        let Some(&confirm) = confirms.get(&hitmarker.player) else {
+            continue;
+        };
+
+        hitmarker.timer = Timer::from_seconds(confirm.duration(), TimerMode::Once);
+        hitmarker.color = confirm.color();
+        ui_transform.scale = Vec2::splat(confirm.scale());
+    }
+
+    // everyone shares the speakers, so only the best hit of the frame is heard
+    if let Some(&confirm) = confirms.values().max() {
+        commands.spawn((AudioPlayer(sounds.get(confirm)), PlaybackSettings::DESPAWN));
+    }
+}
+
+fn fade_hitmarkers(
+    time: Res<Time>,
+    hitmarkers: Query<(&mut Hitmarker, &Children)>,
+    mut arms_q: Query<&mut BackgroundColor, With<HitmarkerArm>>,
+) {
+    for (mut hitmarker, children) in hitmarkers {
+        hitmarker.timer.tick(time.delta());
+
+        let alpha = if hitmarker.timer.duration().is_zero() {
+            0.0
+        } else {
+            1.0 - hitmarker.timer.fraction()
+        };
+
+        for child in children {
+            if let Ok(mut background) = arms_q.get_mut(*child) {
+                background.0 = hitmarker.color.with_alpha(alpha);
+            }
+        }
+    }
+}
+
+fn hud_visibility(difficulty: &Difficulty) -> Visibility {
+    if difficulty.preset().show_hud {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn apply_hud_visibility(
+    difficulty: Res<Difficulty>,
+    crosshairs: Query<&mut Visibility, Or<(With<Crosshair>, With<HealthBar>)>>,
+) {
+    let visibility = hud_visibility(&difficulty);
+
+    for mut crosshair in crosshairs {
+        *crosshair = visibility;
+    }
+}
+
+fn show_reloads(
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
+    mut started_reader: MessageReader<ReloadStarted>,
+    mut finished_reader: MessageReader<ReloadFinished>,
+    mut bars: Query<(&mut ReloadBar, &mut Visibility, &Children)>,
+    mut fills: Query<&mut Node, With<ReloadFill>>,
+) {
+    for started in started_reader.read() {
+        for (mut bar, mut visibility, _) in &mut bars {
+            if bar.player == started.owner {
+                bar.weapon = Some(started.weapon);
+                bar.timer = Timer::from_seconds(started.duration, TimerMode::Once);
+                *visibility = hud_visibility(&difficulty);
+            }
+        }
+    }
+
+    for finished in finished_reader.read() {
+        for (mut bar, ..) in &mut bars {
+            if bar.player == finished.owner && bar.weapon == Some(finished.weapon) {
+                bar.weapon = None;
+            }
+        }
+    }
+
+    for (mut bar, mut visibility, children) in &mut bars {
+        bar.timer.tick(time.delta());
+
+        // a reload that's called off never finishes, so the timer has to end it
+        if bar.weapon.is_none() || bar.timer.is_finished() {
+            bar.weapon = None;
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+
+        let mut fills = fills.iter_many_mut(children);
+        while let Some(mut fill) = fills.fetch_next() {
+            fill.width = Val::Percent(bar.timer.fraction() * 100.0);
+        }
+    }
+}
+
+fn show_encumbrance_warning(
+    encumbrance: Single<&Encumbrance, (With<PrimaryPlayer>, Changed<Encumbrance>)>,
+    mut warning: Single<&mut Visibility, With<EncumbranceWarning>>,
+) {
+    **warning = if encumbrance.is_over() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+/// Tints the crosshair while its player's breathing holds their aim steady, so they can learn
+/// the rhythm.
+fn show_respiratory_pause(
+    theme: Res<HudTheme>,
+    crosshairs: Query<(&Crosshair, &Children)>,
+    players: Query<&RespiratoryPause, Changed<RespiratoryPause>>,
+    mut arms: Query<&mut BackgroundColor, With<CrosshairArm>>,
+) {
+    for (crosshair, children) in crosshairs {
+        let Ok(pause) = players.get(crosshair.player) else {
+            continue;
+        };
+
+        // greyed out until the weapon's up, see `show_weapon_raising`
+        if crosshair.raising.is_some() {
+            continue;
+        }
+
+        let color = if pause.active {
+            theme.steady
+        } else {
+            theme.text
+        };
+
+        let mut arms = arms.iter_many_mut(children);
+        while let Some(mut arm) = arms.fetch_next() {
+            arm.0 = color.with_alpha(0.8);
+        }
+    }
+}
+
+/// Greys the crosshair out while its player's weapon comes up from a sprint and can't fire.
+fn show_weapon_raising(
+    theme: Res<HudTheme>,
+    mut raising_reader: MessageReader<WeaponRaising>,
+    mut raised_reader: MessageReader<WeaponRaised>,
+    mut crosshairs: Query<(&mut Crosshair, &Children)>,
+    mut arms: Query<&mut BackgroundColor, With<CrosshairArm>>,
+) {
+    let raising: Vec<_> = raising_reader.read().collect();
+    let raised: Vec<_> = raised_reader.read().collect();
+
+    for (mut crosshair, children) in &mut crosshairs {
+        let before = crosshair.raising;
+
+        if let Some(started) = raising
+            .iter()
+            .find(|started| started.owner == crosshair.player)
+        {
+            crosshair.raising = Some(started.weapon);
+        }
+
+        if raised.iter().any(|finished| {
+            finished.owner == crosshair.player && crosshair.raising == Some(finished.weapon)
+        }) {
+            crosshair.raising = None;
+        }
+
+        if crosshair.raising == before {
+            continue;
+        }
+
+        let color = if crosshair.raising.is_some() {
+            theme.dim_text
+        } else {
+            theme.text
+        };
+
+        let mut arms = arms.iter_many_mut(children);
+        while let Some(mut arm) = arms.fetch_next() {
+            arm.0 = color.with_alpha(0.8);
+        }
+    }
+}
+
+/// Reshapes the crosshair to suit what its player's weapon fires: spread out as wide as its
+/// [`Accuracy`] and any shotgun pellets go, or turned into a cross for a launcher.
+fn show_fire_mode(
+    weapons: Query<
+        (&SwayTarget, &FireMode, &Accuracy),
+        (
+            With<PlayerWeapon>,
+            With<WeaponActive>,
+            Or<(Changed<FireMode>, Changed<Accuracy>)>,
+        ),
+    >,
+    mut crosshairs: Query<(&Crosshair, &mut UiTransform)>,
+) {
+    for (owner, fire_mode, accuracy) in weapons {
+        for (crosshair, mut transform) in &mut crosshairs {
+            if crosshair.player != owner.0 {
+                continue;
+            }
+
+            let spread = |degrees: f32| Vec2::splat(1.0 + (degrees + accuracy.spread) / 4.0);
+
+            (transform.scale, transform.rotation) = match *fire_mode {
+                FireMode::Single => (spread(0.0), Rot2::IDENTITY),
+                FireMode::Shotgun {
+                    spread: pellets, ..
+                } => (spread(pellets), Rot2::IDENTITY),
+                FireMode::Launcher { .. } => (Vec2::ONE, Rot2::degrees(45.0)),
+            };
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Latching button presses for systems that run at a fixed rate.
+//!
+//! `ButtonInput::just_pressed` only lasts the frame it happened in, so a system in `FixedUpdate`
+//! misses any press made on a frame where no fixed step ran, which happens whenever the frame
+//! rate is above the tick rate. Each player's [`ActionBuffer`] records presses from their
+//! [`PlayerInput`] every frame and holds them until the end of the next fixed step instead. Jumps
+//! and dashes are held the same way by the character controller's movement intent.
+
+use bevy::{input::InputSystems, platform::collections::HashSet, prelude::*};
+
+use crate::split_screen::PlayerInput;
+use crate::touch::{self, TouchButton, TouchControls};
+
+pub struct InputBufferPlugin;
+
+impl Plugin for InputBufferPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            buffer_actions
+                .after(InputSystems)
+                .after(touch::read_touches),
+        )
+        .add_systems(FixedLast, clear_action_buffers);
+    }
+}
+
+/// A player action read from a fixed rate system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Fire,
+    Aim,
+}
+
+impl Action {
+    const ALL: [Action; 2] = [Action::Fire, Action::Aim];
+
+    fn mouse_button(&self) -> MouseButton {
+        match self {
+            Action::Fire => MouseButton::Left,
+            Action::Aim => MouseButton::Right,
+        }
+    }
+
+    fn gamepad_button(&self) -> GamepadButton {
+        match self {
+            Action::Fire => GamepadButton::RightTrigger2,
+            Action::Aim => GamepadButton::LeftTrigger2,
+        }
+    }
+
+    fn touch_button(&self) -> TouchButton {
+        match self {
+            Action::Fire => TouchButton::Fire,
+            Action::Aim => TouchButton::Aim,
+        }
+    }
+}
+
+/// Which actions a player is holding, and which they've pressed since the last fixed step.
+#[derive(Component, Debug, Default)]
+pub struct ActionBuffer {
+    held: HashSet<Action>,
+    pressed: HashSet<Action>,
+}
+
+impl ActionBuffer {
+    /// The action was pressed since the last fixed step
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// The action is held down, or was tapped since the last fixed step
+    pub fn pressed(&self, action: Action) -> bool {
+        self.held.contains(&action) || self.just_pressed(action)
+    }
+}
+
+fn buffer_actions(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touch: Res<TouchControls>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(&PlayerInput, &mut ActionBuffer)>,
+) {
+    for (input, mut buffer) in players {
+        let mouse = input.keyboard_mouse.then_some(&*mouse_input);
+        // on-screen controls go with the keyboard and mouse
+        let touch = input.keyboard_mouse.then_some(&*touch);
+        let gamepad = input.gamepad(&gamepads);
+
+        buffer.held.clear();
+
+        for action in Action::ALL {
+            let held = mouse.is_some_and(|mouse| mouse.pressed(action.mouse_button()))
+                || gamepad.is_some_and(|gamepad| gamepad.pressed(action.gamepad_button()))
+                || touch.is_some_and(|touch| touch.pressed(action.touch_button()));
+
+            let just_pressed = mouse.is_some_and(|mouse| mouse.just_pressed(action.mouse_button()))
+                || gamepad.is_some_and(|gamepad| gamepad.just_pressed(action.gamepad_button()))
+                || touch.is_some_and(|touch| touch.just_pressed(action.touch_button()));
+
+            if held {
+                buffer.held.insert(action);
+            }
+
+            if just_pressed {
+                buffer.pressed.insert(action);
+            }
+        }
+    }
+}
+
+fn clear_action_buffers(buffers: Query<&mut ActionBuffer>) {
+    for mut buffer in buffers {
+        buffer.pressed.clear();
+    }
+}
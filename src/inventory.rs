@@ -0,0 +1,159 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::split_screen::PrimaryPlayer;
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemWeights>()
+            .add_console_command("give", "give <item> [count]")
+            .add_systems(Update, give_command);
+    }
+}
+
+/// Items carried by a character, keyed by item name.
+#[derive(Component, Default, Debug)]
+pub struct Inventory {
+    items: HashMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn with_items<'a>(items: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+        let mut inventory = Self::default();
+
+        for (item, count) in items {
+            inventory.add(item, count);
+        }
+
+        inventory
+    }
+
+    pub fn add(&mut self, item: &str, count: u32) {
+        *self.items.entry(item.to_owned()).or_default() += count;
+    }
+
+    /// Remove `count` of an item, returning `false` without removing any if there aren't enough
+    pub fn take(&mut self, item: &str, count: u32) -> bool {
+        let Some(carried) = self.items.get_mut(item) else {
+            return false;
+        };
+
+        if *carried < count {
+            return false;
+        }
+
+        *carried -= count;
+
+        if *carried == 0 {
+            self.items.remove(item);
+        }
+
+        true
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or(0)
+    }
+
+    /// Total weight of everything carried
+    pub fn weight(&self, weights: &ItemWeights) -> f32 {
+        self.items
+            .iter()
+            .map(|(item, count)| weights.get(item) * *count as f32)
+            .sum()
+    }
+}
+
+/// The weight of one of each item, in kilograms.
+#[derive(Resource, Debug)]
+pub struct ItemWeights {
+    weights: HashMap<String, f32>,
+    /// Weight of items missing from the table
+    unknown: f32,
+}
+
+impl ItemWeights {
+    pub fn get(&self, item: &str) -> f32 {
+        self.weights.get(item).copied().unwrap_or(self.unknown)
+    }
+}
+
+impl Default for ItemWeights {
+    fn default() -> Self {
+        let weights = [
+            ("ammo", 0.015),
+            ("medkit", 0.5),
+            ("grenade", 0.6),
+            ("armor_plate", 2.5),
+            ("rations", 0.4),
+            ("stim", 0.1),
+            ("battery", 0.05),
+            ("flare", 0.3),
+        ];
+
+        Self {
+            weights: weights
+                .into_iter()
+                .map(|(item, weight)| (item.to_owned(), weight))
+                .collect(),
+            unknown: 1.0,
+        }
+    }
+}
+
+fn give_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    mut inventory: Single<&mut Inventory, With<PrimaryPlayer>>,
+) {
+    for command in command_reader.read() {
+        if !command.is("give") {
+            continue;
+        }
+
+        let Some(item) = command.arg(0) else {
+            output_writer.write(ConsoleOutput("usage: give <item> [count]".into()));
+            continue;
+        };
+
+        let count = command.arg(1).and_then(|c| c.parse().ok()).unwrap_or(1);
+        inventory.add(item, count);
+
+        output_writer.write(ConsoleOutput(format!(
+            "gave {count} {item} (now {})",
+            inventory.count(item)
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Player;
+
+    #[test]
+    fn give_goes_to_player_one_in_split_screen() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InventoryPlugin))
+            .add_message::<ConsoleCommand>()
+            .add_message::<ConsoleOutput>();
+
+        let one = app
+            .world_mut()
+            .spawn((Player, PrimaryPlayer, Inventory::default()))
+            .id();
+        let two = app.world_mut().spawn((Player, Inventory::default())).id();
+
+        app.world_mut().write_message(ConsoleCommand {
+            name: "give".to_owned(),
+            args: vec!["flare".to_owned(), "2".to_owned()],
+        });
+        app.update();
+
+        let flares = |entity| app.world().get::<Inventory>(entity).unwrap().count("flare");
+        assert_eq!(flares(one), 2);
+        assert_eq!(flares(two), 0);
+    }
+}
@@ -0,0 +1,108 @@
+//! Best times for finished time trials.
+//!
+//! Every finished run is added to `saves/leaderboard.ron` along with the level it was played on,
+//! which for a generated level includes the seed, so anyone can replay the same layout and compare
+//! times fairly.
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::level::{GameMode, Level, RunFinished};
+use crate::profile::{ActiveProfile, Profile};
+use crate::storage::{self, write_atomic};
+
+const LEADERBOARD_PATH: &str = "saves/leaderboard.ron";
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load(Path::new(LEADERBOARD_PATH)))
+            .add_systems(Update, record_runs);
+    }
+}
+
+/// One finished run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub profile: String,
+    pub level: Level,
+    pub mode: GameMode,
+    /// Seconds the run took
+    pub time: f32,
+}
+
+/// Every run recorded on this machine.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn load(path: &Path) -> Self {
+        match storage::read_to_string(path) {
+            Ok(saved) => ron::from_str(&saved).unwrap_or_else(|err| {
+                error!("could not read {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(std::io::Error::other)?;
+
+        write_atomic(path, serialized.as_bytes())
+    }
+
+    /// Adds a finished run by `profile`, saving the leaderboard to `path`
+    pub fn record(&mut self, run: &RunFinished, profile: Option<&Profile>, path: &Path) {
+        let best = self.best(run.level, run.mode);
+
+        if best.is_none_or(|best| run.time < best) {
+            info!("new best time on {}: {:.2}s", run.level, run.time);
+        }
+
+        self.entries.push(LeaderboardEntry {
+            profile: profile.map_or_else(String::new, |profile| profile.name.clone()),
+            level: run.level,
+            mode: run.mode,
+            time: run.time,
+        });
+
+        if let Err(err) = self.save(path) {
+            error!("could not save {}: {err}", path.display());
+        }
+    }
+
+    /// The fastest recorded time on a level and mode
+    pub fn best(&self, level: Level, mode: GameMode) -> Option<f32> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.level == level && entry.mode == mode)
+            .map(|entry| entry.time)
+            .min_by(f32::total_cmp)
+    }
+}
+
+fn record_runs(
+    mut finished_reader: MessageReader<RunFinished>,
+    mut leaderboard: ResMut<Leaderboard>,
+    profile: Option<Res<ActiveProfile>>,
+) {
+    for run in finished_reader.read() {
+        // daily runs have a leaderboard of their own, see `crate::daily`
+        if run.mode == GameMode::Daily {
+            continue;
+        }
+
+        leaderboard.record(
+            run,
+            profile.as_deref().map(|profile| &profile.0),
+            Path::new(LEADERBOARD_PATH),
+        );
+    }
+}
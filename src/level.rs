@@ -0,0 +1,545 @@
+//! The levels and game modes picked from the main menu.
+//!
+//! Each [`Level`] spawns its own content on top of the shared floor and border when play starts,
+//! and all of it is despawned again when play ends. The [`GameMode`] decides what counts as
+//! finishing the level.
+
+use std::fmt;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::Player;
+use crate::environment::{Climate, EnvironmentZone};
+use crate::equipment::ArmorPiece;
+use crate::grapple::GrappleSurface;
+use crate::loading::LoadingBlocker;
+use crate::lod::{Lod, LodSettings};
+use crate::menu::GameState;
+use crate::minimap::MinimapIcon;
+use crate::scene::{SpawnArenaExt, SpawnPoint, Target};
+use crate::trigger::{TriggerFilter, TriggerVolume};
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<RunFinished>()
+            .init_resource::<Level>()
+            .init_resource::<GameMode>()
+            .init_resource::<RunTimer>()
+            .add_systems(OnEnter(GameState::Loading), spawn_level)
+            .add_systems(
+                Update,
+                spawn_generated_layout.run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(
+                OnEnter(GameState::InGame),
+                (move_player_to_start, setup_run_timer),
+            )
+            .add_systems(
+                Update,
+                (reach_checkpoints, finish_run, update_run_timer)
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+/// The map to play on.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Target dummies and climate zones on an open floor
+    #[default]
+    Arena,
+    /// A lane of targets at increasing range
+    TargetCourse,
+    /// A loop of checkpoints to run through in order
+    Race,
+    /// Obstacles and targets scattered from a seed
+    Generated { seed: u64 },
+}
+
+impl Level {
+    /// Every level in menu order, with `seed` used for the generated one
+    pub fn all(seed: u64) -> [Level; 4] {
+        [
+            Level::Arena,
+            Level::TargetCourse,
+            Level::Race,
+            Level::Generated { seed },
+        ]
+    }
+
+    fn spawn_point(&self) -> Vec3 {
+        match self {
+            Level::Race => Vec3::new(0.0, 1.5, 40.0),
+            _ => Vec3::new(0.5, 1.5, 0.5),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Arena => write!(f, "Arena"),
+            Level::TargetCourse => write!(f, "Target course"),
+            Level::Race => write!(f, "Race"),
+            Level::Generated { seed } => write!(f, "Generated ({seed})"),
+        }
+    }
+}
+
+/// The rules for a run.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// No objective, play for as long as you like
+    #[default]
+    FreePlay,
+    /// Clear every target (or every checkpoint in a race) against the clock
+    TimeTrial,
+    /// A time trial on the day's generated level, see [`crate::daily`]
+    Daily,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::FreePlay, GameMode::TimeTrial, GameMode::Daily];
+
+    /// Whether runs are played against the clock
+    pub fn is_timed(&self) -> bool {
+        matches!(self, GameMode::TimeTrial | GameMode::Daily)
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::FreePlay => write!(f, "Free play"),
+            GameMode::TimeTrial => write!(f, "Time trial"),
+            GameMode::Daily => write!(f, "Daily challenge"),
+        }
+    }
+}
+
+/// A race checkpoint, reached in order of `index`.
+#[derive(Component, Debug)]
+pub struct Checkpoint {
+    index: usize,
+    reached: bool,
+}
+
+impl Checkpoint {
+    const RADIUS: f32 = 3.0;
+}
+
+/// What the generator places in a [`Level::Generated`] level.
+#[derive(Debug, Default)]
+struct GeneratedLayout {
+    /// Centre and size of each obstacle box
+    obstacles: Vec<(Vec3, Vec3)>,
+    targets: Vec<(Vec3, bool)>,
+    /// Where each decorative rock sits, and its radius
+    props: Vec<(Vec3, f32)>,
+}
+
+impl GeneratedLayout {
+    /// Rocks are simplified and hidden as players leave them behind, then cleared away
+    const PROP_LOD: LodSettings = LodSettings {
+        simplify: Some(15.0),
+        cull: 45.0,
+        despawn: Some(80.0),
+    };
+    /// Targets are only ever hidden, as they're still there to be shot and cleared
+    const TARGET_LOD: LodSettings = LodSettings {
+        simplify: None,
+        cull: 70.0,
+        despawn: None,
+    };
+
+    fn generate(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut layout = Self::default();
+
+        for _ in 0..rng.random_range(12..24) {
+            let size = Vec3::new(
+                rng.random_range(1.0..6.0),
+                rng.random_range(1.0..4.0),
+                rng.random_range(1.0..6.0),
+            );
+            let position = Vec3::new(
+                rng.random_range(-45.0..45.0),
+                0.5 + size.y / 2.0,
+                rng.random_range(-45.0..45.0),
+            );
+
+            // keep the spawn point clear
+            if position.xz().length() >= 6.0 {
+                layout.obstacles.push((position, size));
+            }
+        }
+
+        for _ in 0..rng.random_range(4..10) {
+            let x = rng.random_range(-40.0..40.0);
+            let z = rng.random_range(-45.0..-10.0);
+            layout
+                .targets
+                .push((Vec3::new(x, 0.5, z), rng.random_bool(0.3)));
+        }
+
+        // after everything else, so adding them left each seed's obstacles and targets as they were
+        for _ in 0..rng.random_range(20..40) {
+            let radius = rng.random_range(0.15..0.6);
+            let position = Vec3::new(
+                rng.random_range(-48.0..48.0),
+                0.5,
+                rng.random_range(-48.0..48.0),
+            );
+            layout.props.push((position, radius));
+        }
+
+        layout
+    }
+}
+
+/// A generated layout being worked out on another thread.
+#[derive(Component)]
+struct PendingLayout(Task<GeneratedLayout>);
+
+/// How long the current time trial has been running.
+#[derive(Resource, Debug, Default)]
+pub struct RunTimer {
+    pub elapsed: f32,
+    pub finished: bool,
+}
+
+/// An event sent when a time trial is completed.
+#[derive(Message, Debug, Clone)]
+pub struct RunFinished {
+    pub level: Level,
+    pub mode: GameMode,
+    /// Seconds the run took
+    pub time: f32,
+}
+
+#[derive(Component)]
+struct RunTimerText;
+
+fn spawn_level(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    info!("loading {}", *level);
+
+    commands
+        .spawn_spawn_point(level.spawn_point().with_y(0.5))
+        .insert(DespawnOnExit(GameState::InGame));
+
+    match *level {
+        Level::Arena => {
+            let targets: Vec<_> = [false, false, true, false, true]
+                .into_iter()
+                .enumerate()
+                .map(|(i, armored)| (Vec3::new((i as f32 - 2.0) * 3.0, 0.5, -20.0), armored))
+                .collect();
+
+            spawn_targets(&mut commands, &targets);
+            spawn_climate_zones(&mut commands, &mut meshes, &mut materials);
+
+            // armour to loot on either side of the start
+            for (x, piece) in [(-4.0, ArmorPiece::Helmet), (5.0, ArmorPiece::Vest)] {
+                commands
+                    .spawn_armor_pickup(level.spawn_point().with_x(x).with_y(0.5), piece)
+                    .insert(DespawnOnExit(GameState::InGame));
+            }
+
+            commands
+                .spawn_vehicle(level.spawn_point().with_x(-10.0).with_y(0.5))
+                .insert(DespawnOnExit(GameState::InGame));
+            commands
+                .spawn_turret(level.spawn_point().with_x(8.0).with_y(0.5))
+                .insert(DespawnOnExit(GameState::InGame));
+            // a tower to swing from
+            commands
+                .spawn_platform(
+                    level.spawn_point().with_x(14.0).with_y(0.5) + Vec3::NEG_Z * 15.0,
+                    Vec3::new(3.0, 10.0, 3.0),
+                )
+                .insert(DespawnOnExit(GameState::InGame));
+            // jump up to grab the high end and ride it down the far side of the range
+            commands
+                .spawn_zipline(
+                    level.spawn_point().with_x(-14.0).with_y(0.5),
+                    level.spawn_point().with_x(-14.0).with_y(0.5) + Vec3::NEG_Z * 40.0,
+                    (5.0, 3.2),
+                )
+                .insert(DespawnOnExit(GameState::InGame));
+        }
+        Level::TargetCourse => {
+            let targets: Vec<_> = (0..8)
+                .map(|i| {
+                    let side = if i % 2 == 0 { -1.5 } else { 1.5 };
+                    (Vec3::new(side, 0.5, -10.0 - i as f32 * 5.0), i >= 4)
+                })
+                .collect();
+
+            spawn_targets(&mut commands, &targets);
+        }
+        Level::Race => {
+            spawn_checkpoints(&mut commands, &mut meshes, &mut materials);
+        }
+        Level::Generated { seed } => {
+            let task =
+                AsyncComputeTaskPool::get().spawn(async move { GeneratedLayout::generate(seed) });
+
+            commands.spawn((PendingLayout(task), LoadingBlocker));
+        }
+    }
+}
+
+fn spawn_targets(commands: &mut Commands, targets: &[(Vec3, bool)]) {
+    for &(position, armored) in targets {
+        commands
+            .spawn_target(position, armored)
+            .insert(DespawnOnExit(GameState::InGame));
+    }
+}
+
+fn spawn_generated_layout(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    pending: Query<(Entity, &mut PendingLayout)>,
+) {
+    for (entity, mut pending) in pending {
+        let Some(layout) = check_ready(&mut pending.0) else {
+            continue;
+        };
+
+        let material = materials.add(Color::srgb_u8(110, 110, 120));
+
+        for (position, size) in layout.obstacles {
+            commands.spawn((
+                Mesh3d(meshes.add(Cuboid::from_size(size))),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(position),
+                RigidBody::Static,
+                Collider::cuboid(size.x, size.y, size.z),
+                GrappleSurface,
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+
+        for &(position, armored) in &layout.targets {
+            commands.spawn_target(position, armored).insert((
+                Lod::new(GeneratedLayout::TARGET_LOD),
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+
+        let rock = Sphere::new(1.0);
+        let rock_mesh = meshes.add(rock.mesh().ico(3).unwrap());
+        let simple_rock_mesh = meshes.add(rock.mesh().ico(0).unwrap());
+        let rock_material = materials.add(Color::srgb_u8(95, 90, 85));
+
+        for (position, radius) in layout.props {
+            commands.spawn((
+                Mesh3d(rock_mesh.clone()),
+                MeshMaterial3d(rock_material.clone()),
+                // half sunk into the floor
+                Transform::from_translation(position).with_scale(Vec3::splat(radius)),
+                Lod::new(GeneratedLayout::PROP_LOD).with_simple_mesh(simple_rock_mesh.clone()),
+                DespawnOnExit(GameState::InGame),
+            ));
+        }
+
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_climate_zones(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let zones = [
+        (
+            Climate::Cold,
+            Vec3::new(-30.0, 5.0, 30.0),
+            Color::srgba(0.6, 0.8, 1.0, 0.08),
+        ),
+        (
+            Climate::Hot,
+            Vec3::new(30.0, 5.0, 30.0),
+            Color::srgba(1.0, 0.6, 0.3, 0.08),
+        ),
+    ];
+
+    let half_extents = Vec3::new(15.0, 5.0, 15.0);
+
+    for (climate, position, color) in zones {
+        commands.spawn((
+            Mesh3d(meshes.add(Cuboid::from_size(half_extents * 2.0))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                alpha_mode: AlphaMode::Blend,
+                cull_mode: None,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(position),
+            EnvironmentZone {
+                climate,
+                half_extents,
+            },
+            DespawnOnExit(GameState::InGame),
+        ));
+    }
+}
+
+fn spawn_checkpoints(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    const COUNT: usize = 8;
+    const RADIUS: f32 = 35.0;
+
+    let mesh = meshes.add(Torus::new(Checkpoint::RADIUS - 0.2, Checkpoint::RADIUS));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::srgb(1.0, 0.8, 0.1),
+        unlit: true,
+        ..default()
+    });
+
+    for index in 0..COUNT {
+        // once round the arena, finishing back by the spawn point
+        let angle = (index + 1) as f32 / COUNT as f32 * std::f32::consts::TAU;
+        let position = Vec3::new(angle.sin() * RADIUS, 3.0, angle.cos() * RADIUS);
+
+        commands.spawn((
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            Checkpoint {
+                index,
+                reached: false,
+            },
+            TriggerVolume::sphere(Checkpoint::RADIUS).with_filter(TriggerFilter::Player),
+            MinimapIcon::Objective,
+            DespawnOnExit(GameState::InGame),
+        ));
+    }
+}
+
+fn move_player_to_start(
+    level: Res<Level>,
+    spawn_points: Query<&Transform, (With<SpawnPoint>, Without<Player>)>,
+    players: Query<(&mut Transform, &mut LinearVelocity), With<Player>>,
+) {
+    let spawn_points: Vec<Vec3> = spawn_points
+        .iter()
+        .map(|point| point.translation.with_y(level.spawn_point().y))
+        .collect();
+    let first = spawn_points
+        .first()
+        .copied()
+        .unwrap_or_else(|| level.spawn_point());
+
+    // each local player takes their own spawn point, lining up beside the first when they run out
+    for (index, (mut transform, mut velocity)) in players.into_iter().enumerate() {
+        transform.translation = spawn_points
+            .get(index)
+            .copied()
+            .unwrap_or(first + Vec3::X * index as f32 * 2.0);
+        velocity.0 = Vec3::ZERO;
+    }
+}
+
+fn setup_run_timer(mut commands: Commands, mode: Res<GameMode>, mut timer: ResMut<RunTimer>) {
+    *timer = RunTimer::default();
+
+    if !mode.is_timed() {
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(56.0),
+            left: Val::Percent(50.0),
+            ..default()
+        },
+        Text::default(),
+        TextFont::from_font_size(28.0),
+        RunTimerText,
+        DespawnOnExit(GameState::InGame),
+    ));
+}
+
+fn reach_checkpoints(mut checkpoints: Query<(&mut Checkpoint, &mut Visibility, &TriggerVolume)>) {
+    let next = checkpoints
+        .iter_mut()
+        .filter(|(checkpoint, ..)| !checkpoint.reached)
+        .min_by_key(|(checkpoint, ..)| checkpoint.index);
+
+    let Some((mut checkpoint, mut visibility, trigger)) = next else {
+        return;
+    };
+
+    // a player already inside when it becomes the next one still counts
+    if trigger.is_occupied() {
+        checkpoint.reached = true;
+        *visibility = Visibility::Hidden;
+        debug!("reached checkpoint {}", checkpoint.index);
+    }
+}
+
+fn finish_run(
+    level: Res<Level>,
+    mode: Res<GameMode>,
+    mut timer: ResMut<RunTimer>,
+    mut finished_writer: MessageWriter<RunFinished>,
+    targets: Query<(), With<Target>>,
+    checkpoints: Query<&Checkpoint>,
+) {
+    if !mode.is_timed() || timer.finished {
+        return;
+    }
+
+    let cleared = if checkpoints.is_empty() {
+        targets.is_empty()
+    } else {
+        checkpoints.iter().all(|checkpoint| checkpoint.reached)
+    };
+
+    if cleared {
+        timer.finished = true;
+        info!("run finished in {:.2}s", timer.elapsed);
+
+        finished_writer.write(RunFinished {
+            level: *level,
+            mode: *mode,
+            time: timer.elapsed,
+        });
+    }
+}
+
+fn update_run_timer(
+    time: Res<Time>,
+    mut timer: ResMut<RunTimer>,
+    text: Option<Single<&mut Text, With<RunTimerText>>>,
+) {
+    if !timer.finished {
+        timer.elapsed += time.delta_secs();
+    }
+
+    if let Some(mut text) = text {
+        text.0 = format!("{:.2}", timer.elapsed);
+    }
+}
@@ -0,0 +1,2580 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+mod accuracy;
+mod ai;
+mod ai_presets;
+mod attributes;
+// saves to the file system
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+mod challenges;
+// the run conditions are used either way, and say the chat's closed while it's left out
+#[cfg_attr(not(feature = "networked"), allow(dead_code))]
+mod chat;
+mod cleanup;
+mod compass;
+mod console;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash_report;
+mod daily;
+mod damage;
+mod debug_overlay;
+mod difficulty;
+mod doppler;
+mod dual_wield;
+mod dynamic_lights;
+mod dynamic_resolution;
+mod encumbrance;
+mod energy;
+mod environment;
+mod equipment;
+mod flinch;
+mod game_assets;
+#[cfg(all(feature = "gameplay_log", not(target_arch = "wasm32")))]
+mod gameplay_log;
+mod glide;
+mod grapple;
+mod hud;
+mod input_buffer;
+mod inventory;
+mod leaderboard;
+mod level;
+mod listener;
+mod loading;
+mod loadout;
+mod lod;
+mod logging;
+mod melee;
+mod menu;
+mod menu_nav;
+mod minimap;
+// browsers have no `mods/` directory to read from
+#[cfg(not(target_arch = "wasm32"))]
+mod mods;
+mod movement;
+mod particles;
+mod ping;
+mod pose_editor;
+mod profile;
+mod projectile;
+mod recoil;
+mod ron_asset;
+mod scene;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod settings;
+mod sockets;
+mod split_screen;
+mod squad;
+mod status;
+mod storage;
+mod sway;
+mod sweep;
+mod timeline;
+mod timestep;
+mod touch;
+mod trigger;
+mod tuning;
+mod turret;
+mod underbarrel;
+mod unlocks;
+mod vehicle;
+mod vision;
+mod wanderer;
+mod weapon;
+mod zipline;
+
+use avian3d::PhysicsPlugins;
+use avian3d::math::Scalar;
+use avian3d::prelude::{
+    CoefficientCombine, Collider, ColliderOf, CollisionEventsEnabled, Friction, GravityScale,
+    LinearVelocity, Mass, Restitution, RigidBody, Sensor, SpatialQuery, SpatialQueryFilter,
+};
+use bevy::camera::Exposure;
+use bevy::ecs::relationship::{Relationship, RelationshipTarget};
+use bevy::pbr::Atmosphere;
+use bevy::post_process::bloom::Bloom;
+use bevy::render::view::ColorGrading;
+use bevy::utils::Parallel;
+use bevy::{
+    core_pipeline::tonemapping::Tonemapping, input::mouse::AccumulatedMouseMotion, prelude::*,
+};
+use bevy_dev_tools::fps_overlay::FpsOverlayPlugin;
+use rand::Rng;
+use smallvec::SmallVec;
+
+/// Builds the game and runs it until the window's closed.
+pub fn run() {
+    let mut app = App::new();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(mods::ModAssetsPlugin);
+
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    // fill the page in a web build
+                    fit_canvas_to_parent: true,
+                    ..default()
+                }),
+                ..default()
+            })
+            .set(logging::log_plugin()),
+        FpsOverlayPlugin::default(),
+        PhysicsPlugins::default(),
+        timestep::TimestepPlugin::default(),
+        scene::ScenePlugin,
+        movement::CharacterControllerPlugin,
+        settings::SettingsPlugin,
+        console::ConsolePlugin,
+        difficulty::DifficultyPlugin,
+        damage::DamagePlugin,
+        energy::EnergyPlugin,
+        hud::HudPlugin,
+        tuning::TuningPlugin,
+        (
+            inventory::InventoryPlugin,
+            weapon::WeaponPlugin,
+            environment::EnvironmentPlugin,
+            encumbrance::EncumbrancePlugin,
+            status::StatusPlugin,
+            attributes::AttributesPlugin,
+            menu::MenuPlugin,
+            level::LevelPlugin,
+            leaderboard::LeaderboardPlugin,
+            minimap::MinimapPlugin,
+            compass::CompassPlugin,
+            ping::PingPlugin,
+            loading::LoadingPlugin,
+            profile::ProfilePlugin,
+            (
+                particles::ParticlesPlugin,
+                input_buffer::InputBufferPlugin,
+                split_screen::SplitScreenPlugin,
+                trigger::TriggerPlugin,
+                timeline::TimelinePlugin,
+                equipment::EquipmentPlugin,
+                vision::VisionPlugin,
+                dynamic_lights::DynamicLightsPlugin,
+                doppler::DopplerPlugin,
+                vehicle::VehiclePlugin,
+                turret::TurretPlugin,
+                zipline::ZiplinePlugin,
+                grapple::GrapplePlugin,
+                glide::GlidePlugin,
+                (
+                    debug_overlay::DebugOverlayPlugin,
+                    pose_editor::PoseEditorPlugin,
+                    sockets::SocketsPlugin,
+                    dual_wield::DualWieldPlugin,
+                    underbarrel::UnderbarrelPlugin,
+                    cleanup::CleanupPlugin,
+                    listener::ListenerPlugin,
+                    sweep::SweepPlugin,
+                    flinch::FlinchPlugin,
+                    ai::AiPlugin,
+                    squad::SquadPlugin,
+                    ai_presets::AiPresetsPlugin,
+                    wanderer::WandererPlugin,
+                    lod::LodPlugin,
+                    (
+                        game_assets::GameAssetsPlugin,
+                        loadout::LoadoutPlugin,
+                        unlocks::UnlocksPlugin,
+                        challenges::ChallengesPlugin,
+                        daily::DailyPlugin,
+                        touch::TouchPlugin,
+                        logging::LoggingPlugin,
+                        accuracy::AccuracyPlugin,
+                        projectile::ProjectilePlugin,
+                        menu_nav::MenuNavPlugin,
+                        dynamic_resolution::DynamicResolutionPlugin,
+                        melee::MeleePlugin,
+                    ),
+                ),
+            ),
+        ),
+    ))
+    .add_message::<ShotFired>()
+    .add_message::<BreathPhaseChanged>()
+    .add_systems(Startup, (setup_player, setup_round_assets))
+    .add_systems(
+        Update,
+        (
+            (rotate_horizontal, look_vertical, damp_weapon_look)
+                .chain()
+                .run_if(chat::wheel_closed),
+            (player_breath_alter, player_hold_breath)
+                .run_if(console::console_closed)
+                .run_if(chat::chat_closed),
+        )
+            .run_if(in_state(menu::GameState::InGame)),
+    )
+    .add_systems(Update, log_breath_phase)
+    .add_systems(
+        FixedUpdate,
+        (
+            (
+                player_shoot.run_if(in_state(menu::GameState::InGame)),
+                recoil::kick,
+            )
+                .chain(),
+            (
+                player_camera_sway,
+                player_walk_init,
+                movement::bob_heads,
+                movement::lower_eyes,
+                movement::lean_cameras,
+                apply_player_camera_sway,
+            )
+                .chain(),
+            // outside the chain below, so a player's aim goes back to nothing while their weapon's
+            // lowered to reload
+            share_aim_state.after(aim),
+            (
+                aim,
+                sprint_pose,
+                breathe,
+                sway::respiratory_pause,
+                weapon_sway,
+                sway::profile_sway,
+                movement::bob_weapons,
+                recoil::recover,
+                set_weapon_transform,
+            )
+                .chain()
+                // nothing to pose while every weapon's holstered
+                .run_if(any_with_component::<WeaponActive>),
+        ),
+    );
+
+    #[cfg(all(feature = "gameplay_log", not(target_arch = "wasm32")))]
+    app.add_plugins(gameplay_log::GameplayLogPlugin);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins((capture::CapturePlugin, crash_report::CrashReportPlugin));
+
+    #[cfg(feature = "scripting")]
+    app.add_plugins(scripting::ScriptingPlugin);
+
+    #[cfg(feature = "networked")]
+    app.add_plugins(chat::ChatPlugin);
+
+    app.run();
+}
+
+#[derive(Component)]
+struct Player;
+
+/// An event sent every time a weapon fires a round.
+#[derive(Message, Debug)]
+#[cfg_attr(
+    not(all(feature = "gameplay_log", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+struct ShotFired {
+    shooter: Entity,
+    /// The gun the round came out of
+    weapon: Entity,
+    origin: Vec3,
+    direction: Vec3,
+}
+
+#[derive(Component)]
+struct Walk {
+    speed: f32,
+    alpha: f32,
+    amount: f32,
+    depth: f32,
+    side: WalkSide,
+}
+
+impl Walk {
+    const MAX_SPEED: f32 = 10.0;
+    const MAX_DEPTH: f32 = 5.0;
+
+    fn walk(&mut self, delta: f32) {
+        self.speed = self.speed.clamp(0.0, Self::MAX_SPEED);
+        self.depth = self.depth.clamp(0.0, Self::MAX_DEPTH);
+
+        let rate = (self.speed / self.depth).clamp(0.0, Self::MAX_SPEED);
+
+        // increase alpha slower for deeper breaths
+        let change = rate * delta;
+
+        self.alpha += change;
+
+        self.alpha = self.alpha.clamp(0.0, 1.0);
+
+        let change_stride = self.alpha >= 1.0 || self.alpha <= 0.0;
+
+        if change_stride {
+            self.alpha = 0.0;
+            self.side = if self.side == WalkSide::Left {
+                WalkSide::Right
+            } else {
+                WalkSide::Left
+            };
+        }
+
+        self.amount =
+            EasingCurve::new(0.0, self.depth, EaseFunction::SmoothStep).sample_clamped(self.alpha);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum WalkSide {
+    Left = 0,
+    Right = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BreathDirection {
+    In = 0,
+    Out = 1,
+}
+
+impl BreathDirection {
+    fn flipped(self) -> Self {
+        match self {
+            Self::In => Self::Out,
+            Self::Out => Self::In,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct Breath {
+    speed: f32,
+    depth: f32,
+    alpha: f32,
+    direction: BreathDirection,
+    /// How many times the breath has changed direction
+    cycle_index: u32,
+    /// How out of breath from exertion, `0..=1`, quickening and deepening each breath on top of
+    /// `speed` and `depth`
+    strain: f32,
+    hold: BreathHold,
+    /// Multiplier on weapon sway from holding the breath, eased towards
+    /// [`Breath::hold_sway_target`]
+    hold_sway: f32,
+}
+
+/// Where a breather is with holding their breath.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum BreathHold {
+    #[default]
+    Free,
+    /// Holding it, for this many seconds so far
+    Holding(f32),
+    /// Gasping for air after letting it go, for this many seconds more
+    Recovering(f32),
+}
+
+impl Breath {
+    const MAX_SPEED: f32 = 10.0;
+    const MAX_DEPTH: f32 = 5.0;
+    /// Multiplier on speed when fully strained
+    const STRAINED_SPEED: f32 = 3.0;
+    /// Multiplier on depth when fully strained
+    const STRAINED_DEPTH: f32 = 1.5;
+    /// Seconds a breath can be held before it has to be let go
+    const MAX_HOLD: f32 = 4.0;
+    /// Seconds of recovery after a full hold, shorter holds needing less
+    const RECOVERY: f32 = 2.5;
+    /// Multiplier on weapon sway at the start of a recovery
+    const RECOVERY_SWAY: f32 = 2.5;
+    /// Seconds to settle into and out of a hold, so the weapon doesn't jump
+    const HOLD_SETTLE: f32 = 0.15;
+    /// Breaths shallower than this are treated as this deep when working out the breathing rate
+    const MIN_RATE_DEPTH: f32 = 0.01;
+
+    fn new(speed: f32, depth: f32, direction: BreathDirection) -> Self {
+        Self {
+            speed,
+            depth,
+            alpha: 0.0,
+            direction,
+            cycle_index: 0,
+            strain: 0.0,
+            hold: BreathHold::Free,
+            hold_sway: 1.0,
+        }
+    }
+
+    /// Strain from exertion, or all of it while recovering from a held breath
+    fn effective_strain(&self) -> f32 {
+        match self.hold {
+            BreathHold::Recovering(_) => 1.0,
+            _ => saturate(self.strain, 0.0, 1.0),
+        }
+    }
+
+    /// Breathing speed with strain taken into account
+    fn strained_speed(&self) -> f32 {
+        self.speed * (1.0 + (Self::STRAINED_SPEED - 1.0) * self.effective_strain())
+    }
+
+    /// Breath depth with strain taken into account
+    fn strained_depth(&self) -> f32 {
+        self.depth * (1.0 + (Self::STRAINED_DEPTH - 1.0) * self.effective_strain())
+    }
+
+    /// Start holding the breath on `start`, keep holding it while `holding`, and let it go
+    /// otherwise. A new hold has to wait until the last one has been recovered from.
+    fn hold(&mut self, start: bool, holding: bool) {
+        self.hold = match self.hold {
+            BreathHold::Free if start => BreathHold::Holding(0.0),
+            BreathHold::Holding(held) if !holding => {
+                BreathHold::Recovering(Self::RECOVERY * saturate(held / Self::MAX_HOLD, 0.25, 1.0))
+            }
+            hold => hold,
+        };
+    }
+
+    fn is_held(&self) -> bool {
+        matches!(self.hold, BreathHold::Holding(_))
+    }
+
+    /// What [`Breath::hold_sway`] is easing towards: nothing while held, and extra while
+    /// recovering that fades as the recovery does
+    fn hold_sway_target(&self) -> f32 {
+        match self.hold {
+            BreathHold::Free => 1.0,
+            BreathHold::Holding(_) => 0.0,
+            BreathHold::Recovering(left) => {
+                1.0 + (Self::RECOVERY_SWAY - 1.0) * saturate(left / Self::RECOVERY, 0.0, 1.0)
+            }
+        }
+    }
+
+    /// Multiplier on weapon sway from holding the breath
+    fn hold_steadiness(&self) -> f32 {
+        self.hold_sway
+    }
+
+    /// Time a held breath or its recovery, returning whether the breath is held
+    fn advance_hold(&mut self, delta: f32) -> bool {
+        let settle = 1.0 - (-delta / Self::HOLD_SETTLE).exp();
+        self.hold_sway = saturate(
+            self.hold_sway.lerp(self.hold_sway_target(), settle),
+            0.0,
+            Self::RECOVERY_SWAY,
+        );
+
+        self.hold = match self.hold {
+            BreathHold::Holding(held) if held + delta >= Self::MAX_HOLD => {
+                BreathHold::Recovering(Self::RECOVERY)
+            }
+            BreathHold::Holding(held) => BreathHold::Holding(held + delta),
+            BreathHold::Recovering(left) if left > delta => BreathHold::Recovering(left - delta),
+            BreathHold::Recovering(_) | BreathHold::Free => BreathHold::Free,
+        };
+
+        self.is_held()
+    }
+
+    fn breath(&mut self, delta: f32) -> BreathSample {
+        self.speed = saturate(self.speed, 0.0, Self::MAX_SPEED);
+        self.depth = saturate(self.depth, 0.0, Self::MAX_DEPTH);
+
+        let delta = saturate(delta, 0.0, f32::MAX);
+
+        // a held breath goes nowhere
+        if self.advance_hold(delta) {
+            return self.sample();
+        }
+
+        let direction = self.direction;
+
+        (self.alpha, self.direction) = advance_breath(
+            self.strained_speed(),
+            self.strained_depth(),
+            self.alpha,
+            self.direction,
+            delta,
+        );
+
+        if self.direction != direction {
+            self.cycle_index = self.cycle_index.wrapping_add(1);
+        }
+
+        self.sample()
+    }
+
+    /// Which breath we are on
+    fn phase(&self) -> BreathPhase {
+        BreathPhase {
+            direction: self.direction,
+            cycle_index: self.cycle_index,
+        }
+    }
+
+    /// Where the breath currently is
+    fn sample(&self) -> BreathSample {
+        BreathSample::new(self.strained_depth(), self.alpha, self.direction)
+    }
+
+    /// Seconds until the current breath turns around
+    fn seconds_left(&self) -> f32 {
+        let depth = saturate(self.strained_depth(), Self::MIN_RATE_DEPTH, Self::MAX_DEPTH);
+        let rate =
+            (saturate(self.strained_speed(), 0.0, Self::MAX_SPEED) / depth).min(Self::MAX_SPEED);
+
+        if rate <= 0.0 {
+            return f32::INFINITY;
+        }
+
+        (1.0 - saturate(self.alpha, 0.0, 1.0)) / rate
+    }
+
+    /// Whether the breath is in the last `window` seconds of an exhale, where the lungs are
+    /// emptiest and the body stillest
+    fn in_respiratory_pause(&self, window: f32) -> bool {
+        self.direction == BreathDirection::Out && self.seconds_left() <= window
+    }
+}
+
+/// One inhale or exhale of a breathing cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BreathPhase {
+    direction: BreathDirection,
+    /// Increases by one every phase, so consecutive phases can be told apart
+    cycle_index: u32,
+}
+
+/// An event sent when a breathing entity switches between inhaling and exhaling.
+#[derive(Message, Debug)]
+struct BreathPhaseChanged {
+    entity: Entity,
+    direction: BreathDirection,
+    cycle_index: u32,
+}
+
+/// A point in a breathing cycle. Every value is finite and within range, whatever the breath
+/// settings were.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BreathSample {
+    /// Progress through the current breath, `0..=1`
+    alpha: f32,
+    /// How far into the breath we are, `0..=depth`
+    amount: f32,
+    depth: f32,
+    direction: BreathDirection,
+}
+
+impl BreathSample {
+    fn new(depth: f32, alpha: f32, direction: BreathDirection) -> Self {
+        let depth = saturate(depth, 0.0, Breath::MAX_DEPTH);
+        let alpha = saturate(alpha, 0.0, 1.0);
+
+        Self {
+            alpha,
+            amount: depth * EaseFunction::SmoothStep.sample_clamped(alpha),
+            depth,
+            direction,
+        }
+    }
+
+    /// Progress through the current breath passed through `ease`, `0..=1`
+    fn eased(&self, ease: EaseFunction) -> f32 {
+        saturate(ease.sample_clamped(self.alpha), 0.0, 1.0)
+    }
+}
+
+/// Clamp `value` to `min..=max`, treating NaN as `min`
+fn saturate(value: f32, min: f32, max: f32) -> f32 {
+    if value.is_nan() {
+        min
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+/// Step the breath cycle forward by `delta` seconds, flipping direction when a breath completes
+fn advance_breath(
+    speed: f32,
+    depth: f32,
+    alpha: f32,
+    direction: BreathDirection,
+    delta: f32,
+) -> (f32, BreathDirection) {
+    let speed = saturate(speed, 0.0, Breath::MAX_SPEED);
+    let depth = saturate(depth, Breath::MIN_RATE_DEPTH, Breath::MAX_DEPTH);
+    let delta = saturate(delta, 0.0, f32::MAX);
+
+    // clamp to max breathing speed to ensure shallow breaths (<1.0) at max breath effort doesnt
+    // create insane breathing rates
+    let breathing_rate = (speed / depth).min(Breath::MAX_SPEED);
+
+    // increase alpha slower for deeper breaths
+    let alpha = saturate(alpha, 0.0, 1.0) + breathing_rate * delta;
+
+    if alpha < 1.0 {
+        return (alpha, direction);
+    }
+
+    (0.0, direction.flipped())
+}
+
+#[derive(Component)]
+#[require(ViewTilt, movement::EyeDrop, movement::HeadBob)]
+struct PlayerCamera;
+
+/// The breath tilt a player's camera was last given, undone before the next so it never adds up
+/// with the player's look.
+#[derive(Component, Default)]
+struct ViewTilt(Quat);
+
+#[derive(Component)]
+#[require(accuracy::Accuracy)]
+struct PlayerWeapon;
+
+fn player_walk_init(time: Res<Time>, players_q: Query<(&mut Walk, &LinearVelocity), With<Player>>) {
+    for (mut walk, speed) in players_q {
+        walk.speed = speed.length() / 2.0;
+        walk.walk(time.delta_secs());
+    }
+}
+
+fn breathe(
+    time: Res<Time>,
+    mut phase_writer: MessageWriter<BreathPhaseChanged>,
+    mut changes: Local<Parallel<Vec<BreathPhaseChanged>>>,
+    mut breathers_q: Query<(Entity, &mut Breath)>,
+) {
+    let delta = time.delta_secs();
+
+    // every breath is independent, so they're spread over threads and the changes gathered after
+    breathers_q.par_iter_mut().for_each(|(entity, mut breath)| {
+        let phase = breath.phase();
+        breath.breath(delta);
+
+        let next = breath.phase();
+
+        if next != phase {
+            changes.borrow_local_mut().push(BreathPhaseChanged {
+                entity,
+                direction: next.direction,
+                cycle_index: next.cycle_index,
+            });
+        }
+    });
+
+    phase_writer.write_batch(changes.drain());
+}
+
+fn log_breath_phase(mut phase_reader: MessageReader<BreathPhaseChanged>) {
+    for phase in phase_reader.read() {
+        debug!(
+            "{} breath {:?} #{}",
+            phase.entity, phase.direction, phase.cycle_index
+        );
+    }
+}
+
+fn player_breath_alter(
+    time: Res<Time>,
+    players_q: Query<&mut Breath, With<Player>>,
+    keys: Res<ButtonInput<KeyCode>>,
+) {
+    /// Change in depth or speed per second a key is held
+    const ALTER_RATE: f32 = 6.0;
+
+    let change = ALTER_RATE * time.delta_secs();
+
+    for mut breath in players_q {
+        if keys.pressed(KeyCode::BracketRight) {
+            breath.depth += change;
+        }
+
+        if keys.pressed(KeyCode::BracketLeft) {
+            breath.depth -= change;
+        }
+
+        if keys.pressed(KeyCode::PageUp) {
+            breath.speed += change;
+        }
+
+        if keys.pressed(KeyCode::PageDown) {
+            breath.speed -= change;
+        }
+    }
+}
+
+/// Sprint while aiming holds the breath instead, freezing it and steadying the weapon for a few
+/// seconds.
+fn player_hold_breath(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<settings::Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players_q: Query<
+        (
+            &mut Breath,
+            &input_buffer::ActionBuffer,
+            &split_screen::PlayerInput,
+        ),
+        With<Player>,
+    >,
+) {
+    const GAMEPAD_BUTTON: GamepadButton = GamepadButton::RightThumb;
+
+    for (mut breath, actions, input) in players_q {
+        let gamepad = input.gamepad(&gamepads);
+        let keyboard = input.keyboard_mouse.then_some(&*keys);
+
+        let start = keyboard.is_some_and(|keys| keys.just_pressed(keybinds.sprint))
+            || gamepad.is_some_and(|gamepad| gamepad.just_pressed(GAMEPAD_BUTTON));
+        let holding = keyboard.is_some_and(|keys| keys.pressed(keybinds.sprint))
+            || gamepad.is_some_and(|gamepad| gamepad.pressed(GAMEPAD_BUTTON));
+        let aiming = actions.pressed(input_buffer::Action::Aim);
+
+        let held = breath.is_held();
+        breath.hold(start && aiming, holding && aiming);
+
+        if breath.is_held() != held {
+            debug!("breath {}", if held { "let go" } else { "held" });
+        }
+    }
+}
+
+#[derive(Component, Default)]
+struct WeaponSway {
+    max_sway: f32,
+    base: Vec3,
+    next: Vec3,
+}
+
+impl WeaponSway {
+    /// Metres a player's weapon sways off with each breath, at most
+    const PLAYER_MAX_SWAY: f32 = 0.0005;
+
+    fn new(max_sway: f32) -> Self {
+        Self {
+            max_sway,
+            ..default()
+        }
+    }
+
+    fn renew(&mut self) {
+        self.base = self.next;
+    }
+
+    /// How far the muzzle turns to follow a weapon swayed `offset` from where it's held, so it
+    /// points off the way it's drifted rather than sliding about parallel
+    fn tilt(offset: Vec3) -> Quat {
+        /// Radians per metre
+        const TILT: f32 = 20.0;
+
+        Quat::from_euler(EulerRot::YXZ, -offset.x * TILT, offset.y * TILT, 0.0)
+    }
+
+    /// Pick the next target, pulled `strafe` of the way towards the side being strafed to
+    fn change(&mut self, breath: &BreathSample, scale: f32, strafe: f32, rng: &mut impl Rng) {
+        /// How far strafing pulls the target sideways, as a share of the full sway
+        const STRAFE_BIAS: f32 = 0.5;
+
+        let max_sway = self.max_sway * scale;
+
+        if let Some(mut next) = sway_target(rng, max_sway, breath) {
+            next.x += strafe.clamp(-1.0, 1.0) * max_sway * breath.depth * STRAFE_BIAS;
+            self.next = next;
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.base == self.next
+    }
+
+    /// Whether it's centred with nowhere to sway to, as it is with no sway at all
+    fn is_still(&self) -> bool {
+        self.base == Vec3::ZERO && self.next == Vec3::ZERO
+    }
+
+    /// Return the vector from base to next relative to the origin
+    ///
+    /// This gives us a way of swaying from one sway location to another without having to revisit
+    /// the centre
+    fn diff_from(&self, origin: Vec3) -> Vec3 {
+        let base_from_origin = origin + self.base;
+        let next_from_origin = origin + self.next;
+        next_from_origin - base_from_origin
+    }
+
+    /// Lerp from the old sway target (base) to the new sway target (next)
+    fn lerp_from(&self, origin: Vec3, alpha: f32) -> Vec3 {
+        self.base + self.diff_from(origin) * alpha
+    }
+}
+
+/// Pick a random sway target for the current breath, or `None` if there is no room to sway
+fn sway_target(rng: &mut impl Rng, max_sway: f32, breath: &BreathSample) -> Option<Vec3> {
+    let effective_sway = max_sway * breath.depth;
+    let half_sway = effective_sway / 2.0;
+
+    let sway_in = if breath.direction == BreathDirection::In {
+        effective_sway
+    } else {
+        0.0
+    };
+
+    let sway_out = if breath.direction == BreathDirection::Out {
+        effective_sway
+    } else {
+        0.0
+    };
+
+    let x_range = -half_sway..=half_sway;
+    let y_range = -sway_in..=sway_out;
+    let z_range = -effective_sway..=effective_sway;
+
+    if x_range.is_empty() || y_range.is_empty() || z_range.is_empty() {
+        return None;
+    }
+
+    Some(Vec3::new(
+        // smaller half-sway in the X
+        rng.random_range(x_range),
+        // flip-flop up and down full sway for Y
+        rng.random_range(y_range),
+        // full sway range in the Z
+        rng.random_range(z_range),
+    ))
+}
+
+/// Makes this entity sway with the breathing of the entity it points at.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = SwayTargets)]
+struct SwayTarget(Entity);
+
+/// Every entity swaying with this entity's breathing.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = SwayTarget)]
+struct SwayTargets(Vec<Entity>);
+
+fn weapon_sway(
+    difficulty: Res<difficulty::Difficulty>,
+    mut phase_reader: MessageReader<BreathPhaseChanged>,
+    breathers_q: Query<(
+        Entity,
+        &Breath,
+        &mut WeaponSway,
+        &SwayTargets,
+        Option<&encumbrance::Encumbrance>,
+        Option<&status::StatusEffects>,
+        Option<&sway::RespiratoryPause>,
+        Option<&movement::AimState>,
+        Option<&movement::Stance>,
+        Has<Player>,
+    )>,
+    mut targets_q: Query<
+        (
+            &mut TransformPipeline,
+            Option<&sway::SwayProfile>,
+            Option<&dual_wield::WeaponHand>,
+        ),
+        With<WeaponActive>,
+    >,
+) {
+    let changed: SmallVec<[Entity; 8]> = phase_reader.read().map(|phase| phase.entity).collect();
+
+    for (
+        entity,
+        breath,
+        mut weapon_sway,
+        targets,
+        encumbrance,
+        effects,
+        pause,
+        aim,
+        stance,
+        is_player,
+    ) in breathers_q
+    {
+        let hold_steadiness = breath.hold_steadiness();
+        let breath = breath.sample();
+
+        if changed.contains(&entity) {
+            weapon_sway.renew();
+        }
+
+        let change_sway = weapon_sway.is_complete();
+
+        if change_sway {
+            // difficulty only eases the player's own aim
+            let difficulty_scale = if is_player {
+                difficulty.preset().sway_scale
+            } else {
+                1.0
+            };
+
+            let scale = difficulty_scale
+                * encumbrance.map_or(1.0, encumbrance::Encumbrance::sway_scale)
+                * effects.map_or(1.0, |effects| effects.modifiers().sway)
+                * stance.map_or(1.0, |stance| stance.sway_scale());
+
+            let strafe = aim.map_or(0.0, |aim| aim.strafe);
+            weapon_sway.change(&breath, scale, strafe, &mut rand::rng());
+        }
+
+        // with sway turned off there's nothing to add to its weapons
+        if weapon_sway.is_still() {
+            continue;
+        }
+
+        let curve_alpha = breath.eased(EaseFunction::SmoothStep);
+        let steadiness = pause.map_or(1.0, sway::RespiratoryPause::steadiness) * hold_steadiness;
+
+        for target in targets.iter() {
+            let Ok((mut position_pipe, profile, hand)) = targets_q.get_mut(target) else {
+                continue;
+            };
+
+            // other profiles are driven by `sway::profile_sway`
+            if profile.is_some_and(|profile| !matches!(profile, sway::SwayProfile::Breath)) {
+                continue;
+            }
+
+            let position = position_pipe.latest();
+            let offset = weapon_sway.lerp_from(position, curve_alpha) * steadiness;
+            let offset = hand.map_or(offset, |hand| hand.mirror(offset));
+            position_pipe.queue(offset);
+            position_pipe.queue_rotation(WeaponSway::tilt(offset));
+        }
+    }
+}
+
+fn player_shoot(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rounds: Local<u32>,
+    round_assets: Res<RoundAssets>,
+    mut pool: ResMut<projectile::ProjectilePool>,
+    spatial_query: SpatialQuery,
+    colliders: Query<&ColliderOf>,
+    mut shot_writer: MessageWriter<ShotFired>,
+    mut hit_writer: MessageWriter<damage::HitEvent>,
+    players: Query<
+        (&input_buffer::ActionBuffer, &status::StatusEffects),
+        (
+            With<Player>,
+            Without<vehicle::Driving>,
+            Without<turret::Manning>,
+            Without<glide::Gliding>,
+        ),
+    >,
+    weapons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &weapon::WeaponStats,
+            &SwayTarget,
+            Option<&Children>,
+            Option<&dual_wield::WeaponHand>,
+            Option<&mut weapon::Magazine>,
+            Option<&mut weapon::Trigger>,
+            &weapon::FireMode,
+            Option<&sockets::WeaponSockets>,
+            Has<underbarrel::UnderbarrelActive>,
+            &accuracy::Accuracy,
+            Option<&SprintAlpha>,
+        ),
+        (
+            With<PlayerWeapon>,
+            Without<weapon::Reloading>,
+            Without<weapon::Raising>,
+        ),
+    >,
+    anchors: Query<(&sockets::SocketAnchor, &GlobalTransform)>,
+) {
+    let mut rng = rand::rng();
+
+    for (
+        weapon,
+        weapon_transform,
+        stats,
+        owner,
+        children,
+        hand,
+        magazine,
+        trigger,
+        fire_mode,
+        sockets,
+        underbarrel,
+        accuracy,
+        sprint_alpha,
+    ) in weapons
+    {
+        // nothing comes out of a weapon still lowered for a sprint
+        if sprint_alpha.is_some_and(|alpha| alpha.0 > 0.5) {
+            continue;
+        }
+
+        let player = owner.0;
+        // filed with the rest of the weapon logs
+        let _span = debug_span!(target: "energy::weapon", "shoot", %weapon, %player).entered();
+
+        let Ok((actions, effects)) = players.get(player) else {
+            continue;
+        };
+
+        let action = hand.map_or(input_buffer::Action::Fire, |hand| hand.trigger());
+        let just_pressed = actions.just_pressed(action);
+
+        // underbarrels fire a round each pull, whatever the weapon's set to
+        let fired = match trigger {
+            Some(mut trigger) if !underbarrel => {
+                trigger.fire(time.delta_secs(), just_pressed, actions.pressed(action))
+            }
+            _ => just_pressed,
+        };
+
+        if !fired {
+            continue;
+        }
+
+        if let Some(mut magazine) = magazine
+            && !magazine.take_round()
+        {
+            continue;
+        }
+
+        // underbarrels fire from where they're attached, if the model says
+        let socket = if underbarrel
+            && sockets.is_some_and(|sockets| sockets.get(sockets::Socket::Attachment).is_some())
+        {
+            sockets::Socket::Attachment
+        } else {
+            sockets::Socket::Muzzle
+        };
+
+        // out of the model's muzzle if it has one
+        let spawn_transform = children
+            .into_iter()
+            .flatten()
+            .filter_map(|child| anchors.get(*child).ok())
+            .find(|(anchor, _)| anchor.0 == socket)
+            .map_or(weapon_transform, |(_, transform)| transform);
+
+        // somewhere in the cone of fire
+        let muzzle = spawn_transform.compute_transform();
+        let spawn_transform = &GlobalTransform::from(
+            muzzle.with_rotation(muzzle.rotation * accuracy.deviation(&mut rng)),
+        );
+
+        let shot = Shot {
+            shooter: player,
+            weapon,
+            damage: stats.damage * effects.modifiers().damage,
+            muzzle_velocity: stats.muzzle_velocity,
+            round_mass: stats.round_mass,
+        };
+
+        let mut fire = |muzzle: &GlobalTransform| {
+            fire_round(
+                &mut commands,
+                &round_assets,
+                &mut pool,
+                &mut shot_writer,
+                &mut rounds,
+                muzzle,
+                shot,
+            )
+        };
+
+        if stats.hitscan && *fire_mode != weapon::FireMode::Single {
+            warn_once!("only single rounds are hitscan, firing the rest as rounds");
+        }
+
+        match *fire_mode {
+            weapon::FireMode::Single if stats.hitscan => {
+                fire_hitscan(
+                    &spatial_query,
+                    &colliders,
+                    &mut shot_writer,
+                    &mut hit_writer,
+                    spawn_transform,
+                    shot,
+                );
+            }
+            weapon::FireMode::Single => {
+                fire(spawn_transform);
+            }
+            weapon::FireMode::Shotgun { pellets, spread } => {
+                let spread = spread.to_radians();
+                let muzzle = spawn_transform.compute_transform();
+
+                for _ in 0..pellets {
+                    let pellet = Quat::from_euler(
+                        EulerRot::YXZ,
+                        rng.random_range(-spread..=spread),
+                        rng.random_range(-spread..=spread),
+                        0.0,
+                    );
+                    fire(&muzzle.with_rotation(muzzle.rotation * pellet).into());
+                }
+            }
+            weapon::FireMode::Launcher { radius } => {
+                let grenade = fire(spawn_transform);
+                commands
+                    .entity(grenade)
+                    .remove::<damage::Projectile>()
+                    .insert(damage::Blast {
+                        damage: shot.damage,
+                        radius,
+                        shooter: player,
+                    });
+            }
+        }
+    }
+}
+
+/// A round about to leave a muzzle.
+#[derive(Clone, Copy)]
+struct Shot {
+    shooter: Entity,
+    weapon: Entity,
+    damage: f32,
+    muzzle_velocity: f32,
+    round_mass: f32,
+}
+
+/// The mesh and materials every round shares, so however many are in flight they're drawn
+/// instanced together.
+#[derive(Resource)]
+struct RoundAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    /// Glowing, for tracers
+    tracer_material: Handle<StandardMaterial>,
+}
+
+fn setup_round_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(RoundAssets {
+        mesh: meshes.add(Sphere::new(0.05)),
+        material: materials.add(Color::WHITE),
+        tracer_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1.0, 0.6, 0.25),
+            emissive: LinearRgba::rgb(12.0, 5.0, 1.5),
+            ..default()
+        }),
+    });
+}
+
+/// Fire a round out of the front of `muzzle`, on an entity from `pool` if there's one spare.
+/// Everything that shoots goes through here, so damage, tracers and muzzle effects are the same
+/// whatever the round came from. `rounds` counts the shooter's rounds so every few can be a
+/// tracer. Returns the round
+fn fire_round(
+    commands: &mut Commands,
+    assets: &RoundAssets,
+    pool: &mut projectile::ProjectilePool,
+    shot_writer: &mut MessageWriter<ShotFired>,
+    rounds: &mut u32,
+    muzzle: &GlobalTransform,
+    shot: Shot,
+) -> Entity {
+    /// Every this many rounds is a tracer
+    const TRACER_EVERY: u32 = 3;
+
+    shot_writer.write(ShotFired {
+        shooter: shot.shooter,
+        weapon: shot.weapon,
+        origin: muzzle.translation(),
+        direction: *muzzle.forward(),
+    });
+
+    *rounds += 1;
+    let tracer = rounds.is_multiple_of(TRACER_EVERY);
+
+    let mut round = pool.take(commands);
+    round.insert((
+        Mesh3d(assets.mesh.clone()),
+        MeshMaterial3d(if tracer {
+            assets.tracer_material.clone()
+        } else {
+            assets.material.clone()
+        }),
+        Transform::from_translation(muzzle.translation()),
+        RigidBody::Dynamic,
+        Collider::sphere(0.05),
+        // the contact hands the round's momentum to whatever it hits, knocking it about
+        Mass(shot.round_mass),
+        LinearVelocity(muzzle.forward() * shot.muzzle_velocity),
+        CollisionEventsEnabled,
+        damage::Projectile {
+            damage: shot.damage,
+            shooter: shot.shooter,
+        },
+        cleanup::Cleanup::new(cleanup::CleanupPolicy::ROUND),
+        sweep::Swept {
+            shooter: shot.shooter,
+        },
+    ));
+
+    if tracer {
+        round.insert(dynamic_lights::DynamicLight::tracer());
+    }
+
+    round.id()
+}
+
+/// Fire a round from `muzzle` that strikes whatever's in front of it straight away, rather than
+/// flying there, so however fast it's meant to be it can't pass through anything
+fn fire_hitscan(
+    spatial_query: &SpatialQuery,
+    colliders: &Query<&ColliderOf>,
+    shot_writer: &mut MessageWriter<ShotFired>,
+    hit_writer: &mut MessageWriter<damage::HitEvent>,
+    muzzle: &GlobalTransform,
+    shot: Shot,
+) {
+    /// Metres a hitscan round reaches
+    const RANGE: f32 = 300.0;
+
+    let origin = muzzle.translation();
+    let direction = muzzle.forward();
+
+    shot_writer.write(ShotFired {
+        shooter: shot.shooter,
+        weapon: shot.weapon,
+        origin,
+        direction: *direction,
+    });
+
+    // rounds leave through the shooter's own hitboxes
+    let Some(hit) = spatial_query.cast_ray_predicate(
+        origin,
+        direction,
+        RANGE,
+        true,
+        &SpatialQueryFilter::default().with_excluded_entities([shot.shooter]),
+        &|entity| {
+            !colliders
+                .get(entity)
+                .is_ok_and(|collider_of| collider_of.body == shot.shooter)
+        },
+    ) else {
+        return;
+    };
+
+    hit_writer.write(damage::HitEvent {
+        entity: hit.entity,
+        point: origin + *direction * hit.distance,
+        normal: hit.normal,
+        shooter: shot.shooter,
+        damage: shot.damage,
+        impulse: direction * shot.muzzle_velocity * shot.round_mass,
+    });
+}
+
+fn set_weapon_transform(
+    mut weapon_query: Query<
+        (
+            &mut Transform,
+            &mut TransformPipeline,
+            &PlayerWeaponTransformConfig,
+            &AdsAlpha,
+            &SprintAlpha,
+        ),
+        (With<PlayerWeapon>, With<WeaponActive>),
+    >,
+) {
+    for (mut trans, mut current_translation, config, ads, sprint) in &mut weapon_query {
+        let (translation, tilt) = current_translation.apply();
+        let rotation = config.rotation(
+            EaseFunction::SmoothStep.sample_clamped(ads.0),
+            EaseFunction::SmoothStep.sample_clamped(sprint.0),
+        ) * tilt;
+
+        // only touched when it's moved, so a weapon held still isn't propagated again every tick
+        trans.set_if_neq(trans.with_translation(translation).with_rotation(rotation));
+    }
+}
+
+/// Brings weapons up into their sprinting pose while their player sprints, unless they're aiming.
+fn sprint_pose(
+    time: Res<Time>,
+    players: Query<
+        (
+            &input_buffer::ActionBuffer,
+            &LinearVelocity,
+            Has<movement::Sprinting>,
+        ),
+        With<Player>,
+    >,
+    mut weapon_query: Query<
+        (
+            &mut TransformPipeline,
+            &PlayerWeaponTransformConfig,
+            &mut SprintAlpha,
+            &SwayTarget,
+            Option<&HeldStance>,
+        ),
+        (With<PlayerWeapon>, With<WeaponActive>),
+    >,
+) {
+    /// Sprint alpha gained or lost per second
+    const SPEED: f32 = 4.0;
+    /// Horizontal speed below which a sprinting player is just standing with the key held
+    const MOVING: f32 = 0.5;
+
+    for (mut pipeline, config, mut sprint_alpha, owner, held) in &mut weapon_query {
+        let Ok((actions, velocity, sprinting)) = players.get(owner.0) else {
+            continue;
+        };
+
+        let sprinting = match held {
+            Some(HeldStance(stance)) => *stance == WeaponStance::Sprint,
+            None => {
+                sprinting
+                    && velocity.xz().length() > MOVING
+                    && !actions.pressed(input_buffer::Action::Aim)
+            }
+        };
+
+        // lowered and staying that way adds nothing
+        if !sprinting && sprint_alpha.0 == 0.0 {
+            continue;
+        }
+
+        let step = if sprinting { SPEED } else { -SPEED };
+        sprint_alpha.set_if_neq(SprintAlpha(
+            (sprint_alpha.0 + step * time.delta_secs()).clamp(0.0, 1.0),
+        ));
+
+        let curve_alpha = EaseFunction::SmoothStep.sample_clamped(sprint_alpha.0);
+        pipeline.queue(config.sprint_difference() * curve_alpha);
+    }
+}
+
+/// Move the ADS alpha towards aiming or not over `delta` seconds
+fn step_ads_alpha(alpha: f32, aiming: bool, handling: f32, delta: f32) -> f32 {
+    // these values could come from some kind of config and or multipliers
+    /// ADS alpha gained per second while aiming
+    const AIM_SPEED: f32 = 3.2;
+    /// ADS alpha lost per second after letting go
+    const UN_AIM_SPEED: f32 = 3.2;
+
+    let step = if aiming {
+        AIM_SPEED * handling
+    } else {
+        -UN_AIM_SPEED
+    };
+
+    (alpha + step * delta).clamp(0.0, 1.0)
+}
+
+fn aim(
+    time: Res<Time>,
+    players: Query<(&input_buffer::ActionBuffer, Option<&attributes::Attributes>), With<Player>>,
+    mut weapon_query: Query<
+        (
+            &mut TransformPipeline,
+            &PlayerWeaponTransformConfig,
+            &mut AdsAlpha,
+            &SwayTarget,
+            &weapon::WeaponStats,
+            Option<&HeldStance>,
+            Has<dual_wield::WeaponHand>,
+        ),
+        (With<PlayerWeapon>, With<WeaponActive>),
+    >,
+) {
+    for (
+        mut current_transform,
+        transform_config,
+        mut ads_alpha,
+        owner,
+        stats,
+        held,
+        dual_wielded,
+    ) in &mut weapon_query
+    {
+        let Ok((actions, attributes)) = players.get(owner.0) else {
+            continue;
+        };
+
+        let handling =
+            attributes.map_or(1.0, attributes::Attributes::handling_scale) * stats.handling;
+        let aiming = match held {
+            Some(HeldStance(stance)) => *stance == WeaponStance::Aim,
+            // the aim button fires the left hand's weapon instead
+            None => !dual_wielded && actions.pressed(input_buffer::Action::Aim),
+        };
+
+        // at the hip and staying there adds nothing
+        if !aiming && ads_alpha.0 == 0.0 {
+            continue;
+        }
+
+        let ease = if aiming {
+            EaseFunction::QuarticOut
+        } else {
+            EaseFunction::QuinticInOut
+        };
+
+        ads_alpha.set_if_neq(AdsAlpha(step_ads_alpha(
+            ads_alpha.0,
+            aiming,
+            handling,
+            time.delta_secs(),
+        )));
+
+        let curve_alpha = EasingCurve::new(0.0, 1.0, ease)
+            .sample(ads_alpha.0)
+            .unwrap_or(0.0);
+
+        current_transform.queue(transform_config.aim_difference() * curve_alpha);
+    }
+}
+
+/// Tells movement how far each player is aimed in, with whichever of their weapons is furthest up,
+/// and how much that weapon slows them down.
+fn share_aim_state(
+    mut players: Query<&mut movement::AimState, With<Player>>,
+    weapons: Query<(&SwayTarget, &AdsAlpha, &weapon::WeaponStats), With<WeaponActive>>,
+) {
+    for mut aim in &mut players {
+        aim.alpha = 0.0;
+    }
+
+    for (owner, ads_alpha, stats) in weapons {
+        let Ok(mut aim) = players.get_mut(owner.0) else {
+            continue;
+        };
+
+        if ads_alpha.0 >= aim.alpha {
+            aim.alpha = ads_alpha.0;
+            aim.speed_scale = stats.ads_speed;
+        }
+    }
+}
+
+fn damp_weapon_look(
+    time: Res<Time>,
+    camera_tuning: Res<tuning::CameraTuning>,
+    mut q_look_amount: Query<(&mut PlayerLookRotation, &Children), With<Player>>,
+    q_camera: Query<&Children, With<PlayerCamera>>,
+    mut q_weapon: Query<&mut Transform, With<PlayerWeapon>>,
+) {
+    let delta = time.delta_secs();
+    for (look_amount, children) in q_look_amount.iter_mut() {
+        children
+            .iter()
+            .filter_map(|x| q_camera.get(x).ok())
+            .flat_map(|x| x.iter())
+            .for_each(|x| {
+                let Ok(mut weapon) = q_weapon.get_mut(x) else {
+                    return;
+                };
+
+                let smooth_reduce = |rot: f32, mut amount: f32| {
+                    if rot != 0.0 {
+                        amount -= (rot * 30.0) * delta;
+                    }
+                    amount
+                };
+
+                let mut amount = look_amount.0;
+
+                amount.x *= camera_tuning.weapon_look_sensitivity_x;
+                amount.y *= camera_tuning.weapon_look_sensitivity_y;
+
+                amount.x = smooth_reduce(weapon.rotation.x, amount.x);
+                amount.y = smooth_reduce(weapon.rotation.y, amount.y);
+
+                weapon.rotate_x(amount.x);
+                weapon.rotate_y(amount.y);
+            });
+    }
+}
+
+fn look_vertical(
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    touch: Res<touch::TouchControls>,
+    gamepads: Query<&Gamepad>,
+    aim_assist: Res<difficulty::AimAssist>,
+    camera_tuning: Res<tuning::CameraTuning>,
+    time: Res<Time>,
+    mut q_look_amount: Query<(&mut PlayerLookRotation, &split_screen::PlayerInput), With<Player>>,
+    mut q_transform: Query<(&ChildOf, &mut Transform), With<PlayerCamera>>,
+) {
+    const LIMIT: f32 = 45_f32;
+    const ZERO: f32 = 0_f32;
+
+    let rotation_speed = camera_tuning.look_sensitivity_y;
+
+    for (is_child, mut transform) in q_transform.iter_mut() {
+        let Ok((mut look_amount, input)) = q_look_amount.get_mut(is_child.get()) else {
+            continue;
+        };
+
+        let look = input.look(&mouse_motion, &gamepads, &touch);
+        let rotation_amount_x = (-look.y * rotation_speed * aim_assist.scale()) * time.delta_secs();
+        let positive_rot = rotation_amount_x > ZERO;
+        let negative_rot = rotation_amount_x < ZERO;
+
+        let current_rot = transform.rotation.to_euler(EulerRot::XYZ).0.to_degrees();
+        let high = current_rot > LIMIT && positive_rot;
+        let low = current_rot < -LIMIT && negative_rot;
+
+        if high || low {
+            continue;
+        }
+
+        let amount = rotation_amount_x.to_radians();
+
+        transform.rotate_x(amount);
+        look_amount.0.x = amount;
+    }
+}
+
+fn rotate_horizontal(
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    touch: Res<touch::TouchControls>,
+    gamepads: Query<&Gamepad>,
+    aim_assist: Res<difficulty::AimAssist>,
+    camera_tuning: Res<tuning::CameraTuning>,
+    time: Res<Time>,
+    mut q_transform: Query<
+        (
+            &mut Transform,
+            &mut PlayerLookRotation,
+            &split_screen::PlayerInput,
+        ),
+        With<Player>,
+    >,
+) {
+    let rotation_speed = camera_tuning.look_sensitivity_x;
+
+    for (mut transform, mut look_rot, input) in q_transform.iter_mut() {
+        let look = input.look(&mouse_motion, &gamepads, &touch);
+        let rotation_amount_y = -look.x * rotation_speed * aim_assist.scale();
+        let amount = rotation_amount_y * time.delta_secs();
+
+        transform.rotate_y(amount);
+        look_rot.0.y = amount;
+    }
+}
+
+#[derive(Component, PartialEq)]
+struct AdsAlpha(f32);
+
+/// How far a weapon has been brought up into its sprinting pose, `0..=1`.
+#[derive(Component, Default, PartialEq)]
+struct SprintAlpha(f32);
+
+/// The ways a weapon can be held, see [`PlayerWeaponTransformConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WeaponStance {
+    Hip,
+    Aim,
+    Sprint,
+}
+
+/// Holds a weapon in one stance whatever its player is doing, for the pose editor.
+#[derive(Component, Debug)]
+struct HeldStance(WeaponStance);
+
+/// Where a weapon sits relative to the camera in one stance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WeaponPose {
+    translation: Vec3,
+    rotation: Quat,
+}
+
+impl WeaponPose {
+    fn at(translation: Vec3) -> Self {
+        Self {
+            translation,
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    /// The same pose on the other side of the screen
+    fn mirrored(&self) -> Self {
+        Self {
+            translation: self.translation * Vec3::new(-1.0, 1.0, 1.0),
+            rotation: Quat::from_xyzw(
+                self.rotation.x,
+                -self.rotation.y,
+                -self.rotation.z,
+                self.rotation.w,
+            ),
+        }
+    }
+}
+
+/// Where a weapon sits in each stance.
+///
+/// When the weapon's model has a sight socket, the aiming position isn't set by hand but worked
+/// out so the sight sits `eye_relief` straight in front of the camera, see [`Self::align_sight`].
+#[derive(Component, Clone)]
+struct PlayerWeaponTransformConfig {
+    hip: WeaponPose,
+    aim: WeaponPose,
+    sprint: WeaponPose,
+    /// Where the model's sight is relative to the weapon, if it has one
+    sight: Option<Vec3>,
+    /// Metres from the eye to the sight when aiming
+    eye_relief: f32,
+}
+
+impl PlayerWeaponTransformConfig {
+    const DEFAULT_EYE_RELIEF: f32 = 0.25;
+
+    /// Held at `hip` in every stance and pointing straight ahead, until a weapon definition or a
+    /// sight says otherwise
+    fn new(hip: Vec3) -> Self {
+        Self {
+            hip: WeaponPose::at(hip),
+            aim: WeaponPose::at(hip),
+            sprint: WeaponPose::at(hip),
+            sight: None,
+            eye_relief: Self::DEFAULT_EYE_RELIEF,
+        }
+    }
+
+    /// The same poses for the weapon in the left hand
+    fn mirrored(&self) -> Self {
+        Self {
+            hip: self.hip.mirrored(),
+            aim: self.aim.mirrored(),
+            sprint: self.sprint.mirrored(),
+            sight: self.sight.map(|sight| sight * Vec3::new(-1.0, 1.0, 1.0)),
+            eye_relief: self.eye_relief,
+        }
+    }
+
+    /// Moves the aiming pose so the sight is on the camera's forward axis, `eye_relief` ahead.
+    /// Weapons without a sight keep the aiming position they were given.
+    fn align_sight(&mut self) {
+        if let Some(sight) = self.sight {
+            self.aim.translation = Vec3::NEG_Z * self.eye_relief - self.aim.rotation * sight;
+        }
+    }
+
+    fn pose(&self, stance: WeaponStance) -> &WeaponPose {
+        match stance {
+            WeaponStance::Hip => &self.hip,
+            WeaponStance::Aim => &self.aim,
+            WeaponStance::Sprint => &self.sprint,
+        }
+    }
+
+    fn pose_mut(&mut self, stance: WeaponStance) -> &mut WeaponPose {
+        match stance {
+            WeaponStance::Hip => &mut self.hip,
+            WeaponStance::Aim => &mut self.aim,
+            WeaponStance::Sprint => &mut self.sprint,
+        }
+    }
+
+    fn aim_difference(&self) -> Vec3 {
+        self.aim.translation - self.hip.translation
+    }
+
+    fn sprint_difference(&self) -> Vec3 {
+        self.sprint.translation - self.hip.translation
+    }
+
+    /// The weapon's rotation `ads` of the way into aiming and `sprint` of the way into sprinting
+    fn rotation(&self, ads: f32, sprint: f32) -> Quat {
+        self.hip
+            .rotation
+            .slerp(self.aim.rotation, ads)
+            .slerp(self.sprint.rotation, sprint)
+    }
+}
+
+/// Translations and rotations queued on top of a base translation each step, and applied all at
+/// once, for a weapon by `set_weapon_transform`.
+#[derive(Component)]
+struct TransformPipeline {
+    base_translation: Vec3,
+    additive_translations: SmallVec<[Vec3; TransformPipeline::LAYERS]>,
+    /// Applied on top of whatever the pose's rotation is
+    additive_rotations: SmallVec<[Quat; TransformPipeline::LAYERS]>,
+}
+
+impl TransformPipeline {
+    /// Translations or rotations that fit without allocating, more than the aim, sprint, sway,
+    /// bob and recoil layers there are
+    const LAYERS: usize = 8;
+
+    fn new(translation: Vec3) -> Self {
+        Self {
+            base_translation: translation,
+            additive_translations: SmallVec::new(),
+            additive_rotations: SmallVec::new(),
+        }
+    }
+
+    fn queue(&mut self, translation: Vec3) -> &Self {
+        self.additive_translations.push(translation);
+        self
+    }
+
+    fn queue_rotation(&mut self, rotation: Quat) -> &Self {
+        self.additive_rotations.push(rotation);
+        self
+    }
+
+    fn latest(&mut self) -> Vec3 {
+        compose_translation(self.base_translation, &self.additive_translations)
+    }
+
+    fn latest_rotation(&self) -> Quat {
+        compose_rotation(&self.additive_rotations)
+    }
+
+    /// The translation and additive rotation queued this step, clearing them for the next
+    fn apply(&mut self) -> (Vec3, Quat) {
+        let output = (self.latest(), self.latest_rotation());
+        self.additive_translations.clear();
+        self.additive_rotations.clear();
+        output
+    }
+}
+
+/// The base translation with every additive translation applied on top
+fn compose_translation(base: Vec3, additive: &[Vec3]) -> Vec3 {
+    additive.iter().fold(base, |output, t| output + t)
+}
+
+/// Every additive rotation applied in turn, the first queued outermost
+fn compose_rotation(additive: &[Quat]) -> Quat {
+    additive
+        .iter()
+        .fold(Quat::IDENTITY, |output, r| output * *r)
+        .normalize()
+}
+
+#[derive(Component)]
+struct WeaponActive;
+
+/// Put away or draw `player`'s weapons, for while their hands are busy with a vehicle or a
+/// mounted gun
+fn holster_weapons(
+    commands: &mut Commands,
+    weapons: &Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+    player: Entity,
+    holstered: bool,
+) {
+    for (weapon, _) in weapons.iter().filter(|(_, owner)| owner.0 == player) {
+        if holstered {
+            commands
+                .entity(weapon)
+                .remove::<WeaponActive>()
+                .insert(Visibility::Hidden);
+        } else {
+            commands
+                .entity(weapon)
+                .try_insert((WeaponActive, Visibility::Inherited));
+        }
+    }
+}
+
+fn apply_player_camera_sway(
+    mut q_camera: Query<
+        (&mut TransformPipeline, &mut Transform, &mut ViewTilt),
+        With<PlayerCamera>,
+    >,
+) {
+    for (mut translation_pipe, mut transform, mut tilt) in &mut q_camera {
+        let (translation, rotation) = translation_pipe.apply();
+        transform.translation = translation;
+
+        // the rest of the camera's rotation is the player's look, so only the tilt is swapped
+        transform.rotation = (transform.rotation * tilt.0.inverse() * rotation).normalize();
+        tilt.0 = rotation;
+    }
+}
+
+/// Moves and tilts each player's view with their breathing, in step with their weapon's sway but
+/// far smaller, and as much as [`settings::GameSettings::view_motion`] allows.
+fn player_camera_sway(
+    camera_tuning: Res<tuning::CameraTuning>,
+    settings: Res<settings::GameSettings>,
+    players_q: Query<&Breath, With<Player>>,
+    q_camera: Query<(&ChildOf, &mut TransformPipeline), With<PlayerCamera>>,
+) {
+    let amount = camera_tuning.breath;
+    let view_motion = settings.view_motion.clamp(0.0, 1.0);
+
+    for (child_of, mut translation_pipe) in q_camera {
+        let Ok(breath) = players_q.get(child_of.get()) else {
+            continue;
+        };
+        let breath = breath.sample();
+
+        let eased = breath.eased(EaseFunction::SmootherStep);
+
+        let curve_alpha = if breath.direction == BreathDirection::In {
+            eased
+        } else {
+            1.0 - eased
+        } * view_motion;
+
+        let tilt = amount.tilt * curve_alpha;
+
+        translation_pipe.additive_translations.clear();
+        translation_pipe.additive_rotations.clear();
+
+        translation_pipe.queue(amount.offset * curve_alpha);
+        translation_pipe.queue_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            tilt.y.to_radians(),
+            tilt.x.to_radians(),
+            tilt.z.to_radians(),
+        ));
+    }
+}
+
+#[derive(Component)]
+struct PlayerLookRotation(Vec2);
+
+fn setup_player(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_assets: Res<game_assets::GameAssets>,
+    weapon_defs: Res<Assets<weapon::WeaponDef>>,
+    players: Res<split_screen::LocalPlayers>,
+) {
+    for index in 0..players.0 {
+        spawn_player(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &game_assets,
+            &weapon_defs,
+            split_screen::PlayerConfig::new(index, players.0),
+        );
+    }
+}
+
+/// Spawn a local player, with their camera and weapon
+fn spawn_player(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    game_assets: &game_assets::GameAssets,
+    weapon_defs: &Assets<weapon::WeaponDef>,
+    config: split_screen::PlayerConfig,
+) -> Entity {
+    // everyone starts with the first shipped weapon. Its model is only known once the definition
+    // has loaded, otherwise `weapon` fills it in when it does
+    let weapon_def = game_assets.weapons[game_assets::GameAssets::WEAPONS[0]].clone();
+    let weapon_model = weapon_defs
+        .get(&weapon_def)
+        .and_then(|def| game_assets.weapon_models.get(&def.model_path()))
+        .cloned()
+        .unwrap_or_default();
+
+    let height = 2.0;
+    let radius = 0.5;
+    let transform = Transform::from_translation(config.position);
+
+    let player = commands
+        .spawn((
+            Mesh3d(meshes.add(Capsule3d::new(radius, height))),
+            MeshMaterial3d(materials.add(Color::WHITE)),
+            Player,
+            transform,
+            movement::CharacterControllerBundle::new(movement::CapsuleSize::new(radius, height))
+                .with_movement(25.0, 2., 0.07, 7.0, (30.0 as Scalar).to_radians()),
+            Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+            Restitution::ZERO.with_combine_rule(CoefficientCombine::Min),
+            GravityScale(2.0),
+            Breath::new(0.75, 1.0, BreathDirection::Out),
+            Walk {
+                amount: 0.0,
+                speed: 1.,
+                depth: 1.0,
+                alpha: 0.0,
+                side: WalkSide::Left,
+            },
+            (
+                WeaponSway::new(WeaponSway::PLAYER_MAX_SWAY),
+                sway::RespiratoryPause::default(),
+            ),
+            (
+                energy::Stamina::new(100.0),
+                environment::Climate::default(),
+                encumbrance::Encumbrance::new(40.0),
+                inventory::Inventory::with_items([("flare", 3)]),
+                status::StatusEffects::default(),
+                damage::Health::new(100.0),
+                damage::HealthRegen::new(damage::RegenSettings::default()),
+                damage::Hitbox(damage::HitZone::Body),
+                equipment::Equipment::default(),
+                vision::VisionDevice::new(180.0),
+                vision::HeatSignature(1.0),
+            ),
+            PlayerLookRotation(Vec2::default()),
+            (config.input, input_buffer::ActionBuffer::default()),
+            movement::Lean::default(),
+        ))
+        .with_children(|parent| {
+            let player = parent.target_entity();
+            let cam_transform =
+                Transform::from_xyz(0.0, 0.85, -0.51).looking_to(Vec3::NEG_Z, Vec3::Y);
+            let mut camera = parent.spawn((
+                Camera3d::default(),
+                Projection::Perspective(PerspectiveProjection {
+                    fov: 36_f32.to_radians(),
+                    aspect_ratio: 16. / 9.,
+                    near: 0.001,
+                    far: 1000.,
+                }),
+                Camera {
+                    order: config.index as isize,
+                    ..default()
+                },
+                split_screen::PlayerView(config.index),
+                Atmosphere::EARTH,
+                Exposure::SUNLIGHT,
+                ColorGrading::default(),
+                Tonemapping::AcesFitted,
+                cam_transform,
+                TransformPipeline::new(cam_transform.translation),
+                Bloom::NATURAL,
+                PlayerCamera,
+            ));
+
+            // the HUD that isn't drawn per player follows player one, as does `listener::Ears`
+            if config.index == 0 {
+                camera.insert(IsDefaultUiCamera);
+            }
+
+            camera.with_children(|parent_camera| {
+                let hip_position = Vec3::new(0.1, -0.1, -0.5);
+                let transform_config = PlayerWeaponTransformConfig::new(hip_position);
+
+                parent_camera.spawn((
+                    SceneRoot(weapon_model),
+                    Transform::from_xyz(hip_position.x, hip_position.y, hip_position.z)
+                        .looking_to(Vec3::NEG_Z, Vec3::Y),
+                    PlayerWeapon,
+                    WeaponActive,
+                    SwayTarget(player),
+                    TransformPipeline::new(hip_position),
+                    transform_config,
+                    (AdsAlpha(0.0), SprintAlpha::default()),
+                    weapon::WeaponDefHandle(weapon_def),
+                    weapon::WeaponStats::default(),
+                    sway::SwayProfile::default(),
+                    sockets::WeaponSockets::default(),
+                    weapon::FireMode::default(),
+                    children![
+                        (
+                            sockets::SocketAnchor(sockets::Socket::Muzzle),
+                            particles::MuzzleHeat::default(),
+                            particles::ParticleEmitter::new(particles::ParticleEffect::MuzzleSmoke),
+                        ),
+                        sockets::SocketAnchor(sockets::Socket::Attachment),
+                    ],
+                ));
+            });
+
+            // inside the capsule, so it only matters to hits and helmets
+            parent.spawn((
+                Collider::sphere(0.3),
+                Sensor,
+                Transform::from_xyz(0.0, 0.9, 0.0),
+                damage::Hitbox(damage::HitZone::Head),
+            ));
+
+            parent.spawn((
+                PointLight {
+                    shadows_enabled: true,
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.5, 0.0),
+            ));
+        })
+        .id();
+
+    if config.index == 0 {
+        commands.entity(player).insert(split_screen::PrimaryPlayer);
+    }
+
+    player
+}
+
+/// A crowd of `breathers` characters, each holding a weapon, breathing and swaying every update,
+/// for `benches/sway.rs`. Their breaths are out of step and their weapons take turns with each
+/// sway profile.
+pub fn sway_crowd(breathers: usize) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<difficulty::Difficulty>()
+        .add_message::<BreathPhaseChanged>()
+        .add_systems(
+            Update,
+            (
+                breathe,
+                weapon_sway,
+                sway::profile_sway,
+                set_weapon_transform,
+            )
+                .chain(),
+        );
+
+    let hip = Vec3::new(0.1, -0.1, -0.5);
+    let profiles = [
+        sway::SwayProfile::Breath,
+        sway::SwayProfile::Spring {
+            stiffness: 120.0,
+            damping: 12.0,
+            bands: default(),
+        },
+        sway::SwayProfile::Noise {
+            octaves: 3,
+            bands: default(),
+        },
+    ];
+
+    for index in 0..breathers {
+        let speed = 0.5 + (index % 7) as f32 * 0.1;
+        let breather = app
+            .world_mut()
+            .spawn((
+                Breath::new(speed, 1.0, BreathDirection::Out),
+                WeaponSway::new(WeaponSway::PLAYER_MAX_SWAY),
+            ))
+            .id();
+
+        app.world_mut().spawn((
+            PlayerWeapon,
+            WeaponActive,
+            SwayTarget(breather),
+            Transform::from_translation(hip),
+            TransformPipeline::new(hip),
+            PlayerWeaponTransformConfig::new(hip),
+            (AdsAlpha(0.0), SprintAlpha::default()),
+            profiles[index % profiles.len()].clone(),
+        ));
+    }
+
+    // past startup, so only the per frame work is measured
+    app.update();
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn breath(depth: f32, direction: BreathDirection) -> Breath {
+        Breath::new(1.0, depth, direction)
+    }
+
+    #[test]
+    fn breath_advances_slower_for_deeper_breaths() {
+        let (shallow, _) = advance_breath(1.0, 1.0, 0.1, BreathDirection::In, 0.1);
+        let (deep, _) = advance_breath(1.0, 2.0, 0.1, BreathDirection::In, 0.1);
+
+        assert!((shallow - 0.2).abs() < 1e-6);
+        assert!((deep - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn breath_flips_direction_when_complete() {
+        let (alpha, direction) = advance_breath(1.0, 1.0, 0.95, BreathDirection::In, 0.1);
+
+        assert_eq!(alpha, 0.0);
+        assert_eq!(direction, BreathDirection::Out);
+
+        let (_, direction) = advance_breath(1.0, 1.0, 0.95, direction, 0.1);
+        assert_eq!(direction, BreathDirection::In);
+    }
+
+    #[test]
+    fn breath_phase_counts_direction_changes() {
+        let mut breath = breath(1.0, BreathDirection::In);
+
+        breath.breath(0.5);
+        assert_eq!(
+            breath.phase(),
+            BreathPhase {
+                direction: BreathDirection::In,
+                cycle_index: 0
+            }
+        );
+
+        breath.breath(0.5);
+        breath.breath(1.0);
+        assert_eq!(
+            breath.phase(),
+            BreathPhase {
+                direction: BreathDirection::In,
+                cycle_index: 2
+            }
+        );
+    }
+
+    #[test]
+    fn breath_rate_is_clamped_at_max_speed() {
+        let (alpha, direction) =
+            advance_breath(Breath::MAX_SPEED, 0.1, 0.1, BreathDirection::In, 0.05);
+
+        assert!((alpha - 0.6).abs() < 1e-6);
+        assert_eq!(direction, BreathDirection::In);
+    }
+
+    #[test]
+    fn zero_depth_breath_runs_at_max_speed() {
+        let (alpha, _) = advance_breath(1.0, 0.0, 0.1, BreathDirection::In, 0.01);
+
+        assert!((alpha - 0.2).abs() < 1e-6);
+        assert_eq!(
+            BreathSample::new(0.0, alpha, BreathDirection::In).amount,
+            0.0
+        );
+    }
+
+    #[test]
+    fn zero_depth_and_speed_breath_holds() {
+        let (alpha, direction) = advance_breath(0.0, 0.0, 0.1, BreathDirection::In, 0.01);
+
+        assert_eq!(alpha, 0.1);
+        assert_eq!(direction, BreathDirection::In);
+    }
+
+    #[test]
+    fn held_breath_does_not_flip_at_zero_alpha() {
+        let (alpha, direction) = advance_breath(0.0, 1.0, 0.0, BreathDirection::In, 0.01);
+
+        assert_eq!(alpha, 0.0);
+        assert_eq!(direction, BreathDirection::In);
+    }
+
+    #[test]
+    fn invalid_breath_inputs_stay_finite() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1.0] {
+            let (alpha, _) = advance_breath(value, value, value, BreathDirection::In, value);
+            assert!((0.0..1.0).contains(&alpha), "{value} gave alpha {alpha}");
+
+            let mut breath = breath(value, BreathDirection::Out);
+            breath.speed = value;
+            breath.alpha = value;
+
+            let sample = breath.breath(value);
+            assert!((0.0..=1.0).contains(&sample.alpha));
+            assert!((0.0..=Breath::MAX_DEPTH).contains(&sample.amount));
+        }
+    }
+
+    #[test]
+    fn large_delta_completes_a_single_breath() {
+        let (alpha, direction) = advance_breath(1.0, 1.0, 0.5, BreathDirection::Out, 100.0);
+
+        assert_eq!(alpha, 0.0);
+        assert_eq!(direction, BreathDirection::In);
+    }
+
+    #[test]
+    fn breath_sample_spans_depth() {
+        let sample = |alpha| BreathSample::new(2.0, alpha, BreathDirection::In).amount;
+
+        assert_eq!(sample(0.0), 0.0);
+        assert_eq!(sample(0.5), 1.0);
+        assert_eq!(sample(1.0), 2.0);
+    }
+
+    #[test]
+    fn breath_sample_clamps_alpha_overshoot() {
+        let over = BreathSample::new(1.0, 1.5, BreathDirection::In);
+        let under = BreathSample::new(1.0, -0.5, BreathDirection::In);
+        let nan = BreathSample::new(1.0, f32::NAN, BreathDirection::In);
+
+        assert_eq!((over.alpha, over.amount), (1.0, 1.0));
+        assert_eq!((under.alpha, under.amount), (0.0, 0.0));
+        assert_eq!((nan.alpha, nan.amount), (0.0, 0.0));
+        assert_eq!(over.eased(EaseFunction::SmootherStep), 1.0);
+    }
+
+    #[test]
+    fn sway_target_is_deterministic_for_a_seed() {
+        let breath = breath(1.0, BreathDirection::In).sample();
+
+        let a = sway_target(&mut StdRng::seed_from_u64(7), 0.5, &breath);
+        let b = sway_target(&mut StdRng::seed_from_u64(7), 0.5, &breath);
+
+        assert!(a.is_some());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn sway_target_stays_within_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for direction in [BreathDirection::In, BreathDirection::Out] {
+            let breath = breath(2.0, direction).sample();
+
+            for _ in 0..100 {
+                let target = sway_target(&mut rng, 0.5, &breath).unwrap();
+
+                assert!(target.x.abs() <= 0.5);
+                assert!(target.z.abs() <= 1.0);
+
+                if direction == BreathDirection::In {
+                    assert!((-1.0..=0.0).contains(&target.y));
+                } else {
+                    assert!((0.0..=1.0).contains(&target.y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_depth_sway_target_is_centred() {
+        let breath = breath(0.0, BreathDirection::Out).sample();
+
+        assert_eq!(
+            sway_target(&mut StdRng::seed_from_u64(0), 0.5, &breath),
+            Some(Vec3::ZERO)
+        );
+    }
+
+    #[test]
+    fn nan_sway_has_no_target() {
+        let breath = breath(1.0, BreathDirection::In).sample();
+
+        assert_eq!(
+            sway_target(&mut StdRng::seed_from_u64(0), f32::NAN, &breath),
+            None
+        );
+    }
+
+    #[test]
+    fn compose_translation_without_additives_is_base() {
+        assert_eq!(compose_translation(Vec3::ONE, &[]), Vec3::ONE);
+    }
+
+    #[test]
+    fn compose_translation_sums_additives() {
+        let output = compose_translation(Vec3::X, &[Vec3::Y, Vec3::Z, -Vec3::X]);
+
+        assert_eq!(output, Vec3::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn pipeline_apply_clears_queue() {
+        let mut pipeline = TransformPipeline::new(Vec3::X);
+        pipeline.queue(Vec3::Y);
+        pipeline.queue_rotation(Quat::from_rotation_x(0.1));
+        pipeline.queue_rotation(Quat::from_rotation_x(0.2));
+
+        assert_eq!(pipeline.latest(), Vec3::new(1.0, 1.0, 0.0));
+
+        let (translation, rotation) = pipeline.apply();
+        assert_eq!(translation, Vec3::new(1.0, 1.0, 0.0));
+        assert!(rotation.angle_between(Quat::from_rotation_x(0.3)) < 1e-5);
+
+        assert_eq!(pipeline.apply(), (Vec3::X, Quat::IDENTITY));
+    }
+
+    /// Fixed rates gameplay is expected to hold up at
+    pub const RATES: [f64; 3] = [30.0, 60.0, 120.0];
+
+    /// Run `step` once per tick for `seconds` at `rate`, passing the tick length
+    pub fn simulate(rate: f64, seconds: f64, mut step: impl FnMut(f32)) {
+        let ticks = (rate * seconds).round() as usize;
+
+        for _ in 0..ticks {
+            step((1.0 / rate) as f32);
+        }
+    }
+
+    #[test]
+    fn breath_takes_the_same_time_at_any_rate() {
+        let results: Vec<_> = RATES
+            .map(|rate| {
+                let mut breath = breath(1.5, BreathDirection::In);
+                simulate(rate, 2.3, |delta| {
+                    breath.breath(delta);
+                });
+                (breath.alpha, breath.phase())
+            })
+            .into_iter()
+            .collect();
+
+        // each flip drops whatever overshot the end of the breath, at most one tick's worth
+        let tolerance = 1.0 / 1.5 / RATES[0] as f32;
+
+        for (alpha, phase) in &results[1..] {
+            assert!((alpha - results[0].0).abs() <= tolerance);
+            assert_eq!(*phase, results[0].1);
+        }
+    }
+
+    #[test]
+    fn walk_takes_the_same_time_at_any_rate() {
+        let alphas = RATES.map(|rate| {
+            let mut walk = Walk {
+                speed: 3.0,
+                alpha: 0.0,
+                amount: 0.0,
+                depth: 1.0,
+                side: WalkSide::Left,
+            };
+            simulate(rate, 0.3, |delta| walk.walk(delta));
+            walk.alpha
+        });
+
+        for alpha in alphas {
+            assert!((alpha - alphas[0]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn ads_takes_the_same_time_at_any_rate() {
+        let alphas = RATES.map(|rate| {
+            let mut alpha = 0.0;
+            simulate(rate, 0.2, |delta| {
+                alpha = step_ads_alpha(alpha, true, 1.0, delta);
+            });
+            alpha
+        });
+
+        for alpha in alphas {
+            assert!((alpha - alphas[0]).abs() < 1e-4);
+            assert!((0.0..1.0).contains(&alpha));
+        }
+    }
+
+    #[test]
+    fn respiratory_pause_is_the_end_of_the_exhale() {
+        // a one second breath
+        let mut breath = Breath::new(1.0, 1.0, BreathDirection::Out);
+        assert!(!breath.in_respiratory_pause(0.3));
+
+        breath.alpha = 0.8;
+        assert!(breath.in_respiratory_pause(0.3));
+
+        breath.direction = BreathDirection::In;
+        assert!(!breath.in_respiratory_pause(0.3), "not while breathing in");
+
+        breath.speed = 0.0;
+        breath.direction = BreathDirection::Out;
+        assert!(
+            !breath.in_respiratory_pause(0.3),
+            "a held breath never turns"
+        );
+    }
+
+    #[test]
+    fn weapon_rotation_blends_between_poses() {
+        let mut config = PlayerWeaponTransformConfig::new(Vec3::ZERO);
+        config.pose_mut(WeaponStance::Aim).rotation = Quat::from_rotation_z(0.4);
+        config.pose_mut(WeaponStance::Sprint).rotation = Quat::from_rotation_y(1.0);
+
+        assert_eq!(config.rotation(0.0, 0.0), Quat::IDENTITY);
+        assert!(
+            config
+                .rotation(1.0, 0.0)
+                .angle_between(Quat::from_rotation_z(0.4))
+                < 1e-5
+        );
+        assert!(
+            config
+                .rotation(0.5, 0.0)
+                .angle_between(Quat::from_rotation_z(0.2))
+                < 1e-5
+        );
+        assert!(
+            config
+                .rotation(1.0, 1.0)
+                .angle_between(Quat::from_rotation_y(1.0))
+                < 1e-5,
+            "sprinting wins over aiming"
+        );
+    }
+
+    #[test]
+    fn aiming_puts_the_sight_in_front_of_the_eye() {
+        let mut config = PlayerWeaponTransformConfig::new(Vec3::new(0.1, -0.1, -0.5));
+        config.aim.translation = Vec3::new(0.0, -0.07, -0.3);
+
+        config.align_sight();
+        assert_eq!(
+            config.aim.translation,
+            Vec3::new(0.0, -0.07, -0.3),
+            "nothing to line up without a sight"
+        );
+
+        config.sight = Some(Vec3::new(0.0, 0.05, 0.1));
+        config.eye_relief = 0.2;
+        config.aim.rotation = Quat::from_rotation_x(0.1);
+        config.align_sight();
+
+        let sight = config.aim.translation + config.aim.rotation * Vec3::new(0.0, 0.05, 0.1);
+        assert!(sight.abs_diff_eq(Vec3::new(0.0, 0.0, -0.2), 1e-6));
+    }
+
+    #[test]
+    fn left_hand_poses_mirror_the_right() {
+        let pose = WeaponPose {
+            translation: Vec3::new(0.1, -0.1, -0.5),
+            rotation: Quat::from_rotation_y(0.2) * Quat::from_rotation_z(0.1),
+        };
+        let mirrored = pose.mirrored();
+
+        assert_eq!(mirrored.translation, Vec3::new(-0.1, -0.1, -0.5));
+        assert!(
+            mirrored
+                .rotation
+                .angle_between(Quat::from_rotation_y(-0.2) * Quat::from_rotation_z(-0.1))
+                < 1e-5
+        );
+        assert_eq!(mirrored.mirrored(), pose);
+
+        let mut magazine = weapon::Magazine {
+            rounds: 1,
+            capacity: 2,
+            reserve: Some(1),
+            reload_time: 1.0,
+        };
+        assert!(magazine.take_round());
+        assert!(!magazine.take_round(), "nothing left to fire");
+
+        magazine.refill();
+        assert_eq!(magazine.rounds, 1, "only one spare round to load");
+        assert!(!magazine.can_reload(), "out of spare rounds");
+    }
+
+    #[test]
+    fn weapons_held_still_are_left_unchanged() {
+        #[derive(Resource, Default)]
+        struct Moved(usize);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Moved>()
+            .add_systems(
+                Update,
+                (
+                    set_weapon_transform,
+                    |weapons: Query<(), Changed<Transform>>, mut moved: ResMut<Moved>| {
+                        moved.0 += weapons.iter().count();
+                    },
+                )
+                    .chain(),
+            );
+
+        let hip = Vec3::new(0.2, -0.2, -0.4);
+        let weapon = app
+            .world_mut()
+            .spawn((
+                PlayerWeapon,
+                WeaponActive,
+                Transform::from_translation(hip),
+                TransformPipeline::new(hip),
+                PlayerWeaponTransformConfig::new(hip),
+                AdsAlpha(0.0),
+                SprintAlpha(0.0),
+            ))
+            .id();
+
+        let moved = |app: &mut App| std::mem::take(&mut app.world_mut().resource_mut::<Moved>().0);
+
+        app.update();
+        moved(&mut app);
+
+        app.update();
+        assert_eq!(moved(&mut app), 0);
+
+        app.world_mut()
+            .get_mut::<TransformPipeline>(weapon)
+            .unwrap()
+            .queue(Vec3::Y * 0.01);
+        app.update();
+        assert_eq!(moved(&mut app), 1);
+    }
+
+    #[test]
+    fn strain_quickens_and_deepens_breaths() {
+        let mut rested = Breath::new(1.0, 1.0, BreathDirection::In);
+        let mut strained = Breath::new(1.0, 1.0, BreathDirection::In);
+        strained.strain = 1.0;
+
+        let rested = rested.breath(0.1);
+        let strained = strained.breath(0.1);
+
+        assert!(strained.alpha > rested.alpha);
+        assert!(strained.depth > rested.depth);
+    }
+
+    #[test]
+    fn held_breaths_freeze_then_recover() {
+        let mut breath = Breath::new(1.0, 1.0, BreathDirection::In);
+        breath.alpha = 0.5;
+
+        breath.hold(true, true);
+        for _ in 0..10 {
+            breath.breath(0.1);
+        }
+        assert_eq!(breath.alpha, 0.5, "a held breath goes nowhere");
+        assert!(breath.hold_steadiness() < 0.01, "and steadies the weapon");
+
+        breath.breath(Breath::MAX_HOLD);
+        assert!(!breath.is_held(), "it can only be held so long");
+
+        breath.hold(true, true);
+        assert!(!breath.is_held(), "nor again straight after");
+
+        breath.breath(0.5);
+        assert!(
+            breath.hold_steadiness() > 1.0,
+            "recovering sways more than usual"
+        );
+    }
+
+    #[test]
+    fn aiming_slows_movement_and_strafing_leans_the_sway() {
+        use movement::AimState;
+
+        let hip = AimState::default();
+        let aimed = AimState {
+            alpha: 1.0,
+            speed_scale: 0.6,
+            ..hip
+        };
+        let halfway = AimState {
+            alpha: 0.5,
+            ..aimed
+        };
+
+        assert_eq!(hip.acceleration_scale(), 1.0);
+        assert!((aimed.acceleration_scale() - 0.6).abs() < 1e-6);
+        assert!((halfway.acceleration_scale() - 0.8).abs() < 1e-6);
+
+        let breath = breath(1.0, BreathDirection::In).sample();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut sideways = |strafe| {
+            let mut sway = WeaponSway::new(0.5);
+            (0..200)
+                .map(|_| {
+                    sway.change(&breath, 1.0, strafe, &mut rng);
+                    sway.next.x
+                })
+                .sum::<f32>()
+                / 200.0
+        };
+
+        let (left, still, right) = (sideways(-1.0), sideways(0.0), sideways(1.0));
+        assert!(left < still && still < right, "{left} {still} {right}");
+        assert!(right > 0.0 && left < 0.0);
+    }
+}
@@ -0,0 +1,77 @@
+//! Where the game is heard from.
+//!
+//! The spatial audio listener lives on its own [`Ears`] entity rather than on a camera, and is
+//! moved onto player one's view every frame just before transforms propagate. Cameras leave their
+//! player's head for vehicle seats and turret sights and come back again, and working the view's
+//! world transform out from its own hierarchy keeps panning right through each switch, whatever
+//! the camera is parented to at the time, rather than going quiet with a despawned camera or
+//! lagging behind. A camera with a [`ListenerOverride`], like a spectator or photo camera, takes
+//! over from player one while it exists.
+
+use bevy::prelude::*;
+
+use crate::doppler::DopplerListener;
+use crate::split_screen::PlayerView;
+
+pub struct ListenerPlugin;
+
+impl Plugin for ListenerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_ears)
+            .add_systems(PostUpdate, follow_view.before(TransformSystems::Propagate));
+    }
+}
+
+/// The spatial audio listener.
+#[derive(Component, Debug)]
+pub struct Ears;
+
+/// Hear the game from this entity instead of player one's view.
+#[derive(Component, Debug)]
+pub struct ListenerOverride;
+
+fn spawn_ears(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Ears"),
+        Ears,
+        SpatialListener::new(0.2),
+        DopplerListener,
+        Transform::default(),
+    ));
+}
+
+/// The world transform of `entity` as of its and its ancestors' current local transforms
+fn world_transform(
+    entity: Entity,
+    hierarchy: &Query<(&Transform, Option<&ChildOf>), Without<Ears>>,
+) -> Option<Transform> {
+    let (world, mut parent) = hierarchy.get(entity).ok()?;
+    let mut world = *world;
+
+    while let Some(child_of) = parent {
+        let (transform, grandparent) = hierarchy.get(child_of.parent()).ok()?;
+        world = *transform * world;
+        parent = grandparent;
+    }
+
+    Some(world)
+}
+
+fn follow_view(
+    mut ears: Single<&mut Transform, With<Ears>>,
+    overrides: Query<Entity, With<ListenerOverride>>,
+    views: Query<(Entity, &PlayerView)>,
+    hierarchy: Query<(&Transform, Option<&ChildOf>), Without<Ears>>,
+) {
+    let view = overrides.iter().next().or_else(|| {
+        views
+            .iter()
+            .find(|(_, view)| view.0 == 0)
+            .map(|(entity, _)| entity)
+    });
+
+    // stays where it last heard from while there's nothing to hear from
+    if let Some(transform) = view.and_then(|view| world_transform(view, &hierarchy)) {
+        **ears = transform;
+    }
+}
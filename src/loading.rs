@@ -0,0 +1,205 @@
+//! The loading screen between the menu and a level.
+//!
+//! While in [`GameState::Loading`] the screen shows a progress bar covering two kinds of work:
+//! asset handles added to [`LoadingAssets`], and pending entities, which are anything holding a
+//! [`LoadingBlocker`] plus colliders that avian is still building from meshes. The game only
+//! moves on to [`GameState::InGame`] once all of it has finished, and nothing here blocks a frame,
+//! so the window stays responsive however long loading takes.
+
+use avian3d::prelude::*;
+use bevy::{
+    asset::{RecursiveDependencyLoadState, UntypedAssetId},
+    prelude::*,
+};
+
+use crate::game_assets::GameAssets;
+use crate::level::Level;
+use crate::menu::GameState;
+use crate::tuning::PlayerTuning;
+use crate::weapon::WeaponDefHandle;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingAssets>()
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (setup_loading_screen, track_game_assets),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_loading_progress,
+                    update_loading_screen,
+                    finish_loading,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(OnExit(GameState::Loading), clear_loading_assets);
+    }
+}
+
+/// Assets that have to finish loading before play starts.
+#[derive(Resource, Default)]
+pub struct LoadingAssets {
+    ids: Vec<UntypedAssetId>,
+    /// How many assets and pending entities have finished, out of `total`
+    done: usize,
+    total: usize,
+    /// The most pending entities seen at once this load
+    peak_pending: usize,
+}
+
+impl LoadingAssets {
+    pub fn track(&mut self, id: impl Into<UntypedAssetId>) {
+        self.ids.push(id.into());
+    }
+
+    /// How far through loading we are, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.done == self.total
+    }
+}
+
+/// Holds the loading screen open while it exists, for work that isn't an asset load, such as a
+/// level layout being generated on another thread.
+#[derive(Component, Default)]
+pub struct LoadingBlocker;
+
+#[derive(Component)]
+struct LoadingBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn setup_loading_screen(mut commands: Commands, level: Res<Level>) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        DespawnOnExit(GameState::Loading),
+        children![
+            (
+                Text::new(format!("Loading {}...", *level)),
+                TextFont::from_font_size(28.0),
+                LoadingText,
+            ),
+            (
+                Node {
+                    width: Val::Px(400.0),
+                    height: Val::Px(12.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                children![(
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::WHITE),
+                    LoadingBar,
+                )],
+            ),
+        ],
+    ));
+}
+
+/// Tracks the assets every level needs: the preloaded [`GameAssets`], the weapon definitions and
+/// models in use and the player tuning.
+fn track_game_assets(
+    mut loading: ResMut<LoadingAssets>,
+    game_assets: Option<Res<GameAssets>>,
+    tuning: Option<Res<PlayerTuning>>,
+    weapon_defs: Query<&WeaponDefHandle>,
+    scenes: Query<&SceneRoot>,
+) {
+    if let Some(game_assets) = game_assets {
+        for id in game_assets.ids() {
+            loading.track(id);
+        }
+    }
+
+    if let Some(tuning) = tuning {
+        loading.track(&tuning.0);
+    }
+
+    for def in weapon_defs {
+        loading.track(&def.0);
+    }
+
+    for scene in scenes {
+        loading.track(&scene.0);
+    }
+}
+
+fn update_loading_progress(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<LoadingAssets>,
+    blockers: Query<(), With<LoadingBlocker>>,
+    colliders: Query<
+        (),
+        Or<(
+            With<ColliderConstructor>,
+            With<ColliderConstructorHierarchy>,
+        )>,
+    >,
+) {
+    let loaded = loading
+        .ids
+        .iter()
+        .filter(
+            |id| match asset_server.get_recursive_dependency_load_state(**id) {
+                Some(RecursiveDependencyLoadState::Loaded) => true,
+                // a broken asset shouldn't hold up the game forever, it's reported by the loader
+                Some(RecursiveDependencyLoadState::Failed(_)) => true,
+                // not handled by the asset server, so already there
+                None => true,
+                _ => false,
+            },
+        )
+        .count();
+
+    let pending = blockers.iter().count() + colliders.iter().count();
+    loading.peak_pending = loading.peak_pending.max(pending);
+
+    loading.total = loading.ids.len() + loading.peak_pending;
+    loading.done = loaded + loading.peak_pending - pending;
+}
+
+fn update_loading_screen(
+    loading: Res<LoadingAssets>,
+    mut bar: Single<&mut Node, With<LoadingBar>>,
+    mut text: Single<&mut Text, With<LoadingText>>,
+    level: Res<Level>,
+) {
+    bar.width = Val::Percent(loading.progress() * 100.0);
+    text.0 = format!("Loading {}... {}/{}", *level, loading.done, loading.total);
+}
+
+fn finish_loading(loading: Res<LoadingAssets>, mut next_state: ResMut<NextState<GameState>>) {
+    if loading.is_finished() {
+        next_state.set(GameState::InGame);
+    }
+}
+
+fn clear_loading_assets(mut loading: ResMut<LoadingAssets>) {
+    *loading = LoadingAssets::default();
+}
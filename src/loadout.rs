@@ -0,0 +1,333 @@
+//! Picking a weapon before a game.
+//!
+//! The loadout screen, reached from the main screen, lists every weapon in [`GameAssets`] with
+//! bars comparing their damage, rate of fire, handling and sway, and lets the underbarrel be left
+//! off those that come with one. The choice is the active profile's [`Loadout`], saved with it and
+//! picked again next time, and the player is given it whenever a level loads. Playing from the
+//! loadout screen goes straight into a game with it. Anything still [`crate::unlocks`] locked
+//! can't be picked.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::dual_wield::WeaponHand;
+use crate::game_assets::GameAssets;
+use crate::menu::{self, GameState, MenuScreen};
+use crate::menu_nav;
+use crate::profile::ActiveProfile;
+use crate::split_screen::PrimaryPlayer;
+use crate::unlocks::{self, Unlockable};
+use crate::weapon::{Attachments, WeaponDef, WeaponDefHandle};
+use crate::{PlayerWeapon, SwayTarget, WeaponSway};
+
+pub struct LoadoutPlugin;
+
+impl Plugin for LoadoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuScreen::Loadout), setup_loadout_screen)
+            .add_systems(
+                Update,
+                (
+                    press_loadout_buttons,
+                    update_loadout_labels,
+                    update_stat_bars,
+                )
+                    .chain()
+                    .run_if(in_state(MenuScreen::Loadout)),
+            )
+            .add_systems(
+                OnEnter(GameState::Loading),
+                equip_loadout.run_if(resource_exists::<ActiveProfile>),
+            );
+    }
+}
+
+/// The weapon a profile plays with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Loadout {
+    /// Asset path of the weapon's definition
+    pub weapon: String,
+    /// Whether its underbarrel is fitted, if it has one
+    pub underbarrel: bool,
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            weapon: GameAssets::WEAPONS[0].to_owned(),
+            underbarrel: true,
+        }
+    }
+}
+
+/// A stat compared on the loadout screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stat {
+    Damage,
+    Rpm,
+    Handling,
+    Sway,
+}
+
+impl Stat {
+    const ALL: [Stat; 4] = [Stat::Damage, Stat::Rpm, Stat::Handling, Stat::Sway];
+
+    fn label(self) -> &'static str {
+        match self {
+            Stat::Damage => "Damage",
+            Stat::Rpm => "RPM",
+            Stat::Handling => "Handling",
+            Stat::Sway => "Sway",
+        }
+    }
+
+    /// How much of a full bar `def` fills
+    fn fraction(self, def: &WeaponDef) -> f32 {
+        let (value, full) = match self {
+            Stat::Damage => (def.damage, 100.0),
+            Stat::Rpm => (def.rpm, 1200.0),
+            Stat::Handling => (def.handling, 2.0),
+            // in metres, as a player holds it
+            Stat::Sway => (def.sway.idle_amplitude(WeaponSway::PLAYER_MAX_SWAY), 0.004),
+        };
+
+        (value / full).clamp(0.0, 1.0)
+    }
+}
+
+/// A button on the loadout screen.
+#[derive(Component, Debug, Clone)]
+enum LoadoutButton {
+    /// Picks the weapon defined at this path
+    Weapon(&'static str),
+    /// Fits or leaves off the picked weapon's underbarrel
+    Underbarrel,
+    Play,
+    Back,
+}
+
+/// The filled part of the bar showing a weapon's stat.
+#[derive(Component, Debug)]
+struct StatBar {
+    weapon: &'static str,
+    stat: Stat,
+}
+
+const BAR_WIDTH: f32 = 90.0;
+const BAR_COLOR: Color = Color::srgb(0.9, 0.75, 0.2);
+
+fn setup_loadout_screen(mut commands: Commands, game_assets: Res<GameAssets>) {
+    let mut weapons: Vec<_> = game_assets.weapons.keys().copied().collect();
+    weapons.sort_unstable();
+
+    let row = || Node {
+        column_gap: Val::Px(12.0),
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn(menu::menu_root("Loadout", MenuScreen::Loadout))
+        .with_children(|parent| {
+            parent.spawn(row()).with_children(|header| {
+                // lines the stat names up over their bars
+                header.spawn(Node {
+                    width: Val::Px(menu::BUTTON_WIDTH),
+                    ..default()
+                });
+
+                for stat in Stat::ALL {
+                    header.spawn((
+                        Text::new(stat.label()),
+                        TextFont::from_font_size(16.0),
+                        Node {
+                            width: Val::Px(BAR_WIDTH),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+
+            for weapon in weapons {
+                parent.spawn(row()).with_children(|row| {
+                    row.spawn(menu::menu_button(LoadoutButton::Weapon(weapon)));
+
+                    for stat in Stat::ALL {
+                        row.spawn((
+                            Node {
+                                width: Val::Px(BAR_WIDTH),
+                                height: Val::Px(10.0),
+                                ..default()
+                            },
+                            BackgroundColor(menu::BUTTON_COLOR),
+                            // sized by `update_stat_bars` once the definition has loaded
+                            children![(
+                                Node {
+                                    width: Val::Percent(0.0),
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                BackgroundColor(BAR_COLOR),
+                                StatBar { weapon, stat },
+                            )],
+                        ));
+                    }
+                });
+            }
+
+            parent.spawn((
+                menu::menu_button(LoadoutButton::Underbarrel),
+                menu_nav::Adjustable,
+            ));
+            parent.spawn(menu::menu_button(LoadoutButton::Play));
+            parent.spawn((menu::menu_button(LoadoutButton::Back), menu_nav::BackButton));
+        });
+}
+
+fn press_loadout_buttons(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut profile: ResMut<ActiveProfile>,
+    buttons: Query<(&Interaction, &LoadoutButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            LoadoutButton::Weapon(weapon) => {
+                if unlocks::locked(&profile, Unlockable::Weapon(weapon)).is_none() {
+                    profile.loadout.weapon = (*weapon).to_owned();
+                }
+            }
+            LoadoutButton::Underbarrel => {
+                let underbarrel = Unlockable::Underbarrel(&profile.loadout.weapon);
+                if unlocks::locked(&profile, underbarrel).is_none() {
+                    profile.loadout.underbarrel = !profile.loadout.underbarrel;
+                }
+            }
+            LoadoutButton::Play => next_state.set(GameState::Loading),
+            LoadoutButton::Back => next_screen.set(MenuScreen::Main),
+        }
+    }
+}
+
+fn update_loadout_labels(
+    profile: Res<ActiveProfile>,
+    game_assets: Res<GameAssets>,
+    defs: Res<Assets<WeaponDef>>,
+    buttons: Query<(&LoadoutButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let loadout = &profile.loadout;
+    let def = |weapon: &str| defs.get(game_assets.weapons.get(weapon)?);
+
+    for (button, children) in buttons {
+        let label = match button {
+            LoadoutButton::Weapon(weapon) => {
+                let name = def(weapon).map_or(*weapon, |def| def.name.as_str());
+
+                if let Some(criterion) = unlocks::locked(&profile, Unlockable::Weapon(weapon)) {
+                    format!("{name} ({criterion})")
+                } else if *weapon == loadout.weapon {
+                    format!("> {name} <")
+                } else {
+                    name.to_owned()
+                }
+            }
+            LoadoutButton::Underbarrel => {
+                let locked = unlocks::locked(&profile, Unlockable::Underbarrel(&loadout.weapon));
+
+                match (
+                    def(&loadout.weapon).and_then(|def| def.underbarrel.as_ref()),
+                    locked,
+                ) {
+                    (Some(underbarrel), Some(criterion)) => {
+                        format!("{} ({criterion})", underbarrel.name)
+                    }
+                    (Some(underbarrel), None) if loadout.underbarrel => {
+                        format!("Underbarrel: {}", underbarrel.name)
+                    }
+                    (Some(_), None) => "Underbarrel: none".to_owned(),
+                    (None, _) => "No attachments".to_owned(),
+                }
+            }
+            LoadoutButton::Play => "Play".to_owned(),
+            LoadoutButton::Back => "Back".to_owned(),
+        };
+
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.set_if_neq(Text(label.clone()));
+            }
+        }
+    }
+}
+
+fn update_stat_bars(
+    game_assets: Res<GameAssets>,
+    defs: Res<Assets<WeaponDef>>,
+    bars: Query<(&StatBar, &mut Node)>,
+) {
+    for (bar, mut node) in bars {
+        let Some(def) = game_assets
+            .weapons
+            .get(bar.weapon)
+            .and_then(|handle| defs.get(handle))
+        else {
+            continue;
+        };
+
+        node.width = Val::Percent(bar.stat.fraction(def) * 100.0);
+    }
+}
+
+/// Gives the player the active profile's loadout as a level starts loading.
+fn equip_loadout(
+    mut commands: Commands,
+    profile: Res<ActiveProfile>,
+    game_assets: Res<GameAssets>,
+    player: Single<Entity, With<PrimaryPlayer>>,
+    mut weapons: Query<
+        (
+            Entity,
+            &SwayTarget,
+            &mut WeaponDefHandle,
+            Option<&WeaponHand>,
+        ),
+        With<PlayerWeapon>,
+    >,
+) {
+    let loadout = &profile.loadout;
+
+    // only reachable by editing the profile by hand
+    let weapon = match unlocks::locked(&profile, Unlockable::Weapon(&loadout.weapon)) {
+        Some(criterion) => {
+            warn!("'{}' needs {criterion} first", loadout.weapon);
+            GameAssets::WEAPONS[0]
+        }
+        None => loadout.weapon.as_str(),
+    };
+    let underbarrel =
+        loadout.underbarrel && unlocks::locked(&profile, Unlockable::Underbarrel(weapon)).is_none();
+
+    let Some(def) = game_assets.weapons.get(weapon) else {
+        warn!("no weapon '{weapon}' to equip, keeping the current one");
+        return;
+    };
+
+    for (weapon, owner, mut handle, hand) in &mut weapons {
+        // a second weapon being dual wielded keeps what it is
+        if owner.0 != *player || hand == Some(&WeaponHand::Left) {
+            continue;
+        }
+
+        if handle.0 != *def {
+            handle.0 = def.clone();
+        }
+
+        commands.entity(weapon).insert(Attachments { underbarrel });
+    }
+}
@@ -0,0 +1,178 @@
+//! Distance based detail for scenery.
+//!
+//! Anything with a [`Lod`] is drawn with a simpler mesh once every player is far enough away,
+//! hidden further out still and, if its [`LodSettings`] say so, despawned altogether. Each change
+//! only happens some way past its distance, and is only undone some way back inside it, so
+//! something right on the edge doesn't flicker between the two as a player moves about.
+
+use bevy::prelude::*;
+
+use crate::Player;
+use crate::menu::GameState;
+
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_lods.run_if(in_state(GameState::InGame)));
+    }
+}
+
+/// The distances something changes detail at, in metres from the nearest player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodSettings {
+    /// Swapped to its simple mesh from here, if it has one
+    pub simplify: Option<f32>,
+    /// Hidden from here
+    pub cull: f32,
+    /// Despawned from here, for scenery nothing else relies on
+    pub despawn: Option<f32>,
+}
+
+impl LodSettings {
+    /// Fraction of each distance a change is put off by either side of it
+    const HYSTERESIS: f32 = 0.1;
+
+    /// The detail something at `level` should be at `distance` away
+    pub fn level_at(&self, level: LodLevel, distance: f32) -> LodLevel {
+        // whether `distance` is past `threshold`, leaning towards staying where it is
+        let past = |threshold: f32, is_past: bool| {
+            let margin = threshold * Self::HYSTERESIS;
+            if is_past {
+                distance > threshold - margin
+            } else {
+                distance > threshold + margin
+            }
+        };
+
+        if past(self.cull, level == LodLevel::Culled) {
+            LodLevel::Culled
+        } else if self
+            .simplify
+            .is_some_and(|simplify| past(simplify, level >= LodLevel::Simple))
+        {
+            LodLevel::Simple
+        } else {
+            LodLevel::Full
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LodLevel {
+    #[default]
+    Full,
+    Simple,
+    Culled,
+}
+
+/// Scenery drawn in less detail the further it is from every player, see [`LodSettings`].
+#[derive(Component, Debug)]
+pub struct Lod {
+    settings: LodSettings,
+    /// The mesh it isn't showing, its simple one while it's at full detail and the other way round
+    other_mesh: Option<Handle<Mesh>>,
+    /// Whether its simple mesh is the one showing, which it keeps while hidden
+    simplified: bool,
+    level: LodLevel,
+}
+
+impl Lod {
+    /// Something only ever hidden or despawned
+    pub fn new(settings: LodSettings) -> Self {
+        Self {
+            settings,
+            other_mesh: None,
+            simplified: false,
+            level: LodLevel::Full,
+        }
+    }
+
+    /// Something drawn with `simple` from [`LodSettings::simplify`] onwards
+    pub fn with_simple_mesh(mut self, simple: Handle<Mesh>) -> Self {
+        self.other_mesh = Some(simple);
+        self
+    }
+}
+
+fn update_lods(
+    mut commands: Commands,
+    players: Query<&GlobalTransform, With<Player>>,
+    mut scenery: Query<(
+        Entity,
+        &mut Lod,
+        &GlobalTransform,
+        &mut Visibility,
+        Option<&mut Mesh3d>,
+    )>,
+) {
+    // nobody to draw it for
+    if players.is_empty() {
+        return;
+    }
+
+    for (entity, mut lod, transform, mut visibility, mesh) in &mut scenery {
+        let distance = players
+            .iter()
+            .map(|player| player.translation().distance(transform.translation()))
+            .fold(f32::INFINITY, f32::min);
+
+        if lod
+            .settings
+            .despawn
+            .is_some_and(|despawn| distance > despawn)
+        {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let level = lod.settings.level_at(lod.level, distance);
+        if level == lod.level {
+            continue;
+        }
+
+        lod.level = level;
+        visibility.set_if_neq(if level == LodLevel::Culled {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+
+        // hidden things keep whichever mesh they had, for when they're back in view
+        let simplify = match level {
+            LodLevel::Full => false,
+            LodLevel::Simple => true,
+            LodLevel::Culled => continue,
+        };
+
+        if simplify != lod.simplified
+            && let Some(mut mesh) = mesh
+            && let Some(other) = lod.other_mesh.as_mut()
+        {
+            std::mem::swap(&mut mesh.0, other);
+            lod.simplified = simplify;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lod_changes_only_well_past_each_distance() {
+        let settings = LodSettings {
+            simplify: Some(10.0),
+            cull: 40.0,
+            despawn: None,
+        };
+
+        assert_eq!(settings.level_at(LodLevel::Full, 10.5), LodLevel::Full);
+        assert_eq!(settings.level_at(LodLevel::Full, 12.0), LodLevel::Simple);
+        assert_eq!(settings.level_at(LodLevel::Simple, 9.5), LodLevel::Simple);
+        assert_eq!(settings.level_at(LodLevel::Simple, 8.0), LodLevel::Full);
+        assert_eq!(settings.level_at(LodLevel::Full, 50.0), LodLevel::Culled);
+        assert_eq!(settings.level_at(LodLevel::Culled, 38.0), LodLevel::Culled);
+        assert_eq!(settings.level_at(LodLevel::Culled, 30.0), LodLevel::Simple);
+    }
+}
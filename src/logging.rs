@@ -0,0 +1,281 @@
+//! Log levels that can be changed while playing, and an in-game log viewer.
+//!
+//! Every module logs under its own target, such as `energy::movement` or `energy::weapon`. The
+//! `log` console command sets which of them are printed, in the same `target=level` form as
+//! `RUST_LOG`, without restarting. Everything the game itself logs at any level is also kept for
+//! the viewer, which `logs` opens filtered down to one target.
+
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write as _};
+use std::sync::{Arc, Mutex, RwLock};
+
+use bevy::log::{
+    BoxedFmtLayer, BoxedLayer, DEFAULT_FILTER, Level, LogPlugin,
+    tracing::{
+        Event, Subscriber,
+        field::{Field, Visit},
+    },
+    tracing_subscriber::{
+        Layer,
+        filter::{FilterFn, Targets},
+        fmt,
+        layer::Context,
+    },
+};
+use bevy::prelude::*;
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::hud::HudTheme;
+
+/// What's printed until the `log` command says otherwise
+const DEFAULT_LEVELS: &str = "info";
+
+pub struct LoggingPlugin;
+
+impl Plugin for LoggingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LogLevels>()
+            .init_resource::<LogLines>()
+            .init_resource::<LogViewer>()
+            .add_console_command(
+                "log",
+                "log [target=level,...] - show or set what's printed, e.g. energy::ai=debug",
+            )
+            .add_console_command(
+                "logs",
+                "logs [target|off] - show recent logs, from one target",
+            )
+            .add_systems(Startup, setup_log_viewer)
+            .add_systems(Update, (log_commands, update_log_viewer).chain());
+    }
+}
+
+/// The [`LogPlugin`] for [`DefaultPlugins`], wired up to the runtime levels and the viewer.
+///
+/// The game's own logs all pass the global filter so the viewer sees them, and what's printed is
+/// filtered by [`LogLevels`] instead.
+pub fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        filter: format!("{DEFAULT_FILTER},{}=trace", env!("CARGO_PKG_NAME")),
+        level: Level::INFO,
+        custom_layer: capture_layer,
+        fmt_layer: printed_layer,
+    }
+}
+
+/// Which targets and levels are printed.
+#[derive(Resource, Clone)]
+struct LogLevels(Arc<RwLock<Targets>>);
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self(Arc::new(RwLock::new(
+            DEFAULT_LEVELS.parse().expect("default log levels parse"),
+        )))
+    }
+}
+
+/// One logged event.
+struct LogLine {
+    level: Level,
+    target: String,
+    text: String,
+}
+
+/// The most recent logs, oldest first, shared with the layer that captures them.
+#[derive(Resource, Clone, Default)]
+struct LogLines(Arc<Mutex<LogBuffer>>);
+
+#[derive(Default)]
+struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    /// Bumped with every line, so the viewer knows when to redraw
+    generation: u64,
+}
+
+impl LogBuffer {
+    const CAPACITY: usize = 500;
+}
+
+/// What the viewer shows, if it's open.
+#[derive(Resource, Default)]
+struct LogViewer {
+    open: bool,
+    /// Only lines from targets starting with this
+    target: String,
+    drawn: u64,
+}
+
+impl LogViewer {
+    const SHOWN: usize = 16;
+}
+
+fn capture_layer(app: &mut App) -> Option<BoxedLayer> {
+    let lines = LogLines::default();
+    app.insert_resource(lines.clone());
+
+    Some(Box::new(CaptureLayer(lines.0)))
+}
+
+fn printed_layer(app: &mut App) -> Option<BoxedFmtLayer> {
+    let levels = LogLevels::default();
+    app.insert_resource(levels.clone());
+
+    let filter = FilterFn::new(move |metadata| {
+        levels
+            .0
+            .read()
+            .is_ok_and(|levels| levels.would_enable(metadata.target(), metadata.level()))
+    });
+
+    Some(Box::new(
+        fmt::Layer::default()
+            .with_writer(std::io::stderr)
+            .with_filter(filter),
+    ))
+}
+
+/// Keeps every event that gets through the global filter for the viewer.
+struct CaptureLayer(Arc<Mutex<LogBuffer>>);
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut text = TextVisitor::default();
+        event.record(&mut text);
+
+        let Ok(mut buffer) = self.0.lock() else {
+            return;
+        };
+
+        if buffer.lines.len() == LogBuffer::CAPACITY {
+            buffer.lines.pop_front();
+        }
+
+        buffer.lines.push_back(LogLine {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_owned(),
+            text: text.0,
+        });
+        buffer.generation += 1;
+    }
+}
+
+/// Formats an event's message followed by its other fields.
+#[derive(Default)]
+struct TextVisitor(String);
+
+impl Visit for TextVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[derive(Component)]
+struct LogViewerText;
+
+fn setup_log_viewer(mut commands: Commands, theme: Res<HudTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            max_width: Val::Percent(60.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(theme.panel),
+        Visibility::Hidden,
+        GlobalZIndex(90),
+        Text::default(),
+        TextFont::from_font_size(theme.small_font_size),
+        TextColor(theme.text),
+        LogViewerText,
+    ));
+}
+
+fn log_commands(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    levels: Res<LogLevels>,
+    mut viewer: ResMut<LogViewer>,
+) {
+    for command in command_reader.read() {
+        if command.is("log") {
+            let Some(directives) = command.arg(0) else {
+                let shown = levels.0.read().map(|levels| levels.to_string());
+                output_writer.write(ConsoleOutput(format!(
+                    "printing {}",
+                    shown.unwrap_or_default()
+                )));
+                continue;
+            };
+
+            match directives.parse::<Targets>() {
+                Ok(targets) => {
+                    if let Ok(mut levels) = levels.0.write() {
+                        *levels = targets;
+                    }
+                    output_writer.write(ConsoleOutput(format!("printing {directives}")));
+                }
+                Err(err) => {
+                    output_writer.write(ConsoleOutput(format!("bad log levels: {err}")));
+                }
+            }
+        } else if command.is("logs") {
+            match command.arg(0) {
+                Some("off") => viewer.open = false,
+                target => {
+                    viewer.open = true;
+                    viewer.target = target.unwrap_or_default().to_owned();
+                }
+            }
+
+            // redraw for the new filter
+            viewer.drawn = u64::MAX;
+        }
+    }
+}
+
+fn update_log_viewer(
+    lines: Res<LogLines>,
+    mut viewer: ResMut<LogViewer>,
+    text: Single<(&mut Text, &mut Visibility), With<LogViewerText>>,
+) {
+    let (mut text, mut visibility) = text.into_inner();
+
+    if !viewer.open {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+
+    let Ok(buffer) = lines.0.lock() else {
+        return;
+    };
+
+    if buffer.generation == viewer.drawn {
+        return;
+    }
+    viewer.drawn = buffer.generation;
+
+    let mut shown: Vec<_> = buffer
+        .lines
+        .iter()
+        .rev()
+        .filter(|line| line.target.starts_with(&viewer.target))
+        .take(LogViewer::SHOWN)
+        .map(|line| format!("{:>5} {}: {}", line.level, line.target, line.text))
+        .collect();
+    shown.reverse();
+
+    text.0 = if shown.is_empty() {
+        format!("nothing logged from '{}'", viewer.target)
+    } else {
+        shown.join("\n")
+    };
+
+    *visibility = Visibility::Inherited;
+}
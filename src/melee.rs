@@ -0,0 +1,93 @@
+//! Melee strikes.
+//!
+//! The melee key (or the left bumper) strikes whatever is right in front of the player's view,
+//! dealt with like a hitscan round through [`HitEvent`]. Each strike costs stamina, so a tired
+//! player can't swing at all.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::damage::HitEvent;
+use crate::energy::{EnergyAction, EnergyCosts, Stamina};
+use crate::menu::GameState;
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::turret::Manning;
+use crate::vehicle::Driving;
+use crate::{Player, PlayerCamera};
+
+pub struct MeleePlugin;
+
+impl Plugin for MeleePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, strike.run_if(in_state(GameState::InGame)));
+    }
+}
+
+/// Metres from the eyes a strike reaches
+const REACH: f32 = 1.8;
+const DAMAGE: f32 = 35.0;
+/// Newton-seconds a strike shoves what it hits with
+const SHOVE: f32 = 40.0;
+
+fn strike(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    costs: Res<EnergyCosts>,
+    spatial_query: SpatialQuery,
+    mut hit_writer: MessageWriter<HitEvent>,
+    gamepads: Query<&Gamepad>,
+    players: Query<
+        (Entity, &PlayerInput, Option<&mut Stamina>, &Children),
+        (With<Player>, Without<Driving>, Without<Manning>),
+    >,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+    colliders: Query<&ColliderOf>,
+) {
+    for (player, input, stamina, children) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.melee);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::LeftTrigger));
+
+        if !keyboard && !gamepad {
+            continue;
+        }
+
+        let Some(eyes) = children.iter().find_map(|child| cameras.get(child).ok()) else {
+            continue;
+        };
+
+        // a swing at nothing still tires
+        if let Some(mut stamina) = stamina
+            && !stamina.try_spend(costs.get(EnergyAction::Melee))
+        {
+            continue;
+        }
+
+        // past the player's own hitboxes, as with hitscan rounds
+        let Some(hit) = spatial_query.cast_ray_predicate(
+            eyes.translation(),
+            eyes.forward(),
+            REACH,
+            true,
+            &SpatialQueryFilter::from_excluded_entities([player]),
+            &|entity| {
+                !colliders
+                    .get(entity)
+                    .is_ok_and(|collider_of| collider_of.body == player)
+            },
+        ) else {
+            continue;
+        };
+
+        hit_writer.write(HitEvent {
+            entity: hit.entity,
+            point: eyes.translation() + eyes.forward() * hit.distance,
+            normal: hit.normal,
+            shooter: player,
+            damage: DAMAGE,
+            impulse: eyes.forward() * SHOVE,
+        });
+    }
+}
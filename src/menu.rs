@@ -0,0 +1,552 @@
+//! The main menu and the game's top level states.
+//!
+//! The game starts in [`GameState::MainMenu`] on the profile select screen. Picking a profile (or
+//! creating a new one) makes it the [`ActiveProfile`] and moves on to the main screen, where the
+//! [`Level`], [`GameMode`] and generator seed are chosen, with the weapon picked on the loadout
+//! screen. Playing goes through [`GameState::Loading`] (see [`crate::loading`]) while the level is
+//! built, and Escape leaves a game for the main screen again. The menus can be got round with a
+//! gamepad or the keyboard too, see [`crate::menu_nav`].
+
+use std::mem::discriminant;
+
+use bevy::{
+    app::AppExit,
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+use crate::difficulty::Difficulty;
+use crate::dynamic_resolution::RenderScale;
+use crate::level::{GameMode, Level};
+use crate::menu_nav;
+use crate::profile::{self, ActiveProfile, Profile};
+use crate::settings::GameSettings;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_sub_state::<MenuScreen>()
+            .init_resource::<SeedEntry>()
+            .add_systems(OnEnter(MenuScreen::Profiles), setup_profile_select)
+            .add_systems(OnEnter(MenuScreen::Main), setup_main_screen)
+            .add_systems(OnEnter(MenuScreen::Settings), setup_settings_screen)
+            .add_systems(
+                Update,
+                (
+                    (
+                        highlight_buttons,
+                        // before the press that starts typing, so the Enter that pressed it
+                        // doesn't also finish it
+                        type_seed,
+                        press_menu_buttons,
+                        update_button_labels,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::MainMenu)),
+                    return_to_menu
+                        .run_if(in_state(GameState::InGame))
+                        .run_if(crate::console::console_closed)
+                        .run_if(crate::chat::chat_closed),
+                ),
+            );
+    }
+}
+
+/// Which part of the game is running.
+#[derive(States, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    /// Building the chosen level
+    Loading,
+    InGame,
+}
+
+/// The screen shown while in the main menu.
+#[derive(SubStates, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::MainMenu)]
+pub enum MenuScreen {
+    #[default]
+    Profiles,
+    Main,
+    /// Picking a weapon, see [`crate::loadout`]
+    Loadout,
+    /// How far along each of [`crate::challenges`] is
+    Challenges,
+    Settings,
+}
+
+pub const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+pub const BUTTON_WIDTH: f32 = 320.0;
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+/// The view motion settings cycled through, full first
+const VIEW_MOTIONS: [f32; 3] = [1.0, 0.5, 0.0];
+/// The frame rates dynamic resolution can aim for
+const TARGET_FPS: [f32; 5] = [30.0, 60.0, 90.0, 120.0, 144.0];
+/// The lowest and highest render scales dynamic resolution can be bound to
+const LOWEST_RESOLUTIONS: [f32; 3] = [0.5, 0.6, 0.75];
+const HIGHEST_RESOLUTIONS: [f32; 3] = [1.0, 0.9, 0.8];
+
+/// A button on one of the menu screens.
+#[derive(Component, Debug, Clone)]
+enum MenuButton {
+    Profile(String),
+    NewProfile,
+    /// Cycles through the levels
+    Level,
+    /// Cycles through the game modes
+    Mode,
+    /// Starts typing in a seed for the generated level
+    Seed,
+    RollSeed,
+    Play,
+    Loadout,
+    Challenges,
+    Settings,
+    ChangeProfile,
+    Quit,
+    /// Cycles through the difficulty presets
+    Difficulty,
+    Hardcore,
+    Doppler,
+    /// Cycles how much the view moves with breathing
+    ViewMotion,
+    /// Turns dynamic resolution on and off
+    DynamicResolution,
+    /// Cycles the frame rate dynamic resolution aims for
+    TargetFps,
+    /// Cycles the lowest resolution dynamic resolution can go to
+    LowestResolution,
+    /// Cycles the highest resolution dynamic resolution can go to
+    HighestResolution,
+    /// Turns keeping the last few seconds of play for clips on and off
+    RecordClips,
+    Back,
+}
+
+/// The menu state a button label is worked out from.
+struct MenuValues<'a> {
+    level: &'a Level,
+    mode: &'a GameMode,
+    difficulty: &'a Difficulty,
+    settings: &'a GameSettings,
+    seed: &'a SeedEntry,
+    render_scale: &'a RenderScale,
+}
+
+/// The seed the generated level is built from, as picked on the main screen.
+#[derive(Resource, Debug)]
+pub struct SeedEntry {
+    seed: u64,
+    /// What has been typed so far while entering a seed
+    typing: Option<String>,
+}
+
+impl Default for SeedEntry {
+    fn default() -> Self {
+        Self {
+            seed: rand::random(),
+            typing: None,
+        }
+    }
+}
+
+impl MenuButton {
+    /// Whether the button steps through values, see [`menu_nav::Adjustable`]
+    fn adjustable(&self) -> bool {
+        matches!(
+            self,
+            MenuButton::Level
+                | MenuButton::Mode
+                | MenuButton::Difficulty
+                | MenuButton::Hardcore
+                | MenuButton::Doppler
+                | MenuButton::ViewMotion
+                | MenuButton::DynamicResolution
+                | MenuButton::TargetFps
+                | MenuButton::LowestResolution
+                | MenuButton::HighestResolution
+                | MenuButton::RecordClips
+        )
+    }
+
+    fn label(&self, values: &MenuValues) -> String {
+        match self {
+            MenuButton::Profile(name) => name.clone(),
+            MenuButton::NewProfile => "New profile".to_owned(),
+            MenuButton::Level => format!("Level: {}", values.level),
+            MenuButton::Mode => format!("Mode: {}", values.mode),
+            MenuButton::Seed => match &values.seed.typing {
+                Some(typing) => format!("Seed: {typing}_"),
+                None => format!("Seed: {}", values.seed.seed),
+            },
+            MenuButton::RollSeed => "Roll seed".to_owned(),
+            MenuButton::Play => "Play".to_owned(),
+            MenuButton::Loadout => "Loadout".to_owned(),
+            MenuButton::Challenges => "Challenges".to_owned(),
+            MenuButton::Settings => "Settings".to_owned(),
+            MenuButton::ChangeProfile => "Change profile".to_owned(),
+            MenuButton::Quit => "Quit".to_owned(),
+            MenuButton::Difficulty => format!("Difficulty: {:?}", values.difficulty),
+            MenuButton::Hardcore => format!(
+                "Hardcore: {}",
+                if values.settings.hardcore {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            MenuButton::Doppler => format!(
+                "Doppler: {}",
+                if values.settings.doppler { "on" } else { "off" }
+            ),
+            MenuButton::ViewMotion => {
+                format!("View motion: {:.0}%", values.settings.view_motion * 100.0)
+            }
+            // shows the resolution it's picked while it's on
+            MenuButton::DynamicResolution if values.settings.dynamic_resolution => format!(
+                "Dynamic resolution: on ({:.0}%)",
+                values.render_scale.scale * 100.0
+            ),
+            MenuButton::DynamicResolution => "Dynamic resolution: off".to_owned(),
+            MenuButton::TargetFps => format!("Target FPS: {:.0}", values.settings.target_fps),
+            MenuButton::LowestResolution => format!(
+                "Lowest resolution: {:.0}%",
+                values.settings.render_scale_bounds.0 * 100.0
+            ),
+            MenuButton::HighestResolution => format!(
+                "Highest resolution: {:.0}%",
+                values.settings.render_scale_bounds.1 * 100.0
+            ),
+            MenuButton::RecordClips => format!(
+                "Record clips: {}",
+                if values.settings.record_clips {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+            MenuButton::Back => "Back".to_owned(),
+        }
+    }
+}
+
+/// The full screen panel every menu screen is built in.
+pub fn menu_root(title: impl Into<String>, screen: MenuScreen) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.85)),
+        DespawnOnExit(screen),
+        children![
+            (
+                Text::new(title),
+                TextFont::from_font_size(32.0),
+                Node {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                },
+            ),
+            menu_nav::menu_prompt(),
+        ],
+    )
+}
+
+/// A button on a menu screen, labelled by whichever system handles `button`s of its kind.
+pub fn menu_button(button: impl Component) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width: Val::Px(BUTTON_WIDTH),
+            padding: UiRect::all(Val::Px(10.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        button,
+        // filled in by `update_button_labels`
+        children![(Text::default(), TextFont::from_font_size(20.0))],
+    )
+}
+
+/// A button back to the main screen.
+pub fn back_button() -> impl Bundle {
+    (menu_button(MenuButton::Back), menu_nav::BackButton)
+}
+
+/// Spawns `button`, marked as [`menu_nav::Adjustable`] if it steps through values.
+fn spawn_menu_button(parent: &mut ChildSpawnerCommands, button: MenuButton) {
+    let adjustable = button.adjustable();
+    let mut button = parent.spawn(menu_button(button));
+    if adjustable {
+        button.insert(menu_nav::Adjustable);
+    }
+}
+
+fn setup_profile_select(mut commands: Commands) {
+    let profiles = profile::list_profiles();
+
+    commands
+        .spawn(menu_root("Select profile", MenuScreen::Profiles))
+        .with_children(|parent| {
+            for name in profiles {
+                parent.spawn(menu_button(MenuButton::Profile(name)));
+            }
+
+            parent.spawn(menu_button(MenuButton::NewProfile));
+        });
+}
+
+fn setup_main_screen(mut commands: Commands, profile: Res<ActiveProfile>) {
+    commands
+        .spawn(menu_root(profile.name.clone(), MenuScreen::Main))
+        .with_children(|parent| {
+            for button in [
+                MenuButton::Level,
+                MenuButton::Mode,
+                MenuButton::Seed,
+                MenuButton::RollSeed,
+                MenuButton::Play,
+                MenuButton::Loadout,
+                MenuButton::Challenges,
+                MenuButton::Settings,
+                MenuButton::ChangeProfile,
+                MenuButton::Quit,
+            ] {
+                spawn_menu_button(parent, button);
+            }
+        });
+}
+
+fn setup_settings_screen(mut commands: Commands) {
+    commands
+        .spawn(menu_root("Settings", MenuScreen::Settings))
+        .with_children(|parent| {
+            for button in [
+                MenuButton::Difficulty,
+                MenuButton::Hardcore,
+                MenuButton::Doppler,
+                MenuButton::ViewMotion,
+                MenuButton::DynamicResolution,
+                MenuButton::TargetFps,
+                MenuButton::LowestResolution,
+                MenuButton::HighestResolution,
+                MenuButton::RecordClips,
+            ] {
+                spawn_menu_button(parent, button);
+            }
+
+            parent.spawn(back_button());
+        });
+}
+
+fn highlight_buttons(
+    buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut color) in buttons {
+        color.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+    }
+}
+
+fn press_menu_buttons(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut level: ResMut<Level>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut settings: ResMut<GameSettings>,
+    mut seed: ResMut<SeedEntry>,
+    mut exit_writer: MessageWriter<AppExit>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MenuButton::Profile(_) | MenuButton::NewProfile => {
+                let profile = match button {
+                    MenuButton::Profile(name) => match Profile::load(name) {
+                        Ok(profile) => profile,
+                        Err(err) => {
+                            error!("{err}");
+                            continue;
+                        }
+                    },
+                    _ => Profile::new(profile::next_profile_name()),
+                };
+
+                info!("playing as profile '{}'", profile.name);
+                commands.insert_resource(ActiveProfile(profile));
+                next_screen.set(MenuScreen::Main);
+            }
+            MenuButton::Level => {
+                let levels = Level::all(seed.seed);
+                *level = next_in(&levels, *level, |a, b| discriminant(a) == discriminant(b));
+            }
+            MenuButton::Seed => seed.typing = Some(String::new()),
+            MenuButton::RollSeed => {
+                seed.seed = rand::random();
+                seed.typing = None;
+                *level = Level::Generated { seed: seed.seed };
+            }
+            MenuButton::Mode => *mode = next_in(&GameMode::ALL, *mode, PartialEq::eq),
+            MenuButton::Play => next_state.set(GameState::Loading),
+            MenuButton::Loadout => next_screen.set(MenuScreen::Loadout),
+            MenuButton::Challenges => next_screen.set(MenuScreen::Challenges),
+            MenuButton::Settings => next_screen.set(MenuScreen::Settings),
+            MenuButton::ChangeProfile => next_screen.set(MenuScreen::Profiles),
+            MenuButton::Quit => {
+                exit_writer.write(AppExit::Success);
+            }
+            MenuButton::Difficulty => {
+                *difficulty = next_in(&Difficulty::ALL, *difficulty, PartialEq::eq);
+            }
+            MenuButton::Hardcore => settings.hardcore = !settings.hardcore,
+            MenuButton::Doppler => settings.doppler = !settings.doppler,
+            MenuButton::ViewMotion => {
+                settings.view_motion = next_in(&VIEW_MOTIONS, settings.view_motion, |a, b| {
+                    (a - b).abs() < f32::EPSILON
+                });
+            }
+            MenuButton::DynamicResolution => {
+                settings.dynamic_resolution = !settings.dynamic_resolution;
+            }
+            MenuButton::TargetFps => {
+                settings.target_fps = next_in(&TARGET_FPS, settings.target_fps, |a, b| {
+                    (a - b).abs() < f32::EPSILON
+                });
+            }
+            MenuButton::LowestResolution => {
+                let bounds = &mut settings.render_scale_bounds;
+                bounds.0 = next_in(&LOWEST_RESOLUTIONS, bounds.0, |a, b| {
+                    (a - b).abs() < f32::EPSILON
+                });
+            }
+            MenuButton::HighestResolution => {
+                let bounds = &mut settings.render_scale_bounds;
+                bounds.1 = next_in(&HIGHEST_RESOLUTIONS, bounds.1, |a, b| {
+                    (a - b).abs() < f32::EPSILON
+                });
+            }
+            MenuButton::RecordClips => settings.record_clips = !settings.record_clips,
+            MenuButton::Back => next_screen.set(MenuScreen::Main),
+        }
+    }
+}
+
+/// The option after `current`, wrapping round to the first
+fn next_in<T: Copy>(options: &[T], current: T, same: impl Fn(&T, &T) -> bool) -> T {
+    let index = options
+        .iter()
+        .position(|option| same(option, &current))
+        .map_or(0, |index| (index + 1) % options.len());
+
+    options[index]
+}
+
+/// Types a seed into the seed button after it has been clicked.
+///
+/// Enter uses the typed seed for the generated level, Escape puts the old seed back.
+fn type_seed(
+    mut keyboard_reader: MessageReader<KeyboardInput>,
+    mut seed: ResMut<SeedEntry>,
+    mut level: ResMut<Level>,
+) {
+    for event in keyboard_reader.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        let Some(typing) = &mut seed.typing else {
+            continue;
+        };
+
+        match &event.logical_key {
+            Key::Enter => {
+                let typed = std::mem::take(typing);
+                seed.typing = None;
+
+                match typed.parse() {
+                    Ok(typed) => {
+                        seed.seed = typed;
+                        *level = Level::Generated { seed: typed };
+                    }
+                    Err(err) if !typed.is_empty() => warn!("invalid seed '{typed}': {err}"),
+                    Err(_) => {}
+                }
+            }
+            Key::Escape => seed.typing = None,
+            Key::Backspace => {
+                typing.pop();
+            }
+            Key::Character(text) if text.chars().all(|c| c.is_ascii_digit()) => {
+                typing.push_str(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_button_labels(
+    level: Res<Level>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    settings: Res<GameSettings>,
+    seed: Res<SeedEntry>,
+    render_scale: Res<RenderScale>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let values = MenuValues {
+        level: &level,
+        mode: &mode,
+        difficulty: &difficulty,
+        settings: &settings,
+        seed: &seed,
+        render_scale: &render_scale,
+    };
+
+    for (button, children) in buttons {
+        let label = button.label(&values);
+
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.set_if_neq(Text(label.clone()));
+            }
+        }
+    }
+}
+
+/// Whether a seed is being typed in, which has the keyboard to itself.
+pub fn entering_seed(seed: Res<SeedEntry>) -> bool {
+    seed.typing.is_some()
+}
+
+fn return_to_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+        next_screen.set(MenuScreen::Main);
+    }
+}
@@ -0,0 +1,337 @@
+//! Getting round the menus without a mouse.
+//!
+//! The arrow keys, Tab, the d-pad or the left stick move the [`MenuFocus`] to the nearest button
+//! in that direction on the current screen. Enter, Space or the gamepad's south button presses
+//! the focused button just as a click would, so each screen's own button handling works
+//! unchanged. Left and right step an [`Adjustable`] button's value when there's no button beside
+//! it, and Escape or the east button presses the screen's [`BackButton`].
+//!
+//! The focused button is outlined, and the [`MenuPrompt`] along the bottom of every screen shows
+//! the keys or gamepad buttons for whichever device was used last, see [`LastInputDevice`].
+
+use bevy::{input::InputSystems, prelude::*, ui::UiSystems};
+
+use crate::menu::{self, GameState};
+
+pub struct MenuNavPlugin;
+
+impl Plugin for MenuNavPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .init_resource::<LastInputDevice>()
+            .add_systems(
+                PreUpdate,
+                (
+                    track_input_device.after(InputSystems),
+                    navigate_menu
+                        .after(UiSystems::Focus)
+                        .run_if(in_state(GameState::MainMenu))
+                        .run_if(not(menu::entering_seed)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    outline_focus.run_if(resource_changed::<MenuFocus>),
+                    update_prompts,
+                )
+                    .run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+const FOCUS_COLOR: Color = Color::srgb(1.0, 0.8, 0.1);
+/// How far the left stick has to be pushed to move the focus
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// The menu button keyboard and gamepad input goes to.
+#[derive(Resource, Debug, Default)]
+pub struct MenuFocus(pub Option<Entity>);
+
+/// A button that steps through values, pressed by left and right when there's no button beside
+/// it.
+#[derive(Component, Debug)]
+pub struct Adjustable;
+
+/// The button Escape or the gamepad's east button presses on its screen.
+#[derive(Component, Debug)]
+pub struct BackButton;
+
+/// The line of input hints along the bottom of a menu screen.
+#[derive(Component, Debug)]
+pub struct MenuPrompt;
+
+/// Whichever of the keyboard and mouse or a gamepad was used last, for showing the right glyphs
+/// in prompts.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LastInputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
+}
+
+/// Something a prompt tells the player how to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    Move,
+    Select,
+    Back,
+}
+
+impl LastInputDevice {
+    /// What to press for `action` on this device
+    pub fn glyph(self, action: PromptAction) -> &'static str {
+        match (self, action) {
+            (LastInputDevice::KeyboardMouse, PromptAction::Move) => "[Arrows]",
+            (LastInputDevice::KeyboardMouse, PromptAction::Select) => "[Enter]",
+            (LastInputDevice::KeyboardMouse, PromptAction::Back) => "[Esc]",
+            (LastInputDevice::Gamepad, PromptAction::Move) => "(D-pad)",
+            (LastInputDevice::Gamepad, PromptAction::Select) => "(A)",
+            (LastInputDevice::Gamepad, PromptAction::Back) => "(B)",
+        }
+    }
+}
+
+/// The prompt along the bottom of a menu screen, filled in by `update_prompts`.
+pub fn menu_prompt() -> impl Bundle {
+    (
+        Text::default(),
+        TextFont::from_font_size(16.0),
+        TextColor(Color::WHITE.with_alpha(0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(24.0),
+            ..default()
+        },
+        MenuPrompt,
+    )
+}
+
+fn track_input_device(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut device: ResMut<LastInputDevice>,
+) {
+    if keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some() {
+        device.set_if_neq(LastInputDevice::KeyboardMouse);
+    }
+
+    let gamepad_used = gamepads.iter().any(|gamepad| {
+        gamepad.get_just_pressed().next().is_some()
+            || gamepad.left_stick().length() > STICK_THRESHOLD
+            || gamepad.right_stick().length() > STICK_THRESHOLD
+    });
+    if gamepad_used {
+        device.set_if_neq(LastInputDevice::Gamepad);
+    }
+}
+
+/// What the keyboard and gamepads asked of the menu this frame.
+#[derive(Debug, Default)]
+struct MenuInput {
+    /// Which way to move the focus, with y growing down the screen like UI coordinates
+    step: Option<Vec2>,
+    select: bool,
+    back: bool,
+}
+
+impl MenuInput {
+    fn read(
+        keys: &ButtonInput<KeyCode>,
+        gamepads: &Query<&Gamepad>,
+        stick_held: &mut bool,
+    ) -> Self {
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let pressed = |key: KeyCode, button: GamepadButton| {
+            keys.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+        };
+
+        let mut step = if pressed(KeyCode::ArrowUp, GamepadButton::DPadUp) {
+            Some(Vec2::NEG_Y)
+        } else if pressed(KeyCode::ArrowDown, GamepadButton::DPadDown) {
+            Some(Vec2::Y)
+        } else if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft) {
+            Some(Vec2::NEG_X)
+        } else if pressed(KeyCode::ArrowRight, GamepadButton::DPadRight) {
+            Some(Vec2::X)
+        } else if keys.just_pressed(KeyCode::Tab) {
+            Some(if shift { Vec2::NEG_Y } else { Vec2::Y })
+        } else {
+            None
+        };
+
+        // the stick moves the focus once each time it's pushed over, rather than every frame
+        let stick = gamepads
+            .iter()
+            .map(Gamepad::left_stick)
+            .find(|stick| stick.length() > STICK_THRESHOLD);
+        if let Some(stick) = stick
+            && !*stick_held
+        {
+            // the stick's y points up the screen
+            step = step.or(Some(if stick.x.abs() > stick.y.abs() {
+                Vec2::new(stick.x.signum(), 0.0)
+            } else {
+                Vec2::new(0.0, -stick.y.signum())
+            }));
+        }
+        *stick_held = stick.is_some();
+
+        Self {
+            step,
+            select: pressed(KeyCode::Enter, GamepadButton::South)
+                || keys.just_pressed(KeyCode::Space),
+            back: pressed(KeyCode::Escape, GamepadButton::East),
+        }
+    }
+}
+
+/// Moves the focus between buttons and presses them.
+///
+/// Runs after bevy_ui has worked out `Interaction`s so a press lands in this frame's `Update`.
+/// bevy_ui only lets go of a press when the mouse button comes up, so the press is undone here
+/// the next frame.
+fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut focus: ResMut<MenuFocus>,
+    mut stick_held: Local<bool>,
+    mut pressed: Local<Option<Entity>>,
+    mut buttons: Query<
+        (
+            Entity,
+            &UiGlobalTransform,
+            &InheritedVisibility,
+            &mut Interaction,
+            Has<Adjustable>,
+            Has<BackButton>,
+        ),
+        With<Button>,
+    >,
+) {
+    if let Some(button) = pressed.take()
+        && let Ok((.., mut interaction, _, _)) = buttons.get_mut(button)
+        && *interaction == Interaction::Pressed
+    {
+        *interaction = Interaction::None;
+    }
+
+    let shown = |visibility: &InheritedVisibility| visibility.get();
+
+    // the focused button went with the screen it was on
+    if let Some(focused) = focus.0
+        && !buttons
+            .get(focused)
+            .is_ok_and(|(_, _, visibility, ..)| shown(visibility))
+    {
+        focus.0 = None;
+    }
+
+    let input = MenuInput::read(&keys, &gamepads, &mut stick_held);
+    let mut press = None;
+
+    if let Some(step) = input.step {
+        let positions = buttons
+            .iter()
+            .filter(|(_, _, visibility, ..)| shown(visibility))
+            .map(|(entity, transform, ..)| (entity, transform.translation));
+
+        match focus.0 {
+            Some(focused) => {
+                let (_, transform, _, _, adjustable, _) = buttons.get(focused).unwrap();
+
+                match neighbour(transform.translation, step, positions) {
+                    Some(next) => focus.0 = Some(next),
+                    None if step.y == 0.0 && adjustable => press = Some(focused),
+                    None => {}
+                }
+            }
+            // the first step picks the top button rather than moving anywhere
+            None => {
+                focus.0 = positions
+                    .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)))
+                    .map(|(entity, _)| entity);
+            }
+        }
+    }
+
+    if input.select {
+        press = press.or(focus.0);
+    }
+    if input.back {
+        press = press.or(buttons
+            .iter()
+            .find(|(_, _, visibility, _, _, back)| *back && shown(visibility))
+            .map(|(entity, ..)| entity));
+    }
+
+    if let Some(button) = press
+        && let Ok((.., mut interaction, _, _)) = buttons.get_mut(button)
+    {
+        *interaction = Interaction::Pressed;
+        *pressed = Some(button);
+    }
+}
+
+/// The nearest of `buttons` from `from` in `direction`, favouring ones straight in line
+fn neighbour(
+    from: Vec2,
+    direction: Vec2,
+    buttons: impl Iterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    /// How much further a button can be for each pixel nearer in line it is
+    const ACROSS_WEIGHT: f32 = 2.0;
+
+    buttons
+        .filter_map(|(entity, at)| {
+            let offset = at - from;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+
+            // anything more off to the side than ahead is in a different direction
+            (along > 0.0 && across <= along).then_some((entity, along + across * ACROSS_WEIGHT))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+fn outline_focus(
+    mut commands: Commands,
+    focus: Res<MenuFocus>,
+    buttons: Query<(Entity, Has<Outline>), With<Button>>,
+) {
+    for (entity, outlined) in buttons {
+        if focus.0 == Some(entity) {
+            commands
+                .entity(entity)
+                .insert(Outline::new(Val::Px(2.0), Val::ZERO, FOCUS_COLOR));
+        } else if outlined {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+}
+
+fn update_prompts(
+    device: Res<LastInputDevice>,
+    back_buttons: Query<(), With<BackButton>>,
+    prompts: Query<&mut Text, With<MenuPrompt>>,
+) {
+    let mut actions = vec![
+        (PromptAction::Move, "Move"),
+        (PromptAction::Select, "Select"),
+    ];
+    if !back_buttons.is_empty() {
+        actions.push((PromptAction::Back, "Back"));
+    }
+
+    let prompt = actions
+        .into_iter()
+        .map(|(action, label)| format!("{} {label}", device.glyph(action)))
+        .collect::<Vec<_>>()
+        .join("    ");
+
+    for mut text in prompts {
+        text.set_if_neq(Text(prompt.clone()));
+    }
+}
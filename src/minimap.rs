@@ -0,0 +1,190 @@
+//! A north-up overhead map in the corner of the screen.
+//!
+//! Anything with a [`MinimapIcon`] shows up as a blip: objectives always, enemies only once the
+//! player is close enough to have spotted them. The map is redrawn a few times a second rather
+//! than every frame, and toggled with M.
+
+use bevy::prelude::*;
+
+use crate::hud::HudTheme;
+use crate::menu::GameState;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MinimapRefresh(Timer::from_seconds(
+            REFRESH_INTERVAL,
+            TimerMode::Repeating,
+        )))
+        .add_systems(Startup, setup_minimap)
+        .add_systems(
+            Update,
+            (
+                toggle_minimap
+                    .run_if(crate::console::console_closed)
+                    .run_if(crate::chat::chat_closed),
+                update_minimap,
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+/// Width of the world shown on the map, in metres
+const MAP_EXTENT: f32 = 100.0;
+/// Size of the map on screen, in pixels
+const MAP_SIZE: f32 = 200.0;
+/// Seconds between redraws
+const REFRESH_INTERVAL: f32 = 0.1;
+/// How close an enemy has to be before it shows up
+const DETECTION_RANGE: f32 = 25.0;
+
+/// How something is drawn on the minimap.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapIcon {
+    Objective,
+    Enemy,
+}
+
+impl MinimapIcon {
+    pub fn color(&self, theme: &HudTheme) -> Color {
+        match self {
+            MinimapIcon::Objective => theme.objective,
+            MinimapIcon::Enemy => theme.enemy,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct MinimapRefresh(Timer);
+
+#[derive(Component)]
+struct Minimap;
+
+/// The parent of every blip, cleared on each redraw.
+#[derive(Component)]
+struct MinimapBlips;
+
+#[derive(Component)]
+struct MinimapPlayer;
+
+fn setup_minimap(mut commands: Commands, theme: Res<HudTheme>) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(24.0),
+            bottom: Val::Px(24.0),
+            width: Val::Px(MAP_SIZE),
+            height: Val::Px(MAP_SIZE),
+            border: UiRect::all(Val::Px(2.0)),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BackgroundColor(theme.panel),
+        BorderColor::all(theme.panel_border),
+        Minimap,
+        children![
+            (
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                MinimapBlips,
+            ),
+            (
+                // an arrow pointing the way the player faces, white at the front
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(8.0),
+                    height: Val::Px(14.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.9, 0.3)),
+                MinimapPlayer,
+                children![(
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(4.0),
+                        ..default()
+                    },
+                    BackgroundColor(theme.text),
+                )],
+            ),
+        ],
+    ));
+}
+
+fn toggle_minimap(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut minimap: Single<&mut Visibility, With<Minimap>>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        minimap.toggle_visible_hidden();
+    }
+}
+
+/// Where a world position lands on the map, in pixels from the top left
+fn map_position(position: Vec3) -> Vec2 {
+    let scale = MAP_SIZE / MAP_EXTENT;
+    Vec2::new(position.x, position.z) * scale + Vec2::splat(MAP_SIZE / 2.0)
+}
+
+fn update_minimap(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<HudTheme>,
+    mut refresh: ResMut<MinimapRefresh>,
+    minimap: Single<&Visibility, With<Minimap>>,
+    blips: Single<Entity, With<MinimapBlips>>,
+    player: Single<&GlobalTransform, With<PrimaryPlayer>>,
+    mut player_icon: Single<(&mut Node, &mut UiTransform), With<MinimapPlayer>>,
+    icons: Query<(&GlobalTransform, &MinimapIcon, &Visibility)>,
+) {
+    if !refresh.0.tick(time.delta()).just_finished() || **minimap == Visibility::Hidden {
+        return;
+    }
+
+    let player_position = player.translation();
+    let forward = player.forward();
+
+    let (node, transform) = &mut *player_icon;
+    let icon_position = map_position(player_position);
+    node.left = Val::Px(icon_position.x - 4.0);
+    node.top = Val::Px(icon_position.y - 7.0);
+    // clockwise from north (-Z)
+    transform.rotation = Rot2::radians(forward.x.atan2(-forward.z));
+
+    commands.entity(*blips).despawn_children();
+
+    for (icon_transform, icon, visibility) in icons {
+        let position = icon_transform.translation();
+
+        let detected = match icon {
+            MinimapIcon::Objective => *visibility != Visibility::Hidden,
+            MinimapIcon::Enemy => position.distance(player_position) <= DETECTION_RANGE,
+        };
+
+        if !detected {
+            continue;
+        }
+
+        let blip_position = map_position(position);
+
+        commands.entity(*blips).with_child((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(blip_position.x - 3.0),
+                top: Val::Px(blip_position.y - 3.0),
+                width: Val::Px(6.0),
+                height: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(icon.color(&theme)),
+            BorderRadius::MAX,
+        ));
+    }
+}
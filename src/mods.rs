@@ -0,0 +1,128 @@
+//! Asset overrides from a `mods/` directory next to `assets/`.
+//!
+//! Any file placed in `mods/` at the same relative path as a built-in asset is loaded instead of
+//! it, so replacement weapon models, sounds and `*.weapon.ron` definitions can be dropped in
+//! without touching the shipped assets. Overridden files are reported at startup.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    asset::io::{
+        AssetReader, AssetReaderError, AssetSource, AssetSourceId, ErasedAssetReader, PathStream,
+        Reader, file::FileAssetReader,
+    },
+    prelude::*,
+};
+
+const BASE_DIRECTORY: &str = "assets";
+const MODS_DIRECTORY: &str = "mods";
+
+/// Must be added before `DefaultPlugins` so the asset source is registered before the
+/// `AssetPlugin` builds.
+pub struct ModAssetsPlugin;
+
+impl Plugin for ModAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_source(
+            AssetSourceId::Default,
+            AssetSource::build()
+                .with_reader(|| {
+                    Box::new(ModAssetReader {
+                        mods: AssetSource::get_default_reader(MODS_DIRECTORY.into())(),
+                        base: AssetSource::get_default_reader(BASE_DIRECTORY.into())(),
+                    })
+                })
+                .with_watcher(AssetSource::get_default_watcher(
+                    BASE_DIRECTORY.into(),
+                    Duration::from_millis(300),
+                )),
+        )
+        .add_systems(Startup, report_overrides);
+    }
+}
+
+/// Reads from the mods directory first, falling back to the built-in assets.
+struct ModAssetReader {
+    mods: Box<dyn ErasedAssetReader>,
+    base: Box<dyn ErasedAssetReader>,
+}
+
+impl AssetReader for ModAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        match self.mods.read(path).await {
+            Err(AssetReaderError::NotFound(_)) => self.base.read(path).await,
+            result => result,
+        }
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        match self.mods.read_meta(path).await {
+            Err(AssetReaderError::NotFound(_)) => self.base.read_meta(path).await,
+            result => result,
+        }
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        match self.mods.read_directory(path).await {
+            Err(AssetReaderError::NotFound(_)) => self.base.read_directory(path).await,
+            result => result,
+        }
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        match self.mods.is_directory(path).await {
+            Ok(true) => Ok(true),
+            _ => self.base.is_directory(path).await,
+        }
+    }
+}
+
+/// Every file under `root`, relative to `root`
+fn files_under(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = std::fs::read_dir(&directory) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                directories.push(path);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+fn report_overrides() {
+    let root = FileAssetReader::get_base_path();
+    let mods_root = root.join(MODS_DIRECTORY);
+
+    if !mods_root.is_dir() {
+        return;
+    }
+
+    let base_root = root.join(BASE_DIRECTORY);
+
+    for file in files_under(&mods_root) {
+        if base_root.join(&file).exists() {
+            warn!("mod overrides built-in asset {}", file.display());
+        } else {
+            info!("mod adds asset {}", file.display());
+        }
+    }
+}
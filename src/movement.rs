@@ -1,43 +1,353 @@
 use avian3d::{math::*, prelude::*};
 use bevy::{ecs::query::Has, prelude::*};
 
+use crate::encumbrance::Encumbrance;
+use crate::energy::{EnergyAction, EnergyCosts, Stamina};
+use crate::input_buffer::{Action, ActionBuffer};
+use crate::menu::GameState;
+use crate::particles::{ParticleEffect, SpawnParticles};
+use crate::settings::{GameSettings, Keybinds};
+use crate::split_screen::PlayerInput;
+use crate::status::StatusEffects;
+use crate::touch::{TouchButton, TouchControls};
+use crate::tuning::{CameraTuning, HeadBobTuning};
+use crate::{PlayerCamera, PlayerWeapon, TransformPipeline, WeaponActive};
+
 pub struct CharacterControllerPlugin;
 
 impl Plugin for CharacterControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_message::<MovementAction>().add_systems(
-            Update,
-            (
+        app.add_message::<MovementAction>()
+            .init_resource::<LeanOffsets>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        (
+                            keyboard_input
+                                .run_if(crate::console::console_closed)
+                                .run_if(crate::chat::chat_closed),
+                            gamepad_input,
+                            touch_input,
+                        )
+                            .run_if(in_state(GameState::InGame)),
+                        collect_movement,
+                    )
+                        .chain(),
+                    sprint.run_if(in_state(GameState::InGame)),
+                ),
+            )
+            // simulated at a fixed rate ahead of the physics step, which runs in
+            // `FixedPostUpdate`, so speed doesn't vary with the frame rate
+            .add_systems(
+                FixedUpdate,
                 (
-                    keyboard_input,
-                    gamepad_input,
                     update_grounded,
+                    change_stance,
+                    resize_capsules,
                     movement,
                     apply_movement_damping,
                 )
                     .chain(),
-                sprint,
-            ),
-        );
+            );
     }
 }
 
 /// An event sent for a movement input action.
 #[derive(Message)]
-pub enum MovementAction {
+pub struct MovementAction {
+    /// The character controller to move
+    pub controller: Entity,
+    pub kind: MovementKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MovementKind {
     Move(Vector2),
     Jump,
+    Dash,
+    /// Crouch, or stand back up from crouching
+    Crouch,
+    /// Go prone, or stand back up from prone
+    Prone,
+}
+
+/// The movement a character has been asked for, collected from [`MovementAction`] events every
+/// frame and acted on by the next fixed step.
+#[derive(Component, Default)]
+pub struct MovementIntent {
+    pub direction: Vector2,
+    pub jump: bool,
+    pub dash: bool,
+    /// The stance asked for, kept until there's room to take it
+    pub stance: Option<Stance>,
 }
 
+/// Speed added in the direction of travel by a dash.
+const DASH_IMPULSE: Scalar = 12.0;
+
 /// A marker component indicating that an entity is using a character controller.
 #[derive(Component)]
+#[require(MovementIntent, Stance, AimState)]
 pub struct CharacterController;
 
+/// How far a character has their weapon up to aim, and which way they're strafing.
+///
+/// The weapon side fills in the aim, which slows the character down here, and the movement side
+/// fills in the strafe, which the weapon's sway leans into.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct AimState {
+    /// How far aimed in, `0..=1`
+    pub alpha: f32,
+    /// The share of acceleration kept fully aimed in, from the weapon
+    pub speed_scale: f32,
+    /// Sideways movement asked for, from -1 left to 1 right
+    pub strafe: f32,
+}
+
+impl Default for AimState {
+    fn default() -> Self {
+        Self {
+            alpha: 0.0,
+            speed_scale: 1.0,
+            strafe: 0.0,
+        }
+    }
+}
+
+impl AimState {
+    /// Multiplier on acceleration for how far aimed in
+    pub fn acceleration_scale(&self) -> f32 {
+        1.0_f32.lerp(self.speed_scale, self.alpha.clamp(0.0, 1.0))
+    }
+}
+
+/// How a character is holding themselves.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Stance {
+    #[default]
+    Standing,
+    Crouched,
+    Prone,
+}
+
+impl Stance {
+    /// The share of the standing capsule's length between its hemispheres kept in this stance
+    pub fn length_scale(self) -> Scalar {
+        match self {
+            Stance::Standing => 1.0,
+            Stance::Crouched => 0.5,
+            Stance::Prone => 0.0,
+        }
+    }
+
+    /// Multiplier on acceleration, slower the lower the character gets
+    pub fn speed_scale(self) -> f32 {
+        match self {
+            Stance::Standing => 1.0,
+            Stance::Crouched => 0.6,
+            Stance::Prone => 0.3,
+        }
+    }
+
+    /// Multiplier on weapon sway, steadier the lower the character gets
+    pub fn sway_scale(self) -> f32 {
+        match self {
+            Stance::Standing => 1.0,
+            Stance::Crouched => 0.7,
+            Stance::Prone => 0.4,
+        }
+    }
+
+    /// A character's capsule in this stance, given its size standing
+    pub fn capsule(self, standing: CapsuleSize) -> CapsuleSize {
+        CapsuleSize {
+            length: standing.length * self.length_scale(),
+            ..standing
+        }
+    }
+
+    /// The stance pressing `toggle`'s button heads for from this one
+    fn toggled(self, toggle: Stance) -> Stance {
+        if self == toggle {
+            Stance::Standing
+        } else {
+            toggle
+        }
+    }
+}
+
+/// How far a player is leaning, from -1 all the way left to 1 all the way right.
+///
+/// Moves at a steady [`Lean::SPEED`] while a lean key is held or let go, and is eased on the way
+/// to the camera so it starts and settles gently.
+#[derive(Component, Debug, Default)]
+pub struct Lean(f32);
+
+impl Lean {
+    /// Full leans per second
+    const SPEED: f32 = 4.0;
+
+    /// How far out the camera sits, eased in and out
+    fn eased(&self) -> f32 {
+        EaseFunction::SmoothStep.sample_clamped(self.0.abs()) * self.0.signum()
+    }
+}
+
+/// How far characters lean out around cover, the same for everyone.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct LeanOffsets {
+    /// Radians rolled leaning all the way out
+    pub angle: f32,
+    /// Metres sideways leaning all the way out
+    pub offset: f32,
+}
+
+impl LeanOffsets {
+    /// The head's offset from upright `amount` of the way out, negative to the left
+    pub fn at(&self, amount: f32) -> Transform {
+        Transform::from_xyz(self.offset * amount, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(-self.angle * amount))
+    }
+}
+
+impl Default for LeanOffsets {
+    fn default() -> Self {
+        Self {
+            angle: 15f32.to_radians(),
+            offset: 0.45,
+        }
+    }
+}
+
+/// How far through their stride a player is, and how big their view's bob is.
+///
+/// The view dips as each foot lands and sways from side to side over a stride, with a stride
+/// taken every [`HeadBobTuning::stride`] metres so the bob keeps time with the feet at any speed.
+/// Its size follows horizontal speed, eased so starting and stopping don't jolt the view, and
+/// grows while sprinting.
+#[derive(Component, Debug, Default)]
+pub struct HeadBob {
+    /// Radians through the stride, with a foot landing every half turn
+    phase: f32,
+    /// How big the bob is, 1 at [`HeadBobTuning::full_speed`]
+    amount: f32,
+    /// The view's offset this step, which the weapon follows
+    offset: Vec3,
+}
+
+impl HeadBob {
+    /// How quickly the bob's size closes in on the speed's, higher is quicker
+    const SETTLE: f32 = 8.0;
+
+    /// Moves `delta` seconds along the stride at `speed` metres per second, returning the view's
+    /// offset
+    pub fn advance(
+        &mut self,
+        tuning: &HeadBobTuning,
+        speed: f32,
+        grounded: bool,
+        sprinting: bool,
+        delta: f32,
+    ) -> Vec3 {
+        let target = if !grounded {
+            0.0
+        } else if sprinting {
+            (speed / tuning.full_speed).min(1.0) * tuning.sprint
+        } else {
+            (speed / tuning.full_speed).min(1.0)
+        };
+        self.amount += (target - self.amount) * (1.0 - (-Self::SETTLE * delta).exp());
+
+        // the feet stop while in the air, picking up where they left off on landing
+        if grounded {
+            self.phase += speed / tuning.stride * std::f32::consts::TAU * delta;
+            self.phase %= std::f32::consts::TAU;
+        }
+
+        self.offset = Vec3::new(
+            tuning.lateral * self.phase.sin(),
+            -tuning.vertical * self.phase.cos().powi(2),
+            0.0,
+        ) * self.amount;
+        self.offset
+    }
+}
+
+/// The size of a character controller's capsule collider.
+///
+/// Don't swap a controller's [`Collider`] out directly, insert a [`ResizeCapsule`] instead so its
+/// ground caster and centre of mass change along with it.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CapsuleSize {
+    pub radius: Scalar,
+    /// Between the centres of the hemispheres
+    pub length: Scalar,
+}
+
+impl CapsuleSize {
+    pub const fn new(radius: Scalar, length: Scalar) -> Self {
+        Self { radius, length }
+    }
+
+    /// From top to bottom
+    pub fn height(&self) -> Scalar {
+        self.length + 2.0 * self.radius
+    }
+
+    pub fn collider(&self) -> Collider {
+        Collider::capsule(self.radius, self.length)
+    }
+}
+
+/// A character's [`CapsuleSize`] when standing, which their [`Stance`] is taken from.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct StandingCapsule(pub CapsuleSize);
+
+/// How far a player's eyes have dropped below where they'd be standing, in metres.
+///
+/// The capsule changes size in a single step, which would drop the camera with it. Instead the
+/// eyes ease down to the new stance's height at [`EyeDrop::SPEED`], starting from where they were.
+#[derive(Component, Debug, Default)]
+pub struct EyeDrop(Scalar);
+
+impl EyeDrop {
+    /// How quickly the eyes close in on their height, higher is quicker
+    const SPEED: Scalar = 12.0;
+}
+
+/// Asks for a character controller's capsule to be resized.
+///
+/// Taken at the next fixed step, ahead of the physics step, which swaps the collider, ground
+/// caster and centre of mass together and keeps the character's feet where they were. A capsule
+/// that would grow into something is left waiting, tried again every step until there's room,
+/// rather than popping the character through it.
+#[derive(Component, Debug, Clone, Copy)]
+#[component(storage = "SparseSet")]
+pub struct ResizeCapsule {
+    pub size: CapsuleSize,
+    /// The [`Stance`] the character takes once it's resized
+    pub stance: Option<Stance>,
+}
+
 /// A marker component indicating that an entity is on the ground.
 #[derive(Component)]
 #[component(storage = "SparseSet")]
 pub struct Grounded;
 
+/// A marker component indicating that a character is hanging from something, like a zipline, that
+/// moves them instead. They never count as grounded, and their [`MovementIntent`] is left for
+/// whatever they're hanging from to act on.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Suspended;
+
+/// A marker component indicating that a character's movement input is ignored, like partway
+/// through a roll. Whatever has locked them moves them instead.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct MovementLocked;
+
 /// A marker component indicating that an entity is sprinting
 #[derive(Component)]
 #[component(storage = "SparseSet")]
@@ -45,24 +355,42 @@ pub struct Sprinting;
 
 /// The acceleration used for character movement.
 #[derive(Component)]
-pub struct MovementAcceleration(Scalar);
+pub struct MovementAcceleration(pub Scalar);
 
 #[derive(Component)]
-pub struct SprintFactor(Scalar);
+pub struct SprintFactor(pub Scalar);
 
-/// The damping factor used for slowing down movement.
+/// How quickly horizontal movement slows down when not driven.
 #[derive(Component)]
-pub struct MovementDampingFactor(Scalar);
+pub struct MovementDampingFactor {
+    /// Seconds for horizontal speed to halve, zero stops dead
+    pub half_life: Scalar,
+}
+
+impl MovementDampingFactor {
+    /// The fraction of speed left after `delta` seconds
+    pub fn decay(&self, delta: Scalar) -> Scalar {
+        if self.half_life > 0.0 {
+            (0.5 as Scalar).powf(delta / self.half_life)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Seconds before a hard landing that pressing crouch turns it into a roll, see `glide`.
+#[derive(Component)]
+pub struct RollWindow(pub Scalar);
 
 /// The strength of a jump.
 #[derive(Component)]
-pub struct JumpImpulse(Scalar);
+pub struct JumpImpulse(pub Scalar);
 
 /// The maximum angle a slope can have for a character controller
 /// to be able to climb and jump. If the slope is steeper than this angle,
 /// the character will slide down.
 #[derive(Component)]
-pub struct MaxSlopeAngle(Scalar);
+pub struct MaxSlopeAngle(pub Scalar);
 
 /// A bundle that contains the components needed for a basic
 /// kinematic character controller.
@@ -70,9 +398,14 @@ pub struct MaxSlopeAngle(Scalar);
 pub struct CharacterControllerBundle {
     character_controller: CharacterController,
     body: RigidBody,
+    capsule: CapsuleSize,
+    standing: StandingCapsule,
     collider: Collider,
     ground_caster: ShapeCaster,
     locked_axes: LockedAxes,
+    /// Smooths the fixed rate simulation out over the frames in between. Only the translation,
+    /// rotation is turned by mouse look every frame
+    interpolation: TranslationInterpolation,
     movement: MovementBundle,
 }
 
@@ -84,50 +417,49 @@ pub struct MovementBundle {
     damping: MovementDampingFactor,
     jump_impulse: JumpImpulse,
     max_slope_angle: MaxSlopeAngle,
+    roll_window: RollWindow,
 }
 
 impl MovementBundle {
     pub const fn new(
         acceleration: Scalar,
         sprint_factor: Scalar,
-        damping: Scalar,
+        damping_half_life: Scalar,
         jump_impulse: Scalar,
         max_slope_angle: Scalar,
     ) -> Self {
         Self {
             acceleration: MovementAcceleration(acceleration),
             sprint_factor: SprintFactor(sprint_factor),
-            damping: MovementDampingFactor(damping),
+            damping: MovementDampingFactor {
+                half_life: damping_half_life,
+            },
             jump_impulse: JumpImpulse(jump_impulse),
             max_slope_angle: MaxSlopeAngle(max_slope_angle),
+            roll_window: RollWindow(0.25),
         }
     }
 }
 
 impl Default for MovementBundle {
     fn default() -> Self {
-        Self::new(30.0, 1.5, 0.9, 7.0, PI * 0.45)
+        Self::new(30.0, 1.5, 0.11, 7.0, PI * 0.45)
     }
 }
 
 impl CharacterControllerBundle {
-    pub fn new(collider: Collider) -> Self {
-        // Create shape caster as a slightly smaller version of collider
-        let mut caster_shape = collider.clone();
-        caster_shape.set_scale(Vector::ONE * 0.99, 10);
+    pub fn new(capsule: CapsuleSize) -> Self {
+        let collider = capsule.collider();
 
         Self {
             character_controller: CharacterController,
             body: RigidBody::Dynamic,
+            capsule,
+            standing: StandingCapsule(capsule),
+            ground_caster: ground_caster(&collider),
             collider,
-            ground_caster: ShapeCaster::new(
-                caster_shape,
-                Vector::ZERO,
-                Quaternion::default(),
-                Dir3::NEG_Y,
-            )
-            .with_max_distance(0.2),
             locked_axes: LockedAxes::ROTATION_LOCKED,
+            interpolation: TranslationInterpolation,
             movement: MovementBundle::default(),
         }
     }
@@ -136,14 +468,14 @@ impl CharacterControllerBundle {
         mut self,
         acceleration: Scalar,
         sprint_factor: Scalar,
-        damping: Scalar,
+        damping_half_life: Scalar,
         jump_impulse: Scalar,
         max_slope_angle: Scalar,
     ) -> Self {
         self.movement = MovementBundle::new(
             acceleration,
             sprint_factor,
-            damping,
+            damping_half_life,
             jump_impulse,
             max_slope_angle,
         );
@@ -151,26 +483,64 @@ impl CharacterControllerBundle {
     }
 }
 
+/// Casts down from a slightly smaller version of `collider` to find the ground beneath it.
+fn ground_caster(collider: &Collider) -> ShapeCaster {
+    let mut caster_shape = collider.clone();
+    caster_shape.set_scale(Vector::ONE * 0.99, 10);
+
+    ShapeCaster::new(
+        caster_shape,
+        Vector::ZERO,
+        Quaternion::default(),
+        Dir3::NEG_Y,
+    )
+    .with_max_distance(0.2)
+}
+
 /// Sends [`MovementAction`] events based on keyboard input.
 fn keyboard_input(
     mut movement_event_writer: MessageWriter<MovementAction>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    players: Query<(Entity, &PlayerInput)>,
 ) {
-    let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
-    let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
-    let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-    let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
+    let up = keyboard_input.any_pressed([keybinds.forward, KeyCode::ArrowUp]);
+    let down = keyboard_input.any_pressed([keybinds.back, KeyCode::ArrowDown]);
+    let left = keyboard_input.any_pressed([keybinds.left, KeyCode::ArrowLeft]);
+    let right = keyboard_input.any_pressed([keybinds.right, KeyCode::ArrowRight]);
 
     let horizontal = right as i8 - left as i8;
     let vertical = up as i8 - down as i8;
     let direction = Vector2::new(horizontal as Scalar, vertical as Scalar).clamp_length_max(1.0);
 
-    if direction != Vector2::ZERO {
-        movement_event_writer.write(MovementAction::Move(direction));
-    }
+    for (controller, input) in players {
+        if !input.keyboard_mouse {
+            continue;
+        }
+
+        let mut send = |kind| {
+            movement_event_writer.write(MovementAction { controller, kind });
+        };
+
+        if direction != Vector2::ZERO {
+            send(MovementKind::Move(direction));
+        }
+
+        if keyboard_input.just_pressed(keybinds.jump) {
+            send(MovementKind::Jump);
+        }
+
+        if keyboard_input.just_pressed(keybinds.dash) {
+            send(MovementKind::Dash);
+        }
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        movement_event_writer.write(MovementAction::Jump);
+        if keyboard_input.just_pressed(keybinds.crouch) {
+            send(MovementKind::Crouch);
+        }
+
+        if keyboard_input.just_pressed(keybinds.prone) {
+            send(MovementKind::Prone);
+        }
     }
 }
 
@@ -178,19 +548,70 @@ fn keyboard_input(
 fn gamepad_input(
     mut movement_event_writer: MessageWriter<MovementAction>,
     gamepads: Query<&Gamepad>,
+    players: Query<(Entity, &PlayerInput)>,
 ) {
-    for gamepad in gamepads.iter() {
+    for (controller, input) in players {
+        let Some(gamepad) = input.gamepad(&gamepads) else {
+            continue;
+        };
+
+        let mut send = |kind| {
+            movement_event_writer.write(MovementAction { controller, kind });
+        };
+
         if let (Some(x), Some(y)) = (
             gamepad.get(GamepadAxis::LeftStickX),
             gamepad.get(GamepadAxis::LeftStickY),
         ) {
-            movement_event_writer.write(MovementAction::Move(
+            send(MovementKind::Move(
                 Vector2::new(x as Scalar, y as Scalar).clamp_length_max(1.0),
             ));
         }
 
         if gamepad.just_pressed(GamepadButton::South) {
-            movement_event_writer.write(MovementAction::Jump);
+            send(MovementKind::Jump);
+        }
+
+        if gamepad.just_pressed(GamepadButton::LeftThumb) {
+            send(MovementKind::Dash);
+        }
+
+        if gamepad.just_pressed(GamepadButton::East) {
+            send(MovementKind::Crouch);
+        }
+
+        if gamepad.just_pressed(GamepadButton::DPadRight) {
+            send(MovementKind::Prone);
+        }
+    }
+}
+
+/// Sends [`MovementAction`] events based on the on-screen touch controls, which go with the
+/// keyboard and mouse.
+fn touch_input(
+    mut movement_event_writer: MessageWriter<MovementAction>,
+    touch: Res<TouchControls>,
+    players: Query<(Entity, &PlayerInput)>,
+) {
+    for (controller, input) in players {
+        if !input.keyboard_mouse {
+            continue;
+        }
+
+        let mut send = |kind| {
+            movement_event_writer.write(MovementAction { controller, kind });
+        };
+
+        let stick = touch.stick();
+        if stick != Vec2::ZERO {
+            send(MovementKind::Move(Vector2::new(
+                stick.x as Scalar,
+                stick.y as Scalar,
+            )));
+        }
+
+        if touch.just_pressed(TouchButton::Jump) {
+            send(MovementKind::Jump);
         }
     }
 }
@@ -199,20 +620,27 @@ fn gamepad_input(
 fn update_grounded(
     mut commands: Commands,
     mut query: Query<
-        (Entity, &ShapeHits, &Rotation, Option<&MaxSlopeAngle>),
+        (
+            Entity,
+            &ShapeHits,
+            &Rotation,
+            Option<&MaxSlopeAngle>,
+            Has<Suspended>,
+        ),
         With<CharacterController>,
     >,
 ) {
-    for (entity, hits, rotation, max_slope_angle) in &mut query {
+    for (entity, hits, rotation, max_slope_angle, suspended) in &mut query {
         // The character is grounded if the shape caster has a hit with a normal
         // that isn't too steep.
-        let is_grounded = hits.iter().any(|hit| {
-            if let Some(angle) = max_slope_angle {
-                (rotation * -hit.normal2).angle_between(Vector::Y).abs() <= angle.0
-            } else {
-                true
-            }
-        });
+        let is_grounded = !suspended
+            && hits.iter().any(|hit| {
+                if let Some(angle) = max_slope_angle {
+                    (rotation * -hit.normal2).angle_between(Vector::Y).abs() <= angle.0
+                } else {
+                    true
+                }
+            });
 
         if is_grounded {
             commands.entity(entity).insert(Grounded);
@@ -225,18 +653,37 @@ fn update_grounded(
 fn sprint(
     mut commands: Commands,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    player_query: Query<Entity, With<CharacterController>>,
+    keybinds: Res<Keybinds>,
+    player_query: Query<
+        (
+            Entity,
+            Option<&Stamina>,
+            &PlayerInput,
+            Option<&ActionBuffer>,
+        ),
+        With<CharacterController>,
+    >,
 ) {
-    for entity in player_query {
-        if keyboard_input.just_pressed(KeyCode::ShiftLeft) {
+    for (entity, stamina, input, actions) in player_query {
+        if !input.keyboard_mouse {
+            continue;
+        }
+
+        let exhausted = stamina.is_some_and(Stamina::is_exhausted);
+        // the sprint key holds the breath while aiming
+        let aiming = actions.is_some_and(|actions| actions.pressed(Action::Aim));
+
+        if keyboard_input.just_pressed(keybinds.sprint) && !exhausted && !aiming {
             commands.entity(entity).insert(Sprinting);
-        } else if keyboard_input.just_released(KeyCode::ShiftLeft) {
+        } else if keyboard_input.just_released(keybinds.sprint) {
             commands.entity(entity).remove::<Sprinting>();
         }
     }
 }
 
 type MovementQuery<'a> = (
+    Entity,
+    &'a mut MovementIntent,
     &'a MovementAcceleration,
     &'a SprintFactor,
     &'a JumpImpulse,
@@ -244,49 +691,255 @@ type MovementQuery<'a> = (
     Has<Grounded>,
     Option<&'a Sprinting>,
     &'a Transform,
+    Option<&'a mut Stamina>,
+    Option<&'a Encumbrance>,
+    Option<&'a StatusEffects>,
+    &'a mut AimState,
+    &'a Stance,
 );
 
-/// Responds to [`MovementAction`] events and moves character controllers accordingly.
+/// Gathers this frame's [`MovementAction`] events into each controller's [`MovementIntent`].
+fn collect_movement(
+    mut movement_event_reader: MessageReader<MovementAction>,
+    mut intents: Query<(
+        &mut MovementIntent,
+        &Stance,
+        Has<Grounded>,
+        Has<MovementLocked>,
+    )>,
+) {
+    for (mut intent, ..) in &mut intents {
+        intent.direction = Vector2::ZERO;
+    }
+
+    for event in movement_event_reader.read() {
+        let Ok((mut intent, stance, grounded, false)) = intents.get_mut(event.controller) else {
+            continue;
+        };
+
+        // pressed again before the last press went through, toggles from where it was heading
+        let heading = intent.stance.unwrap_or(*stance);
+
+        match event.kind {
+            MovementKind::Move(direction) => {
+                intent.direction = (intent.direction + direction).clamp_length_max(1.0);
+            }
+            // held until a fixed step acts on them
+            MovementKind::Jump => intent.jump = true,
+            MovementKind::Dash => intent.dash = true,
+            // crouch in the air braces for landing instead, see `glide`
+            MovementKind::Crouch if grounded => {
+                intent.stance = Some(heading.toggled(Stance::Crouched))
+            }
+            MovementKind::Prone if grounded => intent.stance = Some(heading.toggled(Stance::Prone)),
+            MovementKind::Crouch | MovementKind::Prone => {}
+        }
+    }
+}
+
+/// Takes the stance in each character's [`MovementIntent`] by asking for their capsule to be
+/// resized to suit.
+///
+/// The stance is only taken once `resize_capsules` has made room for it. Getting lower always
+/// works, getting taller waits for [`headroom`] with the stance left in their intent: let go of
+/// crouch under a low ceiling and they stand as soon as they're out from under it. Asking for the
+/// stance they're already in calls off a resize still waiting.
+fn change_stance(
+    mut commands: Commands,
+    controllers: Query<(Entity, &mut MovementIntent, &Stance, &StandingCapsule)>,
+) {
+    for (entity, mut intent, stance, standing) in controllers {
+        let Some(next) = intent.stance else {
+            continue;
+        };
+
+        if next == *stance {
+            intent.stance = None;
+            commands.entity(entity).remove::<ResizeCapsule>();
+            continue;
+        }
+
+        // resized by the next system in the chain, still within this step
+        commands.entity(entity).insert(ResizeCapsule {
+            size: next.capsule(standing.0),
+            stance: Some(next),
+        });
+    }
+}
+
+/// Whether there's `growth` clear above a character's `capsule` for it to get taller.
+///
+/// Only the space the capsule would grow into is checked, by casting a ball the width of its top
+/// upwards. The whole capsule would hit the floor it's standing on straight away. The ball's a
+/// little narrower so a wall the character's leaning on doesn't count.
+fn headroom(
+    spatial_query: &SpatialQuery,
+    entity: Entity,
+    capsule: &CapsuleSize,
+    transform: &Transform,
+    growth: Scalar,
+) -> bool {
+    let top = transform.translation + Vector::Y * capsule.length / 2.0;
+
+    spatial_query
+        .cast_shape(
+            &Collider::sphere(capsule.radius * 0.9),
+            top,
+            Quaternion::IDENTITY,
+            Dir3::Y,
+            &ShapeCastConfig::from_max_distance(growth),
+            &SpatialQueryFilter::from_excluded_entities([entity]),
+        )
+        .is_none()
+}
+
+/// Swaps character controllers' capsules for the size they've asked for, once there's
+/// [`headroom`] for it, and takes the stance that goes with it.
+fn resize_capsules(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    mut controllers: Query<(
+        Entity,
+        &ResizeCapsule,
+        &mut CapsuleSize,
+        &mut Collider,
+        &mut ShapeCaster,
+        Option<&mut CenterOfMass>,
+        &mut Transform,
+        Option<(&mut Stance, &mut MovementIntent)>,
+    )>,
+) {
+    for (
+        entity,
+        resize,
+        mut capsule,
+        mut collider,
+        mut caster,
+        center_of_mass,
+        mut transform,
+        stance,
+    ) in &mut controllers
+    {
+        let growth = resize.size.height() - capsule.height();
+
+        if growth > 0.0 && !headroom(&spatial_query, entity, &capsule, &transform, growth) {
+            continue;
+        }
+
+        let resized = resize.size.collider();
+        *caster = ground_caster(&resized);
+        *collider = resized;
+        *capsule = resize.size;
+        // the capsule grows and shrinks about its centre, keep the feet where they were
+        transform.translation += Vector::Y * growth / 2.0;
+
+        // a set centre of mass would otherwise stay where the old capsule's middle was
+        if let Some(mut center_of_mass) = center_of_mass {
+            center_of_mass.0 = Vector::ZERO;
+        }
+
+        if let Some(next) = resize.stance
+            && let Some((mut stance, mut intent)) = stance
+        {
+            debug!("{entity} {:?} to {next:?}", *stance);
+            *stance = next;
+            if intent.stance == Some(next) {
+                intent.stance = None;
+            }
+        }
+
+        commands.entity(entity).remove::<ResizeCapsule>();
+    }
+}
+
+/// Moves character controllers by their [`MovementIntent`].
 fn movement(
     time: Res<Time>,
-    mut movement_event_reader: MessageReader<MovementAction>,
-    mut controllers: Query<MovementQuery>,
+    energy_costs: Res<EnergyCosts>,
+    mut particle_writer: MessageWriter<SpawnParticles>,
+    mut controllers: Query<MovementQuery, Without<Suspended>>,
 ) {
     // Precision is adjusted so that the example works with
     // both the `f32` and `f64` features. Otherwise you don't need this.
     let delta_time = time.delta_secs();
 
-    for event in movement_event_reader.read() {
-        for (
-            movement_acceleration,
-            sprint_factor,
-            jump_impulse,
-            mut linear_velocity,
-            is_grounded,
-            maybe_sprinting,
-            transform,
-        ) in &mut controllers
-        {
-            match event {
-                MovementAction::Move(direction) => {
-                    let rotated_direction =
-                        transform
-                            .rotation
-                            .mul_vec3(Vec3::new(direction.x, 0., -direction.y));
+    for (
+        entity,
+        mut intent,
+        movement_acceleration,
+        sprint_factor,
+        jump_impulse,
+        mut linear_velocity,
+        is_grounded,
+        maybe_sprinting,
+        transform,
+        mut stamina,
+        encumbrance,
+        effects,
+        mut aim,
+        stance,
+    ) in &mut controllers
+    {
+        let _span = trace_span!("move", %entity).entered();
 
-                    let mut accel = movement_acceleration.0;
+        // weighed before the jump's own cost comes off
+        let jump_scale = encumbrance.map_or(1.0, Encumbrance::jump_scale)
+            * stamina.as_deref().map_or(1.0, Stamina::jump_scale);
 
-                    if maybe_sprinting.is_some() {
-                        accel *= sprint_factor.0;
-                    }
+        // characters without stamina act for free
+        let mut spend = |action| {
+            stamina
+                .as_mut()
+                .is_none_or(|stamina| stamina.try_spend(energy_costs.get(action)))
+        };
 
-                    linear_velocity.x += rotated_direction.x * accel * delta_time;
-                    linear_velocity.z += rotated_direction.z * accel * delta_time;
-                }
-                MovementAction::Jump => {
-                    if is_grounded {
-                        linear_velocity.y = jump_impulse.0;
-                    }
+        aim.strafe = intent.direction.x;
+
+        if intent.direction != Vector2::ZERO {
+            let direction = intent.direction;
+            let rotated_direction =
+                transform
+                    .rotation
+                    .mul_vec3(Vec3::new(direction.x, 0., -direction.y));
+
+            let mut accel = movement_acceleration.0
+                * encumbrance.map_or(1.0, Encumbrance::speed_scale)
+                * effects.map_or(1.0, |effects| effects.modifiers().speed)
+                * aim.acceleration_scale()
+                * stance.speed_scale();
+
+            if maybe_sprinting.is_some() {
+                accel *= sprint_factor.0;
+            }
+
+            linear_velocity.x += rotated_direction.x * accel * delta_time;
+            linear_velocity.z += rotated_direction.z * accel * delta_time;
+        }
+
+        if std::mem::take(&mut intent.jump) && is_grounded && spend(EnergyAction::Jump) {
+            linear_velocity.y = jump_impulse.0 * jump_scale;
+        }
+
+        if std::mem::take(&mut intent.dash) {
+            let horizontal = Vector::new(linear_velocity.x, 0.0, linear_velocity.z);
+
+            // dash the way we're moving, or forwards from a standstill
+            let direction = horizontal
+                .try_normalize()
+                .unwrap_or_else(|| transform.forward().as_vec3());
+
+            if spend(EnergyAction::Dash) {
+                linear_velocity.x += direction.x * DASH_IMPULSE;
+                linear_velocity.z += direction.z * DASH_IMPULSE;
+
+                // kick up dust behind our feet
+                if is_grounded {
+                    particle_writer.write(SpawnParticles {
+                        effect: ParticleEffect::Dust,
+                        position: transform.translation - Vec3::Y * 1.4,
+                        direction: -direction,
+                        count: 12,
+                    });
                 }
             }
         }
@@ -294,10 +947,165 @@ fn movement(
 }
 
 /// Slows down movement in the XZ plane.
-fn apply_movement_damping(mut query: Query<(&MovementDampingFactor, &mut LinearVelocity)>) {
-    for (damping_factor, mut linear_velocity) in &mut query {
+pub fn apply_movement_damping(
+    time: Res<Time>,
+    mut query: Query<(&MovementDampingFactor, &mut LinearVelocity), Without<Suspended>>,
+) {
+    let delta_time = time.delta_secs();
+
+    for (damping, mut linear_velocity) in &mut query {
+        let decay = damping.decay(delta_time);
+
         // We could use `LinearDamping`, but we don't want to dampen movement along the Y axis
-        linear_velocity.x *= damping_factor.0;
-        linear_velocity.z *= damping_factor.0;
+        linear_velocity.x *= decay;
+        linear_velocity.z *= decay;
+    }
+}
+
+/// Eases each player's camera down or up to the height of their stance, see [`EyeDrop`].
+pub fn lower_eyes(
+    time: Res<Time>,
+    players: Query<(&CapsuleSize, &StandingCapsule)>,
+    cameras: Query<(&ChildOf, &mut EyeDrop, &mut TransformPipeline), With<PlayerCamera>>,
+) {
+    let settle = 1.0 - (-EyeDrop::SPEED * time.delta_secs()).exp();
+
+    for (child_of, mut drop, mut pipeline) in cameras {
+        let Ok((capsule, standing)) = players.get(child_of.parent()) else {
+            continue;
+        };
+
+        let shrink = standing.0.height() - capsule.height();
+        drop.0 += (shrink - drop.0) * settle;
+
+        // the camera is placed for standing, and rides the capsule's centre, which has already
+        // come down half of the shrink
+        pipeline.queue(Vec3::Y * (shrink / 2.0 - drop.0));
+    }
+}
+
+/// Bobs each player's camera in step with their feet, as much as
+/// [`GameSettings::view_motion`] allows, see [`HeadBob`].
+pub fn bob_heads(
+    time: Res<Time>,
+    tuning: Res<CameraTuning>,
+    settings: Res<GameSettings>,
+    players: Query<(&LinearVelocity, Has<Grounded>, Has<Sprinting>)>,
+    cameras: Query<(&ChildOf, &mut HeadBob, &mut TransformPipeline), With<PlayerCamera>>,
+) {
+    let view_motion = settings.view_motion.clamp(0.0, 1.0);
+
+    for (child_of, mut bob, mut pipeline) in cameras {
+        let Ok((velocity, grounded, sprinting)) = players.get(child_of.parent()) else {
+            continue;
+        };
+
+        let offset = bob.advance(
+            &tuning.bob,
+            velocity.0.xz().length(),
+            grounded,
+            sprinting,
+            time.delta_secs(),
+        );
+        pipeline.queue(offset * view_motion);
+    }
+}
+
+/// Moves each player's weapon by [`HeadBobTuning::weapon`] of their view's bob, on top of being
+/// carried along with the camera.
+pub fn bob_weapons(
+    tuning: Res<CameraTuning>,
+    settings: Res<GameSettings>,
+    cameras: Query<(&HeadBob, &Children), With<PlayerCamera>>,
+    mut weapons: Query<&mut TransformPipeline, (With<PlayerWeapon>, With<WeaponActive>)>,
+) {
+    let scale = tuning.bob.weapon * settings.view_motion.clamp(0.0, 1.0);
+
+    for (bob, children) in cameras {
+        for child in children {
+            if let Ok(mut pipeline) = weapons.get_mut(*child) {
+                pipeline.queue(bob.offset * scale);
+            }
+        }
+    }
+}
+
+/// Leans each player with their lean keys held, rolling and shifting their camera, and the weapon
+/// it holds, out to the side.
+///
+/// The lean goes through the camera's pipeline like breathing does, so it's swapped in on top of
+/// wherever the player's looking rather than fighting the look for the camera's rotation.
+pub fn lean_cameras(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    offsets: Res<LeanOffsets>,
+    mut players: Query<(&PlayerInput, &mut Lean)>,
+    cameras: Query<(&ChildOf, &mut TransformPipeline), With<PlayerCamera>>,
+) {
+    for (child_of, mut pipeline) in cameras {
+        let Ok((input, mut lean)) = players.get_mut(child_of.parent()) else {
+            continue;
+        };
+
+        let held = |key| input.keyboard_mouse && keys.pressed(key);
+        let target = match (held(keybinds.lean_left), held(keybinds.lean_right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+
+        let step = Lean::SPEED * time.delta_secs();
+        lean.0 += (target - lean.0).clamp(-step, step);
+
+        if lean.0 == 0.0 {
+            continue;
+        }
+
+        let out = offsets.at(lean.eased());
+        pipeline.queue(out.translation);
+        pipeline.queue_rotation(out.rotation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{RATES, simulate};
+
+    #[test]
+    fn damping_takes_the_same_time_at_any_rate() {
+        let damping = MovementDampingFactor { half_life: 0.1 };
+
+        let speeds = RATES.map(|rate| {
+            let mut speed = 8.0;
+            simulate(rate, 0.3, |delta| speed *= damping.decay(delta));
+            speed
+        });
+
+        for speed in speeds {
+            assert!((speed - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn the_view_bobs_harder_the_faster_the_player_goes() {
+        let tuning = crate::tuning::HeadBobTuning::default();
+        let biggest_bob = |speed, sprinting| {
+            let mut bob = HeadBob::default();
+            let mut biggest = 0.0_f32;
+            simulate(60.0, 2.0, |delta| {
+                let offset = bob.advance(&tuning, speed, true, sprinting, delta);
+                biggest = biggest.max(offset.length());
+            });
+            biggest
+        };
+
+        let walking = biggest_bob(2.0, false);
+        let running = biggest_bob(5.0, false);
+        let sprinting = biggest_bob(5.0, true);
+
+        assert_eq!(biggest_bob(0.0, false), 0.0);
+        assert!(0.0 < walking && walking < running && running < sprinting);
     }
 }
@@ -0,0 +1,390 @@
+//! Simple CPU particles for breath vapour, muzzle smoke and dust.
+//!
+//! Particles are small unlit spheres that drift, grow and fade out according to their
+//! [`ParticleEffect`]. Bursts are requested with a [`SpawnParticles`] event, and a
+//! [`ParticleEmitter`] keeps emitting from an entity at whatever rate it is set to. Finished
+//! particles are hidden and kept in a pool for reuse rather than despawned, so heavy use doesn't
+//! churn entities. Every particle shares one mesh, and fades out by stepping through a few shared
+//! materials for its effect rather than having its own, so however many there are they're drawn
+//! instanced in a handful of batches.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use rand::Rng;
+
+use crate::ShotFired;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<SpawnParticles>()
+            .init_resource::<ParticlePool>()
+            .init_resource::<ParticleBudget>()
+            .add_systems(Startup, setup_particle_assets)
+            .add_systems(
+                Update,
+                (
+                    heat_muzzles,
+                    run_emitters,
+                    spawn_particles,
+                    update_particles,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// How many particles can be alive at once, further requests are dropped.
+#[derive(Resource, Debug)]
+pub struct ParticleBudget {
+    pub max: usize,
+}
+
+impl ParticleBudget {
+    /// The budget with nothing cutting it down
+    pub const FULL: usize = 512;
+}
+
+impl Default for ParticleBudget {
+    fn default() -> Self {
+        Self { max: Self::FULL }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticleEffect {
+    BreathVapor,
+    MuzzleSmoke,
+    Dust,
+}
+
+/// How an effect's particles look and move.
+struct ParticleStyle {
+    color: Color,
+    /// Radius when spawned
+    size: f32,
+    /// How many times bigger a particle is at the end of its life
+    growth: f32,
+    lifetime: f32,
+    speed: f32,
+    /// Sideways randomness, as a fraction of `speed`
+    spread: f32,
+    /// Fraction of velocity lost per second
+    drag: f32,
+    /// Upward acceleration, negative to fall
+    rise: f32,
+}
+
+impl ParticleEffect {
+    const ALL: [ParticleEffect; 3] = [
+        ParticleEffect::BreathVapor,
+        ParticleEffect::MuzzleSmoke,
+        ParticleEffect::Dust,
+    ];
+
+    fn style(&self) -> ParticleStyle {
+        match self {
+            ParticleEffect::BreathVapor => ParticleStyle {
+                color: Color::srgba(0.95, 0.97, 1.0, 0.35),
+                size: 0.04,
+                growth: 3.0,
+                lifetime: 1.2,
+                speed: 0.4,
+                spread: 0.4,
+                drag: 1.5,
+                rise: 0.05,
+            },
+            ParticleEffect::MuzzleSmoke => ParticleStyle {
+                color: Color::srgba(0.7, 0.7, 0.7, 0.25),
+                size: 0.03,
+                growth: 5.0,
+                lifetime: 2.5,
+                speed: 0.15,
+                spread: 1.0,
+                drag: 0.8,
+                rise: 0.25,
+            },
+            ParticleEffect::Dust => ParticleStyle {
+                color: Color::srgba(0.6, 0.55, 0.45, 0.4),
+                size: 0.08,
+                growth: 4.0,
+                lifetime: 0.9,
+                speed: 2.0,
+                spread: 0.8,
+                drag: 3.0,
+                rise: -0.5,
+            },
+        }
+    }
+}
+
+/// An event asking for a burst of particles.
+#[derive(Message, Debug, Clone)]
+pub struct SpawnParticles {
+    pub effect: ParticleEffect,
+    pub position: Vec3,
+    /// The way the particles head off, before spread
+    pub direction: Vec3,
+    pub count: usize,
+}
+
+/// Emits particles upwards from the entity's position at `rate` per second.
+#[derive(Component, Debug)]
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    pub rate: f32,
+    /// Fractions of a particle carried over between frames
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: ParticleEffect) -> Self {
+        Self {
+            effect,
+            rate: 0.0,
+            pending: 0.0,
+        }
+    }
+}
+
+/// Builds up with sustained fire and makes the weapon's [`ParticleEmitter`] smoke.
+///
+/// Shots count if they're fired by the entity itself or, for an emitter anchored to a socket, by
+/// the weapon it's parented to.
+#[derive(Component, Debug, Default)]
+pub struct MuzzleHeat(f32);
+
+impl MuzzleHeat {
+    /// Heat added by each shot
+    const PER_SHOT: f32 = 1.0;
+    /// Heat lost per second
+    const COOLING: f32 = 0.6;
+    /// Heat before the muzzle starts smoking
+    const SMOKE_THRESHOLD: f32 = 4.0;
+    /// Particles per second for each point of heat over the threshold
+    const SMOKE_RATE: f32 = 3.0;
+    const MAX: f32 = 15.0;
+}
+
+#[derive(Component)]
+struct Particle {
+    effect: ParticleEffect,
+    velocity: Vec3,
+    age: f32,
+    active: bool,
+}
+
+/// Particles that have finished and are waiting to be reused.
+#[derive(Resource, Default)]
+struct ParticlePool {
+    free: Vec<Entity>,
+    /// Every particle entity, active or not
+    total: usize,
+}
+
+#[derive(Resource)]
+struct ParticleAssets {
+    /// A unit sphere, scaled to each effect's size
+    mesh: Handle<Mesh>,
+    /// Each effect's colour at every step of fading out, from fully there to nearly gone
+    fades: HashMap<ParticleEffect, Vec<Handle<StandardMaterial>>>,
+}
+
+impl ParticleAssets {
+    /// Materials each effect fades out through
+    const FADE_STEPS: usize = 8;
+
+    /// The material for a particle of `effect` that's `life` of the way through its lifetime
+    fn fade(&self, effect: ParticleEffect, life: f32) -> &Handle<StandardMaterial> {
+        let step = ((life * Self::FADE_STEPS as f32) as usize).min(Self::FADE_STEPS - 1);
+        &self.fades[&effect][step]
+    }
+}
+
+fn setup_particle_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let fades = ParticleEffect::ALL
+        .into_iter()
+        .map(|effect| {
+            let color = effect.style().color;
+            let steps = (0..ParticleAssets::FADE_STEPS)
+                .map(|step| {
+                    let left = 1.0 - step as f32 / ParticleAssets::FADE_STEPS as f32;
+
+                    materials.add(StandardMaterial {
+                        base_color: color.with_alpha(color.alpha() * left),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    })
+                })
+                .collect();
+
+            (effect, steps)
+        })
+        .collect();
+
+    commands.insert_resource(ParticleAssets {
+        mesh: meshes.add(Sphere::new(1.0)),
+        fades,
+    });
+}
+
+fn heat_muzzles(
+    time: Res<Time>,
+    mut shot_reader: MessageReader<ShotFired>,
+    weapons: Query<(
+        Entity,
+        Option<&ChildOf>,
+        &mut MuzzleHeat,
+        &mut ParticleEmitter,
+    )>,
+) {
+    let fired: Vec<_> = shot_reader.read().map(|shot| shot.weapon).collect();
+
+    for (muzzle, parent, mut heat, mut emitter) in weapons {
+        let weapon = parent.map(ChildOf::parent);
+        let shots = fired
+            .iter()
+            .filter(|fired| **fired == muzzle || Some(**fired) == weapon)
+            .count() as f32;
+
+        heat.0 = (heat.0 + shots * MuzzleHeat::PER_SHOT - MuzzleHeat::COOLING * time.delta_secs())
+            .clamp(0.0, MuzzleHeat::MAX);
+
+        emitter.rate = (heat.0 - MuzzleHeat::SMOKE_THRESHOLD).max(0.0) * MuzzleHeat::SMOKE_RATE;
+    }
+}
+
+fn run_emitters(
+    time: Res<Time>,
+    mut particle_writer: MessageWriter<SpawnParticles>,
+    emitters: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+) {
+    for (mut emitter, transform) in emitters {
+        emitter.pending += emitter.rate * time.delta_secs();
+
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+
+        if count > 0.0 {
+            particle_writer.write(SpawnParticles {
+                effect: emitter.effect,
+                position: transform.translation(),
+                direction: Vec3::Y,
+                count: count as usize,
+            });
+        }
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    mut particle_reader: MessageReader<SpawnParticles>,
+    mut pool: ResMut<ParticlePool>,
+    budget: Res<ParticleBudget>,
+    assets: Res<ParticleAssets>,
+    mut particles: Query<(
+        &mut Particle,
+        &mut Transform,
+        &mut Visibility,
+        &mut MeshMaterial3d<StandardMaterial>,
+    )>,
+) {
+    let mut rng = rand::rng();
+
+    for request in particle_reader.read() {
+        let style = request.effect.style();
+        let direction = request.direction.normalize_or(Vec3::Y);
+
+        for _ in 0..request.count {
+            // pooled particles count too, so a shrunk budget holds even with plenty parked
+            if pool.total - pool.free.len() >= budget.max {
+                break;
+            }
+
+            let jitter = Vec3::new(
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+                rng.random_range(-1.0..1.0),
+            ) * style.spread;
+
+            let particle = Particle {
+                effect: request.effect,
+                velocity: (direction + jitter) * style.speed,
+                age: 0.0,
+                active: true,
+            };
+            let transform =
+                Transform::from_translation(request.position).with_scale(Vec3::splat(style.size));
+
+            let material = assets.fade(request.effect, 0.0);
+
+            if let Some(entity) = pool.free.pop()
+                && let Ok((mut pooled, mut pooled_transform, mut visibility, mut pooled_material)) =
+                    particles.get_mut(entity)
+            {
+                *pooled = particle;
+                *pooled_transform = transform;
+                *visibility = Visibility::Inherited;
+                pooled_material.0 = material.clone();
+            } else {
+                pool.total += 1;
+
+                commands.spawn((
+                    Mesh3d(assets.mesh.clone()),
+                    MeshMaterial3d(material.clone()),
+                    transform,
+                    particle,
+                ));
+            }
+        }
+    }
+}
+
+fn update_particles(
+    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    assets: Res<ParticleAssets>,
+    particles: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Visibility,
+        &mut MeshMaterial3d<StandardMaterial>,
+    )>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut visibility, mut material) in particles {
+        if !particle.active {
+            continue;
+        }
+
+        let style = particle.effect.style();
+        particle.age += delta;
+
+        if particle.age >= style.lifetime {
+            particle.active = false;
+            *visibility = Visibility::Hidden;
+            pool.free.push(entity);
+            continue;
+        }
+
+        let life = particle.age / style.lifetime;
+
+        particle.velocity *= 1.0 - (style.drag * delta).min(1.0);
+        particle.velocity.y += style.rise * delta;
+        transform.translation += particle.velocity * delta;
+        transform.scale = Vec3::splat(style.size * (1.0 + life * style.growth));
+
+        // only swapped when it steps, so it isn't re-extracted every frame in between
+        let fade = assets.fade(particle.effect, life);
+        if material.0.id() != fade.id() {
+            material.0 = fade.clone();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Pinging the spot the player is looking at.
+//!
+//! Middle-click casts a ray from the camera and sends a [`PingPlaced`] event for the point it
+//! hits. Each ping is drawn as a beacon in the world with a distance readout on screen, and goes
+//! away after a few seconds. A player only has one ping at a time, placing another moves it.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::PlayerCamera;
+use crate::hud::HudTheme;
+use crate::menu::GameState;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PingPlaced>().add_systems(
+            Update,
+            (
+                ping_input.run_if(in_state(GameState::InGame)),
+                spawn_pings,
+                (expire_pings, update_ping_labels),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// How far a ping can be placed
+const PING_RANGE: f32 = 200.0;
+/// Seconds a ping stays up
+const PING_LIFETIME: f32 = 8.0;
+const BEACON_HEIGHT: f32 = 3.0;
+
+/// An event sent when someone pings a point in the world.
+#[derive(Message, Debug, Clone)]
+pub struct PingPlaced {
+    pub owner: Entity,
+    pub position: Vec3,
+}
+
+/// A beacon marking a pinged point.
+#[derive(Component)]
+struct Ping {
+    owner: Entity,
+    timer: Timer,
+}
+
+/// The on-screen distance readout for a [`Ping`].
+#[derive(Component)]
+struct PingLabel(Entity);
+
+fn ping_input(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    spatial_query: SpatialQuery,
+    mut ping_writer: MessageWriter<PingPlaced>,
+    player: Single<(Entity, &Children), With<PrimaryPlayer>>,
+    cameras: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Middle) {
+        return;
+    }
+
+    let (player, children) = *player;
+    let Some(camera) = children.iter().find_map(|child| cameras.get(child).ok()) else {
+        return;
+    };
+
+    if let Some(position) = crosshair_point(&spatial_query, player, camera) {
+        ping_writer.write(PingPlaced {
+            owner: player,
+            position,
+        });
+    }
+}
+
+/// The point `player` is looking at through `camera`, if it's close enough to ping.
+pub fn crosshair_point(
+    spatial_query: &SpatialQuery,
+    player: Entity,
+    camera: &GlobalTransform,
+) -> Option<Vec3> {
+    let filter = SpatialQueryFilter::default().with_excluded_entities([player]);
+
+    let hit = spatial_query.cast_ray(
+        camera.translation(),
+        camera.forward(),
+        PING_RANGE,
+        true,
+        &filter,
+    )?;
+
+    Some(camera.translation() + camera.forward() * hit.distance)
+}
+
+fn spawn_pings(
+    mut commands: Commands,
+    mut ping_reader: MessageReader<PingPlaced>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<HudTheme>,
+    pings: Query<(Entity, &Ping)>,
+) {
+    for placed in ping_reader.read() {
+        // replace the owner's previous ping, its label is cleaned up by `update_ping_labels`
+        for (entity, ping) in &pings {
+            if ping.owner == placed.owner {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        let ping = commands
+            .spawn((
+                Mesh3d(meshes.add(Cylinder::new(0.05, BEACON_HEIGHT))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: theme.objective,
+                    emissive: theme.objective.to_linear() * 4.0,
+                    unlit: true,
+                    ..default()
+                })),
+                Transform::from_translation(placed.position + Vec3::Y * BEACON_HEIGHT / 2.0),
+                Ping {
+                    owner: placed.owner,
+                    timer: Timer::from_seconds(PING_LIFETIME, TimerMode::Once),
+                },
+                DespawnOnExit(GameState::InGame),
+            ))
+            .id();
+
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Text::default(),
+            TextFont::from_font_size(theme.small_font_size),
+            TextColor(theme.objective),
+            PingLabel(ping),
+        ));
+    }
+}
+
+fn expire_pings(mut commands: Commands, time: Res<Time>, pings: Query<(Entity, &mut Ping)>) {
+    for (entity, mut ping) in pings {
+        if ping.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn update_ping_labels(
+    mut commands: Commands,
+    player: Single<&Children, With<PrimaryPlayer>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    pings: Query<&GlobalTransform, With<Ping>>,
+    labels: Query<(Entity, &PingLabel, &mut Node, &mut Text, &mut Visibility)>,
+) {
+    // the labels are part of the shared HUD, so they're placed in player one's view
+    let Some((camera, camera_transform)) = player.iter().find_map(|child| cameras.get(child).ok())
+    else {
+        return;
+    };
+
+    for (entity, label, mut node, mut text, mut visibility) in labels {
+        let Ok(ping) = pings.get(label.0) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        // labels sit at the top of the beacon, hidden when it's behind the camera
+        let top = ping.translation() + Vec3::Y * BEACON_HEIGHT / 2.0;
+
+        let Ok(screen) = camera.world_to_viewport(camera_transform, top) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let distance = ping.translation().distance(camera_transform.translation());
+
+        *visibility = Visibility::Inherited;
+        node.left = Val::Px(screen.x);
+        node.top = Val::Px(screen.y);
+        text.0 = format!("{distance:.0}m");
+    }
+}
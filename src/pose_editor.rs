@@ -0,0 +1,232 @@
+//! Tweaking where a weapon sits on screen while playing.
+//!
+//! `pose hip`, `pose aim` or `pose sprint` in the console holds the keyboard player's weapon in
+//! that stance, and the numpad then nudges it: 4/6 left and right, 8/2 up and down, 7/9 forwards
+//! and back. Holding numpad 0 turns it instead, pitching with 8/2, yawing with 4/6 and rolling
+//! with 7/9, and holding numpad `.` slows everything down for fine adjustments. `pose save`
+//! writes the poses back into the weapon's `*.weapon.ron`, and `pose off` lets go of the weapon.
+//!
+//! Weapons whose model has a sight socket work out where to sit when aiming themselves, so only
+//! their aiming rotation can be tweaked.
+
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput, console_closed};
+use crate::dual_wield::WeaponHand;
+use crate::split_screen::PlayerInput;
+use crate::storage::write_atomic;
+use crate::weapon::{WeaponDef, WeaponDefHandle};
+use crate::{
+    HeldStance, PlayerWeapon, PlayerWeaponTransformConfig, SwayTarget, TransformPipeline,
+    WeaponActive, WeaponStance,
+};
+
+pub struct PoseEditorPlugin;
+
+impl Plugin for PoseEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PoseEditor>()
+            .add_console_command("pose", "pose <hip|aim|sprint|off|save> - edit weapon poses")
+            .add_systems(
+                Update,
+                (
+                    pose_command,
+                    nudge_pose
+                        .run_if(console_closed)
+                        .run_if(crate::chat::chat_closed),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The stance being edited, if any.
+#[derive(Resource, Default, Debug)]
+struct PoseEditor {
+    stance: Option<WeaponStance>,
+}
+
+impl PoseEditor {
+    /// Metres moved per second
+    const MOVE_SPEED: f32 = 0.05;
+    /// Degrees turned per second
+    const TURN_SPEED: f32 = 30.0;
+    /// Scales both speeds while fine adjusting
+    const FINE: f32 = 0.1;
+}
+
+type EditedWeapon = (
+    Entity,
+    &'static mut PlayerWeaponTransformConfig,
+    &'static mut TransformPipeline,
+    &'static WeaponDefHandle,
+    &'static SwayTarget,
+);
+
+/// Dual wielded weapons hold mirrored copies of the poses, so they're left alone
+type EditableWeapons = (With<PlayerWeapon>, With<WeaponActive>, Without<WeaponHand>);
+
+/// The active weapon of whoever is on the keyboard.
+fn keyboard_weapon(
+    weapons: &Query<EditedWeapon, EditableWeapons>,
+    inputs: &Query<&PlayerInput>,
+) -> Option<Entity> {
+    weapons.iter().find_map(|(weapon, .., owner)| {
+        inputs
+            .get(owner.0)
+            .is_ok_and(|input| input.keyboard_mouse)
+            .then_some(weapon)
+    })
+}
+
+fn pose_command(
+    mut commands: Commands,
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    mut editor: ResMut<PoseEditor>,
+    weapons: Query<EditedWeapon, EditableWeapons>,
+    inputs: Query<&PlayerInput>,
+    asset_server: Res<AssetServer>,
+    defs: Res<Assets<WeaponDef>>,
+) {
+    for command in command_reader.read() {
+        if !command.is("pose") {
+            continue;
+        }
+
+        let Some((weapon, config, _, handle, _)) =
+            keyboard_weapon(&weapons, &inputs).and_then(|weapon| weapons.get(weapon).ok())
+        else {
+            output_writer.write(ConsoleOutput("no weapon to pose".to_string()));
+            continue;
+        };
+
+        let stance = match command.arg(0) {
+            None => {
+                let stances = [WeaponStance::Hip, WeaponStance::Aim, WeaponStance::Sprint];
+                for stance in stances {
+                    let pose = config.pose(stance);
+                    let (x, y, z) = pose.rotation.to_euler(EulerRot::XYZ);
+                    output_writer.write(ConsoleOutput(format!(
+                        "{stance:?}: {:.3} rotated ({:.1}, {:.1}, {:.1})",
+                        pose.translation,
+                        x.to_degrees(),
+                        y.to_degrees(),
+                        z.to_degrees()
+                    )));
+                }
+                continue;
+            }
+            Some("hip") => WeaponStance::Hip,
+            Some("aim") => WeaponStance::Aim,
+            Some("sprint") => WeaponStance::Sprint,
+            Some("off") => {
+                editor.stance = None;
+                commands.entity(weapon).remove::<HeldStance>();
+                output_writer.write(ConsoleOutput("pose editing off".to_string()));
+                continue;
+            }
+            Some("save") => {
+                output_writer.write(ConsoleOutput(save_poses(
+                    config,
+                    handle,
+                    &asset_server,
+                    &defs,
+                )));
+                continue;
+            }
+            Some(other) => {
+                output_writer.write(ConsoleOutput(format!("unknown stance '{other}'")));
+                continue;
+            }
+        };
+
+        editor.stance = Some(stance);
+        commands.entity(weapon).insert(HeldStance(stance));
+        output_writer.write(ConsoleOutput(format!("editing the {stance:?} pose")));
+    }
+}
+
+/// Writes the weapon's poses into its definition file, reporting how it went.
+fn save_poses(
+    config: &PlayerWeaponTransformConfig,
+    handle: &WeaponDefHandle,
+    asset_server: &AssetServer,
+    defs: &Assets<WeaponDef>,
+) -> String {
+    let (Some(def), Some(asset_path)) = (defs.get(&handle.0), asset_server.get_path(&handle.0))
+    else {
+        return "the weapon's definition isn't loaded".to_string();
+    };
+
+    let mut def = def.clone();
+    def.set_poses(config);
+
+    let path = Path::new("assets").join(asset_path.path());
+    let saved = ron::ser::to_string_pretty(&def, ron::ser::PrettyConfig::default())
+        .map_err(std::io::Error::other)
+        .and_then(|serialized| write_atomic(&path, serialized.as_bytes()));
+
+    match saved {
+        Ok(()) => format!("saved {}'s poses to {}", def.name, path.display()),
+        Err(err) => format!("could not save {}: {err}", path.display()),
+    }
+}
+
+fn nudge_pose(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    editor: Res<PoseEditor>,
+    mut weapons: Query<EditedWeapon, EditableWeapons>,
+    inputs: Query<&PlayerInput>,
+) {
+    let Some(stance) = editor.stance else {
+        return;
+    };
+
+    let axis = |negative, positive| {
+        keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+    };
+    let input = Vec3::new(
+        axis(KeyCode::Numpad4, KeyCode::Numpad6),
+        axis(KeyCode::Numpad2, KeyCode::Numpad8),
+        axis(KeyCode::Numpad7, KeyCode::Numpad9),
+    );
+
+    if input == Vec3::ZERO {
+        return;
+    }
+
+    let Some((_, mut config, mut pipeline, ..)) =
+        keyboard_weapon(&weapons, &inputs).and_then(|weapon| weapons.get_mut(weapon).ok())
+    else {
+        return;
+    };
+
+    let fine = if keys.pressed(KeyCode::NumpadDecimal) {
+        PoseEditor::FINE
+    } else {
+        1.0
+    };
+    let delta = time.delta_secs() * fine;
+    let pose = config.pose_mut(stance);
+
+    if keys.pressed(KeyCode::Numpad0) {
+        let turn = (PoseEditor::TURN_SPEED * delta).to_radians();
+        // pitch up on 8, yaw left on 4 and roll left on 7
+        let (pitch, yaw, roll) = (input.y * turn, -input.x * turn, -input.z * turn);
+        pose.rotation =
+            (pose.rotation * Quat::from_euler(EulerRot::XYZ, pitch, yaw, roll)).normalize();
+    } else {
+        pose.translation += input * PoseEditor::MOVE_SPEED * delta;
+    }
+
+    // keeping the sight on the eye line as it turns
+    config.align_sight();
+
+    if stance == WeaponStance::Hip {
+        pipeline.base_translation = config.hip.translation;
+    }
+}
@@ -0,0 +1,332 @@
+//! Named player profiles.
+//!
+//! A [`Profile`] holds everything that belongs to one player rather than to the install: their
+//! settings, keybinds, loadout, lifetime stats, attributes, unlocks and challenge progress. Each
+//! profile lives in its own file under `saves/profiles/`, and the one picked on the profile select
+//! screen becomes the [`ActiveProfile`] for the rest of the session.
+//!
+//! Saves are written with [`write_atomic`], so a crash or power cut mid-write leaves the previous
+//! save intact instead of a truncated one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::ShotFired;
+use crate::attributes::Attributes;
+use crate::daily::DailyRun;
+use crate::damage::{DamageEvent, HitZone};
+use crate::difficulty::Difficulty;
+use crate::loadout::Loadout;
+use crate::menu::{GameState, MenuScreen};
+use crate::settings::{GameSettings, Keybinds};
+use crate::split_screen::PrimaryPlayer;
+use crate::storage::{self, write_atomic};
+
+const PROFILE_DIR: &str = "saves/profiles";
+/// Seconds between saves while the profile is changing
+const SAVE_INTERVAL: f32 = 30.0;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ProfileSaves {
+            timer: Timer::from_seconds(SAVE_INTERVAL, TimerMode::Repeating),
+            unsaved: false,
+        })
+        .add_systems(
+            Update,
+            (
+                apply_profile,
+                // a daily run's standard settings aren't the profile's own
+                record_settings.run_if(not(resource_exists::<DailyRun>)),
+                (
+                    record_attributes.run_if(not(resource_exists::<DailyRun>)),
+                    record_stats,
+                )
+                    .run_if(in_state(GameState::InGame)),
+                save_profile,
+            )
+                .chain()
+                .run_if(resource_exists::<ActiveProfile>),
+        )
+        // so nothing is lost when leaving a game or switching to another profile
+        .add_systems(OnExit(GameState::InGame), flush_profile)
+        .add_systems(
+            OnEnter(MenuScreen::Profiles),
+            flush_profile.run_if(resource_exists::<ActiveProfile>),
+        );
+    }
+}
+
+/// One player's saved progress and preferences.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub settings: GameSettings,
+    pub keybinds: Keybinds,
+    /// The weapon last picked on the loadout screen
+    pub loadout: Loadout,
+    pub stats: ProfileStats,
+    pub attributes: Attributes,
+    pub unlocks: BTreeSet<String>,
+    /// Kills or waves counted towards each challenge, by id
+    pub challenges: BTreeMap<String, u32>,
+}
+
+/// Lifetime totals for a profile.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProfileStats {
+    pub shots_fired: u64,
+    pub hits: u64,
+    pub kills: u64,
+    pub headshots: u64,
+    /// Headshots from [`ProfileStats::LONG_RANGE`] or further
+    pub long_headshots: u64,
+    /// Points for hits and kills, see [`ProfileStats::SCORE_PER_HIT`]
+    pub score: u64,
+    /// Seconds spent in game
+    pub time_played: f64,
+}
+
+impl ProfileStats {
+    /// Metres from the target a headshot counts as long range from
+    pub const LONG_RANGE: f32 = 50.0;
+    pub const SCORE_PER_HIT: u64 = 10;
+    /// On top of the hit itself
+    pub const SCORE_PER_KILL: u64 = 100;
+}
+
+impl Profile {
+    pub fn new(name: String) -> Self {
+        Self { name, ..default() }
+    }
+
+    /// Read a profile from disk, starting a fresh one under that name if there's no save.
+    ///
+    /// A save that can't be read is moved aside to `<name>.ron.bad` before starting afresh, so
+    /// saving the fresh profile doesn't overwrite it. If it can't be moved either, the profile
+    /// isn't loaded at all.
+    pub fn load(name: &str) -> Result<Self, String> {
+        let path = profile_path(name);
+
+        let read = match storage::read_to_string(&path) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::new(name.to_owned()));
+            }
+            read => read
+                .map_err(|err| err.to_string())
+                .and_then(|saved| ron::from_str::<Self>(&saved).map_err(|err| err.to_string())),
+        };
+
+        match read {
+            Ok(mut profile) => {
+                profile.keybinds.reset_duplicates();
+
+                Ok(Self {
+                    // the file name is the source of truth in case the file was renamed by hand
+                    name: name.to_owned(),
+                    ..profile
+                })
+            }
+            Err(err) => {
+                error!("could not read {}: {err}", path.display());
+
+                let backup = path.with_extension("ron.bad");
+                storage::rename(&path, &backup).map_err(|rename_err| {
+                    format!(
+                        "could not move unreadable profile {} aside: {rename_err}",
+                        path.display()
+                    )
+                })?;
+
+                warn!(
+                    "starting profile '{name}' afresh, the unreadable save was kept as {}",
+                    backup.display()
+                );
+                Ok(Self::new(name.to_owned()))
+            }
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(std::io::Error::other)?;
+
+        write_atomic(&profile_path(&self.name), serialized.as_bytes())
+    }
+}
+
+/// The profile being played.
+///
+/// Inserting a profile with a different name switches to it, applying its settings, keybinds and
+/// attributes.
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct ActiveProfile(pub Profile);
+
+#[derive(Resource)]
+struct ProfileSaves {
+    timer: Timer,
+    /// The profile has changed since it was last saved
+    unsaved: bool,
+}
+
+fn profile_path(name: &str) -> PathBuf {
+    Path::new(PROFILE_DIR).join(format!("{name}.ron"))
+}
+
+/// The names of every saved profile, sorted.
+pub fn list_profiles() -> Vec<String> {
+    let Ok(saves) = storage::saves_in(Path::new(PROFILE_DIR)) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = saves
+        .into_iter()
+        // skips the temporary files left behind by an interrupted save
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+        .collect();
+
+    names.sort();
+    names
+}
+
+/// A name for a new profile that doesn't clash with a saved one.
+pub fn next_profile_name() -> String {
+    let existing = list_profiles();
+
+    (1..)
+        .map(|index| format!("Profile {index}"))
+        .find(|name| !existing.contains(name))
+        .expect("ran out of profile names")
+}
+
+fn apply_profile(
+    mut commands: Commands,
+    mut applied: Local<Option<String>>,
+    mut saves: ResMut<ProfileSaves>,
+    profile: Res<ActiveProfile>,
+    mut difficulty: ResMut<Difficulty>,
+    mut settings: ResMut<GameSettings>,
+    mut keybinds: ResMut<Keybinds>,
+    player: Single<Entity, With<PrimaryPlayer>>,
+) {
+    if applied.as_ref() == Some(&profile.name) {
+        return;
+    }
+
+    *applied = Some(profile.name.clone());
+
+    // the saved settings already reflect the saved difficulty, so don't let the difficulty
+    // preset overwrite them
+    *difficulty.bypass_change_detection() = profile.difficulty;
+    *settings = profile.settings.clone();
+    *keybinds = profile.keybinds.clone();
+
+    commands.entity(*player).insert(profile.attributes.clone());
+
+    // new profiles are saved straight away so they show up on the profile select screen
+    write_profile(&profile, &mut saves);
+}
+
+fn record_settings(
+    difficulty: Res<Difficulty>,
+    settings: Res<GameSettings>,
+    keybinds: Res<Keybinds>,
+    mut profile: ResMut<ActiveProfile>,
+) {
+    if difficulty.is_changed() {
+        profile.difficulty = *difficulty;
+    }
+
+    if settings.is_changed() {
+        profile.settings = settings.clone();
+    }
+
+    if keybinds.is_changed() {
+        profile.keybinds = keybinds.clone();
+    }
+}
+
+fn record_attributes(
+    player: Single<Ref<Attributes>, With<PrimaryPlayer>>,
+    mut profile: ResMut<ActiveProfile>,
+) {
+    if player.is_changed() {
+        profile.attributes = player.clone();
+    }
+}
+
+fn record_stats(
+    time: Res<Time>,
+    mut shot_reader: MessageReader<ShotFired>,
+    mut damage_reader: MessageReader<DamageEvent>,
+    mut profile: ResMut<ActiveProfile>,
+    player: Single<(Entity, &GlobalTransform), With<PrimaryPlayer>>,
+) {
+    let (player, player_transform) = *player;
+    let stats = &mut profile.stats;
+
+    stats.time_played += time.delta_secs_f64();
+
+    for shot in shot_reader.read() {
+        if shot.shooter == player {
+            stats.shots_fired += 1;
+        }
+    }
+
+    for damage in damage_reader.read() {
+        if damage.source != player {
+            continue;
+        }
+
+        stats.hits += 1;
+        stats.score += ProfileStats::SCORE_PER_HIT;
+
+        if damage.killed {
+            stats.kills += 1;
+            stats.score += ProfileStats::SCORE_PER_KILL;
+        }
+
+        if damage.zone == HitZone::Head {
+            stats.headshots += 1;
+
+            let range = player_transform.translation().distance(damage.point);
+            stats.long_headshots += (range >= ProfileStats::LONG_RANGE) as u64;
+        }
+    }
+}
+
+fn save_profile(
+    time: Res<Time>,
+    mut saves: ResMut<ProfileSaves>,
+    mut exit_reader: MessageReader<AppExit>,
+    profile: Res<ActiveProfile>,
+) {
+    saves.unsaved |= profile.is_changed();
+
+    let exiting = exit_reader.read().count() > 0;
+    let due = saves.timer.tick(time.delta()).just_finished();
+
+    if saves.unsaved && (exiting || due) {
+        write_profile(&profile, &mut saves);
+    }
+}
+
+fn flush_profile(profile: Res<ActiveProfile>, mut saves: ResMut<ProfileSaves>) {
+    write_profile(&profile, &mut saves);
+}
+
+fn write_profile(profile: &Profile, saves: &mut ProfileSaves) {
+    match profile.save() {
+        Ok(()) => saves.unsaved = false,
+        Err(err) => error!("could not save profile '{}': {err}", profile.name),
+    }
+}
@@ -0,0 +1,126 @@
+//! Reusing round entities rather than spawning a fresh one for every shot.
+//!
+//! Rounds are [`Pooled`]. When `cleanup` is done with one, whether it's aged out, flown out of
+//! range, fallen out of the world or come to rest, [`release`] strips it back to nothing but the
+//! marker and parks it in the [`ProjectilePool`]. The next round fired takes it back out and is
+//! built on the same entity, so a long firefight settles into a steady set of entities instead
+//! of churning through new ones. The pool only keeps [`ProjectilePool::CAPACITY`] spares; past
+//! that rounds are despawned as usual.
+
+use bevy::prelude::*;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectilePool>()
+            .add_observer(forget_despawned);
+    }
+}
+
+/// A round that goes back in the [`ProjectilePool`] when it's cleaned up.
+#[derive(Component, Debug)]
+pub struct Pooled;
+
+/// Parked round entities, waiting to be fired again.
+#[derive(Resource, Debug, Default)]
+pub struct ProjectilePool {
+    spare: Vec<Entity>,
+    /// How many rounds have been fired on a reused entity
+    pub reused: usize,
+}
+
+impl ProjectilePool {
+    const CAPACITY: usize = 256;
+
+    /// How many rounds are parked
+    pub fn spare(&self) -> usize {
+        self.spare.len()
+    }
+
+    /// A parked round, or a new entity if there isn't one, ready to be fired
+    pub fn take<'a>(&mut self, commands: &'a mut Commands) -> EntityCommands<'a> {
+        match self.spare.pop() {
+            Some(round) => {
+                self.reused += 1;
+                commands.entity(round)
+            }
+            None => commands.spawn(Pooled),
+        }
+    }
+}
+
+/// Parks `round` in the pool, or despawns it if the pool's full.
+///
+/// Both happen together when the command is applied. Parking it straight away would let it be
+/// taken and fired again before its old components were stripped, taking the new ones with them.
+pub fn release(commands: &mut Commands, round: Entity) {
+    commands.queue(move |world: &mut World| {
+        if world.resource::<ProjectilePool>().spare.len() >= ProjectilePool::CAPACITY {
+            world.try_despawn(round).ok();
+            return;
+        }
+
+        let Ok(mut entity) = world.get_entity_mut(round) else {
+            return;
+        };
+
+        // everything it picked up in flight goes, whichever module added it
+        entity.retain::<Pooled>();
+        world.resource_mut::<ProjectilePool>().spare.push(round);
+    });
+}
+
+/// Drops parked rounds despawned by something else, such as a blast, from the pool.
+fn forget_despawned(remove: On<Remove, Pooled>, mut pool: ResMut<ProjectilePool>) {
+    pool.spare.retain(|round| *round != remove.entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spent_rounds_are_parked_and_fired_again() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ProjectilePlugin));
+        let world = app.world_mut();
+
+        let fire = |mut commands: Commands, mut pool: ResMut<ProjectilePool>| {
+            pool.take(&mut commands).insert(Transform::default()).id()
+        };
+        let release = move |In(round): In<Entity>, mut commands: Commands| {
+            release(&mut commands, round);
+        };
+
+        let round = world.run_system_once(fire).unwrap();
+        world.run_system_once_with(release, round).unwrap();
+
+        assert!(world.get::<Transform>(round).is_none());
+        assert!(world.get::<Pooled>(round).is_some());
+        assert_eq!(world.resource::<ProjectilePool>().spare(), 1);
+
+        assert_eq!(world.run_system_once(fire).unwrap(), round);
+        assert_eq!(world.resource::<ProjectilePool>().reused, 1);
+
+        // one despawned while parked isn't handed out again
+        world.run_system_once_with(release, round).unwrap();
+        world.despawn(round);
+        assert_eq!(world.resource::<ProjectilePool>().spare(), 0);
+        assert_ne!(world.run_system_once(fire).unwrap(), round);
+
+        // and one released this frame isn't fired again until it's been stripped
+        let round = world.run_system_once(fire).unwrap();
+        let refired = world
+            .run_system_once(
+                move |mut commands: Commands, mut pool: ResMut<ProjectilePool>| {
+                    super::release(&mut commands, round);
+                    pool.take(&mut commands).id()
+                },
+            )
+            .unwrap();
+        assert_ne!(refired, round);
+    }
+}
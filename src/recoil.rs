@@ -0,0 +1,192 @@
+//! Recoil.
+//!
+//! Every shot throws its owner's view up and off to one side and shoves the weapon back at them,
+//! and both settle back again at the weapon's [`RecoilPattern::recovery`] rate. Which way each shot
+//! goes sideways is picked from the pattern's seed, so a weapon climbs the same way every burst
+//! and can be learned. A burst starts over from the first shot once the weapon has settled.
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::{Player, PlayerCamera, ShotFired, SwayTarget, TransformPipeline, WeaponActive};
+
+/// How a weapon kicks, set from its definition.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RecoilPattern {
+    /// Degrees the view climbs each shot
+    pub vertical: f32,
+    /// Degrees the view can be thrown to either side each shot
+    pub horizontal: f32,
+    /// Metres the weapon is shoved back each shot
+    pub kick: f32,
+    /// How quickly the view and weapon settle back, higher being quicker
+    pub recovery: f32,
+    /// Picks which way each shot in a burst goes sideways
+    pub seed: u64,
+}
+
+impl Default for RecoilPattern {
+    fn default() -> Self {
+        Self {
+            vertical: 0.6,
+            horizontal: 0.3,
+            kick: 0.02,
+            recovery: 8.0,
+            seed: 0,
+        }
+    }
+}
+
+impl RecoilPattern {
+    /// Pitch and yaw in radians the view is thrown by the shot `index` into a burst
+    pub fn shot(&self, index: u32) -> Vec2 {
+        let side =
+            StdRng::seed_from_u64(self.seed.wrapping_add(index.into())).random_range(-1.0..=1.0);
+
+        Vec2::new(self.vertical, self.horizontal * side).map(f32::to_radians)
+    }
+}
+
+/// A weapon's recoil and how far into it the weapon is.
+#[derive(Component, Debug, Default)]
+pub struct Recoil {
+    pub pattern: RecoilPattern,
+    /// Shots into the current burst
+    shots: u32,
+    /// Pitch and yaw in radians the view has been thrown and still has to come back
+    view: Vec2,
+    /// Metres the weapon has been shoved back
+    kick: f32,
+}
+
+impl Recoil {
+    /// Radians and metres close enough to still to start the next burst over
+    const SETTLED: f32 = 1e-4;
+    /// Which way the weapon is shoved, back and a little up
+    const KICK_DIRECTION: Vec3 = Vec3::new(0.0, 0.3, 1.0);
+    /// Radians the muzzle flips up per metre the weapon's shoved back
+    const KICK_TILT: f32 = 4.0;
+
+    pub fn new(pattern: RecoilPattern) -> Self {
+        Self {
+            pattern,
+            ..default()
+        }
+    }
+}
+
+type Players<'w, 's> = Query<'w, 's, &'static mut Transform, (With<Player>, Without<PlayerCamera>)>;
+type Cameras<'w, 's> =
+    Query<'w, 's, (&'static ChildOf, &'static mut Transform), With<PlayerCamera>>;
+
+/// Turns `player`'s view by `turn`, pitching their camera and yawing them.
+fn turn_view(player: Entity, turn: Vec2, players: &mut Players, cameras: &mut Cameras) {
+    if let Ok(mut transform) = players.get_mut(player) {
+        transform.rotate_y(turn.y);
+    }
+
+    for (child_of, mut transform) in cameras.iter_mut() {
+        if child_of.parent() == player {
+            transform.rotate_x(turn.x);
+        }
+    }
+}
+
+/// Kicks every weapon that fired since the last step, once however many rounds it fired.
+pub fn kick(
+    mut shot_reader: MessageReader<ShotFired>,
+    mut weapons: Query<(&mut Recoil, &SwayTarget)>,
+    mut players: Players,
+    mut cameras: Cameras,
+) {
+    // a shotgun blast is a round per pellet
+    let mut fired: SmallVec<[Entity; 4]> = SmallVec::new();
+    for shot in shot_reader.read() {
+        if !fired.contains(&shot.weapon) {
+            fired.push(shot.weapon);
+        }
+    }
+
+    for weapon in fired {
+        let Ok((mut recoil, owner)) = weapons.get_mut(weapon) else {
+            continue;
+        };
+
+        let turn = recoil.pattern.shot(recoil.shots);
+        recoil.shots += 1;
+        recoil.view += turn;
+        recoil.kick += recoil.pattern.kick;
+
+        turn_view(owner.0, turn, &mut players, &mut cameras);
+    }
+}
+
+/// Settles weapons and their owners' views back after firing.
+pub fn recover(
+    time: Res<Time>,
+    mut weapons: Query<(
+        &mut Recoil,
+        &SwayTarget,
+        &mut TransformPipeline,
+        Has<WeaponActive>,
+    )>,
+    mut players: Players,
+    mut cameras: Cameras,
+) {
+    for (mut recoil, owner, mut pipeline, active) in &mut weapons {
+        // a weapon nobody's fired stays as it is
+        if recoil.shots == 0 {
+            continue;
+        }
+
+        let settle = 1.0 - (-recoil.pattern.recovery * time.delta_secs()).exp();
+
+        let back = recoil.view * settle;
+        recoil.view -= back;
+        recoil.kick -= recoil.kick * settle;
+        turn_view(owner.0, -back, &mut players, &mut cameras);
+
+        if recoil.view.length() < Recoil::SETTLED && recoil.kick < Recoil::SETTLED {
+            recoil.shots = 0;
+        }
+
+        // holstered weapons aren't posed
+        if active {
+            pipeline.queue(Recoil::KICK_DIRECTION * recoil.kick);
+            pipeline.queue_rotation(Quat::from_rotation_x(recoil.kick * Recoil::KICK_TILT));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recoil_patterns_repeat_every_burst() {
+        let pattern = RecoilPattern {
+            seed: 17,
+            ..default()
+        };
+        let burst: Vec<_> = (0..10).map(|shot| pattern.shot(shot)).collect();
+
+        assert!(
+            burst
+                .iter()
+                .zip(0..)
+                .all(|(turn, shot)| *turn == pattern.clone().shot(shot))
+        );
+        assert!(
+            burst
+                .iter()
+                .all(|turn| turn.x == pattern.vertical.to_radians())
+        );
+        assert!(
+            burst.iter().any(|turn| turn.y > 0.0) && burst.iter().any(|turn| turn.y < 0.0),
+            "thrown both ways over a burst"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Loads any deserializable asset from a RON file with one of the given extensions.
+pub struct RonLoader<A> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> A>,
+}
+
+impl<A> RonLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _asset: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RonLoaderError {
+    #[error("could not read file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -6,13 +6,48 @@ use bevy::{
 };
 use std::f32::consts::PI;
 
+use crate::Player;
+use crate::ai::{Soldier, SoldierHead};
+use crate::ai_presets::AiPreset;
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::damage::{Armor, Health, HitZone, Hitbox};
+use crate::equipment::{ArmorPickup, ArmorPiece, EquipmentAssets};
+use crate::flinch::Flinches;
+use crate::grapple::GrappleSurface;
+use crate::menu::GameState;
+use crate::minimap::MinimapIcon;
+use crate::movement::{CapsuleSize, CharacterControllerBundle};
+use crate::trigger::{TriggerEntered, TriggerVolume};
+use crate::turret::TurretAssets;
+use crate::vehicle::VehicleAssets;
+use crate::vision::HeatSignature;
+use crate::wanderer::Wanderer;
+use crate::zipline::Zipline;
+
 pub struct ScenePlugin;
 
 impl Plugin for ScenePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_floor, add_border, setup_atmos))
-            .add_systems(Update, (hide_cursor, dynamic_scene))
-            .insert_resource(FloorSize(100.0));
+        app.add_systems(
+            Startup,
+            (
+                setup_floor,
+                add_border,
+                add_kill_zone,
+                setup_atmos,
+                setup_arena_assets,
+            ),
+        )
+        .add_console_command(
+            "spawn",
+            "spawn <target|armored|soldier [preset]|wanderer|crate|ramp|spawnpoint|helmet|vest|buggy|turret> - place a piece in front of you",
+        )
+        .add_systems(
+            Update,
+            (hide_cursor, dynamic_scene, spawn_command, fall_out_of_world),
+        )
+        .insert_resource(FloorSize(100.0))
+        .init_resource::<TimeOfDay>();
     }
 }
 
@@ -22,9 +57,54 @@ struct FloorSize(f32);
 #[derive(Component)]
 struct Cube;
 
-fn dynamic_scene(mut suns: Query<&mut Transform, With<DirectionalLight>>, time: Res<Time>) {
-    suns.iter_mut()
-        .for_each(|mut tf| tf.rotate_x(-time.delta_secs() * PI / 200.0));
+/// A shootable dummy standing in the arena.
+#[derive(Component)]
+pub struct Target;
+
+/// The in-game clock driving the sun.
+#[derive(Resource)]
+pub struct TimeOfDay {
+    pub hours: f32,
+    pub hours_per_second: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hours: 10.0,
+            hours_per_second: 0.06,
+        }
+    }
+}
+
+impl TimeOfDay {
+    pub fn set(&mut self, hours: f32) {
+        self.hours = hours.rem_euclid(24.0);
+    }
+
+    /// How much sunlight there is, 0 from dusk until dawn and 1 at midday
+    pub fn daylight(&self) -> f32 {
+        self.sun_elevation().sin().max(0.0)
+    }
+
+    /// Angle of the sun above the horizon, 0 at 06:00 and PI at 18:00
+    fn sun_elevation(&self) -> f32 {
+        (self.hours - 6.0) / 12.0 * PI
+    }
+}
+
+fn dynamic_scene(
+    mut suns: Query<&mut Transform, With<DirectionalLight>>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    time: Res<Time>,
+) {
+    let hours = time_of_day.hours + time.delta_secs() * time_of_day.hours_per_second;
+    time_of_day.set(hours);
+
+    let rotation =
+        Quat::from_rotation_y(PI / 4.0) * Quat::from_rotation_x(-time_of_day.sun_elevation());
+
+    suns.iter_mut().for_each(|mut tf| tf.rotation = rotation);
 }
 
 fn setup_atmos(mut commands: Commands) {
@@ -52,8 +132,10 @@ fn hide_cursor(
     mut cursor: Single<&mut CursorOptions>,
     mut lock_cursor: Local<bool>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
 ) {
-    if *lock_cursor {
+    // the menus always need the cursor
+    if *lock_cursor && *state.get() == GameState::InGame {
         cursor.grab_mode = CursorGrabMode::Confined;
         cursor.visible = false;
     } else {
@@ -94,6 +176,48 @@ fn setup_floor(
     ));
 }
 
+/// Anything that ends up below the arena.
+#[derive(Component, Debug)]
+struct KillZone;
+
+fn add_kill_zone(mut commands: Commands, floor_size: Res<FloorSize>) {
+    let size = floor_size.0 * 10.0;
+
+    commands.spawn((
+        KillZone,
+        TriggerVolume::cuboid(Vec3::new(size, 10.0, size)),
+        Transform::from_xyz(0.0, -30.0, 0.0),
+    ));
+}
+
+/// Players that fall out of the world go back to the start, everything else is removed.
+fn fall_out_of_world(
+    mut commands: Commands,
+    mut entered_reader: MessageReader<TriggerEntered>,
+    kill_zones: Query<(), With<KillZone>>,
+    spawn_points: Query<&Transform, (With<SpawnPoint>, Without<Player>)>,
+    mut players: Query<(&mut Transform, &mut LinearVelocity), With<Player>>,
+) {
+    for entered in entered_reader.read() {
+        if !kill_zones.contains(entered.trigger) {
+            continue;
+        }
+
+        if let Ok((mut transform, mut velocity)) = players.get_mut(entered.body) {
+            let start = spawn_points
+                .iter()
+                .next()
+                .map_or(Vec3::new(0.5, 0.5, 0.5), |point| point.translation);
+
+            transform.translation = start + Vec3::Y;
+            velocity.0 = Vec3::ZERO;
+            info!("{} fell out of the world", entered.body);
+        } else {
+            commands.entity(entered.body).try_despawn();
+        }
+    }
+}
+
 fn add_border(
     mut commands: Commands,
     floor_size_res: Res<FloorSize>,
@@ -120,6 +244,7 @@ fn add_border(
         MeshMaterial3d(cube_mat.clone()),
         transform_t,
         Cube,
+        GrappleSurface,
         Collider::cuboid(floor_size, HEIGHT, 1.),
     ));
 
@@ -129,6 +254,7 @@ fn add_border(
         MeshMaterial3d(cube_mat.clone()),
         transform_b,
         Cube,
+        GrappleSurface,
         Collider::cuboid(floor_size, HEIGHT, 1.),
     ));
 
@@ -138,6 +264,7 @@ fn add_border(
         MeshMaterial3d(cube_mat.clone()),
         transform_l,
         Cube,
+        GrappleSurface,
         Collider::cuboid(1., HEIGHT, floor_size),
     ));
 
@@ -147,6 +274,507 @@ fn add_border(
         MeshMaterial3d(cube_mat.clone()),
         transform_r,
         Cube,
+        GrappleSurface,
         Collider::cuboid(1., HEIGHT, floor_size),
     ));
 }
+
+/// Shared meshes and materials for the pieces spawned by [`SpawnArenaExt`].
+#[derive(Resource)]
+struct ArenaAssets {
+    body_mesh: Handle<Mesh>,
+    head_mesh: Handle<Mesh>,
+    body_mat: Handle<StandardMaterial>,
+    armored_mat: Handle<StandardMaterial>,
+    soldier_mat: Handle<StandardMaterial>,
+    wanderer_mat: Handle<StandardMaterial>,
+    crate_mat: Handle<StandardMaterial>,
+    ramp_mat: Handle<StandardMaterial>,
+    spawn_point_mesh: Handle<Mesh>,
+    spawn_point_mat: Handle<StandardMaterial>,
+}
+
+impl ArenaAssets {
+    const BODY_RADIUS: f32 = 0.35;
+    const BODY_HEIGHT: f32 = 1.1;
+    const HEAD_RADIUS: f32 = 0.18;
+}
+
+/// Where a player starts a level, see `level::move_player_to_start`.
+#[derive(Component, Debug)]
+pub struct SpawnPoint;
+
+/// Building arena layouts straight from [`Commands`], so game modes and the console don't need
+/// to put together the meshes and colliders themselves.
+///
+/// Every piece stands on `position`, with its base at that height.
+pub trait SpawnArenaExt {
+    /// A target dummy, optionally wearing armour
+    fn spawn_target(&mut self, position: Vec3, armored: bool) -> EntityCommands<'_>;
+
+    /// A hostile soldier, see `ai`
+    fn spawn_soldier(&mut self, position: Vec3) -> EntityCommands<'_>;
+
+    /// A harmless passer-by, see `wanderer`
+    fn spawn_wanderer(&mut self, position: Vec3) -> EntityCommands<'_>;
+
+    /// A box that can be knocked around
+    fn spawn_crate(&mut self, position: Vec3, size: Vec3) -> EntityCommands<'_>;
+
+    /// A box that only moves when told to, see `timeline::PlatformMove`
+    fn spawn_platform(&mut self, position: Vec3, size: Vec3) -> EntityCommands<'_>;
+
+    /// A fixed slope rising `angle` radians towards -Z
+    fn spawn_ramp(&mut self, position: Vec3, size: Vec3, angle: f32) -> EntityCommands<'_>;
+
+    /// A piece of armour for players to pick up
+    fn spawn_armor_pickup(&mut self, position: Vec3, piece: ArmorPiece) -> EntityCommands<'_>;
+
+    /// A pad marking where a player starts
+    fn spawn_spawn_point(&mut self, position: Vec3) -> EntityCommands<'_>;
+
+    /// A buggy players can drive, facing -Z
+    fn spawn_vehicle(&mut self, position: Vec3) -> EntityCommands<'_>;
+
+    /// A mounted gun players can man, facing -Z
+    fn spawn_turret(&mut self, position: Vec3) -> EntityCommands<'_>;
+
+    /// A cable between the tops of two posts standing on `from` and `to`, `heights` tall
+    fn spawn_zipline(&mut self, from: Vec3, to: Vec3, heights: (f32, f32)) -> EntityCommands<'_>;
+}
+
+impl SpawnArenaExt for Commands<'_, '_> {
+    fn spawn_target(&mut self, position: Vec3, armored: bool) -> EntityCommands<'_> {
+        let target = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let assets = world.resource::<ArenaAssets>();
+            let body_centre = ArenaAssets::BODY_RADIUS + ArenaAssets::BODY_HEIGHT / 2.0;
+            let head_offset = ArenaAssets::BODY_HEIGHT / 2.0
+                + ArenaAssets::BODY_RADIUS
+                + ArenaAssets::HEAD_RADIUS;
+
+            let body = (
+                RigidBody::Static,
+                Mesh3d(assets.body_mesh.clone()),
+                MeshMaterial3d(if armored {
+                    assets.armored_mat.clone()
+                } else {
+                    assets.body_mat.clone()
+                }),
+                Transform::from_translation(position + Vec3::Y * body_centre),
+                Collider::capsule(ArenaAssets::BODY_RADIUS, ArenaAssets::BODY_HEIGHT),
+                Target,
+                MinimapIcon::Enemy,
+                Health::new(100.0),
+                Hitbox(HitZone::Body),
+                HeatSignature(0.8),
+                Flinches::default(),
+            );
+
+            let head = (
+                Mesh3d(assets.head_mesh.clone()),
+                MeshMaterial3d(assets.body_mat.clone()),
+                Transform::from_xyz(0.0, head_offset, 0.0),
+                Collider::sphere(ArenaAssets::HEAD_RADIUS),
+                Hitbox(HitZone::Head),
+                HeatSignature(0.9),
+            );
+
+            let Ok(mut target) = world.get_entity_mut(target) else {
+                return;
+            };
+
+            target.insert(body).with_child(head);
+
+            if armored {
+                target.insert(Armor::new(100.0, 0.7));
+            }
+        });
+
+        self.entity(target)
+    }
+
+    fn spawn_soldier(&mut self, position: Vec3) -> EntityCommands<'_> {
+        let soldier = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let assets = world.resource::<ArenaAssets>();
+            let body_centre = ArenaAssets::BODY_RADIUS + ArenaAssets::BODY_HEIGHT / 2.0;
+            let head_offset = Vec3::Y
+                * (ArenaAssets::BODY_HEIGHT / 2.0
+                    + ArenaAssets::BODY_RADIUS
+                    + ArenaAssets::HEAD_RADIUS);
+
+            let body = (
+                Name::new("Soldier"),
+                Soldier::default(),
+                Mesh3d(assets.body_mesh.clone()),
+                MeshMaterial3d(assets.soldier_mat.clone()),
+                Transform::from_translation(position + Vec3::Y * body_centre),
+                CharacterControllerBundle::new(CapsuleSize::new(
+                    ArenaAssets::BODY_RADIUS,
+                    ArenaAssets::BODY_HEIGHT,
+                )),
+                Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+                GravityScale(2.0),
+                MinimapIcon::Enemy,
+                (
+                    Health::new(100.0),
+                    Hitbox(HitZone::Body),
+                    HeatSignature(0.8),
+                    Flinches::default(),
+                ),
+            );
+
+            let head = (
+                Mesh3d(assets.head_mesh.clone()),
+                MeshMaterial3d(assets.soldier_mat.clone()),
+                Transform::from_translation(head_offset),
+                SoldierHead { rest: head_offset },
+                Collider::sphere(ArenaAssets::HEAD_RADIUS),
+                Hitbox(HitZone::Head),
+                HeatSignature(0.9),
+            );
+
+            if let Ok(mut soldier) = world.get_entity_mut(soldier) {
+                soldier.insert(body).with_child(head);
+            }
+        });
+
+        self.entity(soldier)
+    }
+
+    fn spawn_wanderer(&mut self, position: Vec3) -> EntityCommands<'_> {
+        let wanderer = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let assets = world.resource::<ArenaAssets>();
+            let body_centre = ArenaAssets::BODY_RADIUS + ArenaAssets::BODY_HEIGHT / 2.0;
+            let head_offset = Vec3::Y
+                * (ArenaAssets::BODY_HEIGHT / 2.0
+                    + ArenaAssets::BODY_RADIUS
+                    + ArenaAssets::HEAD_RADIUS);
+
+            let body = (
+                Name::new("Wanderer"),
+                Wanderer::new(position),
+                Mesh3d(assets.body_mesh.clone()),
+                MeshMaterial3d(assets.wanderer_mat.clone()),
+                Transform::from_translation(position + Vec3::Y * body_centre),
+                CharacterControllerBundle::new(CapsuleSize::new(
+                    ArenaAssets::BODY_RADIUS,
+                    ArenaAssets::BODY_HEIGHT,
+                )),
+                Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+                GravityScale(2.0),
+                HeatSignature(0.8),
+            );
+
+            let head = (
+                Mesh3d(assets.head_mesh.clone()),
+                MeshMaterial3d(assets.wanderer_mat.clone()),
+                Transform::from_translation(head_offset),
+            );
+
+            if let Ok(mut wanderer) = world.get_entity_mut(wanderer) {
+                wanderer.insert(body).with_child(head);
+            }
+        });
+
+        self.entity(wanderer)
+    }
+
+    fn spawn_crate(&mut self, position: Vec3, size: Vec3) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Cuboid::from_size(size));
+            let material = world.resource::<ArenaAssets>().crate_mat.clone();
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert((
+                    RigidBody::Dynamic,
+                    Mesh3d(mesh),
+                    MeshMaterial3d(material),
+                    Transform::from_translation(position + Vec3::Y * size.y / 2.0),
+                    Collider::cuboid(size.x, size.y, size.z),
+                ));
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_platform(&mut self, position: Vec3, size: Vec3) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Cuboid::from_size(size));
+            let material = world.resource::<ArenaAssets>().ramp_mat.clone();
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert((
+                    RigidBody::Kinematic,
+                    Mesh3d(mesh),
+                    MeshMaterial3d(material),
+                    Transform::from_translation(position + Vec3::Y * size.y / 2.0),
+                    Collider::cuboid(size.x, size.y, size.z),
+                    GrappleSurface,
+                ));
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_ramp(&mut self, position: Vec3, size: Vec3, angle: f32) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Cuboid::from_size(size));
+            let material = world.resource::<ArenaAssets>().ramp_mat.clone();
+
+            // tip the box up around its near bottom edge, so that edge stays on the ground
+            let rotation = Quat::from_rotation_x(angle);
+            let edge = Vec3::new(0.0, -size.y / 2.0, size.z / 2.0);
+            let centre = position - rotation * edge;
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert((
+                    RigidBody::Static,
+                    Mesh3d(mesh),
+                    MeshMaterial3d(material),
+                    Transform::from_translation(centre).with_rotation(rotation),
+                    Collider::cuboid(size.x, size.y, size.z),
+                    GrappleSurface,
+                ));
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_armor_pickup(&mut self, position: Vec3, piece: ArmorPiece) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let assets = world.resource::<EquipmentAssets>();
+            let pickup = (
+                Mesh3d(assets.mesh(piece)),
+                MeshMaterial3d(assets.material.clone()),
+                Transform::from_translation(position + Vec3::Y * 0.6).with_scale(Vec3::splat(0.6)),
+                ArmorPickup(piece),
+                ArmorPickup::trigger(),
+            );
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(pickup);
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_spawn_point(&mut self, position: Vec3) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let assets = world.resource::<ArenaAssets>();
+            let pad = (
+                Mesh3d(assets.spawn_point_mesh.clone()),
+                MeshMaterial3d(assets.spawn_point_mat.clone()),
+                Transform::from_translation(position),
+                SpawnPoint,
+            );
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(pad);
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_vehicle(&mut self, position: Vec3) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let buggy = world.resource::<VehicleAssets>().buggy(position);
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(buggy);
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_turret(&mut self, position: Vec3) -> EntityCommands<'_> {
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let barrel = world
+                .resource_mut::<Assets<StandardMaterial>>()
+                .add(TurretAssets::barrel_material());
+            let turret = world.resource::<TurretAssets>().turret(position, barrel);
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(turret);
+            }
+        });
+
+        self.entity(entity)
+    }
+
+    fn spawn_zipline(&mut self, from: Vec3, to: Vec3, heights: (f32, f32)) -> EntityCommands<'_> {
+        /// Thickness of the posts
+        const POST: f32 = 0.2;
+        const CABLE_RADIUS: f32 = 0.02;
+
+        let entity = self.spawn_empty().id();
+
+        self.queue(move |world: &mut World| {
+            let zipline = Zipline {
+                from: from + Vec3::Y * heights.0,
+                to: to + Vec3::Y * heights.1,
+            };
+
+            let mut meshes = world.resource_mut::<Assets<Mesh>>();
+            let posts = [(from, heights.0), (to, heights.1)].map(|(base, height)| {
+                (
+                    meshes.add(Cuboid::new(POST, height, POST)),
+                    Transform::from_translation(base + Vec3::Y * height / 2.0),
+                    Collider::cuboid(POST, height, POST),
+                )
+            });
+            let cable = meshes.add(Cylinder::new(CABLE_RADIUS, zipline.length()));
+            let material = world.resource::<ArenaAssets>().ramp_mat.clone();
+
+            let posts = posts.map(|(mesh, transform, collider)| {
+                (
+                    RigidBody::Static,
+                    Mesh3d(mesh),
+                    MeshMaterial3d(material.clone()),
+                    transform,
+                    collider,
+                    GrappleSurface,
+                )
+            });
+            let cable = (
+                Mesh3d(cable),
+                MeshMaterial3d(material),
+                Transform::from_translation(zipline.from.midpoint(zipline.to))
+                    .with_rotation(Quat::from_rotation_arc(Vec3::Y, zipline.direction())),
+            );
+
+            if let Ok(mut entity) = world.get_entity_mut(entity) {
+                entity.insert((
+                    zipline,
+                    Visibility::default(),
+                    Children::spawn((SpawnIter(posts.into_iter()), Spawn(cable))),
+                ));
+            }
+        });
+
+        self.entity(entity)
+    }
+}
+
+fn setup_arena_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let assets = ArenaAssets {
+        body_mesh: meshes.add(Capsule3d::new(
+            ArenaAssets::BODY_RADIUS,
+            ArenaAssets::BODY_HEIGHT,
+        )),
+        head_mesh: meshes.add(Sphere::new(ArenaAssets::HEAD_RADIUS)),
+        body_mat: materials.add(Color::srgb_u8(200, 120, 60)),
+        armored_mat: materials.add(Color::srgb_u8(70, 80, 95)),
+        soldier_mat: materials.add(Color::srgb_u8(150, 40, 40)),
+        wanderer_mat: materials.add(Color::srgb_u8(90, 140, 90)),
+        crate_mat: materials.add(Color::srgb_u8(150, 110, 70)),
+        ramp_mat: materials.add(Color::srgb_u8(110, 110, 120)),
+        spawn_point_mesh: meshes.add(Cylinder::new(0.6, 0.02)),
+        spawn_point_mat: materials.add(Color::srgba(0.3, 0.8, 1.0, 0.5)),
+    };
+
+    commands.insert_resource(assets);
+}
+
+/// `spawn <target|armored|soldier|wanderer|crate|ramp|spawnpoint|helmet|vest|buggy|turret|zipline>` puts a piece on the
+/// floor in front of player one. Ziplines run away from the player, downhill, and soldiers can be
+/// given an AI preset, like `spawn soldier veteran`.
+fn spawn_command(
+    mut commands: Commands,
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    players: Query<&Transform, With<Player>>,
+) {
+    /// How far in front of the player pieces are placed
+    const DISTANCE: f32 = 5.0;
+
+    for command in command_reader.read() {
+        if !command.is("spawn") {
+            continue;
+        }
+
+        let Some(player) = players.iter().next() else {
+            output_writer.write(ConsoleOutput("no player to spawn in front of".into()));
+            continue;
+        };
+
+        let forward = player
+            .forward()
+            .as_vec3()
+            .with_y(0.0)
+            .normalize_or(Vec3::NEG_Z);
+        let position = (player.translation + forward * DISTANCE).with_y(0.5);
+
+        let mut entity = match command.arg(0) {
+            Some("target") => commands.spawn_target(position, false),
+            Some("armored") => commands.spawn_target(position, true),
+            Some("soldier") => {
+                let mut soldier = commands.spawn_soldier(position);
+                if let Some(preset) = command.arg(1) {
+                    soldier.insert(AiPreset(preset.to_string()));
+                }
+                soldier
+            }
+            Some("wanderer") => commands.spawn_wanderer(position),
+            Some("crate") => commands.spawn_crate(position, Vec3::splat(1.0)),
+            Some("ramp") => commands.spawn_ramp(position, Vec3::new(3.0, 0.3, 6.0), 0.3),
+            Some("spawnpoint") => commands.spawn_spawn_point(position),
+            Some("helmet") => commands.spawn_armor_pickup(position, ArmorPiece::Helmet),
+            Some("vest") => commands.spawn_armor_pickup(position, ArmorPiece::Vest),
+            Some("buggy") => commands.spawn_vehicle(position),
+            Some("turret") => commands.spawn_turret(position),
+            Some("zipline") => {
+                commands.spawn_zipline(position, position + forward * 25.0, (4.5, 3.2))
+            }
+            _ => {
+                output_writer.write(ConsoleOutput(
+                    "usage: spawn <target|armored|soldier|wanderer|crate|ramp|spawnpoint|helmet|vest|buggy|turret|zipline>"
+                        .into(),
+                ));
+                continue;
+            }
+        };
+
+        entity.insert(DespawnOnExit(GameState::InGame));
+
+        output_writer.write(ConsoleOutput(format!(
+            "spawned {} at {:.1}",
+            command.args[0], position
+        )));
+    }
+}
@@ -0,0 +1,300 @@
+//! Rhai game mode scripts.
+//!
+//! Only compiled with the `scripting` feature. The script at `assets/scripts/game_mode.rhai` is run
+//! once when loaded (and again whenever it is saved), and can define callbacks that are invoked
+//! as gameplay happens:
+//!
+//! - `on_hit(target, amount, zone)` when anything takes damage
+//! - `on_wave_start(wave)` when a wave begins
+//!
+//! Scripts drive the world through `spawn_target(x, y, z)`, `spawn_armored_target(x, y, z)`,
+//! `teleport(x, y, z)`, `give_item(name, count)`, `set_time(hours)` and `log(message)`. Calls are
+//! queued and applied by [`apply_script_actions`] at the end of the frame.
+
+use std::sync::{Arc, Mutex};
+
+use avian3d::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use rhai::{AST, Engine, Scope};
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::damage::{DamageEvent, HitZone};
+use crate::inventory::Inventory;
+use crate::scene::{SpawnArenaExt, TimeOfDay};
+use crate::split_screen::PrimaryPlayer;
+use crate::timeline::WaveStarted;
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Script>()
+            .init_asset_loader::<ScriptLoader>()
+            .insert_resource(ScriptHost::new())
+            .add_console_command("wave", "wave <n> - start a scripted wave")
+            .add_systems(Startup, load_game_mode)
+            .add_systems(
+                Update,
+                (
+                    run_loaded_script,
+                    wave_command,
+                    (script_on_hit, script_on_wave_start),
+                    apply_script_actions,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, Debug)]
+pub struct Script {
+    source: String,
+}
+
+#[derive(Default)]
+struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    type Asset = Script;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let source = String::from_utf8(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+        Ok(Script { source })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+/// A world change requested by a script.
+#[derive(Debug)]
+enum ScriptAction {
+    SpawnTarget { position: Vec3, armored: bool },
+    Teleport(Vec3),
+    GiveItem { item: String, count: u32 },
+    SetTime(f32),
+    Log(String),
+}
+
+#[derive(Resource)]
+struct ScriptHost {
+    engine: Engine,
+    scope: Scope<'static>,
+    ast: Option<AST>,
+    actions: Arc<Mutex<Vec<ScriptAction>>>,
+}
+
+impl ScriptHost {
+    fn new() -> Self {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+
+        let queue = |actions: &Arc<Mutex<Vec<ScriptAction>>>| {
+            let actions = actions.clone();
+            move |action: ScriptAction| actions.lock().unwrap().push(action)
+        };
+
+        let push = queue(&actions);
+        engine.register_fn("spawn_target", move |x: f64, y: f64, z: f64| {
+            push(ScriptAction::SpawnTarget {
+                position: Vec3::new(x as f32, y as f32, z as f32),
+                armored: false,
+            })
+        });
+
+        let push = queue(&actions);
+        engine.register_fn("spawn_armored_target", move |x: f64, y: f64, z: f64| {
+            push(ScriptAction::SpawnTarget {
+                position: Vec3::new(x as f32, y as f32, z as f32),
+                armored: true,
+            })
+        });
+
+        let push = queue(&actions);
+        engine.register_fn("teleport", move |x: f64, y: f64, z: f64| {
+            push(ScriptAction::Teleport(Vec3::new(
+                x as f32, y as f32, z as f32,
+            )))
+        });
+
+        let push = queue(&actions);
+        engine.register_fn("give_item", move |item: &str, count: i64| {
+            push(ScriptAction::GiveItem {
+                item: item.to_owned(),
+                count: count.max(0) as u32,
+            })
+        });
+
+        let push = queue(&actions);
+        engine.register_fn("set_time", move |hours: f64| {
+            push(ScriptAction::SetTime(hours as f32))
+        });
+
+        let push = queue(&actions);
+        engine.register_fn("log", move |message: &str| {
+            push(ScriptAction::Log(message.to_owned()))
+        });
+
+        Self {
+            engine,
+            scope: Scope::new(),
+            ast: None,
+            actions,
+        }
+    }
+
+    /// Call a script function if the script defines it
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) {
+        let Some(ast) = &self.ast else {
+            return;
+        };
+
+        if !ast.iter_functions().any(|function| function.name == name) {
+            return;
+        }
+
+        if let Err(err) = self.engine.call_fn::<()>(&mut self.scope, ast, name, args) {
+            error!("script error in {name}: {err}");
+        }
+    }
+}
+
+#[derive(Resource)]
+struct GameModeScript(Handle<Script>);
+
+fn load_game_mode(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameModeScript(asset_server.load("scripts/game_mode.rhai")));
+}
+
+fn run_loaded_script(
+    mut asset_events: MessageReader<AssetEvent<Script>>,
+    scripts: Res<Assets<Script>>,
+    game_mode: Res<GameModeScript>,
+    mut host: ResMut<ScriptHost>,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if *id == game_mode.0.id()
+        )
+    });
+
+    if !changed {
+        return;
+    }
+
+    let Some(script) = scripts.get(&game_mode.0) else {
+        return;
+    };
+
+    let host = &mut *host;
+
+    let ast = match host.engine.compile(&script.source) {
+        Ok(ast) => ast,
+        Err(err) => {
+            error!("failed to compile game mode script: {err}");
+            return;
+        }
+    };
+
+    host.scope.clear();
+
+    if let Err(err) = host.engine.run_ast_with_scope(&mut host.scope, &ast) {
+        error!("game mode script failed: {err}");
+    }
+
+    host.ast = Some(ast);
+    info!("game mode script loaded");
+}
+
+fn wave_command(
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+    mut wave_writer: MessageWriter<WaveStarted>,
+) {
+    for command in command_reader.read() {
+        if !command.is("wave") {
+            continue;
+        }
+
+        match command.arg(0).map(str::parse) {
+            Some(Ok(wave)) => {
+                wave_writer.write(WaveStarted(wave));
+            }
+            _ => {
+                output_writer.write(ConsoleOutput("usage: wave <n>".into()));
+            }
+        }
+    }
+}
+
+fn script_on_hit(mut host: ResMut<ScriptHost>, mut damage_reader: MessageReader<DamageEvent>) {
+    for damage in damage_reader.read() {
+        let zone = match damage.zone {
+            HitZone::Body => "body",
+            HitZone::Head => "head",
+        };
+
+        host.call(
+            "on_hit",
+            (
+                damage.target.to_bits() as i64,
+                damage.amount as f64,
+                zone.to_owned(),
+            ),
+        );
+    }
+}
+
+fn script_on_wave_start(mut host: ResMut<ScriptHost>, mut wave_reader: MessageReader<WaveStarted>) {
+    for WaveStarted(wave) in wave_reader.read() {
+        host.call("on_wave_start", (*wave as i64,));
+    }
+}
+
+fn apply_script_actions(
+    mut commands: Commands,
+    host: Res<ScriptHost>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    player: Single<(&mut Transform, &mut LinearVelocity, &mut Inventory), With<PrimaryPlayer>>,
+) {
+    let actions = std::mem::take(&mut *host.actions.lock().unwrap());
+
+    if actions.is_empty() {
+        return;
+    }
+
+    let (mut transform, mut velocity, mut inventory) = player.into_inner();
+
+    for action in actions {
+        match action {
+            ScriptAction::SpawnTarget { position, armored } => {
+                commands.spawn_target(position, armored);
+            }
+            ScriptAction::Teleport(position) => {
+                transform.translation = position;
+                velocity.0 = Vec3::ZERO;
+            }
+            ScriptAction::GiveItem { item, count } => inventory.add(&item, count),
+            ScriptAction::SetTime(hours) => time_of_day.set(hours),
+            ScriptAction::Log(message) => info!(target: "script", "{message}"),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>()
+            .init_resource::<Keybinds>()
+            .add_systems(Update, toggle_hardcore);
+    }
+}
+
+/// Player facing options that other plugins read from.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Hardcore mode removes all hit and kill confirmation feedback
+    pub hardcore: bool,
+    /// Shift the pitch of sounds moving towards or away from the player
+    pub doppler: bool,
+    /// How much the view moves with the player's breathing, `0..=1`, turned down for players who
+    /// get motion sick
+    pub view_motion: f32,
+    /// Lower the resolution the game's drawn at while frames run slow, see
+    /// [`crate::dynamic_resolution`]
+    pub dynamic_resolution: bool,
+    /// The frame rate dynamic resolution tries to hold
+    pub target_fps: f32,
+    /// The lowest and highest share of full resolution dynamic resolution picks from
+    pub render_scale_bounds: (f32, f32),
+    /// Keep the last few seconds of play in memory for the clip key, see [`crate::capture`]
+    pub record_clips: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            hardcore: false,
+            doppler: true,
+            view_motion: 1.0,
+            dynamic_resolution: false,
+            target_fps: 60.0,
+            render_scale_bounds: (0.5, 1.0),
+            record_clips: false,
+        }
+    }
+}
+
+impl GameSettings {
+    pub fn hit_confirmation(&self) -> bool {
+        !self.hardcore
+    }
+}
+
+/// The keys bound to each player action.
+///
+/// The arrow keys always move as well, whatever the movement keys are bound to.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Keybinds {
+    pub forward: KeyCode,
+    pub back: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub jump: KeyCode,
+    pub dash: KeyCode,
+    pub sprint: KeyCode,
+    pub crouch: KeyCode,
+    pub prone: KeyCode,
+    pub stim: KeyCode,
+    /// Cycles night and thermal vision
+    pub vision: KeyCode,
+    pub flare: KeyCode,
+    /// Gets in and out of vehicles
+    pub interact: KeyCode,
+    pub grapple: KeyCode,
+    pub melee: KeyCode,
+    /// Held to peek out around cover
+    pub lean_left: KeyCode,
+    pub lean_right: KeyCode,
+    /// Switches to and from a weapon's underbarrel launcher or shotgun
+    pub underbarrel: KeyCode,
+    pub reload: KeyCode,
+    /// Cycles between a weapon's trigger modes
+    pub fire_mode: KeyCode,
+    pub screenshot: KeyCode,
+    /// Saves the last few seconds of play
+    pub clip: KeyCode,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            back: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            jump: KeyCode::Space,
+            dash: KeyCode::AltLeft,
+            sprint: KeyCode::ShiftLeft,
+            crouch: KeyCode::ControlLeft,
+            prone: KeyCode::KeyZ,
+            stim: KeyCode::KeyH,
+            vision: KeyCode::KeyN,
+            flare: KeyCode::KeyG,
+            interact: KeyCode::KeyF,
+            grapple: KeyCode::KeyC,
+            melee: KeyCode::KeyK,
+            lean_left: KeyCode::KeyQ,
+            lean_right: KeyCode::KeyE,
+            underbarrel: KeyCode::KeyB,
+            reload: KeyCode::KeyR,
+            fire_mode: KeyCode::KeyX,
+            screenshot: KeyCode::F12,
+            clip: KeyCode::F11,
+        }
+    }
+}
+
+impl Keybinds {
+    /// Puts actions sharing a key with another back on their default key.
+    ///
+    /// Saves from before a default changed keep their old binds, which can land on a key a newer
+    /// action has taken since, so these are sorted out when a profile's loaded. Only binds off
+    /// their default are moved, until no two actions share a key.
+    pub fn reset_duplicates(&mut self) {
+        let mut defaults = Self::default();
+        let defaults = defaults.binds().map(|(_, key)| *key);
+
+        loop {
+            let mut binds = self.binds();
+            let keys = binds.each_ref().map(|(_, key)| **key);
+            let mut changed = false;
+
+            for (index, (name, key)) in binds.iter_mut().enumerate() {
+                let shared = keys
+                    .iter()
+                    .enumerate()
+                    .any(|(other, other_key)| other != index && other_key == *key);
+
+                if shared && **key != defaults[index] {
+                    warn!(
+                        "{name} shared {:?} with another action, moved back to {:?}",
+                        **key, defaults[index]
+                    );
+                    **key = defaults[index];
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn binds(&mut self) -> [(&'static str, &mut KeyCode); 22] {
+        [
+            ("forward", &mut self.forward),
+            ("back", &mut self.back),
+            ("left", &mut self.left),
+            ("right", &mut self.right),
+            ("jump", &mut self.jump),
+            ("dash", &mut self.dash),
+            ("sprint", &mut self.sprint),
+            ("crouch", &mut self.crouch),
+            ("prone", &mut self.prone),
+            ("stim", &mut self.stim),
+            ("vision", &mut self.vision),
+            ("flare", &mut self.flare),
+            ("interact", &mut self.interact),
+            ("grapple", &mut self.grapple),
+            ("melee", &mut self.melee),
+            ("lean_left", &mut self.lean_left),
+            ("lean_right", &mut self.lean_right),
+            ("underbarrel", &mut self.underbarrel),
+            ("reload", &mut self.reload),
+            ("fire_mode", &mut self.fire_mode),
+            ("screenshot", &mut self.screenshot),
+            ("clip", &mut self.clip),
+        ]
+    }
+}
+
+fn toggle_hardcore(mut settings: ResMut<GameSettings>, keyboard_input: Res<ButtonInput<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        settings.hardcore = !settings.hardcore;
+        info!("hardcore mode: {}", settings.hardcore);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_binds_clashing_with_new_defaults_are_reset() {
+        // saved before interact and grapple moved to make room for leaning
+        let mut keybinds = Keybinds {
+            interact: KeyCode::KeyE,
+            grapple: KeyCode::KeyQ,
+            reload: KeyCode::KeyT,
+            ..default()
+        };
+        keybinds.reset_duplicates();
+
+        assert_eq!(keybinds.interact, KeyCode::KeyF);
+        assert_eq!(keybinds.grapple, KeyCode::KeyC);
+        assert_eq!(keybinds.lean_left, KeyCode::KeyQ);
+        assert_eq!(keybinds.lean_right, KeyCode::KeyE);
+        assert_eq!(
+            keybinds.reload,
+            KeyCode::KeyT,
+            "binds on a key of their own stay"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Named points on weapon models.
+//!
+//! A weapon's GLTF marks where its muzzle, ejection port, sight, grip and attachment rail are with
+//! nodes called `muzzle`, `eject`, `sight`, `grip` and `attachment`. Whenever the weapon's scene
+//! spawns, which includes every time the model changes on disk and hot reloading respawns it, the
+//! nodes are looked up again: each gets the marker component for its socket, like
+//! [`SightSocket`], [`WeaponSockets`] is refilled, and each [`SocketAnchor`] on the weapon is moved
+//! onto its socket so the effects hanging off it follow the new model without a restart. Anchors
+//! for sockets a model doesn't have sit at the model's origin.
+
+use bevy::{platform::collections::HashMap, prelude::*, scene::SceneInstanceReady};
+
+use crate::PlayerWeaponTransformConfig;
+
+pub struct SocketsPlugin;
+
+impl Plugin for SocketsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(bind_sockets);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Socket {
+    Muzzle,
+    Eject,
+    Sight,
+    Grip,
+    Attachment,
+}
+
+impl Socket {
+    pub const ALL: [Socket; 5] = [
+        Socket::Muzzle,
+        Socket::Eject,
+        Socket::Sight,
+        Socket::Grip,
+        Socket::Attachment,
+    ];
+
+    /// The name of the GLTF node marking this socket
+    pub fn node_name(self) -> &'static str {
+        match self {
+            Socket::Muzzle => "muzzle",
+            Socket::Eject => "eject",
+            Socket::Sight => "sight",
+            Socket::Grip => "grip",
+            Socket::Attachment => "attachment",
+        }
+    }
+
+    fn mark(self, node: &mut EntityCommands) {
+        match self {
+            Socket::Muzzle => node.insert(MuzzleSocket),
+            Socket::Eject => node.insert(EjectSocket),
+            Socket::Sight => node.insert(SightSocket),
+            Socket::Grip => node.insert(GripSocket),
+            Socket::Attachment => node.insert(AttachmentSocket),
+        };
+    }
+}
+
+/// The node rounds leave a weapon's model from.
+#[derive(Component, Debug)]
+pub struct MuzzleSocket;
+
+/// The node spent cases fly out of.
+#[derive(Component, Debug)]
+pub struct EjectSocket;
+
+/// The node on the line of sight, lined up with the eye when aiming.
+#[derive(Component, Debug)]
+pub struct SightSocket;
+
+/// The node the hand holds.
+#[derive(Component, Debug)]
+pub struct GripSocket;
+
+/// The node attachments like lights and lasers mount on.
+#[derive(Component, Debug)]
+pub struct AttachmentSocket;
+
+/// Where each socket found in a weapon's model is, relative to the weapon.
+#[derive(Component, Debug, Default)]
+pub struct WeaponSockets(HashMap<Socket, Transform>);
+
+impl WeaponSockets {
+    pub fn get(&self, socket: Socket) -> Option<Transform> {
+        self.0.get(&socket).copied()
+    }
+}
+
+/// A child of a weapon kept on one of its sockets, for effects like muzzle smoke.
+///
+/// Only the socket's position is taken, the anchor keeps facing the way the weapon does, so a
+/// node exported with a different forward axis can't send rounds off sideways.
+#[derive(Component, Debug)]
+#[require(Transform)]
+pub struct SocketAnchor(pub Socket);
+
+/// Finds the sockets in a weapon's freshly spawned scene, marks them and moves the weapon's
+/// anchors onto them.
+fn bind_sockets(
+    ready: On<SceneInstanceReady>,
+    mut commands: Commands,
+    mut weapons: Query<(
+        &mut WeaponSockets,
+        &Children,
+        Option<&mut PlayerWeaponTransformConfig>,
+    )>,
+    nodes: Query<(&Transform, Option<&Name>, Option<&Children>), Without<SocketAnchor>>,
+    mut anchors: Query<(&SocketAnchor, &mut Transform)>,
+) {
+    let Ok((mut sockets, children, transform_config)) = weapons.get_mut(ready.entity) else {
+        return;
+    };
+
+    sockets.0.clear();
+
+    // each node with its parent's transform relative to the weapon
+    let mut stack: Vec<_> = children
+        .iter()
+        .map(|child| (child, Transform::IDENTITY))
+        .collect();
+
+    while let Some((node, parent_transform)) = stack.pop() {
+        let Ok((transform, name, node_children)) = nodes.get(node) else {
+            continue;
+        };
+
+        let transform = parent_transform * *transform;
+
+        if let Some(name) = name
+            && let Some(socket) = Socket::ALL
+                .into_iter()
+                .find(|socket| socket.node_name() == name.as_str())
+        {
+            socket.mark(&mut commands.entity(node));
+            sockets.0.insert(socket, transform);
+        }
+
+        if let Some(node_children) = node_children {
+            stack.extend(node_children.iter().map(|child| (child, transform)));
+        }
+    }
+
+    if let Some(mut transform_config) = transform_config {
+        transform_config.sight = sockets.get(Socket::Sight).map(|sight| sight.translation);
+        transform_config.align_sight();
+    }
+
+    for child in children {
+        if let Ok((anchor, mut transform)) = anchors.get_mut(*child) {
+            transform.translation = sockets
+                .get(anchor.0)
+                .map_or(Vec3::ZERO, |socket| socket.translation);
+        }
+    }
+
+    debug!(
+        "bound sockets {:?} on {}",
+        sockets.0.keys().collect::<Vec<_>>(),
+        ready.entity
+    );
+}
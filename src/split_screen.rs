@@ -0,0 +1,224 @@
+//! Local multiplayer on one screen.
+//!
+//! Starting the game with `--players <n>` spawns 2 to 4 players through [`crate::spawn_player`],
+//! each with their own camera, viewport and [`PlayerInput`]. Player one plays on keyboard and
+//! mouse and everyone else on a gamepad, in the order the gamepads were connected. A lone player
+//! can use either. Every view draws its own crosshair and hitmarker, the rest of the HUD follows
+//! player one.
+
+use bevy::{
+    camera::Viewport, input::mouse::AccumulatedMouseMotion, prelude::*, window::PrimaryWindow,
+};
+
+use crate::dynamic_resolution::ScaledView;
+use crate::touch::TouchControls;
+
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LocalPlayers::from_args())
+            .add_systems(Update, update_viewports);
+    }
+}
+
+/// How many players are sharing the screen.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct LocalPlayers(pub usize);
+
+impl LocalPlayers {
+    pub const MAX: usize = 4;
+
+    /// Read `--players <n>` from the command line, falling back to one player
+    fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+
+        let count = args
+            .windows(2)
+            .find(|pair| pair[0] == "--players")
+            .and_then(|pair| match pair[1].parse::<usize>() {
+                Ok(count) => Some(count),
+                Err(err) => {
+                    warn!("invalid player count '{}': {err}", pair[1]);
+                    None
+                }
+            })
+            .unwrap_or(1);
+
+        Self(count.clamp(1, Self::MAX))
+    }
+}
+
+/// How a player spawned by [`crate::spawn_player`] is set up.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerConfig {
+    /// Which of the local players this is, from 0
+    pub index: usize,
+    pub input: PlayerInput,
+    pub position: Vec3,
+}
+
+impl PlayerConfig {
+    /// The usual setup for player `index` of `count`, spread out along the X axis
+    pub fn new(index: usize, count: usize) -> Self {
+        Self {
+            index,
+            input: PlayerInput::for_slot(index, count),
+            position: Vec3::new(0.5 + index as f32 * 2.0, 1.5, 0.5),
+        }
+    }
+}
+
+/// The devices a player is controlled with.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PlayerInput {
+    pub keyboard_mouse: bool,
+    /// Which connected gamepad, counting in the order they were connected
+    pub gamepad: Option<usize>,
+}
+
+impl PlayerInput {
+    /// Gamepad right stick look speed, in the same units as mouse motion
+    const STICK_LOOK_SPEED: f32 = 12.0;
+
+    /// Player one gets the keyboard and mouse, and the first gamepad when playing alone
+    pub fn for_slot(index: usize, count: usize) -> Self {
+        match (index, count) {
+            (0, 1) => Self {
+                keyboard_mouse: true,
+                gamepad: Some(0),
+            },
+            (0, _) => Self {
+                keyboard_mouse: true,
+                gamepad: None,
+            },
+            (index, _) => Self {
+                keyboard_mouse: false,
+                gamepad: Some(index - 1),
+            },
+        }
+    }
+
+    /// The player's gamepad, if it's connected
+    pub fn gamepad<'a>(&self, gamepads: &'a Query<&Gamepad>) -> Option<&'a Gamepad> {
+        self.gamepad
+            .and_then(|index| gamepads.iter().sort::<Entity>().nth(index))
+    }
+
+    /// How far the player wants to look this frame, as mouse motion. Touch controls go with the
+    /// keyboard and mouse
+    pub fn look(
+        &self,
+        mouse_motion: &AccumulatedMouseMotion,
+        gamepads: &Query<&Gamepad>,
+        touch: &TouchControls,
+    ) -> Vec2 {
+        let mouse = if self.keyboard_mouse {
+            mouse_motion.delta + touch.look()
+        } else {
+            Vec2::ZERO
+        };
+
+        // stick up looks up, where mouse motion is measured downwards
+        let stick = self.gamepad(gamepads).map_or(Vec2::ZERO, |gamepad| {
+            gamepad.right_stick() * Vec2::new(1.0, -1.0)
+        });
+
+        mouse + stick * Self::STICK_LOOK_SPEED
+    }
+}
+
+/// The camera showing the view of the local player with this index.
+#[derive(Component, Debug)]
+pub struct PlayerView(pub usize);
+
+/// Player one, who the profile, the shared HUD and the keyboard-only actions belong to.
+///
+/// There's only ever one, so systems with nothing to do for the other players can take it as a
+/// `Single`, where `With<Player>` would match every local player and skip the system.
+#[derive(Component, Debug)]
+pub struct PrimaryPlayer;
+
+/// The part of a `size` screen that view `index` of `count` is drawn to: stacked halves for
+/// two players, quarters for three or four
+pub fn viewport_rect(index: usize, count: usize, size: UVec2) -> URect {
+    let (columns, rows) = match count {
+        0 | 1 => (1, 1),
+        2 => (1, 2),
+        _ => (2, 2),
+    };
+
+    let cell = size / UVec2::new(columns, rows);
+    let index = index as u32;
+    let min = cell * UVec2::new(index % columns, index / columns);
+
+    URect::from_corners(min, min + cell)
+}
+
+fn update_viewports(
+    players: Res<LocalPlayers>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    // scaled views are laid out by `dynamic_resolution` instead
+    cameras: Query<(&mut Camera, &PlayerView), Without<ScaledView>>,
+) {
+    if players.0 < 2 {
+        return;
+    }
+
+    let size = window.physical_size();
+
+    for (mut camera, view) in cameras {
+        let rect = viewport_rect(view.0, players.0, size);
+
+        let viewport = Viewport {
+            physical_position: rect.min,
+            physical_size: rect.size(),
+            ..default()
+        };
+
+        if camera.viewport.as_ref().is_none_or(|current| {
+            current.physical_position != viewport.physical_position
+                || current.physical_size != viewport.physical_size
+        }) {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_screen_viewports_tile_the_screen() {
+        let size = UVec2::new(1920, 1080);
+        let rects = |count| {
+            (0..count)
+                .map(|index| viewport_rect(index, count, size))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(rects(1), [URect::new(0, 0, 1920, 1080)]);
+        assert_eq!(
+            rects(2),
+            [URect::new(0, 0, 1920, 540), URect::new(0, 540, 1920, 1080)]
+        );
+
+        let quarters = rects(4);
+        assert_eq!(quarters[1], URect::new(960, 0, 1920, 540));
+        assert_eq!(quarters[3], URect::new(960, 540, 1920, 1080));
+        assert_eq!(rects(3), quarters[..3]);
+    }
+
+    #[test]
+    fn split_screen_players_get_their_own_gamepads() {
+        let solo = PlayerInput::for_slot(0, 1);
+        assert!(solo.keyboard_mouse);
+        assert_eq!(solo.gamepad, Some(0));
+
+        let inputs = (0..3).map(|index| PlayerInput::for_slot(index, 3));
+        let gamepads: Vec<_> = inputs.map(|input| input.gamepad).collect();
+
+        assert_eq!(gamepads, [None, Some(0), Some(1)]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Soldiers fighting together.
+//!
+//! A [`Squad`] is a group of soldiers, each with a [`SquadRole`]. Whenever one of them sees a
+//! player the whole squad knows where, so the rest turn on them too. Suppressors keep up fire on
+//! that spot whether they can see it or not, pinning the player down while flankers work round to
+//! their side, and those holding their ground take it in turns to fire, one after another every
+//! `stagger` seconds, rather than all at once. Squads and their roles come from a timeline's
+//! `SpawnSquad` events, so each wave of a game mode can be set up differently.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use smallvec::SmallVec;
+
+use crate::ai::{AiSystems, Soldier};
+use crate::ai_presets::AiPreset;
+use crate::scene::SpawnArenaExt;
+
+pub struct SquadPlugin;
+
+impl Plugin for SquadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (share_sightings, stagger_attacks, disband_squads)
+                .chain()
+                .after(AiSystems::Perceive)
+                .before(AiSystems::Act),
+        );
+    }
+}
+
+/// What a soldier does for its squad.
+#[derive(Component, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquadRole {
+    /// Fires at where a player was last seen, faster and without waiting its turn
+    Suppress,
+    /// Goes round to the side of a player once one's been seen, then fights from there
+    Flank,
+    /// Stands its ground, firing in turn
+    Hold,
+}
+
+impl SquadRole {
+    /// The role of member `index` of a squad that wasn't given one: a suppressor, then a flanker,
+    /// then the rest holding
+    pub fn assign(index: usize) -> Self {
+        match index {
+            0 => SquadRole::Suppress,
+            1 => SquadRole::Flank,
+            _ => SquadRole::Hold,
+        }
+    }
+}
+
+/// A group of soldiers fighting together, see [`spawn_squad`].
+#[derive(Component, Debug)]
+pub struct Squad {
+    /// The player a member last saw, and where
+    pub last_known: Option<(Entity, Vec3)>,
+    /// Seconds between members holding their ground taking their turn to fire
+    pub stagger: f32,
+    /// Seconds until the next member's turn
+    turn: f32,
+    /// Counts through the members holding their ground
+    next: usize,
+}
+
+impl Squad {
+    /// Metres beside a player flankers go to
+    const FLANK_DISTANCE: f32 = 12.0;
+}
+
+/// The squad a soldier is in.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = SquadMembers)]
+pub struct InSquad(pub Entity);
+
+/// The soldiers in a squad, despawned along with it.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = InSquad, linked_spawn)]
+pub struct SquadMembers(Vec<Entity>);
+
+/// Where a flanker is going before it fights.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct FlankTo(pub Vec3);
+
+/// Marks a soldier waiting its turn to fire.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct HoldFire;
+
+/// One soldier in a squad's definition.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SquadMemberDef {
+    pub position: Vec3,
+    /// Given one by [`SquadRole::assign`] if left out
+    #[serde(default)]
+    pub role: Option<SquadRole>,
+    /// The AI preset it fights with, `regular` if left out
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+/// Spawns a squad of soldiers, returning the squad.
+pub fn spawn_squad(commands: &mut Commands, members: &[SquadMemberDef], stagger: f32) -> Entity {
+    let squad = commands
+        .spawn((
+            Name::new("Squad"),
+            Squad {
+                last_known: None,
+                stagger,
+                turn: stagger,
+                next: 0,
+            },
+        ))
+        .id();
+
+    for (index, member) in members.iter().enumerate() {
+        let role = member.role.unwrap_or_else(|| SquadRole::assign(index));
+        let mut soldier = commands.spawn_soldier(member.position);
+        soldier.insert((role, InSquad(squad)));
+        if let Some(preset) = &member.preset {
+            soldier.insert(AiPreset(preset.clone()));
+        }
+    }
+
+    squad
+}
+
+/// Tells every member of a squad where a player is as soon as one of them sees them, and sends
+/// the flankers off the first time.
+fn share_sightings(
+    mut commands: Commands,
+    mut squads: Query<(&mut Squad, &SquadMembers)>,
+    mut soldiers: Query<(&mut Soldier, &SquadRole, &Transform)>,
+) {
+    for (mut squad, members) in &mut squads {
+        let Some(spotted) = members
+            .iter()
+            .find_map(|member| soldiers.get(member).ok()?.0.spotted())
+        else {
+            continue;
+        };
+
+        let first_sighting = squad.last_known.is_none();
+        squad.last_known = Some(spotted);
+
+        let (_, seen_at) = spotted;
+        let centre = members
+            .iter()
+            .filter_map(|member| soldiers.get(member).ok())
+            .map(|(_, _, transform)| transform.translation)
+            .sum::<Vec3>()
+            / members.len() as f32;
+        let across = (seen_at - centre)
+            .with_y(0.0)
+            .cross(Vec3::Y)
+            .normalize_or_zero();
+
+        let mut flankers = 0;
+
+        for member in members.iter() {
+            let Ok((mut soldier, role, _)) = soldiers.get_mut(member) else {
+                continue;
+            };
+
+            soldier.alert(spotted);
+
+            if first_sighting && *role == SquadRole::Flank {
+                // alternating sides
+                let side = if flankers % 2 == 0 { 1.0 } else { -1.0 };
+                flankers += 1;
+
+                commands
+                    .entity(member)
+                    .insert(FlankTo(seen_at + across * side * Squad::FLANK_DISTANCE));
+            }
+        }
+    }
+}
+
+/// Clears one member holding its ground to fire at a time, in turn.
+fn stagger_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut squads: Query<(&mut Squad, &SquadMembers)>,
+    roles: Query<&SquadRole>,
+) {
+    for (mut squad, members) in &mut squads {
+        let holding: SmallVec<[Entity; 8]> = members
+            .iter()
+            .filter(|member| {
+                roles
+                    .get(*member)
+                    .is_ok_and(|role| *role == SquadRole::Hold)
+            })
+            .collect();
+
+        if holding.is_empty() {
+            continue;
+        }
+
+        squad.turn -= time.delta_secs();
+        if squad.turn <= 0.0 {
+            squad.turn = squad.stagger;
+            squad.next += 1;
+        }
+
+        let turn = squad.next % holding.len();
+
+        for (index, member) in holding.into_iter().enumerate() {
+            // everyone fires at will without a stagger
+            if squad.stagger > 0.0 && index != turn {
+                commands.entity(member).insert(HoldFire);
+            } else {
+                commands.entity(member).remove::<HoldFire>();
+            }
+        }
+    }
+}
+
+/// Squads go when the last of their members does.
+fn disband_squads(
+    mut commands: Commands,
+    squads: Query<Entity, (With<Squad>, Without<SquadMembers>)>,
+) {
+    for squad in squads {
+        commands.entity(squad).despawn();
+    }
+}
@@ -0,0 +1,379 @@
+//! Timed buffs and debuffs.
+//!
+//! A [`StatusEffects`] component holds every effect currently on a character. Each [`StatusKind`]
+//! has a fixed set of [`Modifiers`] and a [`Stacking`] rule deciding what happens when it is
+//! applied again while still active. Systems that care about a stat read the combined
+//! [`StatusEffects::modifiers`] rather than tracking their own timers.
+
+use bevy::prelude::*;
+
+use crate::damage::{DamageEvent, Health};
+use crate::energy::{StaminaDepleted, StaminaRecovered};
+use crate::inventory::Inventory;
+use crate::menu::GameState;
+use crate::movement::Sprinting;
+use crate::settings::Keybinds;
+use crate::split_screen::PrimaryPlayer;
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<StatusApplied>()
+            .add_message::<StatusExpired>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        use_stim
+                            .run_if(crate::console::console_closed)
+                            .run_if(crate::chat::chat_closed)
+                            .run_if(in_state(GameState::InGame)),
+                        winded_after_sprint,
+                        exhaustion_status,
+                        damage_status,
+                    ),
+                    tick_status_effects,
+                    log_status,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusKind {
+    /// A combat stimulant: quicker recovery, steadier aim
+    Stim,
+    /// Rounds landing close by
+    Suppressed,
+    /// Out of stamina, lasts until the stamina recovers
+    Exhausted,
+    /// Catching breath after a sprint
+    Winded,
+    /// A heavy hit that hasn't been treated
+    Injured,
+}
+
+impl StatusKind {
+    /// How long the effect lasts, `None` for effects removed by whatever applied them
+    fn duration(&self) -> Option<f32> {
+        match self {
+            Self::Stim => Some(30.0),
+            Self::Suppressed => Some(2.5),
+            Self::Exhausted => None,
+            Self::Winded => Some(3.0),
+            Self::Injured => Some(60.0),
+        }
+    }
+
+    fn stacking(&self) -> Stacking {
+        match self {
+            Self::Stim => Stacking::Extend { max: 60.0 },
+            Self::Suppressed | Self::Winded | Self::Exhausted => Stacking::Refresh,
+            Self::Injured => Stacking::Stack { max: 3 },
+        }
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        match self {
+            Self::Stim => Modifiers {
+                regen: 1.5,
+                speed: 1.1,
+                sway: 0.6,
+                ..default()
+            },
+            Self::Suppressed => Modifiers {
+                sway: 1.8,
+                damage: 0.9,
+                ..default()
+            },
+            Self::Exhausted => Modifiers {
+                speed: 0.85,
+                ..default()
+            },
+            Self::Winded => Modifiers::default(),
+            Self::Injured => Modifiers {
+                regen: 0.8,
+                speed: 0.9,
+                sway: 1.2,
+                ..default()
+            },
+        }
+    }
+}
+
+/// What happens when an effect is applied while it is already active.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stacking {
+    /// Restart the existing effect's timer
+    Refresh,
+    /// Add the new duration to the existing effect, up to a total of `max` seconds
+    Extend { max: f32 },
+    /// Add another independent copy, up to `max` copies
+    Stack { max: usize },
+}
+
+/// Multipliers on character stats, 1.0 leaves a stat unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Modifiers {
+    /// Stamina regeneration
+    pub regen: f32,
+    /// Movement acceleration
+    pub speed: f32,
+    /// Weapon sway
+    pub sway: f32,
+    /// Damage dealt
+    pub damage: f32,
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self {
+            regen: 1.0,
+            speed: 1.0,
+            sway: 1.0,
+            damage: 1.0,
+        }
+    }
+}
+
+impl std::ops::Mul for Modifiers {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            regen: self.regen * other.regen,
+            speed: self.speed * other.speed,
+            sway: self.sway * other.sway,
+            damage: self.damage * other.damage,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ActiveEffect {
+    kind: StatusKind,
+    /// Seconds left, `None` if the effect doesn't expire on its own
+    remaining: Option<f32>,
+}
+
+/// Every status effect on a character.
+#[derive(Component, Default, Debug)]
+pub struct StatusEffects {
+    active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, kind: StatusKind) {
+        let duration = kind.duration();
+        let count = self.active.iter().filter(|e| e.kind == kind).count();
+        let existing = self.active.iter_mut().find(|e| e.kind == kind);
+
+        match (kind.stacking(), existing) {
+            (_, None) => self.active.push(ActiveEffect {
+                kind,
+                remaining: duration,
+            }),
+            (Stacking::Refresh, Some(effect)) => effect.remaining = duration,
+            (Stacking::Extend { max }, Some(effect)) => {
+                effect.remaining = effect
+                    .remaining
+                    .zip(duration)
+                    .map(|(remaining, duration)| (remaining + duration).min(max));
+            }
+            (Stacking::Stack { max }, Some(_)) => {
+                if count < max {
+                    self.active.push(ActiveEffect {
+                        kind,
+                        remaining: duration,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Remove every copy of an effect, returning whether there were any
+    pub fn remove(&mut self, kind: StatusKind) -> bool {
+        let before = self.active.len();
+        self.active.retain(|effect| effect.kind != kind);
+        self.active.len() != before
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.active.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Every active effect's modifiers combined
+    pub fn modifiers(&self) -> Modifiers {
+        self.active
+            .iter()
+            .fold(Modifiers::default(), |modifiers, effect| {
+                modifiers * effect.kind.modifiers()
+            })
+    }
+}
+
+/// An event sent when a status effect is put on a character.
+#[derive(Message, Debug)]
+pub struct StatusApplied {
+    pub entity: Entity,
+    pub kind: StatusKind,
+}
+
+/// An event sent when a status effect runs out or is removed.
+#[derive(Message, Debug)]
+pub struct StatusExpired {
+    pub entity: Entity,
+    pub kind: StatusKind,
+}
+
+/// Applies an effect and sends the matching [`StatusApplied`] event.
+fn apply_status(
+    entity: Entity,
+    effects: &mut StatusEffects,
+    kind: StatusKind,
+    writer: &mut MessageWriter<StatusApplied>,
+) {
+    effects.apply(kind);
+    writer.write(StatusApplied { entity, kind });
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut expired_writer: MessageWriter<StatusExpired>,
+    query: Query<(Entity, &mut StatusEffects)>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, mut effects) in query {
+        effects.active.retain_mut(|effect| {
+            let Some(remaining) = &mut effect.remaining else {
+                return true;
+            };
+
+            *remaining -= delta;
+
+            if *remaining > 0.0 {
+                return true;
+            }
+
+            expired_writer.write(StatusExpired {
+                entity,
+                kind: effect.kind,
+            });
+            false
+        });
+    }
+}
+
+fn use_stim(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    mut applied_writer: MessageWriter<StatusApplied>,
+    player: Single<(Entity, &mut Inventory, &mut StatusEffects), With<PrimaryPlayer>>,
+) {
+    if !keys.just_pressed(keybinds.stim) {
+        return;
+    }
+
+    let (entity, mut inventory, mut effects) = player.into_inner();
+
+    if !inventory.take("stim", 1) {
+        debug!("no stims left");
+        return;
+    }
+
+    apply_status(entity, &mut effects, StatusKind::Stim, &mut applied_writer);
+}
+
+fn winded_after_sprint(
+    mut stopped: RemovedComponents<Sprinting>,
+    mut applied_writer: MessageWriter<StatusApplied>,
+    mut query: Query<&mut StatusEffects>,
+) {
+    for entity in stopped.read() {
+        if let Ok(mut effects) = query.get_mut(entity) {
+            apply_status(
+                entity,
+                &mut effects,
+                StatusKind::Winded,
+                &mut applied_writer,
+            );
+        }
+    }
+}
+
+fn exhaustion_status(
+    mut depleted_reader: MessageReader<StaminaDepleted>,
+    mut recovered_reader: MessageReader<StaminaRecovered>,
+    mut applied_writer: MessageWriter<StatusApplied>,
+    mut expired_writer: MessageWriter<StatusExpired>,
+    mut query: Query<&mut StatusEffects>,
+) {
+    for StaminaDepleted(entity) in depleted_reader.read() {
+        if let Ok(mut effects) = query.get_mut(*entity) {
+            apply_status(
+                *entity,
+                &mut effects,
+                StatusKind::Exhausted,
+                &mut applied_writer,
+            );
+        }
+    }
+
+    for StaminaRecovered(entity) in recovered_reader.read() {
+        if let Ok(mut effects) = query.get_mut(*entity)
+            && effects.remove(StatusKind::Exhausted)
+        {
+            expired_writer.write(StatusExpired {
+                entity: *entity,
+                kind: StatusKind::Exhausted,
+            });
+        }
+    }
+}
+
+fn damage_status(
+    mut damage_reader: MessageReader<DamageEvent>,
+    mut applied_writer: MessageWriter<StatusApplied>,
+    mut query: Query<(&mut StatusEffects, &Health)>,
+) {
+    /// Share of max health a single hit has to take to injure
+    const INJURY_THRESHOLD: f32 = 0.25;
+
+    for damage in damage_reader.read() {
+        let Ok((mut effects, health)) = query.get_mut(damage.target) else {
+            continue;
+        };
+
+        apply_status(
+            damage.target,
+            &mut effects,
+            StatusKind::Suppressed,
+            &mut applied_writer,
+        );
+
+        if damage.amount >= health.max * INJURY_THRESHOLD {
+            apply_status(
+                damage.target,
+                &mut effects,
+                StatusKind::Injured,
+                &mut applied_writer,
+            );
+        }
+    }
+}
+
+fn log_status(
+    mut applied_reader: MessageReader<StatusApplied>,
+    mut expired_reader: MessageReader<StatusExpired>,
+) {
+    for StatusApplied { entity, kind } in applied_reader.read() {
+        debug!("{entity} gained {kind:?}");
+    }
+
+    for StatusExpired { entity, kind } in expired_reader.read() {
+        debug!("{entity} lost {kind:?}");
+    }
+}
@@ -0,0 +1,125 @@
+//! Where saves are kept.
+//!
+//! On the desktop saves are files under `saves/`. A browser has no file system to write to, so
+//! in a web build each save is kept in the page's local storage instead, keyed by the path it
+//! would have had on disk. Everything that saves goes through here so the rest of the game doesn't
+//! need to know which it's running on.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Read a whole save
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::read_to_string(path)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        local_storage()?
+            .get_item(&key(path))
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+/// Write a save so that it is either fully replaced or left untouched.
+///
+/// On the desktop the contents go to a sibling `.tmp` file that is flushed to disk and then
+/// renamed over `path`, which is atomic on the platforms we ship on. Local storage replaces an
+/// item in one go already.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::io::Write;
+
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let temporary = path.with_extension("tmp");
+
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        std::fs::rename(&temporary, path)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let contents = std::str::from_utf8(contents).map_err(io::Error::other)?;
+
+        local_storage()?
+            .set_item(&key(path), contents)
+            .map_err(js_error)
+    }
+}
+
+/// Move a save to `to`, replacing whatever was there
+pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::rename(from, to)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let storage = local_storage()?;
+        let contents = storage
+            .get_item(&key(from))
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+        storage.set_item(&key(to), &contents).map_err(js_error)?;
+        storage.remove_item(&key(from)).map_err(js_error)
+    }
+}
+
+/// The saves directly in `directory`, unsorted
+pub fn saves_in(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Ok(std::fs::read_dir(directory)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let storage = local_storage()?;
+        let mut saves = Vec::new();
+
+        for index in 0..storage.length().map_err(js_error)? {
+            let Some(key) = storage.key(index).map_err(js_error)? else {
+                continue;
+            };
+
+            let path = PathBuf::from(key);
+            if path.parent() == Some(directory) {
+                saves.push(path);
+            }
+        }
+
+        Ok(saves)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no local storage"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(err: web_sys::wasm_bindgen::JsValue) -> io::Error {
+    io::Error::other(format!("{err:?}"))
+}
@@ -0,0 +1,371 @@
+//! Alternative weapon sway models.
+//!
+//! By default a weapon lerps between random targets picked every breath (see `WeaponSway`). A
+//! [`SwayProfile`] on the weapon, usually set from its `*.weapon.ron` definition, swaps that for a
+//! spring-damper or layered noise model. Both are shaped by [`SwayBands`], which pick the sway
+//! amplitude and frequency depending on whether the breathing character is idle, exhausted or
+//! getting their breath back after a sprint. Mounted guns use [`SwayProfile::None`] and don't
+//! sway at all.
+//!
+//! Whatever the model, a player aiming down the sights through the bottom of an exhale gets a
+//! [`RespiratoryPause`]: for a moment, as long as the difficulty allows, the sway all but stops.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::Difficulty;
+use crate::encumbrance::Encumbrance;
+use crate::input_buffer::{Action, ActionBuffer};
+use crate::movement::{Sprinting, Stance};
+use crate::status::{StatusEffects, StatusKind};
+use crate::{
+    Breath, BreathDirection, Player, SwayTargets, TransformPipeline, WeaponActive, WeaponSway,
+};
+
+/// Which sway model drives a weapon.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Default)]
+#[require(SwayMotion)]
+pub enum SwayProfile {
+    /// Lerp between random targets picked every breath
+    #[default]
+    Breath,
+    /// Held perfectly still, for guns on a mount
+    None,
+    /// Chase a wandering target through a spring-damper
+    Spring {
+        stiffness: f32,
+        damping: f32,
+        #[serde(default)]
+        bands: SwayBands,
+    },
+    /// Follow layered 1D Perlin noise on each axis
+    Noise {
+        octaves: u32,
+        #[serde(default)]
+        bands: SwayBands,
+    },
+}
+
+/// How far and how fast a weapon sways.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SwayBand {
+    /// Largest offset from the rest position, in metres
+    pub amplitude: f32,
+    /// Sway cycles per second
+    pub frequency: f32,
+}
+
+impl SwayProfile {
+    /// Metres it sways off at rest, at most, for a weapon that sways `breathing` metres with each
+    /// breath
+    pub fn idle_amplitude(&self, breathing: f32) -> f32 {
+        match self {
+            SwayProfile::Breath => breathing,
+            SwayProfile::None => 0.0,
+            SwayProfile::Spring { bands, .. } | SwayProfile::Noise { bands, .. } => {
+                bands.idle.amplitude
+            }
+        }
+    }
+}
+
+impl SwayBand {
+    const fn new(amplitude: f32, frequency: f32) -> Self {
+        Self {
+            amplitude,
+            frequency,
+        }
+    }
+}
+
+/// The sway band used in each state of the breathing character.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SwayBands {
+    pub idle: SwayBand,
+    pub fatigue: SwayBand,
+    pub post_sprint: SwayBand,
+}
+
+impl Default for SwayBands {
+    fn default() -> Self {
+        Self {
+            idle: SwayBand::new(0.002, 0.3),
+            fatigue: SwayBand::new(0.008, 1.2),
+            post_sprint: SwayBand::new(0.005, 0.8),
+        }
+    }
+}
+
+impl SwayBands {
+    fn band(&self, state: SwayState) -> SwayBand {
+        match state {
+            SwayState::Idle => self.idle,
+            SwayState::Fatigue => self.fatigue,
+            SwayState::PostSprint => self.post_sprint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SwayState {
+    Idle,
+    Fatigue,
+    PostSprint,
+}
+
+/// Where a profiled weapon has swayed to.
+#[derive(Component, Default, Debug)]
+pub struct SwayMotion {
+    offset: Vec3,
+    velocity: Vec3,
+    /// Where the spring is pulling towards
+    goal: Vec3,
+    /// Seconds until the spring picks a new goal
+    next_goal: f32,
+    /// Noise coordinate, advanced by the band frequency
+    phase: f32,
+}
+
+/// How steady a player's aim is through the natural pause at the bottom of their breath.
+#[derive(Component, Debug)]
+pub struct RespiratoryPause {
+    /// Whether they're in the pause now
+    pub active: bool,
+    steadiness: f32,
+}
+
+impl Default for RespiratoryPause {
+    fn default() -> Self {
+        Self {
+            active: false,
+            steadiness: 1.0,
+        }
+    }
+}
+
+impl RespiratoryPause {
+    /// Share of the usual sway left in the pause
+    const STEADY: f32 = 0.15;
+    /// Seconds to settle in and out of it, so the sway doesn't jump
+    const SETTLE: f32 = 0.08;
+
+    /// Multiplier on sway, easing between 1 and [`Self::STEADY`]
+    pub fn steadiness(&self) -> f32 {
+        self.steadiness
+    }
+}
+
+pub fn respiratory_pause(
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
+    players: Query<(&Breath, &ActionBuffer, &mut RespiratoryPause), With<Player>>,
+) {
+    let window = difficulty.preset().respiratory_pause;
+    let settle = 1.0 - (-time.delta_secs() / RespiratoryPause::SETTLE).exp();
+
+    for (breath, actions, mut pause) in players {
+        pause.active = actions.pressed(Action::Aim) && breath.in_respiratory_pause(window);
+
+        let target = if pause.active {
+            RespiratoryPause::STEADY
+        } else {
+            1.0
+        };
+
+        pause.steadiness = pause.steadiness.lerp(target, settle);
+    }
+}
+
+pub fn profile_sway(
+    time: Res<Time>,
+    difficulty: Res<Difficulty>,
+    breathers_q: Query<(
+        &Breath,
+        &SwayTargets,
+        Option<&StatusEffects>,
+        Option<&Encumbrance>,
+        Option<&RespiratoryPause>,
+        Option<&Stance>,
+        Has<Sprinting>,
+        Has<Player>,
+    )>,
+    mut targets_q: Query<
+        (&SwayProfile, &mut SwayMotion, &mut TransformPipeline),
+        With<WeaponActive>,
+    >,
+) {
+    let delta = time.delta_secs();
+    let mut rng = rand::rng();
+
+    for (breath, targets, effects, encumbrance, pause, stance, sprinting, is_player) in breathers_q
+    {
+        let hold_steadiness = breath.hold_steadiness();
+        let breath = breath.sample();
+
+        // difficulty only eases the player's own aim
+        let difficulty_scale = if is_player {
+            difficulty.preset().sway_scale
+        } else {
+            1.0
+        };
+
+        let has = |kind| effects.is_some_and(|effects| effects.has(kind));
+        let scale = difficulty_scale
+            * effects.map_or(1.0, |effects| effects.modifiers().sway)
+            * pause.map_or(1.0, RespiratoryPause::steadiness)
+            * stance.map_or(1.0, |stance| stance.sway_scale())
+            * hold_steadiness;
+
+        let state = if has(StatusKind::Exhausted) {
+            SwayState::Fatigue
+        } else if sprinting || has(StatusKind::Winded) {
+            SwayState::PostSprint
+        } else {
+            SwayState::Idle
+        };
+
+        for target in targets.iter() {
+            let Ok((profile, mut motion, mut pipeline)) = targets_q.get_mut(target) else {
+                continue;
+            };
+
+            // carried weight unsteadies a resting aim, heavy breathing already swamps it otherwise
+            let load_scale = if state == SwayState::Idle {
+                encumbrance.map_or(1.0, Encumbrance::sway_scale)
+            } else {
+                1.0
+            };
+
+            let (bands, offset) = match profile {
+                SwayProfile::Breath | SwayProfile::None => continue,
+                SwayProfile::Spring {
+                    stiffness,
+                    damping,
+                    bands,
+                } => {
+                    let band = bands.band(state);
+
+                    motion.next_goal -= delta;
+
+                    if motion.next_goal <= 0.0 {
+                        motion.goal = random_goal(&mut rng, band.amplitude);
+                        motion.next_goal = 1.0 / band.frequency.max(0.01);
+                    }
+
+                    let (offset, velocity) = spring_step(
+                        motion.offset,
+                        motion.velocity,
+                        motion.goal,
+                        *stiffness,
+                        *damping,
+                        delta,
+                    );
+
+                    motion.velocity = velocity;
+                    (bands, offset)
+                }
+                SwayProfile::Noise { octaves, bands } => {
+                    let band = bands.band(state);
+
+                    motion.phase += band.frequency * delta;
+
+                    let offset = Vec3::new(
+                        // smaller half-sway in the X, like the breath model
+                        layered_noise(motion.phase, *octaves, 0) * 0.5,
+                        layered_noise(motion.phase, *octaves, 1),
+                        layered_noise(motion.phase, *octaves, 2),
+                    ) * band.amplitude;
+
+                    (bands, offset)
+                }
+            };
+
+            motion.offset = offset;
+
+            // breathing in dips the weapon, breathing out raises it
+            let breath_sign = if breath.direction == BreathDirection::In {
+                -1.0
+            } else {
+                1.0
+            };
+            let breath_lift = breath_sign * breath.eased(EaseFunction::SmoothStep);
+            let lift = Vec3::Y * bands.band(state).amplitude * breath_lift;
+
+            let offset = (offset + lift) * scale * load_scale;
+            pipeline.queue(offset);
+            pipeline.queue_rotation(WeaponSway::tilt(offset));
+        }
+    }
+}
+
+fn random_goal(rng: &mut impl Rng, amplitude: f32) -> Vec3 {
+    if amplitude.is_nan() || amplitude <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let half = amplitude / 2.0;
+
+    Vec3::new(
+        rng.random_range(-half..=half),
+        rng.random_range(-amplitude..=amplitude),
+        rng.random_range(-amplitude..=amplitude),
+    )
+}
+
+/// Advance a spring-damper pulling `offset` towards `goal` by `delta` seconds
+fn spring_step(
+    offset: Vec3,
+    velocity: Vec3,
+    goal: Vec3,
+    stiffness: f32,
+    damping: f32,
+    delta: f32,
+) -> (Vec3, Vec3) {
+    let acceleration = (goal - offset) * stiffness - velocity * damping;
+
+    // semi-implicit Euler so stiff springs stay stable at the fixed timestep
+    let velocity = velocity + acceleration * delta;
+    (offset + velocity * delta, velocity)
+}
+
+/// Pseudo-random gradient in `-1..=1` for a noise lattice point
+fn gradient(cell: i32, seed: u32) -> f32 {
+    let mut hash = (cell as u32).wrapping_mul(0x9E37_79B1) ^ seed.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// 1D Perlin noise, roughly `-1..=1`
+fn perlin(x: f32, seed: u32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+
+    let a = gradient(cell as i32, seed) * t;
+    let b = gradient(cell as i32 + 1, seed) * (t - 1.0);
+
+    let fade = t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+
+    // 1D Perlin peaks at 0.5, so double it
+    (a + (b - a) * fade) * 2.0
+}
+
+/// Octaves of Perlin noise, each twice the frequency and half the weight of the last
+fn layered_noise(x: f32, octaves: u32, seed: u32) -> f32 {
+    let mut total = 0.0;
+    let mut weight = 1.0;
+    let mut weights = 0.0;
+    let mut frequency = 1.0;
+
+    for octave in 0..octaves.max(1) {
+        total += perlin(x * frequency, seed.wrapping_mul(31).wrapping_add(octave)) * weight;
+        weights += weight;
+        weight *= 0.5;
+        frequency *= 2.0;
+    }
+
+    total / weights
+}
@@ -0,0 +1,122 @@
+//! Keeping fast rounds from passing through things.
+//!
+//! A round only exists where the physics step leaves it, and one fast enough can be on one side
+//! of a thin wall or a target at one step and the far side the next without ever touching it.
+//! Every fixed step, ahead of physics, each [`Swept`] round casts a ray along the path it's about
+//! to travel. If that finds something the round is moved onto the point of impact, so the physics
+//! step sees it touching and the hit counts as it would for a slow round.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+pub struct SweepPlugin;
+
+impl Plugin for SweepPlugin {
+    fn build(&self, app: &mut App) {
+        // the physics step runs in `FixedPostUpdate`
+        app.add_systems(FixedUpdate, sweep_rounds);
+    }
+}
+
+/// A round whose path is checked for things between physics steps. Rounds pass through their
+/// shooter, like they do in `damage`.
+#[derive(Component, Debug)]
+pub struct Swept {
+    pub shooter: Entity,
+}
+
+/// Where a round at `position` moving at `velocity` first hits something over the next `delta`
+/// seconds, if it does, given `cast` returning the distance to the first thing along a ray.
+pub fn impact(
+    position: Vector,
+    velocity: Vector,
+    delta: Scalar,
+    cast: impl FnOnce(Vector, Dir3, Scalar) -> Option<Scalar>,
+) -> Option<Vector> {
+    let (direction, speed) = Dir3::new_and_length(velocity).ok()?;
+    let distance = cast(position, direction, speed * delta)?;
+
+    Some(position + direction * distance)
+}
+
+fn sweep_rounds(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    colliders: Query<&ColliderOf>,
+    mut rounds: Query<(Entity, &Swept, &LinearVelocity, &mut Transform)>,
+) {
+    for (round, swept, velocity, mut transform) in &mut rounds {
+        let filter = SpatialQueryFilter::from_excluded_entities([round, swept.shooter]);
+
+        let hit = impact(
+            transform.translation,
+            velocity.0,
+            time.delta_secs(),
+            |origin, direction, max_distance| {
+                let hit = spatial_query.cast_ray(origin, direction, max_distance, true, &filter)?;
+
+                // the shooter's hitboxes are colliders of their own
+                let own = colliders
+                    .get(hit.entity)
+                    .is_ok_and(|collider_of| collider_of.body == swept.shooter);
+
+                (!own).then_some(hit.distance)
+            },
+        );
+
+        if let Some(point) = hit {
+            transform.translation = point;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_rounds_stop_at_thin_walls_at_any_rate() {
+        const WALL: f32 = 50.0;
+        const THICKNESS: f32 = 0.01;
+
+        // a wall across the x axis, as a ray cast would find it
+        let cast = |origin: Vec3, direction: Dir3, max_distance: f32| {
+            let distance = (WALL - origin.x) / direction.x;
+            (origin.x <= WALL + THICKNESS && (0.0..=max_distance).contains(&distance))
+                .then_some(distance.max(0.0))
+        };
+
+        for speed in [100.0, 400.0, 900.0, 3000.0] {
+            for rate in [30.0, 64.0, 144.0] {
+                let delta = 1.0 / rate;
+                let velocity = Vec3::X * speed;
+                let mut position = Vec3::ZERO;
+
+                for _ in 0..rate as usize {
+                    match impact(position, velocity, delta, cast) {
+                        Some(point) => {
+                            position = point;
+                            break;
+                        }
+                        None => position += velocity * delta,
+                    }
+                }
+
+                assert!(
+                    (position.x - WALL).abs() < 1e-3,
+                    "{speed} m/s at {rate} Hz ended at {position}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rounds_short_of_a_wall_fly_on() {
+        let cast = |origin: Vec3, _: Dir3, max_distance: f32| {
+            (50.0 - origin.x <= max_distance).then_some(50.0 - origin.x)
+        };
+
+        assert_eq!(impact(Vec3::ZERO, Vec3::X * 400.0, 1.0 / 64.0, cast), None);
+        assert_eq!(impact(Vec3::ZERO, Vec3::ZERO, 1.0 / 64.0, cast), None);
+    }
+}
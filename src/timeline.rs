@@ -0,0 +1,342 @@
+//! Timed scene events.
+//!
+//! A [`TimelineScript`] is a list of events, each with the time in seconds at which it happens,
+//! loaded from a `*.timeline.ron` file in `assets/timelines/`. The [`Timeline`] resource plays one
+//! back while in game: starting waves, spawning targets, squads and platforms, moving platforms and
+//! changing the time of day. The `timeline` console command plays, pauses and seeks, so the later
+//! parts of a long script can be tested without waiting for them.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::console::{ConsoleAppExt, ConsoleCommand, ConsoleOutput};
+use crate::menu::GameState;
+use crate::ron_asset::RonLoader;
+use crate::scene::{SpawnArenaExt, TimeOfDay};
+use crate::squad::{SquadMemberDef, spawn_squad};
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TimelineScript>()
+            .register_asset_loader(RonLoader::<TimelineScript>::new(&["timeline.ron"]))
+            .add_message::<WaveStarted>()
+            .init_resource::<Timeline>()
+            .add_console_command(
+                "timeline",
+                "timeline <play <name>|pause|resume|seek <seconds>|stop> - run a scripted timeline",
+            )
+            .add_systems(OnExit(GameState::InGame), stop_timeline)
+            .add_systems(
+                Update,
+                (timeline_command, run_timeline, move_platforms, log_waves)
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+/// An event sent when a new wave of a game mode begins.
+#[derive(Message, Debug)]
+pub struct WaveStarted(pub u32);
+
+/// A script of timed events, see `assets/timelines/waves.timeline.ron`.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct TimelineScript {
+    pub events: Vec<TimedEvent>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimedEvent {
+    /// Seconds from the start of the timeline
+    pub at: f32,
+    pub event: TimelineEvent,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum TimelineEvent {
+    /// Start a numbered wave, which game mode scripts react to
+    Wave(u32),
+    /// Targets standing at each position, and whether they're armoured
+    SpawnTargets(Vec<(Vec3, bool)>),
+    /// Soldiers fighting together, holding their ground firing in turn every `stagger` seconds
+    SpawnSquad {
+        members: Vec<SquadMemberDef>,
+        #[serde(default)]
+        stagger: f32,
+    },
+    /// A platform that `MovePlatform` can later refer to by name
+    SpawnPlatform {
+        name: String,
+        position: Vec3,
+        size: Vec3,
+    },
+    /// Slide a named platform to `to` over `seconds`
+    MovePlatform {
+        name: String,
+        to: Vec3,
+        seconds: f32,
+    },
+    /// Jump the clock to this hour of the day
+    SetTime(f32),
+    Log(String),
+}
+
+/// The timeline being played, if any.
+#[derive(Resource, Debug, Default)]
+pub struct Timeline {
+    script: Option<Handle<TimelineScript>>,
+    /// Seconds since the timeline started
+    time: f32,
+    paused: bool,
+}
+
+impl Timeline {
+    pub fn play(&mut self, script: Handle<TimelineScript>) {
+        *self = Self {
+            script: Some(script),
+            ..default()
+        };
+    }
+
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Jump to `time` seconds in, without running the events in between
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.0);
+    }
+
+    /// Move the clock on by `delta`, returning the events that came due
+    pub fn advance<'a>(
+        &mut self,
+        script: &'a TimelineScript,
+        delta: f32,
+    ) -> impl Iterator<Item = &'a TimelineEvent> + use<'a> {
+        let from = self.time;
+        let to = from + delta;
+        self.time = to;
+
+        script
+            .events
+            .iter()
+            .filter(move |timed| timed.at >= from && timed.at < to)
+            .map(|timed| &timed.event)
+    }
+}
+
+/// Moves a kinematic body to `to` at a steady `speed`, then removes itself.
+#[derive(Component, Debug)]
+pub struct PlatformMove {
+    pub to: Vec3,
+    pub speed: f32,
+}
+
+fn timeline_command(
+    asset_server: Res<AssetServer>,
+    mut timeline: ResMut<Timeline>,
+    mut command_reader: MessageReader<ConsoleCommand>,
+    mut output_writer: MessageWriter<ConsoleOutput>,
+) {
+    for command in command_reader.read() {
+        if !command.is("timeline") {
+            continue;
+        }
+
+        let reply = match (command.arg(0), command.arg(1)) {
+            (Some("play"), Some(name)) => {
+                timeline.play(asset_server.load(format!("timelines/{name}.timeline.ron")));
+                format!("playing {name}")
+            }
+            (Some("pause"), _) => {
+                timeline.set_paused(true);
+                format!("paused at {:.1}s", timeline.time)
+            }
+            (Some("resume"), _) => {
+                timeline.set_paused(false);
+                format!("resumed at {:.1}s", timeline.time)
+            }
+            (Some("seek"), Some(time)) => match time.parse() {
+                Ok(time) => {
+                    timeline.seek(time);
+                    format!("seeked to {:.1}s", timeline.time)
+                }
+                Err(_) => format!("'{time}' isn't a number of seconds"),
+            },
+            (Some("stop"), _) => {
+                timeline.stop();
+                "stopped".into()
+            }
+            _ => "usage: timeline <play <name>|pause|resume|seek <seconds>|stop>".into(),
+        };
+
+        output_writer.write(ConsoleOutput(reply));
+    }
+}
+
+fn run_timeline(
+    mut commands: Commands,
+    time: Res<Time>,
+    scripts: Res<Assets<TimelineScript>>,
+    mut timeline: ResMut<Timeline>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut wave_writer: MessageWriter<WaveStarted>,
+    platforms: Query<(Entity, &Name, &Transform)>,
+) {
+    if timeline.paused {
+        return;
+    }
+
+    // the clock waits for the script to finish loading
+    let Some(script) = timeline
+        .script
+        .clone()
+        .and_then(|handle| scripts.get(&handle))
+    else {
+        return;
+    };
+
+    for event in timeline.advance(script, time.delta_secs()) {
+        debug!("timeline: {event:?}");
+
+        match event {
+            TimelineEvent::Wave(wave) => {
+                wave_writer.write(WaveStarted(*wave));
+            }
+            TimelineEvent::SpawnTargets(targets) => {
+                for &(position, armored) in targets {
+                    commands
+                        .spawn_target(position, armored)
+                        .insert(DespawnOnExit(GameState::InGame));
+                }
+            }
+            TimelineEvent::SpawnSquad { members, stagger } => {
+                let squad = spawn_squad(&mut commands, members, *stagger);
+                commands
+                    .entity(squad)
+                    .insert(DespawnOnExit(GameState::InGame));
+            }
+            TimelineEvent::SpawnPlatform {
+                name,
+                position,
+                size,
+            } => {
+                commands
+                    .spawn_platform(*position, *size)
+                    .insert((Name::new(name.clone()), DespawnOnExit(GameState::InGame)));
+            }
+            TimelineEvent::MovePlatform { name, to, seconds } => {
+                let Some((entity, _, transform)) = platforms
+                    .iter()
+                    .find(|(_, platform, _)| platform.as_str() == name)
+                else {
+                    warn!("timeline: no platform called '{name}'");
+                    continue;
+                };
+
+                let speed = transform.translation.distance(*to) / seconds.max(0.01);
+                commands
+                    .entity(entity)
+                    .insert(PlatformMove { to: *to, speed });
+            }
+            TimelineEvent::SetTime(hours) => time_of_day.set(*hours),
+            TimelineEvent::Log(message) => info!(target: "timeline", "{message}"),
+        }
+    }
+}
+
+fn move_platforms(
+    mut commands: Commands,
+    time: Res<Time>,
+    platforms: Query<(Entity, &PlatformMove, &mut Transform, &mut LinearVelocity)>,
+) {
+    for (entity, platform_move, mut transform, mut velocity) in platforms {
+        let remaining = platform_move.to - transform.translation;
+        let step = platform_move.speed * time.delta_secs();
+
+        if remaining.length() <= step {
+            transform.translation = platform_move.to;
+            velocity.0 = Vec3::ZERO;
+            commands.entity(entity).remove::<PlatformMove>();
+        } else {
+            // moved by velocity rather than teleported, so anything standing on it is carried along
+            velocity.0 = remaining.normalize() * platform_move.speed;
+        }
+    }
+}
+
+fn log_waves(mut wave_reader: MessageReader<WaveStarted>) {
+    for WaveStarted(wave) in wave_reader.read() {
+        info!("wave {wave} started");
+    }
+}
+
+fn stop_timeline(mut timeline: ResMut<Timeline>) {
+    timeline.stop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline_runs_each_event_once_and_skips_over_seeks() {
+        let script: TimelineScript = ron::from_str(
+            r#"(events: [
+                (at: 0.0, event: Wave(1)),
+                (at: 1.0, event: SetTime(12.0)),
+                (at: 5.0, event: Wave(2)),
+            ])"#,
+        )
+        .unwrap();
+
+        let mut timeline = Timeline::default();
+        let due = |timeline: &mut Timeline, delta| timeline.advance(&script, delta).count();
+
+        assert_eq!(due(&mut timeline, 0.5), 1);
+        assert_eq!(due(&mut timeline, 0.5), 0);
+        assert_eq!(due(&mut timeline, 0.5), 1);
+
+        timeline.seek(5.5);
+        assert_eq!(due(&mut timeline, 10.0), 0);
+    }
+
+    #[test]
+    fn shipped_timelines_parse() {
+        let script: TimelineScript =
+            ron::from_str(include_str!("../assets/timelines/waves.timeline.ron")).unwrap();
+
+        let roles: Vec<_> = script
+            .events
+            .iter()
+            .filter_map(|timed| match &timed.event {
+                TimelineEvent::SpawnSquad { members, .. } => Some(members),
+                _ => None,
+            })
+            .flatten()
+            .enumerate()
+            .map(|(index, member)| {
+                member
+                    .role
+                    .unwrap_or(crate::squad::SquadRole::assign(index))
+            })
+            .collect();
+
+        assert_eq!(
+            roles,
+            [
+                crate::squad::SquadRole::Suppress,
+                crate::squad::SquadRole::Flank,
+                crate::squad::SquadRole::Hold,
+                crate::squad::SquadRole::Hold,
+            ]
+        );
+    }
+}
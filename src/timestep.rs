@@ -0,0 +1,102 @@
+//! The fixed timestep gameplay simulation runs at.
+//!
+//! [`TimestepPlugin`] sets how many times a second `FixedUpdate` runs. Systems there should only
+//! ever scale by `Time::delta_secs`, never assume a tick length, so breathing, sway and aiming
+//! play out over the same number of seconds at any rate. It also keeps them correct when
+//! `Time<Virtual>` is slowed down for bullet time, as fewer fixed steps run per real second.
+
+use bevy::prelude::*;
+
+pub struct TimestepPlugin {
+    /// Fixed steps per second, clamped to [`TimestepPlugin::MIN_RATE`]..=[`TimestepPlugin::MAX_RATE`]
+    pub rate: f64,
+}
+
+impl TimestepPlugin {
+    pub const MIN_RATE: f64 = 10.0;
+    pub const MAX_RATE: f64 = 240.0;
+
+    /// The rate actually used, `rate` brought into range
+    pub fn clamped_rate(&self) -> f64 {
+        if self.rate.is_finite() {
+            self.rate.clamp(Self::MIN_RATE, Self::MAX_RATE)
+        } else {
+            Self::default().rate
+        }
+    }
+}
+
+impl Default for TimestepPlugin {
+    fn default() -> Self {
+        Self { rate: 60.0 }
+    }
+}
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        let rate = self.clamped_rate();
+
+        if rate != self.rate {
+            warn!(
+                "fixed timestep of {} Hz is out of range, using {rate} Hz",
+                self.rate
+            );
+        }
+
+        // replaces the default set up by `TimePlugin`, so this has to be added after it
+        app.insert_resource(Time::<Fixed>::from_hz(rate));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::RATES;
+
+    #[test]
+    fn timestep_plugin_sets_fixed_rate() {
+        for rate in RATES {
+            let mut app = App::new();
+            app.add_plugins((bevy::time::TimePlugin, TimestepPlugin { rate }));
+
+            let timestep = app.world().resource::<Time<Fixed>>().timestep();
+            assert!((timestep.as_secs_f64() - 1.0 / rate).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn timestep_plugin_clamps_rate() {
+        let rate = |rate| TimestepPlugin { rate }.clamped_rate();
+
+        assert_eq!(rate(1.0), TimestepPlugin::MIN_RATE);
+        assert_eq!(rate(10_000.0), TimestepPlugin::MAX_RATE);
+        assert_eq!(rate(f64::NAN), TimestepPlugin::default().rate);
+    }
+
+    #[test]
+    fn fixed_update_runs_at_configured_rate() {
+        #[derive(Resource, Default)]
+        struct Ticks(u32);
+
+        for rate in RATES {
+            let mut app = App::new();
+            app.add_plugins((bevy::time::TimePlugin, TimestepPlugin { rate }))
+                .init_resource::<Ticks>()
+                .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                    std::time::Duration::from_millis(10),
+                ))
+                .add_systems(FixedUpdate, |mut ticks: ResMut<Ticks>| ticks.0 += 1);
+
+            // the first update only starts the clock
+            for _ in 0..=100 {
+                app.update();
+            }
+
+            let ticks = app.world().resource::<Ticks>().0;
+            assert!(
+                (ticks as f64 - rate).abs() <= 1.0,
+                "{ticks} ticks in a second at {rate} Hz"
+            );
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! On-screen touch controls, for playing on a phone or in a browser.
+//!
+//! Nothing shows until the screen is first touched. After that the layout stays up over the
+//! game. A touch anywhere on the left half of the screen becomes a virtual stick to move with,
+//! centred where it landed. The fire, aim and jump buttons sit in the bottom right, and dragging
+//! anywhere else on the right half looks around. They drive the same [`MovementAction`]s and
+//! [`ActionBuffer`] actions as the keyboard and mouse, for player one.
+//!
+//! [`MovementAction`]: crate::movement::MovementAction
+//! [`ActionBuffer`]: crate::input_buffer::ActionBuffer
+
+use bevy::{
+    input::InputSystems,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::hud::HudTheme;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>()
+            .add_systems(Startup, setup_touch_controls)
+            .add_systems(PreUpdate, read_touches.after(InputSystems))
+            .add_systems(Update, update_touch_controls);
+    }
+}
+
+/// An on-screen button.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TouchButton {
+    Fire,
+    Aim,
+    Jump,
+}
+
+impl TouchButton {
+    const ALL: [TouchButton; 3] = [TouchButton::Fire, TouchButton::Aim, TouchButton::Jump];
+    /// Logical pixels
+    const RADIUS: f32 = 44.0;
+
+    fn label(self) -> &'static str {
+        match self {
+            TouchButton::Fire => "Fire",
+            TouchButton::Aim => "Aim",
+            TouchButton::Jump => "Jump",
+        }
+    }
+
+    /// Logical pixels from the bottom right corner of the screen to the button's centre, left and
+    /// up
+    fn corner_offset(self) -> Vec2 {
+        match self {
+            TouchButton::Fire => Vec2::new(80.0, 200.0),
+            TouchButton::Aim => Vec2::new(200.0, 80.0),
+            TouchButton::Jump => Vec2::new(80.0, 80.0),
+        }
+    }
+
+    /// The button's centre on a `size` screen, in window coordinates
+    fn centre(self, size: Vec2) -> Vec2 {
+        size - self.corner_offset()
+    }
+}
+
+/// What's being pressed on the touch screen.
+#[derive(Resource, Debug, Default)]
+pub struct TouchControls {
+    /// Whether the screen's been touched, and so whether the layout is up
+    shown: bool,
+    /// The touch moving the stick, and where it landed
+    stick_touch: Option<(u64, Vec2)>,
+    /// The touch looking around
+    look_touch: Option<u64>,
+    button_touches: HashMap<u64, TouchButton>,
+    /// Which way the stick is pushed, up being forwards, with a length of at most one
+    stick: Vec2,
+    /// Logical pixels the look touch moved this frame, downwards like mouse motion
+    look: Vec2,
+    pressed: HashSet<TouchButton>,
+}
+
+impl TouchControls {
+    /// Logical pixels from the centre of the stick to its edge
+    const STICK_RADIUS: f32 = 70.0;
+    /// Where the stick rests, from the bottom left corner, right and up
+    const STICK_REST: Vec2 = Vec2::new(120.0, 120.0);
+    /// Look speed, in mouse motion per logical pixel dragged
+    const LOOK_SPEED: f32 = 1.5;
+
+    pub fn stick(&self) -> Vec2 {
+        self.stick
+    }
+
+    /// How far the player wants to look this frame, as mouse motion
+    pub fn look(&self) -> Vec2 {
+        self.look * Self::LOOK_SPEED
+    }
+
+    /// The button is held down
+    pub fn pressed(&self, button: TouchButton) -> bool {
+        self.button_touches.values().any(|held| *held == button)
+    }
+
+    /// The button was pressed this frame
+    pub fn just_pressed(&self, button: TouchButton) -> bool {
+        self.pressed.contains(&button)
+    }
+}
+
+/// The ring the stick moves in, centred where the stick touch landed.
+#[derive(Component)]
+struct StickBase;
+
+#[derive(Component)]
+struct StickKnob;
+
+/// Everything drawn for the touch controls, hidden until the screen's touched.
+#[derive(Component)]
+struct TouchLayout;
+
+fn setup_touch_controls(mut commands: Commands, theme: Res<HudTheme>) {
+    const KNOB_RADIUS: f32 = 28.0;
+
+    let circle = |radius: f32| Node {
+        position_type: PositionType::Absolute,
+        width: Val::Px(radius * 2.0),
+        height: Val::Px(radius * 2.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            // not in the way of any menu buttons
+            Pickable::IGNORE,
+            TouchLayout,
+        ))
+        .with_children(|layout| {
+            layout.spawn((
+                circle(TouchControls::STICK_RADIUS),
+                BackgroundColor(theme.panel),
+                BorderRadius::MAX,
+                StickBase,
+                children![(
+                    circle(KNOB_RADIUS),
+                    BackgroundColor(theme.dim_text),
+                    BorderRadius::MAX,
+                    StickKnob,
+                )],
+            ));
+
+            for button in TouchButton::ALL {
+                let offset = button.corner_offset() - TouchButton::RADIUS;
+
+                layout.spawn((
+                    Node {
+                        right: Val::Px(offset.x),
+                        bottom: Val::Px(offset.y),
+                        ..circle(TouchButton::RADIUS)
+                    },
+                    BackgroundColor(theme.panel),
+                    BorderRadius::MAX,
+                    button,
+                    children![(
+                        Text::new(button.label()),
+                        TextFont::from_font_size(theme.small_font_size),
+                        TextColor(theme.text),
+                    )],
+                ));
+            }
+        });
+}
+
+/// Works out what every touch on the screen is doing.
+pub fn read_touches(
+    mut controls: ResMut<TouchControls>,
+    touches: Res<Touches>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    let size = window.size();
+
+    controls.pressed.clear();
+
+    for touch in touches.iter_just_pressed() {
+        let id = touch.id();
+        let position = touch.position();
+        controls.shown = true;
+
+        let button = TouchButton::ALL
+            .into_iter()
+            .find(|button| position.distance(button.centre(size)) <= TouchButton::RADIUS);
+
+        if let Some(button) = button {
+            controls.button_touches.insert(id, button);
+            controls.pressed.insert(button);
+        } else if position.x < size.x / 2.0 {
+            controls.stick_touch.get_or_insert((id, position));
+        } else {
+            controls.look_touch.get_or_insert(id);
+        }
+    }
+
+    for touch in touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+    {
+        let id = touch.id();
+
+        controls.button_touches.remove(&id);
+        if controls.stick_touch.is_some_and(|(stick, _)| stick == id) {
+            controls.stick_touch = None;
+        }
+        if controls.look_touch == Some(id) {
+            controls.look_touch = None;
+        }
+    }
+
+    // screen space has y down, and the stick has forwards up
+    controls.stick = controls
+        .stick_touch
+        .and_then(|(id, origin)| Some(touches.get_pressed(id)?.position() - origin))
+        .map_or(Vec2::ZERO, |offset| {
+            (offset * Vec2::new(1.0, -1.0) / TouchControls::STICK_RADIUS).clamp_length_max(1.0)
+        });
+
+    controls.look = controls
+        .look_touch
+        .and_then(|id| touches.get_pressed(id))
+        .map_or(Vec2::ZERO, |touch| touch.delta());
+}
+
+fn update_touch_controls(
+    controls: Res<TouchControls>,
+    theme: Res<HudTheme>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut layout: Single<&mut Visibility, With<TouchLayout>>,
+    mut base: Single<&mut Node, (With<StickBase>, Without<StickKnob>)>,
+    mut knob: Single<&mut UiTransform, With<StickKnob>>,
+    mut buttons: Query<(&TouchButton, &mut BackgroundColor)>,
+) {
+    if !controls.is_changed() || !controls.shown {
+        return;
+    }
+
+    layout.set_if_neq(Visibility::Inherited);
+
+    let rest = Vec2::new(
+        TouchControls::STICK_REST.x,
+        window.height() - TouchControls::STICK_REST.y,
+    );
+    let centre = controls.stick_touch.map_or(rest, |(_, origin)| origin);
+    base.left = Val::Px(centre.x - TouchControls::STICK_RADIUS);
+    base.top = Val::Px(centre.y - TouchControls::STICK_RADIUS);
+
+    let knob_offset = controls.stick * Vec2::new(1.0, -1.0) * TouchControls::STICK_RADIUS;
+    knob.translation = Val2::px(knob_offset.x, knob_offset.y);
+
+    for (button, mut color) in &mut buttons {
+        color.0 = if controls.pressed(*button) {
+            theme.objective.with_alpha(0.6)
+        } else {
+            theme.panel
+        };
+    }
+}
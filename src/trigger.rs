@@ -0,0 +1,282 @@
+//! Trigger volumes.
+//!
+//! A [`TriggerVolume`] is a box or sphere sensor that bodies pass through rather than bump into.
+//! When a body it cares about (see [`TriggerFilter`]) comes in or goes out, a [`TriggerEntered`]
+//! or [`TriggerExited`] message is written, and the volume keeps track of who is inside so systems
+//! can also just ask it. Race checkpoints and the kill plane under the arena are built on these.
+
+use avian3d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::Player;
+use crate::damage::Projectile;
+
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<TriggerEntered>()
+            .add_message::<TriggerExited>()
+            .add_systems(
+                Update,
+                (add_trigger_colliders, detect_triggers, log_triggers).chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerShape {
+    Sphere { radius: f32 },
+    Cuboid { size: Vec3 },
+}
+
+/// Which bodies a trigger reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriggerFilter {
+    Player,
+    /// Rounds still in flight, nothing in the built in levels listens for these yet
+    #[allow(dead_code)]
+    Projectiles,
+    #[default]
+    Any,
+}
+
+/// A sensor centred on the entity's transform.
+#[derive(Component, Debug)]
+#[require(Transform, Sensor, CollisionEventsEnabled)]
+pub struct TriggerVolume {
+    pub shape: TriggerShape,
+    pub filter: TriggerFilter,
+    /// Bodies inside, with how many of their colliders are touching
+    occupants: HashMap<Entity, u32>,
+}
+
+impl TriggerVolume {
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(TriggerShape::Sphere { radius })
+    }
+
+    pub fn cuboid(size: Vec3) -> Self {
+        Self::new(TriggerShape::Cuboid { size })
+    }
+
+    fn new(shape: TriggerShape) -> Self {
+        Self {
+            shape,
+            filter: TriggerFilter::default(),
+            occupants: HashMap::default(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: TriggerFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn is_occupied(&self) -> bool {
+        !self.occupants.is_empty()
+    }
+
+    /// Count another of `body`'s colliders in, true if the body wasn't already inside
+    fn enter(&mut self, body: Entity) -> bool {
+        let count = self.occupants.entry(body).or_default();
+        *count += 1;
+        *count == 1
+    }
+
+    /// Count one of `body`'s colliders out, true if that was the last one
+    fn leave(&mut self, body: Entity) -> bool {
+        let Some(count) = self.occupants.get_mut(&body) else {
+            return false;
+        };
+
+        *count -= 1;
+
+        if *count == 0 {
+            self.occupants.remove(&body);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        match self.shape {
+            TriggerShape::Sphere { radius } => Collider::sphere(radius),
+            TriggerShape::Cuboid { size } => Collider::cuboid(size.x, size.y, size.z),
+        }
+    }
+}
+
+/// A body matching the trigger's filter has come inside.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub body: Entity,
+}
+
+/// A body has left a trigger, or been despawned while inside it.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub body: Entity,
+}
+
+fn add_trigger_colliders(
+    mut commands: Commands,
+    triggers: Query<(Entity, &TriggerVolume), Added<TriggerVolume>>,
+) {
+    for (entity, trigger) in triggers {
+        commands.entity(entity).insert(trigger.collider());
+    }
+}
+
+fn detect_triggers(
+    mut started: MessageReader<CollisionStart>,
+    mut ended: MessageReader<CollisionEnd>,
+    mut entered_writer: MessageWriter<TriggerEntered>,
+    mut exited_writer: MessageWriter<TriggerExited>,
+    mut triggers: Query<&mut TriggerVolume>,
+    players: Query<(), With<Player>>,
+    projectiles: Query<(), With<Projectile>>,
+) {
+    let matches = |filter: TriggerFilter, body: Entity| match filter {
+        TriggerFilter::Player => players.contains(body),
+        TriggerFilter::Projectiles => projectiles.contains(body),
+        TriggerFilter::Any => true,
+    };
+
+    for collision in started.read() {
+        let pair = [
+            (collision.collider1, collision.collider2, collision.body2),
+            (collision.collider2, collision.collider1, collision.body1),
+        ];
+
+        for (trigger_entity, other, body) in pair {
+            let Ok(mut trigger) = triggers.get_mut(trigger_entity) else {
+                continue;
+            };
+
+            // colliders without a rigid body count as their own body
+            let body = body.unwrap_or(other);
+
+            if matches(trigger.filter, body) && trigger.enter(body) {
+                entered_writer.write(TriggerEntered {
+                    trigger: trigger_entity,
+                    body,
+                });
+            }
+        }
+    }
+
+    for collision in ended.read() {
+        let pair = [
+            (collision.collider1, collision.collider2, collision.body2),
+            (collision.collider2, collision.collider1, collision.body1),
+        ];
+
+        for (trigger_entity, other, body) in pair {
+            let Ok(mut trigger) = triggers.get_mut(trigger_entity) else {
+                continue;
+            };
+
+            let body = body.unwrap_or(other);
+
+            // no filter check, a body that has stopped matching still needs to be let out
+            if trigger.leave(body) {
+                exited_writer.write(TriggerExited {
+                    trigger: trigger_entity,
+                    body,
+                });
+            }
+        }
+    }
+}
+
+fn log_triggers(
+    mut entered_reader: MessageReader<TriggerEntered>,
+    mut exited_reader: MessageReader<TriggerExited>,
+) {
+    for entered in entered_reader.read() {
+        debug!("{} entered trigger {}", entered.body, entered.trigger);
+    }
+
+    for exited in exited_reader.read() {
+        debug!("{} left trigger {}", exited.body, exited.trigger);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_counts_a_body_once_across_its_colliders() {
+        use avian3d::prelude::{CollisionEnd, CollisionStart};
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TriggerPlugin))
+            .add_message::<CollisionStart>()
+            .add_message::<CollisionEnd>();
+
+        let trigger = app.world_mut().spawn(TriggerVolume::sphere(1.0)).id();
+        let body = app.world_mut().spawn_empty().id();
+        let colliders = [
+            app.world_mut().spawn_empty().id(),
+            app.world_mut().spawn_empty().id(),
+        ];
+
+        let count = |app: &App, entered: bool| {
+            if entered {
+                app.world()
+                    .resource::<Messages<TriggerEntered>>()
+                    .iter_current_update_messages()
+                    .count()
+            } else {
+                app.world()
+                    .resource::<Messages<TriggerExited>>()
+                    .iter_current_update_messages()
+                    .count()
+            }
+        };
+
+        for collider in colliders {
+            app.world_mut().write_message(CollisionStart {
+                collider1: trigger,
+                collider2: collider,
+                body1: None,
+                body2: Some(body),
+            });
+        }
+        app.update();
+
+        assert_eq!(count(&app, true), 1);
+        assert!(
+            app.world()
+                .get::<TriggerVolume>(trigger)
+                .unwrap()
+                .is_occupied()
+        );
+
+        let end = |collider| CollisionEnd {
+            collider1: collider,
+            collider2: trigger,
+            body1: Some(body),
+            body2: None,
+        };
+
+        app.world_mut().write_message(end(colliders[0]));
+        app.update();
+        assert_eq!(count(&app, false), 0);
+
+        app.world_mut().write_message(end(colliders[1]));
+        app.update();
+        assert_eq!(count(&app, false), 1);
+        assert!(
+            !app.world()
+                .get::<TriggerVolume>(trigger)
+                .unwrap()
+                .is_occupied()
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Gameplay constants loaded from `*.tuning.ron` assets.
+//!
+//! The asset is watched by the asset server, so saving the file while the game is running applies
+//! the new values straight away.
+
+use avian3d::math::Scalar;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::damage::{Health, HealthRegen, RegenSettings};
+use crate::energy::{EnergyCosts, Exertion, ExertionRates, JumpCurve, Stamina};
+use crate::movement::{
+    JumpImpulse, MaxSlopeAngle, MovementAcceleration, MovementDampingFactor, RollWindow,
+    SprintFactor,
+};
+use crate::ron_asset::RonLoader;
+use crate::{Breath, Player};
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tuning>()
+            .register_asset_loader(RonLoader::<Tuning>::new(&["tuning.ron"]))
+            .init_resource::<CameraTuning>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(Update, apply_tuning);
+    }
+}
+
+/// Tuning values for the player, see `assets/tuning/player.tuning.ron`.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct Tuning {
+    pub movement: MovementTuning,
+    pub breath: BreathTuning,
+    pub energy: EnergyTuning,
+    pub health: HealthTuning,
+    pub camera: CameraTuning,
+}
+
+impl Tuning {
+    /// The player tuning as shipped, ignoring any edits to the file or mods replacing it
+    pub fn standard() -> Self {
+        ron::from_str(include_str!("../assets/tuning/player.tuning.ron"))
+            .expect("the shipped player tuning parses")
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MovementTuning {
+    pub acceleration: Scalar,
+    pub sprint_factor: Scalar,
+    /// Seconds for horizontal speed to halve once the player stops moving
+    pub damping_half_life: Scalar,
+    pub jump_impulse: Scalar,
+    pub max_slope_angle_degrees: Scalar,
+    /// Seconds before a hard landing that crouching turns it into a roll
+    pub roll_window: Scalar,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BreathTuning {
+    pub speed: f32,
+    pub depth: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnergyTuning {
+    pub max: f32,
+    pub sprint_drain: f32,
+    pub regen: f32,
+    pub recovery_threshold: f32,
+    #[serde(default)]
+    pub costs: EnergyCosts,
+    #[serde(default)]
+    pub jump_curve: JumpCurve,
+    #[serde(default)]
+    pub exertion: ExertionRates,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HealthTuning {
+    pub max: f32,
+    /// Overridden by the casual and hardcore difficulties
+    pub regen: RegenSettings,
+}
+
+/// Look sensitivities and how the view breathes, read every frame by the camera systems.
+#[derive(Resource, Deserialize, Debug, Clone)]
+pub struct CameraTuning {
+    pub look_sensitivity_x: f32,
+    pub look_sensitivity_y: f32,
+    pub weapon_look_sensitivity_x: f32,
+    pub weapon_look_sensitivity_y: f32,
+    #[serde(default)]
+    pub breath: CameraBreathTuning,
+    #[serde(default)]
+    pub bob: HeadBobTuning,
+}
+
+impl Default for CameraTuning {
+    fn default() -> Self {
+        Self {
+            look_sensitivity_x: 0.1,
+            look_sensitivity_y: 4.0,
+            weapon_look_sensitivity_x: 0.3,
+            weapon_look_sensitivity_y: 0.15,
+            breath: CameraBreathTuning::default(),
+            bob: HeadBobTuning::default(),
+        }
+    }
+}
+
+/// How far the view moves at the top of a breath in, kept well under the weapon's sway.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CameraBreathTuning {
+    /// Metres the camera rises and drifts by
+    pub offset: Vec3,
+    /// Degrees the camera pitches, yaws and rolls by
+    pub tilt: Vec3,
+}
+
+impl Default for CameraBreathTuning {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::new(0.01, 0.03, 0.02),
+            tilt: Vec3::new(0.25, 0.05, 0.1),
+        }
+    }
+}
+
+/// How the view bobs with each step, see [`crate::movement::HeadBob`].
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct HeadBobTuning {
+    /// Metres the head dips at each footfall
+    pub vertical: f32,
+    /// Metres the head sways to either side over a stride
+    pub lateral: f32,
+    /// Metres covered in a stride, a step with each foot
+    pub stride: f32,
+    /// Metres per second of horizontal speed the bob is at its full size from
+    pub full_speed: f32,
+    /// Multiplier on the bob while sprinting
+    pub sprint: f32,
+    /// Share of the head's bob the weapon moves by on top of being carried with it, negative to
+    /// trail behind
+    pub weapon: f32,
+}
+
+impl Default for HeadBobTuning {
+    fn default() -> Self {
+        Self {
+            vertical: 0.025,
+            lateral: 0.015,
+            stride: 1.6,
+            full_speed: 5.0,
+            sprint: 1.8,
+            weapon: -0.4,
+        }
+    }
+}
+
+/// The player's tuning asset, kept loaded so edits to it are picked up. Swapping in another
+/// applies that one instead.
+#[derive(Resource)]
+pub struct PlayerTuning(pub Handle<Tuning>);
+
+fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PlayerTuning(asset_server.load("tuning/player.tuning.ron")));
+}
+
+/// Applies the tuning to every player when it's loaded, edited or swapped, and to each player
+/// spawned after that as they come in
+fn apply_tuning(
+    mut asset_events: MessageReader<AssetEvent<Tuning>>,
+    tunings: Res<Assets<Tuning>>,
+    player_tuning: Res<PlayerTuning>,
+    mut camera_tuning: ResMut<CameraTuning>,
+    mut energy_costs: ResMut<EnergyCosts>,
+    new_players_q: Query<(), Added<Player>>,
+    players_q: Query<
+        (
+            Ref<Player>,
+            &mut MovementAcceleration,
+            &mut SprintFactor,
+            &mut MovementDampingFactor,
+            &mut JumpImpulse,
+            &mut MaxSlopeAngle,
+            &mut RollWindow,
+            &mut Breath,
+            &mut Stamina,
+            &mut Exertion,
+            &mut Health,
+            &mut HealthRegen,
+        ),
+        With<Player>,
+    >,
+) {
+    let changed = asset_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if *id == player_tuning.0.id()
+        )
+    });
+
+    let all = changed || player_tuning.is_changed();
+
+    if !all && new_players_q.is_empty() {
+        return;
+    }
+
+    let Some(tuning) = tunings.get(&player_tuning.0) else {
+        return;
+    };
+
+    if all {
+        info!("applying player tuning");
+
+        *camera_tuning = tuning.camera.clone();
+        *energy_costs = tuning.energy.costs.clone();
+    }
+
+    for (
+        player,
+        mut acceleration,
+        mut sprint_factor,
+        mut damping,
+        mut jump_impulse,
+        mut max_slope_angle,
+        mut roll_window,
+        mut breath,
+        mut stamina,
+        mut exertion,
+        mut health,
+        mut health_regen,
+    ) in players_q
+    {
+        if !all && !player.is_added() {
+            continue;
+        }
+
+        let movement = &tuning.movement;
+        acceleration.0 = movement.acceleration;
+        sprint_factor.0 = movement.sprint_factor;
+        damping.half_life = movement.damping_half_life;
+        jump_impulse.0 = movement.jump_impulse;
+        max_slope_angle.0 = movement.max_slope_angle_degrees.to_radians();
+        roll_window.0 = movement.roll_window;
+
+        breath.speed = tuning.breath.speed;
+        breath.depth = tuning.breath.depth;
+
+        let energy = &tuning.energy;
+        stamina.max = energy.max;
+        stamina.current = stamina.current.min(stamina.capacity());
+        stamina.sprint_drain = energy.sprint_drain;
+        stamina.regen = energy.regen;
+        stamina.recovery_threshold = energy.recovery_threshold;
+        stamina.jump_curve = energy.jump_curve;
+        exertion.rates = energy.exertion;
+
+        health.max = tuning.health.max;
+        health.current = health.current.min(health.max);
+        health_regen.settings = tuning.health.regen;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BreathDirection;
+    use crate::movement::MovementBundle;
+
+    #[test]
+    fn players_spawned_later_are_tuned_too() {
+        let tuning: Tuning =
+            ron::from_str(include_str!("../assets/tuning/player.tuning.ron")).unwrap();
+        let acceleration = tuning.movement.acceleration;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Assets<Tuning>>()
+            .add_message::<AssetEvent<Tuning>>()
+            .init_resource::<CameraTuning>()
+            .init_resource::<EnergyCosts>()
+            .add_systems(Update, apply_tuning);
+
+        let handle = app.world_mut().resource_mut::<Assets<Tuning>>().add(tuning);
+        app.insert_resource(PlayerTuning(handle));
+        app.update();
+
+        let player = app
+            .world_mut()
+            .spawn((
+                Player,
+                MovementBundle::default(),
+                Breath::new(1.0, 1.0, BreathDirection::In),
+                Stamina::new(100.0),
+                Health::new(100.0),
+                HealthRegen::new(RegenSettings::default()),
+            ))
+            .id();
+        app.update();
+
+        let tuned = app.world().get::<MovementAcceleration>(player).unwrap();
+        assert_eq!(tuned.0, acceleration);
+    }
+}
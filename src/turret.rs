@@ -0,0 +1,489 @@
+//! Mounted guns.
+//!
+//! A [`Turret`] is a fixed emplacement players man with the interact key (or the west face button)
+//! when standing at it. While on the gun, look input traverses and elevates it within its limits,
+//! the camera sits behind its sights and fire shoots through the same [`fire_round`] as handheld
+//! weapons, so rounds hit, trace and smoke the same. Mounted guns don't sway and never run dry,
+//! but every round heats the barrel, and once it's too hot the gun won't fire until it has cooled
+//! back down.
+
+use avian3d::prelude::*;
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*};
+
+use crate::difficulty::AimAssist;
+use crate::glide::Gliding;
+use crate::grapple::Grappling;
+use crate::input_buffer::{Action, ActionBuffer};
+use crate::menu::GameState;
+use crate::particles::{MuzzleHeat, ParticleEffect, ParticleEmitter};
+use crate::projectile::ProjectilePool;
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::status::StatusEffects;
+use crate::sway::SwayProfile;
+use crate::touch::TouchControls;
+use crate::tuning::CameraTuning;
+use crate::vehicle::{Driving, enter_and_exit_vehicles};
+use crate::zipline::Ziplining;
+use crate::{
+    Player, PlayerCamera, PlayerWeapon, RoundAssets, Shot, ShotFired, SwayTarget,
+    TransformPipeline, fire_round, holster_weapons,
+};
+
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_turret_assets)
+            .add_observer(leave_turret)
+            .add_systems(
+                Update,
+                (
+                    // after vehicles, so one press doesn't get a player into both
+                    man_turrets.after(enter_and_exit_vehicles),
+                    aim_turrets,
+                    glow_barrels,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    cool_turrets,
+                    fire_turrets.run_if(in_state(GameState::InGame)),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                follow_turrets.before(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// A gun on a fixed mount, see the module docs.
+#[derive(Component, Debug, Default)]
+#[require(Transform)]
+pub struct Turret {
+    /// Radians left (positive) or right of the mount's facing
+    yaw: f32,
+    /// Radians above (positive) or below level
+    pitch: f32,
+    /// 0 for a cold barrel up to 1 for one too hot to fire
+    heat: f32,
+    overheated: bool,
+    /// Seconds until the next round can fire
+    cooldown: f32,
+}
+
+impl Turret {
+    const YAW_LIMIT: f32 = std::f32::consts::FRAC_PI_3;
+    const PITCH_MIN: f32 = -0.17;
+    const PITCH_MAX: f32 = 0.5;
+    const FIRE_INTERVAL: f32 = 0.1;
+    const HEAT_PER_ROUND: f32 = 0.05;
+    /// Heat lost per second
+    const COOLING: f32 = 0.3;
+    /// An overheated barrel fires again once it has cooled to this
+    const RECOVERED: f32 = 0.4;
+    const DAMAGE: f32 = 45.0;
+    const MUZZLE_VELOCITY: f32 = 90.0;
+    /// Kilograms per round
+    const ROUND_MASS: f32 = 0.045;
+    /// Height of the gun's pivot above the base
+    const HEIGHT: f32 = 1.2;
+    /// The end of the barrel, from the pivot
+    const MUZZLE: Vec3 = Vec3::new(0.0, 0.0, -1.2);
+    /// Where the gunner's camera sits, behind the pivot and looking down the barrel
+    const SIGHT: Vec3 = Vec3::new(0.0, 0.35, 1.1);
+    /// Where the gunner stands, behind the mount
+    const GRIP: Vec3 = Vec3::new(0.0, 0.9, 1.6);
+    /// How close to the grip a player has to be to get on the gun
+    const REACH: f32 = 2.0;
+
+    /// Traverse and elevate by the given radians, as far as the mount allows
+    pub fn aim(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw).clamp(-Self::YAW_LIMIT, Self::YAW_LIMIT);
+        self.pitch = (self.pitch + pitch).clamp(Self::PITCH_MIN, Self::PITCH_MAX);
+    }
+
+    /// The gun's rotation relative to its mount
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+
+    pub fn is_overheated(&self) -> bool {
+        self.overheated
+    }
+
+    /// Heat the barrel for a round, false if it's too hot or too soon after the last one
+    pub fn try_fire(&mut self) -> bool {
+        if self.overheated || self.cooldown > 0.0 {
+            return false;
+        }
+
+        self.cooldown = Self::FIRE_INTERVAL;
+        self.heat = (self.heat + Self::HEAT_PER_ROUND).min(1.0);
+
+        if self.heat >= 1.0 {
+            self.overheated = true;
+        }
+
+        true
+    }
+
+    pub fn cool(&mut self, delta: f32) {
+        self.cooldown = (self.cooldown - delta).max(0.0);
+        self.heat = (self.heat - Self::COOLING * delta).max(0.0);
+
+        if self.overheated && self.heat <= Self::RECOVERED {
+            self.overheated = false;
+        }
+    }
+}
+
+/// The part of a [`Turret`] that traverses and elevates.
+#[derive(Component)]
+struct TurretGun;
+
+/// The end of a [`TurretGun`]'s barrel, where rounds and smoke come out.
+#[derive(Component)]
+struct TurretMuzzle;
+
+/// The turret a player is manning.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = Gunner)]
+pub struct Manning(pub Entity);
+
+/// The player manning a turret.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = Manning)]
+pub struct Gunner(Entity);
+
+/// The camera a gunner left their head for, put back when they get off the gun.
+#[derive(Component, Debug)]
+struct GunnerCamera(Entity);
+
+#[derive(Resource)]
+pub struct TurretAssets {
+    pub base_mesh: Handle<Mesh>,
+    pub barrel_mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl TurretAssets {
+    /// A barrel material of its own, so it can glow with the turret's heat
+    pub fn barrel_material() -> StandardMaterial {
+        StandardMaterial::from(Color::srgb_u8(50, 55, 50))
+    }
+
+    /// The mount, facing -Z, with its gun
+    pub fn turret(&self, position: Vec3, barrel_material: Handle<StandardMaterial>) -> impl Bundle {
+        (
+            Turret::default(),
+            Name::new("Turret"),
+            Mesh3d(self.base_mesh.clone()),
+            MeshMaterial3d(self.material.clone()),
+            Transform::from_translation(position + Vec3::Y * Turret::HEIGHT / 2.0),
+            RigidBody::Static,
+            Collider::cylinder(0.3, Turret::HEIGHT),
+            children![(
+                TurretGun,
+                Mesh3d(self.barrel_mesh.clone()),
+                MeshMaterial3d(barrel_material),
+                Transform::from_xyz(0.0, Turret::HEIGHT / 2.0, 0.0),
+                SwayProfile::None,
+                children![(
+                    TurretMuzzle,
+                    Transform::from_translation(Turret::MUZZLE),
+                    MuzzleHeat::default(),
+                    ParticleEmitter::new(ParticleEffect::MuzzleSmoke),
+                )],
+            )],
+        )
+    }
+}
+
+fn setup_turret_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let barrel = Cuboid::new(0.14, 0.14, Turret::MUZZLE.length());
+
+    commands.insert_resource(TurretAssets {
+        base_mesh: meshes.add(Cylinder::new(0.3, Turret::HEIGHT)),
+        // running from the pivot out to the muzzle
+        barrel_mesh: meshes.add(Mesh::from(barrel).translated_by(Turret::MUZZLE / 2.0)),
+        material: materials.add(Color::srgb_u8(70, 75, 70)),
+    });
+}
+
+pub fn man_turrets(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    mut left_vehicle: RemovedComponents<Driving>,
+    players: Query<
+        (Entity, &PlayerInput, &Transform, &Children, Has<Manning>),
+        (
+            With<Player>,
+            Without<Driving>,
+            Without<Ziplining>,
+            Without<Grappling>,
+            Without<Gliding>,
+        ),
+    >,
+    turrets: Query<(Entity, &Transform), (With<Turret>, Without<Gunner>)>,
+    cameras: Query<(), With<PlayerCamera>>,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    // the same press that got them out of a vehicle
+    let left_vehicle: Vec<_> = left_vehicle.read().collect();
+
+    for (player, input, transform, children, manning) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.interact);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::West));
+
+        if (!keyboard && !gamepad) || left_vehicle.contains(&player) {
+            continue;
+        }
+
+        // `leave_turret` does the rest
+        if manning {
+            commands.entity(player).remove::<Manning>();
+            continue;
+        }
+
+        let Some((turret, _)) = turrets
+            .iter()
+            .map(|(turret, turret_transform)| {
+                let grip = turret_transform.transform_point(Turret::GRIP);
+                (turret, grip.distance(transform.translation))
+            })
+            .filter(|(_, distance)| *distance <= Turret::REACH)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            continue;
+        };
+
+        let Some(camera) = children.iter().find(|child| cameras.contains(*child)) else {
+            continue;
+        };
+
+        // held at the grip by `follow_turrets`
+        commands
+            .entity(player)
+            .insert((Manning(turret), GunnerCamera(camera), RigidBodyDisabled));
+        commands.entity(camera).remove::<ChildOf>();
+        holster_weapons(&mut commands, &weapons, player, true);
+
+        info!("{player} got on {turret}");
+    }
+}
+
+/// Gives a gunner their camera and weapon back, whether they got off the gun or it went away.
+fn leave_turret(
+    remove: On<Remove, Manning>,
+    mut commands: Commands,
+    gunners: Query<(&Manning, &GunnerCamera)>,
+    cameras: Query<&TransformPipeline, With<PlayerCamera>>,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    let player = remove.entity;
+
+    let Ok((Manning(turret), GunnerCamera(camera))) = gunners.get(player) else {
+        return;
+    };
+
+    commands
+        .entity(player)
+        .try_remove::<(GunnerCamera, RigidBodyDisabled)>()
+        .try_insert(LinearVelocity::ZERO);
+
+    if let Ok(pipeline) = cameras.get(*camera) {
+        commands.entity(*camera).try_insert((
+            ChildOf(player),
+            Transform::from_translation(pipeline.base_translation),
+        ));
+    }
+
+    holster_weapons(&mut commands, &weapons, player, false);
+
+    info!("{player} got off {turret}");
+}
+
+fn aim_turrets(
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    touch: Res<TouchControls>,
+    gamepads: Query<&Gamepad>,
+    aim_assist: Res<AimAssist>,
+    camera_tuning: Res<CameraTuning>,
+    time: Res<Time>,
+    gunners: Query<(&Manning, &PlayerInput)>,
+    mut turrets: Query<(&mut Turret, &Children)>,
+    mut guns: Query<&mut Transform, With<TurretGun>>,
+) {
+    for (Manning(turret), input) in gunners {
+        let Ok((mut turret, children)) = turrets.get_mut(*turret) else {
+            continue;
+        };
+
+        // the same sensitivities as looking around on foot
+        let look =
+            input.look(&mouse_motion, &gamepads, &touch) * aim_assist.scale() * time.delta_secs();
+        turret.aim(
+            -look.x * camera_tuning.look_sensitivity_x,
+            (-look.y * camera_tuning.look_sensitivity_y).to_radians(),
+        );
+
+        for child in children {
+            if let Ok(mut gun) = guns.get_mut(*child) {
+                gun.rotation = turret.rotation();
+            }
+        }
+    }
+}
+
+fn cool_turrets(time: Res<Time>, turrets: Query<&mut Turret>) {
+    for mut turret in turrets {
+        turret.cool(time.delta_secs());
+    }
+}
+
+fn fire_turrets(
+    mut commands: Commands,
+    mut rounds: Local<u32>,
+    round_assets: Res<RoundAssets>,
+    mut pool: ResMut<ProjectilePool>,
+    mut shot_writer: MessageWriter<ShotFired>,
+    gunners: Query<(Entity, &Manning, &ActionBuffer, Option<&StatusEffects>)>,
+    mut turrets: Query<&mut Turret>,
+    guns: Query<(&ChildOf, &Children), With<TurretGun>>,
+    muzzles: Query<&GlobalTransform, With<TurretMuzzle>>,
+) {
+    for (gunner, Manning(turret_entity), actions, effects) in gunners {
+        if !actions.pressed(Action::Fire) {
+            continue;
+        }
+
+        let Ok(mut turret) = turrets.get_mut(*turret_entity) else {
+            continue;
+        };
+
+        let was_overheated = turret.is_overheated();
+
+        if !turret.try_fire() {
+            continue;
+        }
+
+        if turret.is_overheated() && !was_overheated {
+            info!("{turret_entity} overheated");
+        }
+
+        let Some((muzzle, transform)) = guns
+            .iter()
+            .filter(|(child_of, _)| child_of.parent() == *turret_entity)
+            .flat_map(|(_, children)| children.iter())
+            .find_map(|child| muzzles.get(child).ok().map(|transform| (child, transform)))
+        else {
+            continue;
+        };
+
+        fire_round(
+            &mut commands,
+            &round_assets,
+            &mut pool,
+            &mut shot_writer,
+            &mut rounds,
+            transform,
+            Shot {
+                shooter: gunner,
+                weapon: muzzle,
+                damage: Turret::DAMAGE * effects.map_or(1.0, |effects| effects.modifiers().damage),
+                muzzle_velocity: Turret::MUZZLE_VELOCITY,
+                round_mass: Turret::ROUND_MASS,
+            },
+        );
+    }
+}
+
+/// Barrels glow as they heat up.
+fn glow_barrels(
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    turrets: Query<(&Turret, &Children), Changed<Turret>>,
+    guns: Query<&MeshMaterial3d<StandardMaterial>, With<TurretGun>>,
+) {
+    for (turret, children) in turrets {
+        for child in children {
+            let Ok(material) = guns.get(*child) else {
+                continue;
+            };
+
+            if let Some(material) = materials.get_mut(&material.0) {
+                // nothing shows until the barrel is properly warm
+                let glow = ((turret.heat - 0.3) / 0.7).max(0.0).powi(2);
+                material.emissive = LinearRgba::rgb(8.0, 1.5, 0.2) * glow;
+            }
+        }
+    }
+}
+
+/// Keeps gunners at the grip and their cameras behind the sights.
+fn follow_turrets(
+    gunners: Query<
+        (&Manning, &GunnerCamera, &mut Transform),
+        (With<Player>, Without<Turret>, Without<PlayerCamera>),
+    >,
+    turrets: Query<(&Turret, &Transform), (Without<Player>, Without<PlayerCamera>)>,
+    mut cameras: Query<&mut Transform, (With<PlayerCamera>, Without<Player>, Without<Turret>)>,
+) {
+    for (Manning(turret), GunnerCamera(camera), mut transform) in gunners {
+        let Ok((turret, mount)) = turrets.get(*turret) else {
+            continue;
+        };
+
+        transform.translation = mount.transform_point(Turret::GRIP);
+        transform.rotation = mount.rotation * Quat::from_rotation_y(turret.yaw);
+
+        // the gun's pivot, turned the way it's aimed
+        let gun = mount.mul_transform(
+            Transform::from_xyz(0.0, Turret::HEIGHT / 2.0, 0.0).with_rotation(turret.rotation()),
+        );
+
+        if let Ok(mut camera) = cameras.get_mut(*camera) {
+            *camera = gun * Transform::from_translation(Turret::SIGHT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turret_locks_out_when_overheated_until_cooled() {
+        let mut turret = Turret::default();
+        let mut fired = 0;
+
+        // holding the trigger, one round every step
+        while !turret.is_overheated() {
+            assert!(turret.try_fire());
+            turret.cool(0.1);
+            fired += 1;
+            assert!(fired < 1000, "never overheated");
+        }
+
+        assert!(!turret.try_fire());
+
+        turret.cool(0.5);
+        assert!(!turret.try_fire());
+
+        turret.cool(5.0);
+        assert!(turret.try_fire());
+    }
+}
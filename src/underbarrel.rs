@@ -0,0 +1,137 @@
+//! Underbarrel launchers and shotguns.
+//!
+//! A weapon whose definition has an `underbarrel` gets an [`Underbarrel`] holding the mounted
+//! weapon's stats, [`FireMode`] and magazine. The underbarrel key (or d-pad left) swaps those with
+//! the main weapon's, so the same model fires something else: out of its attachment socket rather
+//! than the muzzle, with its own ammunition and the crosshair changing to suit. Switching cancels
+//! a reload in progress.
+
+use bevy::prelude::*;
+
+use crate::menu::GameState;
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::weapon::{FireMode, Magazine, Reloading, UnderbarrelDef, WeaponStats};
+use crate::{Player, PlayerWeapon, SwayTarget, WeaponActive};
+
+pub struct UnderbarrelPlugin;
+
+impl Plugin for UnderbarrelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            switch_underbarrels.run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+/// Everything about how a weapon fires that the underbarrel swaps out.
+#[derive(Debug, Clone)]
+struct Firing {
+    stats: WeaponStats,
+    mode: FireMode,
+    magazine: Option<Magazine>,
+}
+
+/// A weapon mounted under another, holding whichever of the two isn't in use.
+#[derive(Component, Debug)]
+pub struct Underbarrel {
+    name: String,
+    stowed: Firing,
+}
+
+impl Underbarrel {
+    pub fn new(def: &UnderbarrelDef) -> Self {
+        Self {
+            name: def.name.clone(),
+            stowed: Firing {
+                stats: WeaponStats {
+                    damage: def.damage,
+                    muzzle_velocity: def.muzzle_velocity,
+                    round_mass: def.round_mass,
+                    ..default()
+                },
+                mode: def.fire_mode.clone(),
+                magazine: Some(Magazine {
+                    rounds: def.magazine,
+                    capacity: def.magazine,
+                    reserve: def.reserve,
+                    reload_time: def.reload_time,
+                }),
+            },
+        }
+    }
+}
+
+/// Marks a weapon firing its underbarrel.
+#[derive(Component, Debug)]
+pub struct UnderbarrelActive;
+
+fn switch_underbarrels(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(Entity, &PlayerInput), With<Player>>,
+    weapons: Query<
+        (
+            Entity,
+            &SwayTarget,
+            &mut Underbarrel,
+            &mut WeaponStats,
+            &mut FireMode,
+            Option<&Magazine>,
+            Has<UnderbarrelActive>,
+        ),
+        (With<PlayerWeapon>, With<WeaponActive>),
+    >,
+) {
+    let switching: Vec<_> = players
+        .iter()
+        .filter(|(_, input)| {
+            let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.underbarrel);
+            let gamepad = input
+                .gamepad(&gamepads)
+                .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::DPadLeft));
+
+            keyboard || gamepad
+        })
+        .map(|(player, _)| player)
+        .collect();
+
+    if switching.is_empty() {
+        return;
+    }
+
+    for (weapon, owner, mut underbarrel, mut stats, mut mode, magazine, active) in weapons {
+        if !switching.contains(&owner.0) {
+            continue;
+        }
+
+        let current = Firing {
+            stats: stats.clone(),
+            mode: mode.clone(),
+            magazine: magazine.cloned(),
+        };
+        let next = std::mem::replace(&mut underbarrel.stowed, current);
+
+        *stats = next.stats;
+        *mode = next.mode;
+
+        let mut weapon_commands = commands.entity(weapon);
+        weapon_commands.remove::<Reloading>();
+
+        match next.magazine {
+            Some(magazine) => weapon_commands.insert(magazine),
+            None => weapon_commands.remove::<Magazine>(),
+        };
+
+        if active {
+            weapon_commands.remove::<UnderbarrelActive>();
+            debug!("{weapon} back off the {}", underbarrel.name);
+        } else {
+            weapon_commands.insert(UnderbarrelActive);
+            debug!("{weapon} switched to the {}", underbarrel.name);
+        }
+    }
+}
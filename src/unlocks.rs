@@ -0,0 +1,159 @@
+//! Weapons and attachments earned by playing.
+//!
+//! Some of what the loadout screen offers starts locked, each behind a [`Criterion`] on the
+//! active profile's lifetime [`ProfileStats`]. Once one is met it's added to the profile's
+//! unlocks for good, with a toast to say so, and until then the loadout screen shows what it
+//! takes instead.
+
+use bevy::prelude::*;
+
+use crate::game_assets::GameAssets;
+use crate::hud::Toast;
+use crate::profile::{ActiveProfile, Profile, ProfileStats};
+
+pub struct UnlocksPlugin;
+
+impl Plugin for UnlocksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            check_unlocks.run_if(resource_exists::<ActiveProfile>),
+        );
+    }
+}
+
+/// Something a profile can have locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlockable<'a> {
+    /// The weapon defined at this path
+    Weapon(&'a str),
+    /// The underbarrel on the weapon defined at this path
+    Underbarrel(&'a str),
+}
+
+impl Unlockable<'_> {
+    /// How it's kept in [`Profile::unlocks`]
+    fn id(&self) -> String {
+        match self {
+            Unlockable::Weapon(path) => format!("weapon:{path}"),
+            Unlockable::Underbarrel(path) => format!("underbarrel:{path}"),
+        }
+    }
+}
+
+/// What a profile's lifetime stats need to reach to unlock something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    Score(u64),
+    /// Headshots from [`ProfileStats::LONG_RANGE`] or further
+    LongHeadshots(u64),
+}
+
+impl Criterion {
+    fn met(&self, stats: &ProfileStats) -> bool {
+        match *self {
+            Criterion::Score(score) => stats.score >= score,
+            Criterion::LongHeadshots(headshots) => stats.long_headshots >= headshots,
+        }
+    }
+}
+
+impl std::fmt::Display for Criterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Criterion::Score(score) => write!(f, "score {score}"),
+            Criterion::LongHeadshots(headshots) => {
+                write!(f, "{headshots} headshots at {} m", ProfileStats::LONG_RANGE)
+            }
+        }
+    }
+}
+
+struct Unlock {
+    item: Unlockable<'static>,
+    /// What the toast calls it
+    name: &'static str,
+    criterion: Criterion,
+}
+
+/// Everything that starts out locked. Anything not here is always available.
+const UNLOCKS: [Unlock; 2] = [
+    Unlock {
+        item: Unlockable::Underbarrel(GameAssets::WEAPONS[0]),
+        name: "M320 underbarrel",
+        criterion: Criterion::Score(2000),
+    },
+    Unlock {
+        item: Unlockable::Weapon(GameAssets::WEAPONS[1]),
+        name: "MPX-SD",
+        criterion: Criterion::LongHeadshots(10),
+    },
+];
+
+/// What `profile` still has to do to unlock `item`, or `None` if it's available.
+pub fn locked(profile: &Profile, item: Unlockable) -> Option<Criterion> {
+    UNLOCKS
+        .iter()
+        .find(|unlock| unlock.item == item)
+        .filter(|unlock| !profile.unlocks.contains(&unlock.item.id()))
+        .map(|unlock| unlock.criterion)
+}
+
+fn check_unlocks(mut profile: ResMut<ActiveProfile>, mut toast_writer: MessageWriter<Toast>) {
+    for unlock in &UNLOCKS {
+        let id = unlock.item.id();
+
+        // only read until then, so the profile only counts as changed when something's unlocked
+        if profile.unlocks.contains(&id) || !unlock.criterion.met(&profile.stats) {
+            continue;
+        }
+
+        info!("profile '{}' unlocked {}", profile.name, unlock.name);
+        profile.unlocks.insert(id);
+        toast_writer.write(Toast(format!("Unlocked {}", unlock.name)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlocks_follow_profile_stats() {
+        let mut app = App::new();
+        app.add_message::<crate::hud::Toast>()
+            .add_plugins(UnlocksPlugin)
+            .insert_resource(crate::profile::ActiveProfile(crate::profile::Profile::new(
+                "test".to_owned(),
+            )));
+
+        let suppressed = Unlockable::Weapon(crate::game_assets::GameAssets::WEAPONS[1]);
+        let locked = |app: &App, item| {
+            locked(
+                app.world().resource::<crate::profile::ActiveProfile>(),
+                item,
+            )
+        };
+
+        app.update();
+        assert_eq!(locked(&app, suppressed), Some(Criterion::LongHeadshots(10)));
+        assert_eq!(
+            locked(
+                &app,
+                Unlockable::Weapon(crate::game_assets::GameAssets::WEAPONS[0])
+            ),
+            None,
+            "anything not gated is always available"
+        );
+
+        app.world_mut()
+            .resource_mut::<crate::profile::ActiveProfile>()
+            .stats
+            .long_headshots = 10;
+        app.update();
+
+        assert_eq!(locked(&app, suppressed), None);
+        let toasts = app.world().resource::<Messages<crate::hud::Toast>>();
+        assert_eq!(toasts.iter_current_update_messages().count(), 1);
+    }
+}
@@ -0,0 +1,506 @@
+//! A drivable buggy.
+//!
+//! A [`Vehicle`] is a dynamic box held up by four raycast wheels. Each step every wheel casts down
+//! from its mount, and where it finds the ground a spring pushes the body up, the tyre resists
+//! sliding sideways and the rear wheels push it along. The forces are turned straight into changes
+//! of the body's velocity, the same way the character controller moves players.
+//!
+//! Players get in and out with the interact key (or the west face button) when standing next to
+//! one. While driving, their camera leaves their head for a seat behind the buggy, their movement
+//! input works the throttle and steering, and their weapon is put away. Getting out, or the buggy
+//! being despawned, puts them down beside the driver's door.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::doppler::DopplerEmitter;
+use crate::glide::Gliding;
+use crate::grapple::Grappling;
+use crate::menu::GameState;
+use crate::movement::{MovementAction, MovementKind};
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::turret::Manning;
+use crate::zipline::Ziplining;
+use crate::{Player, PlayerCamera, PlayerWeapon, SwayTarget, TransformPipeline, holster_weapons};
+
+pub struct VehiclePlugin;
+
+impl Plugin for VehiclePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_vehicle_assets)
+            .add_observer(leave_vehicle)
+            .add_systems(
+                Update,
+                (enter_and_exit_vehicles, steer_vehicles, turn_wheels)
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(FixedUpdate, drive_vehicles)
+            .add_systems(
+                PostUpdate,
+                follow_vehicles.before(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// A four wheeled buggy, see the module docs.
+#[derive(Component, Debug, Default)]
+#[require(Transform)]
+pub struct Vehicle {
+    /// -1 for full reverse up to 1 for full throttle
+    pub throttle: f32,
+    /// -1 for full left lock up to 1 for full right
+    pub steer: f32,
+    /// How far down each wheel's ray found the ground last step, `None` while it's in the air
+    contacts: [Option<f32>; 4],
+}
+
+impl Vehicle {
+    const MASS: f32 = 600.0;
+    const SIZE: Vec3 = Vec3::new(1.8, 0.6, 3.2);
+    /// Wheel mounts relative to the body's centre, front left, front right, rear left, rear right
+    const WHEELS: [Vec3; 4] = [
+        Vec3::new(-0.9, -0.2, -1.2),
+        Vec3::new(0.9, -0.2, -1.2),
+        Vec3::new(-0.9, -0.2, 1.2),
+        Vec3::new(0.9, -0.2, 1.2),
+    ];
+    const WHEEL_RADIUS: f32 = 0.35;
+    /// Suspension travel, from the mount to the wheel's centre at full stretch
+    const SUSPENSION: f32 = 0.5;
+    /// Newtons per metre of suspension compression
+    const STIFFNESS: f32 = 20_000.0;
+    /// Newtons per metre per second of suspension movement
+    const DAMPING: f32 = 2_000.0;
+    /// Newtons each rear wheel pushes with at full throttle
+    const ENGINE: f32 = 3_000.0;
+    /// Newtons per metre per second of sideways slide, before the tyre lets go
+    const GRIP: f32 = 1_500.0;
+    /// Newtons per metre per second slowing the wheel along its heading
+    const ROLLING: f32 = 100.0;
+    /// The most a tyre can push sideways or along, as a multiple of the load on it
+    const FRICTION: f32 = 1.2;
+    const MAX_STEER: f32 = 0.5;
+    /// Where the driver's camera sits, behind and above the buggy
+    const SEAT: Vec3 = Vec3::new(0.0, 2.4, 6.5);
+    /// Where the driver's camera looks, a little ahead of the buggy
+    const LOOK_AT: Vec3 = Vec3::new(0.0, 0.5, -3.0);
+    /// Where the driver is put down on getting out
+    const DOOR: Vec3 = Vec3::new(-2.0, 0.5, 0.0);
+    /// How close a player has to be to get in
+    const REACH: f32 = 3.0;
+
+    /// The longest a wheel's ray reaches, suspension stretched out plus the wheel
+    pub const RAY_LENGTH: f32 = Self::SUSPENSION + Self::WHEEL_RADIUS;
+
+    /// Moment of inertia about each local axis, treating the buggy as a solid box
+    fn inertia() -> Vec3 {
+        let Vec3 { x, y, z } = Self::SIZE * Self::SIZE;
+        Vec3::new(y + z, x + z, x + y) * Self::MASS / 12.0
+    }
+
+    /// The force wheel `index` puts on the body, given how far down its ray hit the ground, how
+    /// fast its mount is moving and the way the body is facing
+    pub fn wheel_force(
+        &self,
+        index: usize,
+        ground: Option<f32>,
+        velocity: Vec3,
+        rotation: Quat,
+    ) -> Vec3 {
+        let Some(distance) = ground else {
+            return Vec3::ZERO;
+        };
+
+        let front = index < 2;
+        let steer = if front {
+            -self.steer * Self::MAX_STEER
+        } else {
+            0.0
+        };
+
+        let up = rotation * Vec3::Y;
+        let forward = rotation * Quat::from_rotation_y(steer) * Vec3::NEG_Z;
+        let right = forward.cross(up);
+
+        // the spring only ever pushes, a wheel leaving the ground doesn't pull the body down
+        let compression = Self::RAY_LENGTH - distance;
+        let load = (compression * Self::STIFFNESS - velocity.dot(up) * Self::DAMPING).max(0.0);
+
+        let drive = if front {
+            0.0
+        } else {
+            self.throttle * Self::ENGINE
+        };
+        let traction = forward * (drive - velocity.dot(forward) * Self::ROLLING)
+            - right * velocity.dot(right) * Self::GRIP;
+
+        up * load + traction.clamp_length_max(load * Self::FRICTION)
+    }
+}
+
+/// One of a [`Vehicle`]'s wheels, only for show.
+#[derive(Component)]
+struct Wheel(usize);
+
+/// The vehicle a player is driving.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = Driver)]
+pub struct Driving(pub Entity);
+
+/// The player driving a vehicle.
+#[derive(Component, Debug)]
+#[relationship_target(relationship = Driving)]
+pub struct Driver(Entity);
+
+/// The camera a driver left their head for, put back when they get out.
+#[derive(Component, Debug)]
+struct DriverCamera(Entity);
+
+#[derive(Resource)]
+pub struct VehicleAssets {
+    pub body_mesh: Handle<Mesh>,
+    pub wheel_mesh: Handle<Mesh>,
+    pub body_material: Handle<StandardMaterial>,
+    pub wheel_material: Handle<StandardMaterial>,
+    pub engine: Handle<Pitch>,
+}
+
+impl VehicleAssets {
+    /// The buggy's body, with its wheels, engine hum and everything the physics needs
+    pub fn buggy(&self, position: Vec3) -> impl Bundle {
+        // roughly where the body settles on its springs
+        let ride_height = Vehicle::RAY_LENGTH - Vehicle::WHEELS[0].y;
+
+        let wheels: Vec<_> = Vehicle::WHEELS
+            .into_iter()
+            .enumerate()
+            .map(|(index, mount)| {
+                (
+                    Wheel(index),
+                    Mesh3d(self.wheel_mesh.clone()),
+                    MeshMaterial3d(self.wheel_material.clone()),
+                    Transform::from_translation(mount),
+                )
+            })
+            .collect();
+
+        (
+            Vehicle::default(),
+            Name::new("Buggy"),
+            Mesh3d(self.body_mesh.clone()),
+            MeshMaterial3d(self.body_material.clone()),
+            Transform::from_translation(position + Vec3::Y * ride_height),
+            RigidBody::Dynamic,
+            Collider::cuboid(Vehicle::SIZE.x, Vehicle::SIZE.y, Vehicle::SIZE.z),
+            Mass(Vehicle::MASS),
+            AngularDamping(1.0),
+            (
+                AudioPlayer(self.engine.clone()),
+                PlaybackSettings::LOOP
+                    .with_spatial(true)
+                    .with_volume(bevy::audio::Volume::Linear(0.3)),
+                DopplerEmitter,
+            ),
+            Children::spawn(SpawnIter(wheels.into_iter())),
+        )
+    }
+}
+
+fn setup_vehicle_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    let wheel = Cylinder::new(Vehicle::WHEEL_RADIUS, 0.3);
+
+    commands.insert_resource(VehicleAssets {
+        body_mesh: meshes.add(Cuboid::from_size(Vehicle::SIZE)),
+        // stood on its edge, rolling along -Z
+        wheel_mesh: meshes
+            .add(Mesh::from(wheel).rotated_by(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))),
+        body_material: materials.add(Color::srgb_u8(200, 120, 30)),
+        wheel_material: materials.add(Color::srgb_u8(30, 30, 30)),
+        engine: pitches.add(Pitch::new(90.0, std::time::Duration::from_secs(1))),
+    });
+}
+
+pub fn enter_and_exit_vehicles(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<
+        (Entity, &PlayerInput, &Transform, &Children, Has<Driving>),
+        (
+            With<Player>,
+            Without<Manning>,
+            Without<Ziplining>,
+            Without<Grappling>,
+            Without<Gliding>,
+        ),
+    >,
+    vehicles: Query<(Entity, &Transform), (With<Vehicle>, Without<Driver>)>,
+    cameras: Query<(), With<PlayerCamera>>,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    for (player, input, transform, children, driving) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.interact);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::West));
+
+        if !keyboard && !gamepad {
+            continue;
+        }
+
+        // `leave_vehicle` does the rest
+        if driving {
+            commands.entity(player).remove::<Driving>();
+            continue;
+        }
+
+        let Some((vehicle, _)) = vehicles
+            .iter()
+            .map(|(vehicle, vehicle_transform)| {
+                let distance = vehicle_transform
+                    .translation
+                    .distance(transform.translation);
+                (vehicle, distance)
+            })
+            .filter(|(_, distance)| *distance <= Vehicle::REACH)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            continue;
+        };
+
+        let Some(camera) = children.iter().find(|child| cameras.contains(*child)) else {
+            continue;
+        };
+
+        commands.entity(player).insert((
+            Driving(vehicle),
+            DriverCamera(camera),
+            RigidBodyDisabled,
+            ColliderDisabled,
+            Visibility::Hidden,
+        ));
+
+        // moved onto the seat by `follow_vehicles`
+        commands.entity(camera).remove::<ChildOf>();
+
+        holster_weapons(&mut commands, &weapons, player, true);
+
+        info!("{player} got into {vehicle}");
+    }
+}
+
+/// Puts a player back on their feet when they stop driving, whether they got out or the vehicle
+/// went away.
+fn leave_vehicle(
+    remove: On<Remove, Driving>,
+    mut commands: Commands,
+    drivers: Query<(&Driving, &DriverCamera, &Transform)>,
+    vehicles: Query<&Transform, With<Vehicle>>,
+    cameras: Query<&TransformPipeline, With<PlayerCamera>>,
+    weapons: Query<(Entity, &SwayTarget), With<PlayerWeapon>>,
+) {
+    let player = remove.entity;
+
+    let Ok((Driving(vehicle), DriverCamera(camera), transform)) = drivers.get(player) else {
+        return;
+    };
+
+    let position = match vehicles.get(*vehicle) {
+        Ok(vehicle) => {
+            let (yaw, ..) = vehicle.rotation.to_euler(EulerRot::YXZ);
+            Transform::from_translation(vehicle.transform_point(Vehicle::DOOR))
+                .with_rotation(Quat::from_rotation_y(yaw))
+        }
+        // gone already, drop them where they sat
+        Err(_) => Transform::from_translation(transform.translation + Vec3::Y),
+    };
+
+    commands
+        .entity(player)
+        .try_remove::<(DriverCamera, RigidBodyDisabled, ColliderDisabled)>()
+        .try_insert((position, LinearVelocity::ZERO, Visibility::Inherited));
+
+    if let Ok(pipeline) = cameras.get(*camera) {
+        commands.entity(*camera).try_insert((
+            ChildOf(player),
+            Transform::from_translation(pipeline.base_translation),
+        ));
+    }
+
+    holster_weapons(&mut commands, &weapons, player, false);
+
+    info!("{player} got out of {vehicle}");
+}
+
+/// Turns the driver's movement input into throttle and steering.
+fn steer_vehicles(
+    mut movement_reader: MessageReader<MovementAction>,
+    drivers: Query<&Driving>,
+    mut vehicles: Query<&mut Vehicle>,
+) {
+    for mut vehicle in &mut vehicles {
+        vehicle.throttle = 0.0;
+        vehicle.steer = 0.0;
+    }
+
+    for action in movement_reader.read() {
+        let MovementKind::Move(direction) = action.kind else {
+            continue;
+        };
+
+        let Ok(driving) = drivers.get(action.controller) else {
+            continue;
+        };
+
+        if let Ok(mut vehicle) = vehicles.get_mut(driving.0) {
+            vehicle.throttle = direction.y;
+            vehicle.steer = direction.x;
+        }
+    }
+}
+
+fn drive_vehicles(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    vehicles: Query<(
+        Entity,
+        &mut Vehicle,
+        &Transform,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, mut vehicle, transform, mut linear_velocity, mut angular_velocity) in vehicles {
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+
+        let mut force = Vec3::ZERO;
+        let mut torque = Vec3::ZERO;
+
+        for (index, mount) in Vehicle::WHEELS.into_iter().enumerate() {
+            let offset = transform.rotation * mount;
+
+            let ground = spatial_query
+                .cast_ray(
+                    transform.translation + offset,
+                    transform.down(),
+                    Vehicle::RAY_LENGTH,
+                    true,
+                    &filter,
+                )
+                .map(|hit| hit.distance);
+
+            vehicle.contacts[index] = ground;
+
+            let velocity = linear_velocity.0 + angular_velocity.0.cross(offset);
+            let wheel_force = vehicle.wheel_force(index, ground, velocity, transform.rotation);
+
+            force += wheel_force;
+            torque += offset.cross(wheel_force);
+        }
+
+        // torque is turned into the body's frame, where the inertia of a box is simple
+        let angular_acceleration =
+            transform.rotation * (transform.rotation.inverse() * torque / Vehicle::inertia());
+
+        linear_velocity.0 += force / Vehicle::MASS * delta;
+        angular_velocity.0 += angular_acceleration * delta;
+    }
+}
+
+/// Moves the wheels up and down with the suspension, and turns the front ones with the steering.
+fn turn_wheels(
+    vehicles: Query<(&Vehicle, &Children)>,
+    mut wheels: Query<(&Wheel, &mut Transform)>,
+) {
+    for (vehicle, children) in vehicles {
+        for child in children {
+            let Ok((wheel, mut transform)) = wheels.get_mut(*child) else {
+                continue;
+            };
+
+            let drop =
+                vehicle.contacts[wheel.0].unwrap_or(Vehicle::RAY_LENGTH) - Vehicle::WHEEL_RADIUS;
+            let steer = if wheel.0 < 2 {
+                -vehicle.steer * Vehicle::MAX_STEER
+            } else {
+                0.0
+            };
+
+            transform.translation = Vehicle::WHEELS[wheel.0] - Vec3::Y * drop;
+            transform.rotation = Quat::from_rotation_y(steer);
+        }
+    }
+}
+
+/// Keeps drivers in their vehicles and their cameras on the seat behind.
+fn follow_vehicles(
+    drivers: Query<
+        (&Driving, &DriverCamera, &mut Transform),
+        (With<Player>, Without<Vehicle>, Without<PlayerCamera>),
+    >,
+    vehicles: Query<&Transform, (With<Vehicle>, Without<Player>, Without<PlayerCamera>)>,
+    mut cameras: Query<&mut Transform, (With<PlayerCamera>, Without<Player>, Without<Vehicle>)>,
+) {
+    for (Driving(vehicle), DriverCamera(camera), mut transform) in drivers {
+        let Ok(vehicle) = vehicles.get(*vehicle) else {
+            continue;
+        };
+
+        transform.translation = vehicle.translation;
+
+        // only the heading, so the view doesn't pitch and roll with every bump
+        let (yaw, ..) = vehicle.rotation.to_euler(EulerRot::YXZ);
+        let heading = Transform::from_translation(vehicle.translation)
+            .with_rotation(Quat::from_rotation_y(yaw));
+        let seat = Transform::from_translation(Vehicle::SEAT).looking_at(Vehicle::LOOK_AT, Vec3::Y);
+
+        if let Ok(mut camera) = cameras.get_mut(*camera) {
+            *camera = heading * seat;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wheels_hold_the_buggy_up_and_resist_sliding() {
+        let buggy = Vehicle::default();
+        let rest = Some(Vehicle::RAY_LENGTH - 0.1);
+
+        let standing = buggy.wheel_force(2, rest, Vec3::ZERO, Quat::IDENTITY);
+        assert!(standing.y > 0.0);
+        assert_eq!(standing.with_y(0.0), Vec3::ZERO);
+
+        // sliding right is pushed back left
+        let sliding = buggy.wheel_force(2, rest, Vec3::X * 5.0, Quat::IDENTITY);
+        assert!(sliding.x < 0.0);
+
+        assert_eq!(
+            buggy.wheel_force(2, None, Vec3::X * 5.0, Quat::IDENTITY),
+            Vec3::ZERO
+        );
+
+        // only the rear wheels are driven
+        let driving = Vehicle {
+            throttle: 1.0,
+            ..default()
+        };
+        assert!(driving.wheel_force(2, rest, Vec3::ZERO, Quat::IDENTITY).z < 0.0);
+        assert_eq!(
+            driving.wheel_force(0, rest, Vec3::ZERO, Quat::IDENTITY).z,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Night and thermal vision.
+//!
+//! Players carry a [`VisionDevice`] that cycles through [`VisionMode`]s with the vision key (or
+//! d-pad up). Night vision desaturates the view, washes it green, pushes the exposure up by
+//! several stops and adds grain, so it works best in the dark and blows out in daylight. Thermal
+//! vision dims the world and draws anything with a [`HeatSignature`] in a palette from cold blue
+//! to white hot. Both run the device's battery down, and a flat battery is swapped for a spare
+//! `battery` from the inventory if there is one.
+//!
+//! Exposure, grading and overlay are per view, but heat signatures are drawn with swapped
+//! materials, so they show in every view while anyone is using thermal.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    camera::Exposure,
+    post_process::bloom::Bloom,
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::ColorGrading,
+    },
+};
+use rand::Rng;
+
+use crate::inventory::Inventory;
+use crate::menu::GameState;
+use crate::scene::TimeOfDay;
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::{Player, PlayerCamera};
+
+pub struct VisionPlugin;
+
+impl Plugin for VisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_vision_assets)
+            .add_systems(Update, setup_vision_overlays)
+            .add_systems(
+                Update,
+                (
+                    toggle_vision,
+                    drain_batteries,
+                    apply_vision,
+                    swap_heat_materials,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VisionMode {
+    #[default]
+    Off,
+    Night,
+    Thermal,
+}
+
+impl VisionMode {
+    fn next(&self) -> Self {
+        match self {
+            VisionMode::Off => VisionMode::Night,
+            VisionMode::Night => VisionMode::Thermal,
+            VisionMode::Thermal => VisionMode::Off,
+        }
+    }
+
+    /// Battery seconds used per second
+    fn drain(&self) -> f32 {
+        match self {
+            VisionMode::Off => 0.0,
+            VisionMode::Night => 1.0,
+            VisionMode::Thermal => 1.5,
+        }
+    }
+}
+
+/// Head mounted goggles with a battery, in seconds of night vision.
+#[derive(Component, Debug)]
+#[require(VisionMode)]
+pub struct VisionDevice {
+    pub charge: f32,
+    pub capacity: f32,
+}
+
+impl VisionDevice {
+    pub fn new(capacity: f32) -> Self {
+        Self {
+            charge: capacity,
+            capacity,
+        }
+    }
+}
+
+/// How warm a body looks through thermal vision, from 0 (ambient) to 1 (white hot).
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HeatSignature(pub f32);
+
+/// The material a heat signature had before thermal vision swapped it out.
+#[derive(Component)]
+struct ThermalSwap(Handle<StandardMaterial>);
+
+/// Full screen tint and grain over one player's view.
+#[derive(Component)]
+struct VisionOverlay {
+    player: Entity,
+}
+
+#[derive(Component)]
+struct VisionGrain;
+
+#[derive(Component)]
+struct VisionBattery;
+
+#[derive(Resource)]
+struct VisionAssets {
+    /// Noise frames, cycled through every frame
+    grain: Vec<Handle<Image>>,
+    /// Unlit thermal palette, coldest first
+    palette: Vec<Handle<StandardMaterial>>,
+}
+
+impl VisionAssets {
+    const GRAIN_FRAMES: usize = 4;
+    const GRAIN_SIZE: u32 = 128;
+    const PALETTE_STEPS: usize = 8;
+
+    fn thermal_material(&self, heat: f32) -> Handle<StandardMaterial> {
+        let last = self.palette.len() - 1;
+        let index = (heat.clamp(0.0, 1.0) * last as f32).round() as usize;
+        self.palette[index].clone()
+    }
+}
+
+/// Iron style thermal palette: cold is deep blue, warming through magenta and orange to white
+fn thermal_color(heat: f32) -> Color {
+    let stops = [
+        (0.0, Color::srgb(0.05, 0.0, 0.3)),
+        (0.35, Color::srgb(0.6, 0.0, 0.6)),
+        (0.7, Color::srgb(1.0, 0.5, 0.0)),
+        (1.0, Color::srgb(1.0, 1.0, 0.9)),
+    ];
+
+    let heat = heat.clamp(0.0, 1.0);
+
+    stops
+        .windows(2)
+        .find(|pair| heat <= pair[1].0)
+        .map_or(stops[3].1, |pair| {
+            let ((from, from_color), (to, to_color)) = (pair[0], pair[1]);
+            from_color.mix(&to_color, (heat - from) / (to - from))
+        })
+}
+
+fn setup_vision_assets(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut rng = rand::rng();
+    let size = VisionAssets::GRAIN_SIZE;
+
+    let grain = (0..VisionAssets::GRAIN_FRAMES)
+        .map(|_| {
+            let data = (0..size * size)
+                .flat_map(|_| {
+                    let value: u8 = rng.random();
+                    [value, value, value, 255]
+                })
+                .collect();
+
+            images.add(Image::new(
+                Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            ))
+        })
+        .collect();
+
+    let last = (VisionAssets::PALETTE_STEPS - 1) as f32;
+    let palette = (0..VisionAssets::PALETTE_STEPS)
+        .map(|step| {
+            materials.add(StandardMaterial {
+                base_color: thermal_color(step as f32 / last),
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect();
+
+    commands.insert_resource(VisionAssets { grain, palette });
+}
+
+/// Gives each player's view its own overlay, hidden until their goggles are on.
+fn setup_vision_overlays(
+    mut commands: Commands,
+    cameras: Query<(Entity, &ChildOf), Added<PlayerCamera>>,
+) {
+    for (camera, child_of) in cameras {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            GlobalZIndex(-1),
+            BackgroundColor::default(),
+            Visibility::Hidden,
+            UiTargetCamera(camera),
+            VisionOverlay {
+                player: child_of.parent(),
+            },
+            children![
+                (
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    ImageNode::default().with_mode(NodeImageMode::Tiled {
+                        tile_x: true,
+                        tile_y: true,
+                        stretch_value: 1.0,
+                    }),
+                    VisionGrain,
+                ),
+                (
+                    Node {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(12.0),
+                        bottom: Val::Px(12.0),
+                        ..default()
+                    },
+                    Text::default(),
+                    TextFont::from_font_size(14.0),
+                    VisionBattery,
+                )
+            ],
+        ));
+    }
+}
+
+fn toggle_vision(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(
+        Entity,
+        &PlayerInput,
+        &VisionDevice,
+        &mut VisionMode,
+        &Inventory,
+    )>,
+) {
+    for (entity, input, device, mut mode, inventory) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.vision);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::DPadUp));
+
+        if !keyboard && !gamepad {
+            continue;
+        }
+
+        if *mode == VisionMode::Off && device.charge <= 0.0 && inventory.count("battery") == 0 {
+            info!("{entity} has no battery for their goggles");
+            continue;
+        }
+
+        *mode = mode.next();
+        debug!("{entity} vision: {:?}", *mode);
+    }
+}
+
+fn drain_batteries(
+    time: Res<Time>,
+    players: Query<(Entity, &mut VisionDevice, &mut VisionMode, &mut Inventory)>,
+) {
+    for (entity, mut device, mut mode, mut inventory) in players {
+        if *mode == VisionMode::Off {
+            continue;
+        }
+
+        device.charge -= mode.drain() * time.delta_secs();
+
+        if device.charge > 0.0 {
+            continue;
+        }
+
+        if inventory.take("battery", 1) {
+            device.charge = device.capacity;
+            info!("{entity} swapped in a fresh battery");
+        } else {
+            device.charge = 0.0;
+            *mode = VisionMode::Off;
+            info!("{entity}'s goggles went flat");
+        }
+    }
+}
+
+fn apply_vision(
+    mut frame: Local<usize>,
+    time_of_day: Res<TimeOfDay>,
+    assets: Res<VisionAssets>,
+    players: Query<(&VisionMode, &VisionDevice, &Children), With<Player>>,
+    mut cameras: Query<(&mut Exposure, &mut ColorGrading, &mut Bloom), With<PlayerCamera>>,
+    overlays: Query<(
+        &VisionOverlay,
+        &mut Visibility,
+        &mut BackgroundColor,
+        &Children,
+    )>,
+    mut grains: Query<&mut ImageNode, With<VisionGrain>>,
+    mut batteries: Query<&mut Text, With<VisionBattery>>,
+) {
+    /// Stops of light the night vision amplifies by
+    const NIGHT_GAIN: f32 = 7.0;
+    /// Stops darker thermal draws the world, so heat signatures stand out
+    const THERMAL_DIM: f32 = 3.0;
+
+    *frame = (*frame + 1) % VisionAssets::GRAIN_FRAMES;
+
+    // the darker it is, the harder the goggles have to work and the noisier the picture
+    let darkness = 1.0 - time_of_day.daylight();
+
+    for (mode, _, children) in players {
+        for child in children {
+            let Ok((mut exposure, mut grading, mut bloom)) = cameras.get_mut(*child) else {
+                continue;
+            };
+
+            let (ev100, post_saturation, bloom_intensity) = match mode {
+                VisionMode::Off => (Exposure::SUNLIGHT.ev100, 1.0, Bloom::NATURAL.intensity),
+                VisionMode::Night => (Exposure::SUNLIGHT.ev100 - NIGHT_GAIN, 0.0, 0.6),
+                VisionMode::Thermal => (Exposure::SUNLIGHT.ev100 + THERMAL_DIM, 0.2, 0.3),
+            };
+
+            exposure.ev100 = ev100;
+            grading.global.post_saturation = post_saturation;
+            bloom.intensity = bloom_intensity;
+        }
+    }
+
+    for (overlay, mut visibility, mut background, children) in overlays {
+        let Ok((mode, device, _)) = players.get(overlay.player) else {
+            continue;
+        };
+
+        let (tint, grain) = match mode {
+            VisionMode::Off => {
+                *visibility = Visibility::Hidden;
+                continue;
+            }
+            VisionMode::Night => (Color::srgba(0.1, 1.0, 0.2, 0.35), 0.06 + 0.14 * darkness),
+            VisionMode::Thermal => (Color::srgba(0.0, 0.05, 0.2, 0.2), 0.04),
+        };
+
+        *visibility = Visibility::Inherited;
+        background.0 = tint;
+
+        for child in children {
+            if let Ok(mut image) = grains.get_mut(*child) {
+                image.image = assets.grain[*frame].clone();
+                image.color = Color::WHITE.with_alpha(grain);
+            }
+
+            if let Ok(mut text) = batteries.get_mut(*child) {
+                text.0 = format!("BAT {:.0}%", device.charge / device.capacity * 100.0);
+            }
+        }
+    }
+}
+
+/// Heat signatures take on the thermal palette while anyone is using thermal vision.
+fn swap_heat_materials(
+    mut commands: Commands,
+    assets: Res<VisionAssets>,
+    modes: Query<&VisionMode>,
+    mut hot: Query<(
+        Entity,
+        &HeatSignature,
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&ThermalSwap>,
+    )>,
+) {
+    let thermal = modes.iter().any(|mode| *mode == VisionMode::Thermal);
+
+    for (entity, heat, mut material, swap) in &mut hot {
+        match (thermal, swap) {
+            (true, None) => {
+                let original = std::mem::replace(&mut material.0, assets.thermal_material(heat.0));
+                commands.entity(entity).insert(ThermalSwap(original));
+            }
+            (false, Some(ThermalSwap(original))) => {
+                material.0 = original.clone();
+                commands.entity(entity).remove::<ThermalSwap>();
+            }
+            _ => {}
+        }
+    }
+}
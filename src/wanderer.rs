@@ -0,0 +1,200 @@
+//! Passers-by.
+//!
+//! A [`Wanderer`] strolls about the spot it was spawned on, pausing now and then, and runs from
+//! gunfire. It isn't hostile, can't be hurt and has no say in the fight, so it's there to liven a
+//! level up and to give the AI's perception and pathing something to look at. Wanderers are
+//! character controllers like players, so players can shove them out of the way.
+//!
+//! There's no navmesh to path over yet, so a wanderer only heads for places it can walk to in a
+//! straight line, and when fleeing turns aside from whatever it's about to run into.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ShotFired;
+use crate::menu::GameState;
+use crate::movement::{MovementAction, MovementKind, Sprinting};
+
+pub struct WandererPlugin;
+
+impl Plugin for WandererPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (hear_gunfire, wander)
+                .chain()
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+/// A harmless character that walks around and flees from gunfire.
+#[derive(Component, Debug)]
+pub struct Wanderer {
+    /// Where it strolls around
+    home: Vec3,
+    state: WandererState,
+}
+
+impl Wanderer {
+    /// Metres from home it strolls to
+    const RANGE: f32 = 10.0;
+    /// Fraction of a full run it strolls at
+    const STROLL: f32 = 0.4;
+    /// Seconds it pauses between strolls, at most
+    const PAUSE: f32 = 4.0;
+    /// Metres from gunfire it hears it from
+    const HEARING: f32 = 30.0;
+    /// Seconds it keeps running after the last shot it heard
+    const FLEE: f32 = 4.0;
+    /// Metres ahead it looks for something in the way when fleeing
+    const LOOK_AHEAD: f32 = 1.5;
+    /// How quickly it turns to face the way it's going
+    const TURN_SPEED: f32 = 6.0;
+
+    pub fn new(home: Vec3) -> Self {
+        Self {
+            home,
+            state: WandererState::Pausing(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WandererState {
+    /// Standing about for the given seconds
+    Pausing(f32),
+    /// Strolling over to somewhere
+    Walking(Vec3),
+    /// Running from gunfire at `from` for `timer` more seconds
+    Fleeing { from: Vec3, timer: f32 },
+}
+
+/// Sets wanderers that hear a shot running from it.
+fn hear_gunfire(
+    mut commands: Commands,
+    mut shot_reader: MessageReader<ShotFired>,
+    mut wanderers: Query<(Entity, &mut Wanderer, &Transform)>,
+) {
+    for shot in shot_reader.read() {
+        for (entity, mut wanderer, transform) in &mut wanderers {
+            if transform.translation.distance(shot.origin) > Wanderer::HEARING {
+                continue;
+            }
+
+            wanderer.state = WandererState::Fleeing {
+                from: shot.origin,
+                timer: Wanderer::FLEE,
+            };
+            commands.entity(entity).insert(Sprinting);
+        }
+    }
+}
+
+fn wander(
+    mut commands: Commands,
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut movement_writer: MessageWriter<MovementAction>,
+    mut wanderers: Query<(Entity, &mut Wanderer, &mut Transform, &Collider)>,
+) {
+    /// Metres from where it's going that count as there
+    const ARRIVED: f32 = 0.5;
+
+    let mut rng = rand::rng();
+
+    for (entity, mut wanderer, mut transform, collider) in &mut wanderers {
+        // whether it could walk `distance` metres along `direction` without bumping into anything
+        let clear = |direction: Dir3, distance: f32| {
+            spatial_query
+                .cast_shape(
+                    collider,
+                    // just off the ground, so it doesn't catch on the floor it's standing on
+                    transform.translation + Vec3::Y * 0.2,
+                    transform.rotation,
+                    direction,
+                    &ShapeCastConfig::from_max_distance(distance),
+                    &SpatialQueryFilter::from_excluded_entities([entity]),
+                )
+                .is_none()
+        };
+
+        let heading = match wanderer.state {
+            WandererState::Pausing(timer) => {
+                let timer = timer - time.delta_secs();
+                wanderer.state = WandererState::Pausing(timer);
+
+                if timer <= 0.0 {
+                    // somewhere around home it can get to, or pause a little longer
+                    let to = (0..8)
+                        .map(|_| {
+                            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+                            let distance = rng.random_range(0.0..Wanderer::RANGE);
+                            wanderer.home + Quat::from_rotation_y(angle) * Vec3::Z * distance
+                        })
+                        .find(|to| {
+                            let to = to.with_y(transform.translation.y);
+                            Dir3::new_and_length(to - transform.translation)
+                                .is_ok_and(|(direction, distance)| clear(direction, distance))
+                        });
+
+                    wanderer.state = match to {
+                        Some(to) => WandererState::Walking(to),
+                        None => WandererState::Pausing(rng.random_range(0.0..Wanderer::PAUSE)),
+                    };
+                }
+
+                continue;
+            }
+            WandererState::Walking(to) => {
+                let offset = (to - transform.translation).with_y(0.0);
+
+                if offset.length() <= ARRIVED {
+                    wanderer.state = WandererState::Pausing(rng.random_range(0.0..Wanderer::PAUSE));
+                    continue;
+                }
+
+                offset.normalize() * Wanderer::STROLL
+            }
+            WandererState::Fleeing { from, timer } => {
+                let timer = timer - time.delta_secs();
+
+                if timer <= 0.0 {
+                    wanderer.state = WandererState::Pausing(Wanderer::PAUSE);
+                    commands.entity(entity).remove::<Sprinting>();
+                    continue;
+                }
+
+                wanderer.state = WandererState::Fleeing { from, timer };
+
+                let away = (transform.translation - from)
+                    .with_y(0.0)
+                    .normalize_or(*transform.forward());
+
+                // straight away if it can, otherwise whichever way round is clear
+                [0.0, 0.8, -0.8, 1.6, -1.6]
+                    .into_iter()
+                    .map(|angle| Quat::from_rotation_y(angle) * away)
+                    .find(|direction| {
+                        Dir3::new(*direction)
+                            .is_ok_and(|direction| clear(direction, Wanderer::LOOK_AHEAD))
+                    })
+                    .unwrap_or(away)
+            }
+        };
+
+        // turn to face the way it's going, then walk that way
+        let facing = Transform::IDENTITY.looking_to(heading, Vec3::Y).rotation;
+        transform.rotation = transform
+            .rotation
+            .slerp(facing, (Wanderer::TURN_SPEED * time.delta_secs()).min(1.0));
+
+        // movement is relative to the way the character faces
+        let local = transform.rotation.inverse() * heading;
+        movement_writer.write(MovementAction {
+            controller: entity,
+            kind: MovementKind::Move(Vec2::new(local.x, -local.z)),
+        });
+    }
+}
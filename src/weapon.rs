@@ -0,0 +1,912 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    asset::{AssetPath, LoadState},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::attributes::Attributes;
+use crate::dual_wield::WeaponHand;
+use crate::hud::Toast;
+use crate::movement::Sprinting;
+use crate::recoil::{Recoil, RecoilPattern};
+use crate::ron_asset::RonLoader;
+use crate::settings::Keybinds;
+use crate::sockets::{Socket, WeaponSockets};
+use crate::split_screen::PlayerInput;
+use crate::sway::SwayProfile;
+use crate::underbarrel::{Underbarrel, UnderbarrelActive};
+use crate::{
+    Player, PlayerWeaponTransformConfig, SwayTarget, SwayTargets, TransformPipeline, WeaponActive,
+    WeaponPose,
+};
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WeaponDef>()
+            .register_asset_loader(RonLoader::<WeaponDef>::new(&["weapon.ron"]))
+            .add_message::<ReloadStarted>()
+            .add_message::<ReloadFinished>()
+            .add_message::<WeaponRaising>()
+            .add_message::<WeaponRaised>()
+            .add_observer(lower_reloading)
+            .add_observer(raise_reloaded)
+            .add_observer(raise_after_sprint)
+            .add_systems(Startup, setup_placeholder_model)
+            .add_systems(
+                Update,
+                (
+                    apply_weapon_def,
+                    stand_in_for_missing_models,
+                    reload_weapons,
+                    finish_raising,
+                    cycle_trigger_modes,
+                ),
+            );
+    }
+}
+
+/// A weapon definition loaded from a `*.weapon.ron` file.
+///
+/// Rotations are XYZ Euler angles in degrees. The pose editor writes these back out, so they
+/// round trip through [`Serialize`].
+#[derive(Asset, TypePath, Serialize, Deserialize, Debug, Clone)]
+pub struct WeaponDef {
+    pub name: String,
+    /// Asset path of the GLTF model
+    pub model: String,
+    pub hip: [f32; 3],
+    /// Where the weapon sits when aiming, for models without a `sight` socket. Those with one
+    /// line the sight up with the eye instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aim: Option<[f32; 3]>,
+    /// Metres from the eye to the sight when aiming
+    #[serde(default = "WeaponDef::default_eye_relief")]
+    pub eye_relief: f32,
+    /// Held at `hip` while sprinting if there's no sprint pose
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sprint: Option<[f32; 3]>,
+    #[serde(default)]
+    pub hip_rotation: [f32; 3],
+    #[serde(default)]
+    pub aim_rotation: [f32; 3],
+    #[serde(default)]
+    pub sprint_rotation: [f32; 3],
+    pub damage: f32,
+    pub muzzle_velocity: f32,
+    /// Kilograms per round, for how hard hits knock things about
+    #[serde(default = "WeaponDef::default_round_mass")]
+    pub round_mass: f32,
+    /// Rounds per minute it cycles at
+    #[serde(default = "WeaponDef::default_rpm")]
+    pub rpm: f32,
+    /// The trigger modes it can be switched between, the first selected to begin with
+    #[serde(default = "WeaponDef::default_trigger_modes")]
+    pub trigger_modes: Vec<TriggerMode>,
+    /// How quickly it's brought up to aim, as a multiple of the usual speed
+    #[serde(default = "WeaponDef::default_handling")]
+    pub handling: f32,
+    /// The share of movement speed kept while aimed down the sights
+    #[serde(default = "WeaponDef::default_ads_speed")]
+    pub ads_speed: f32,
+    /// Seconds after a sprint before it can fire
+    #[serde(default = "WeaponDef::default_raise_time")]
+    pub raise_time: f32,
+    /// Rounds per magazine, never running dry if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub magazine: Option<u32>,
+    /// Spare rounds carried for it, never running out if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserve: Option<u32>,
+    /// Seconds to reload an empty magazine
+    #[serde(default = "WeaponDef::default_reload_time")]
+    pub reload_time: f32,
+    /// Light enough to hold in one hand, so it can be dual wielded
+    #[serde(default)]
+    pub one_handed: bool,
+    #[serde(default)]
+    pub fire_mode: FireMode,
+    /// Rounds strike whatever's in front of the muzzle the moment they're fired, instead of
+    /// flying there. Launchers always fire a grenade
+    #[serde(default)]
+    pub hitscan: bool,
+    /// A second weapon mounted under the barrel, switched to with the underbarrel key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underbarrel: Option<UnderbarrelDef>,
+    #[serde(default)]
+    pub sway: SwayProfile,
+    #[serde(default)]
+    pub recoil: RecoilPattern,
+}
+
+impl WeaponDef {
+    fn default_eye_relief() -> f32 {
+        PlayerWeaponTransformConfig::DEFAULT_EYE_RELIEF
+    }
+
+    fn default_reload_time() -> f32 {
+        1.5
+    }
+
+    fn default_round_mass() -> f32 {
+        WeaponStats::default().round_mass
+    }
+
+    fn default_rpm() -> f32 {
+        600.0
+    }
+
+    fn default_trigger_modes() -> Vec<TriggerMode> {
+        vec![TriggerMode::Semi]
+    }
+
+    fn default_handling() -> f32 {
+        WeaponStats::default().handling
+    }
+
+    fn default_ads_speed() -> f32 {
+        WeaponStats::default().ads_speed
+    }
+
+    fn default_raise_time() -> f32 {
+        WeaponStats::default().raise_time
+    }
+
+    /// A full magazine, if the weapon has one
+    pub fn full_magazine(&self) -> Option<Magazine> {
+        self.magazine.map(|capacity| Magazine {
+            rounds: capacity,
+            capacity,
+            reserve: self.reserve,
+            reload_time: self.reload_time,
+        })
+    }
+
+    pub fn model_path(&self) -> AssetPath<'static> {
+        GltfAssetLabel::Scene(0).from_asset(self.model.clone())
+    }
+
+    /// The weapon's poses, with the sight at `sight` if its model has one
+    fn transform_config(&self, sight: Option<Vec3>) -> PlayerWeaponTransformConfig {
+        let aim = self.aim.unwrap_or(self.hip);
+        let sprint = self.sprint.unwrap_or(self.hip);
+
+        let mut config = PlayerWeaponTransformConfig {
+            hip: pose_from(self.hip, self.hip_rotation),
+            aim: pose_from(aim, self.aim_rotation),
+            sprint: pose_from(sprint, self.sprint_rotation),
+            sight,
+            eye_relief: self.eye_relief,
+        };
+        config.align_sight();
+
+        config
+    }
+
+    /// Replaces the poses with those in `config`, as tweaked in the pose editor. The aiming
+    /// position is left out for weapons that line up their sight.
+    pub fn set_poses(&mut self, config: &PlayerWeaponTransformConfig) {
+        (self.hip, self.hip_rotation) = pose_to(&config.hip);
+
+        let (aim, aim_rotation) = pose_to(&config.aim);
+        if config.sight.is_none() {
+            self.aim = Some(aim);
+        }
+        self.aim_rotation = aim_rotation;
+
+        let (sprint, sprint_rotation) = pose_to(&config.sprint);
+        self.sprint = Some(sprint);
+        self.sprint_rotation = sprint_rotation;
+    }
+}
+
+fn pose_from(translation: [f32; 3], degrees: [f32; 3]) -> WeaponPose {
+    let [x, y, z] = degrees.map(f32::to_radians);
+
+    WeaponPose {
+        translation: Vec3::from(translation),
+        rotation: Quat::from_euler(EulerRot::XYZ, x, y, z),
+    }
+}
+
+fn pose_to(pose: &WeaponPose) -> ([f32; 3], [f32; 3]) {
+    let (x, y, z) = pose.rotation.to_euler(EulerRot::XYZ);
+
+    (pose.translation.to_array(), [x, y, z].map(f32::to_degrees))
+}
+
+/// What comes out of a weapon each time it fires.
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub enum FireMode {
+    /// A single round
+    #[default]
+    Single,
+    /// A spread of pellets, each doing the weapon's damage
+    Shotgun {
+        pellets: u32,
+        /// Degrees from the centre of the spread to its edge
+        spread: f32,
+    },
+    /// A grenade that bursts on contact, doing the weapon's damage to everything within
+    /// `radius` metres
+    Launcher { radius: f32 },
+}
+
+/// How a weapon fires while its trigger's held. Whatever the mode, it never fires faster than its
+/// [`WeaponDef::rpm`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TriggerMode {
+    /// A round each pull
+    #[default]
+    Semi,
+    /// This many rounds each pull
+    Burst(u32),
+    /// Rounds for as long as it's held
+    Auto,
+}
+
+impl std::fmt::Display for TriggerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriggerMode::Semi => write!(f, "semi-automatic"),
+            TriggerMode::Burst(rounds) => write!(f, "{rounds} round burst"),
+            TriggerMode::Auto => write!(f, "automatic"),
+        }
+    }
+}
+
+/// A weapon's trigger modes and how soon it can fire again, set from its definition. Weapons
+/// without one fire a round each pull, as fast as it's pulled.
+#[derive(Component, Debug, Clone)]
+pub struct Trigger {
+    modes: Vec<TriggerMode>,
+    selected: usize,
+    /// Seconds between rounds
+    interval: f32,
+    /// Seconds until the next round can be fired, below zero when a round's overdue
+    cooldown: f32,
+    /// Rounds still to come in the current burst
+    burst_left: u32,
+}
+
+impl Trigger {
+    pub fn new(modes: Vec<TriggerMode>, rpm: f32) -> Self {
+        Self {
+            modes,
+            selected: 0,
+            interval: 60.0 / rpm.max(1.0),
+            cooldown: 0.0,
+            burst_left: 0,
+        }
+    }
+
+    pub fn mode(&self) -> TriggerMode {
+        self.modes.get(self.selected).copied().unwrap_or_default()
+    }
+
+    /// Switches to the next mode, calling off any burst
+    pub fn cycle(&mut self) {
+        self.selected = (self.selected + 1) % self.modes.len().max(1);
+        self.burst_left = 0;
+    }
+
+    /// Advances the trigger by `delta` seconds, returning whether a round's fired. A round fires
+    /// once per step at most, so the cadence can't beat the fixed timestep
+    pub fn fire(&mut self, delta: f32, just_pressed: bool, held: bool) -> bool {
+        self.cooldown -= delta;
+
+        if just_pressed && let TriggerMode::Burst(rounds) = self.mode() {
+            self.burst_left = rounds;
+        }
+
+        let firing = match self.mode() {
+            TriggerMode::Semi => just_pressed,
+            TriggerMode::Burst(_) => self.burst_left > 0,
+            TriggerMode::Auto => held,
+        };
+
+        if !firing {
+            // a round's due straight away once the trigger's pulled again, but no sooner
+            self.cooldown = self.cooldown.max(0.0);
+            return false;
+        }
+
+        if self.cooldown > 0.0 {
+            return false;
+        }
+
+        // whatever's overdue carries over, so the cadence holds between steps
+        self.cooldown += self.interval;
+        self.burst_left = self.burst_left.saturating_sub(1);
+        true
+    }
+}
+
+/// A launcher or shotgun mounted under a weapon's barrel, with its own ammunition.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnderbarrelDef {
+    pub name: String,
+    pub fire_mode: FireMode,
+    pub damage: f32,
+    pub muzzle_velocity: f32,
+    /// Kilograms per round
+    #[serde(default = "WeaponDef::default_round_mass")]
+    pub round_mass: f32,
+    pub magazine: u32,
+    /// Spare rounds carried for it, never running out if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserve: Option<u32>,
+    /// Seconds to reload an empty magazine
+    #[serde(default = "WeaponDef::default_reload_time")]
+    pub reload_time: f32,
+}
+
+/// The definition a weapon entity is built from.
+#[derive(Component)]
+pub struct WeaponDefHandle(pub Handle<WeaponDef>);
+
+/// Ballistics of the rounds a weapon fires.
+#[derive(Component, Debug, Clone)]
+pub struct WeaponStats {
+    pub damage: f32,
+    pub muzzle_velocity: f32,
+    /// Kilograms per round
+    pub round_mass: f32,
+    /// How quickly it's brought up to aim, as a multiple of the usual speed
+    pub handling: f32,
+    /// The share of movement speed kept while aimed down the sights
+    pub ads_speed: f32,
+    /// Seconds after a sprint before it can fire
+    pub raise_time: f32,
+    /// Rounds strike instantly rather than flying, see [`WeaponDef::hitscan`]
+    pub hitscan: bool,
+}
+
+impl Default for WeaponStats {
+    fn default() -> Self {
+        Self {
+            damage: 34.0,
+            muzzle_velocity: 60.0,
+            // a 9mm round
+            round_mass: 0.008,
+            handling: 1.0,
+            ads_speed: 0.6,
+            raise_time: 0.3,
+            hitscan: false,
+        }
+    }
+}
+
+/// Which of the optional attachments in its definition a weapon has fitted. Weapons without one
+/// have them all.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Attachments {
+    pub underbarrel: bool,
+}
+
+impl Default for Attachments {
+    fn default() -> Self {
+        Self { underbarrel: true }
+    }
+}
+
+/// Rounds left in a weapon. Weapons without one never run dry.
+#[derive(Component, Debug, Clone)]
+pub struct Magazine {
+    pub rounds: u32,
+    pub capacity: u32,
+    /// Spare rounds to reload from, or `None` for as many as it takes
+    pub reserve: Option<u32>,
+    /// Seconds a reload takes
+    pub reload_time: f32,
+}
+
+impl Magazine {
+    /// There's room in the magazine and spare rounds to fill it with
+    pub fn can_reload(&self) -> bool {
+        self.rounds < self.capacity && self.reserve != Some(0)
+    }
+
+    /// Fills the magazine from the reserve, as far as the reserve goes
+    pub fn refill(&mut self) {
+        let wanted = self.capacity.saturating_sub(self.rounds);
+        let loaded = self.reserve.map_or(wanted, |reserve| reserve.min(wanted));
+
+        self.rounds += loaded;
+        if let Some(reserve) = &mut self.reserve {
+            *reserve -= loaded;
+        }
+    }
+
+    /// Takes a round to fire, or returns `false` if there are none left
+    pub fn take_round(&mut self) -> bool {
+        let Some(rounds) = self.rounds.checked_sub(1) else {
+            return false;
+        };
+
+        self.rounds = rounds;
+        true
+    }
+
+    /// Seconds a reload takes for a player with these attributes
+    pub fn reload_duration(&self, attributes: Option<&Attributes>) -> f32 {
+        self.reload_time / attributes.map_or(1.0, Attributes::handling_scale)
+    }
+}
+
+/// A weapon being reloaded, which can't fire until it's done. It's lowered meanwhile, losing its
+/// [`WeaponActive`], so it isn't aimed, swayed or switched either.
+#[derive(Component, Debug)]
+pub struct Reloading(Timer);
+
+/// Sent when a weapon starts reloading.
+#[derive(Message, Debug)]
+pub struct ReloadStarted {
+    pub weapon: Entity,
+    pub owner: Entity,
+    /// Seconds it'll take
+    pub duration: f32,
+}
+
+/// Sent when a weapon finishes reloading, but not when a reload's called off by its definition
+/// changing.
+#[derive(Message, Debug)]
+pub struct ReloadFinished {
+    pub weapon: Entity,
+    pub owner: Entity,
+}
+
+/// A weapon being brought back up after a sprint, which can't fire until it's ready.
+#[derive(Component, Debug)]
+pub struct Raising(Timer);
+
+/// Sent when a weapon starts coming up from a sprint.
+#[derive(Message, Debug)]
+pub struct WeaponRaising {
+    pub weapon: Entity,
+    pub owner: Entity,
+}
+
+/// Sent when a weapon that was coming up from a sprint can fire again.
+#[derive(Message, Debug)]
+pub struct WeaponRaised {
+    pub weapon: Entity,
+    pub owner: Entity,
+}
+
+fn apply_weapon_def(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<WeaponDef>>,
+    defs: Res<Assets<WeaponDef>>,
+    asset_server: Res<AssetServer>,
+    weapons_q: Query<(
+        Entity,
+        Ref<WeaponDefHandle>,
+        &mut WeaponStats,
+        &mut PlayerWeaponTransformConfig,
+        &mut TransformPipeline,
+        &mut SceneRoot,
+        &mut SwayProfile,
+        &mut FireMode,
+        Option<&WeaponSockets>,
+        Option<&WeaponHand>,
+        Option<Ref<Attachments>>,
+    )>,
+) {
+    let changed: Vec<_> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (
+        weapon,
+        handle,
+        mut stats,
+        mut transform_config,
+        mut pipeline,
+        mut scene_root,
+        mut sway,
+        mut fire_mode,
+        sockets,
+        hand,
+        attachments,
+    ) in weapons_q
+    {
+        // also when switched to another definition, or to other attachments
+        if !changed.contains(&handle.0.id())
+            && !handle.is_changed()
+            && !attachments.as_ref().is_some_and(Ref::is_changed)
+        {
+            continue;
+        }
+
+        let Some(def) = defs.get(&handle.0) else {
+            continue;
+        };
+
+        info!("applying weapon definition '{}'", def.name);
+
+        let sight = sockets
+            .and_then(|sockets| sockets.get(Socket::Sight))
+            .map(|sight| sight.translation);
+        *transform_config = def.transform_config(sight);
+        if hand == Some(&WeaponHand::Left) {
+            *transform_config = transform_config.mirrored();
+        }
+        pipeline.base_translation = transform_config.hip.translation;
+
+        stats.damage = def.damage;
+        stats.muzzle_velocity = def.muzzle_velocity;
+        stats.round_mass = def.round_mass;
+        stats.handling = def.handling;
+        stats.ads_speed = def.ads_speed;
+        stats.raise_time = def.raise_time;
+        stats.hitscan = def.hitscan;
+        *sway = def.sway.clone();
+        *fire_mode = def.fire_mode.clone();
+
+        commands.entity(weapon).insert((
+            Recoil::new(def.recoil.clone()),
+            Trigger::new(def.trigger_modes.clone(), def.rpm),
+        ));
+
+        match def.full_magazine() {
+            Some(magazine) => commands.entity(weapon).insert(magazine),
+            None => commands.entity(weapon).remove::<(Magazine, Reloading)>(),
+        };
+
+        // back on the main weapon, whatever was in use before
+        commands.entity(weapon).remove::<UnderbarrelActive>();
+        let fitted = attachments.is_none_or(|attachments| attachments.underbarrel);
+        match def.underbarrel.as_ref().filter(|_| fitted) {
+            Some(underbarrel) => commands
+                .entity(weapon)
+                .insert(Underbarrel::new(underbarrel)),
+            None => commands.entity(weapon).remove::<Underbarrel>(),
+        };
+
+        let model_path = def.model_path();
+        if scene_root.0.path() != Some(&model_path) {
+            scene_root.0 = asset_server.load(model_path);
+        }
+    }
+}
+
+/// Reloads empty weapons, and any with room in the magazine when the reload key's pressed. A
+/// player only has two hands, so a dual wielder's weapons take turns.
+fn reload_weapons(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(Entity, &PlayerInput, Option<&Attributes>), With<Player>>,
+    mut weapons: Query<(Entity, &mut Magazine, &SwayTarget, Option<&mut Reloading>)>,
+    mut started_writer: MessageWriter<ReloadStarted>,
+    mut finished_writer: MessageWriter<ReloadFinished>,
+) {
+    let requested: Vec<_> = players
+        .iter()
+        .filter(|(_, input, _)| {
+            let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.reload);
+            let gamepad = input
+                .gamepad(&gamepads)
+                .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::North));
+
+            keyboard || gamepad
+        })
+        .map(|(player, ..)| player)
+        .collect();
+
+    let mut busy = Vec::new();
+
+    for (weapon, mut magazine, owner, reloading) in &mut weapons {
+        let Some(mut reloading) = reloading else {
+            continue;
+        };
+
+        if reloading.0.tick(time.delta()).is_finished() {
+            magazine.refill();
+            commands.entity(weapon).remove::<Reloading>();
+            finished_writer.write(ReloadFinished {
+                weapon,
+                owner: owner.0,
+            });
+            debug!("{weapon} reloaded");
+        } else {
+            busy.push(owner.0);
+        }
+    }
+
+    for (weapon, magazine, owner, reloading) in &weapons {
+        let wanted = magazine.rounds == 0 || requested.contains(&owner.0);
+
+        if reloading.is_some() || !wanted || !magazine.can_reload() || busy.contains(&owner.0) {
+            continue;
+        }
+
+        let attributes = players
+            .get(owner.0)
+            .ok()
+            .and_then(|(_, _, attributes)| attributes);
+        let duration = magazine.reload_duration(attributes);
+
+        busy.push(owner.0);
+        commands
+            .entity(weapon)
+            .insert(Reloading(Timer::from_seconds(duration, TimerMode::Once)));
+        started_writer.write(ReloadStarted {
+            weapon,
+            owner: owner.0,
+            duration,
+        });
+        debug!("{weapon} reloading");
+    }
+}
+
+fn cycle_trigger_modes(
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    players: Query<(Entity, &PlayerInput), With<Player>>,
+    weapons: Query<(&SwayTarget, &mut Trigger), (With<WeaponActive>, Without<UnderbarrelActive>)>,
+    mut toast_writer: MessageWriter<Toast>,
+) {
+    let cycling: Vec<_> = players
+        .iter()
+        .filter(|(_, input)| {
+            let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.fire_mode);
+            let gamepad = input
+                .gamepad(&gamepads)
+                .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::Select));
+
+            keyboard || gamepad
+        })
+        .map(|(player, _)| player)
+        .collect();
+
+    for (owner, mut trigger) in weapons {
+        if !cycling.contains(&owner.0) || trigger.modes.len() < 2 {
+            continue;
+        }
+
+        trigger.cycle();
+        toast_writer.write(Toast(format!("Firing {}", trigger.mode())));
+    }
+}
+
+/// Starts bringing a player's weapons up when they stop sprinting.
+fn raise_after_sprint(
+    remove: On<Remove, Sprinting>,
+    mut commands: Commands,
+    players: Query<&SwayTargets, With<Player>>,
+    weapons: Query<&WeaponStats, With<WeaponActive>>,
+    mut raising_writer: MessageWriter<WeaponRaising>,
+) {
+    let Ok(targets) = players.get(remove.entity) else {
+        return;
+    };
+
+    for weapon in targets.iter() {
+        let Ok(stats) = weapons.get(weapon) else {
+            continue;
+        };
+
+        if stats.raise_time <= 0.0 {
+            continue;
+        }
+
+        commands
+            .entity(weapon)
+            .try_insert(Raising(Timer::from_seconds(
+                stats.raise_time,
+                TimerMode::Once,
+            )));
+        raising_writer.write(WeaponRaising {
+            weapon,
+            owner: remove.entity,
+        });
+    }
+}
+
+fn finish_raising(
+    mut commands: Commands,
+    time: Res<Time>,
+    weapons: Query<(Entity, &mut Raising, &SwayTarget)>,
+    mut raised_writer: MessageWriter<WeaponRaised>,
+) {
+    for (weapon, mut raising, owner) in weapons {
+        if raising.0.tick(time.delta()).is_finished() {
+            commands.entity(weapon).remove::<Raising>();
+            raised_writer.write(WeaponRaised {
+                weapon,
+                owner: owner.0,
+            });
+        }
+    }
+}
+
+fn lower_reloading(add: On<Add, Reloading>, mut commands: Commands) {
+    commands.entity(add.entity).remove::<WeaponActive>();
+}
+
+/// Brings a weapon back up once its reload's done or called off, unless it's been holstered
+/// meanwhile.
+fn raise_reloaded(
+    remove: On<Remove, Reloading>,
+    mut commands: Commands,
+    weapons: Query<&Visibility>,
+) {
+    if weapons
+        .get(remove.entity)
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden)
+    {
+        commands.entity(remove.entity).try_insert(WeaponActive);
+    }
+}
+
+/// A plain blockout gun, shown in place of a weapon model that failed to load so the game's still
+/// playable.
+#[derive(Resource)]
+struct PlaceholderModel {
+    receiver: Handle<Mesh>,
+    barrel: Handle<Mesh>,
+    magazine: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Marks a weapon standing in a [`PlaceholderModel`] for its own, the placeholder's entity.
+#[derive(Component)]
+struct ShowingPlaceholder(Entity);
+
+fn setup_placeholder_model(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PlaceholderModel {
+        receiver: meshes.add(Cuboid::new(0.05, 0.08, 0.3)),
+        barrel: meshes.add(Cylinder::new(0.012, 0.15)),
+        magazine: meshes.add(Cuboid::new(0.03, 0.12, 0.05)),
+        // loud, so it's obvious something's missing
+        material: materials.add(Color::srgb(1.0, 0.0, 1.0)),
+    });
+}
+
+/// Swaps in the [`PlaceholderModel`] for weapons whose model failed to load, and back out again if
+/// it turns up later, from a mod or a fixed definition.
+fn stand_in_for_missing_models(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    placeholder: Res<PlaceholderModel>,
+    weapons: Query<(Entity, &SceneRoot, Option<&ShowingPlaceholder>), With<WeaponDefHandle>>,
+) {
+    for (weapon, scene, showing) in &weapons {
+        match (asset_server.load_state(&scene.0), showing) {
+            (LoadState::Failed(error), None) => {
+                let path = scene
+                    .0
+                    .path()
+                    .map_or("an unnamed model".to_string(), ToString::to_string);
+                error!("weapon model {path} failed to load, showing a placeholder: {error}");
+
+                let placeholder = commands
+                    .spawn((
+                        Name::new("Placeholder weapon model"),
+                        Transform::default(),
+                        Visibility::default(),
+                        children![
+                            (
+                                Mesh3d(placeholder.receiver.clone()),
+                                MeshMaterial3d(placeholder.material.clone()),
+                            ),
+                            (
+                                Mesh3d(placeholder.barrel.clone()),
+                                MeshMaterial3d(placeholder.material.clone()),
+                                Transform::from_xyz(0.0, 0.02, -0.22)
+                                    .with_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+                            ),
+                            (
+                                Mesh3d(placeholder.magazine.clone()),
+                                MeshMaterial3d(placeholder.material.clone()),
+                                Transform::from_xyz(0.0, -0.09, -0.05),
+                            ),
+                        ],
+                        ChildOf(weapon),
+                    ))
+                    .id();
+
+                commands
+                    .entity(weapon)
+                    .insert(ShowingPlaceholder(placeholder));
+            }
+            (LoadState::Loaded, Some(showing)) => {
+                info!("{weapon}'s model has loaded, removing its placeholder");
+                commands.entity(showing.0).despawn();
+                commands.entity(weapon).remove::<ShowingPlaceholder>();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_weapon_definitions_parse() {
+        let def: WeaponDef =
+            ron::from_str(include_str!("../assets/weapons/mpx/mpx.weapon.ron")).unwrap();
+
+        assert_eq!(def.aim, Some([0.0, -0.07, -0.3]));
+        assert_eq!(def.fire_mode, FireMode::Single);
+        assert!(!def.hitscan);
+        assert_eq!(
+            def.underbarrel.map(|underbarrel| underbarrel.fire_mode),
+            Some(FireMode::Launcher { radius: 4.0 })
+        );
+
+        let suppressed: WeaponDef =
+            ron::from_str(include_str!("../assets/weapons/mpx/mpx_sd.weapon.ron")).unwrap();
+
+        assert_eq!(suppressed.rpm, 800.0);
+        assert!(suppressed.underbarrel.is_none());
+    }
+
+    #[test]
+    fn better_handling_reloads_quicker() {
+        let magazine = Magazine {
+            rounds: 0,
+            capacity: 30,
+            reserve: None,
+            reload_time: 2.0,
+        };
+        let practised = Attributes {
+            handling: 10.0,
+            ..default()
+        };
+
+        assert_eq!(magazine.reload_duration(None), 2.0);
+        assert_eq!(magazine.reload_duration(Some(&Attributes::default())), 2.0);
+        assert!(magazine.reload_duration(Some(&practised)) < 2.0);
+    }
+
+    #[test]
+    fn triggers_hold_their_cadence() {
+        const STEP: f32 = 1.0 / 64.0;
+
+        let mut trigger = Trigger::new(
+            vec![TriggerMode::Auto, TriggerMode::Burst(3), TriggerMode::Semi],
+            600.0,
+        );
+
+        let held_for_a_second = (0..64)
+            .filter(|step| trigger.fire(STEP, *step == 0, true))
+            .count();
+        assert_eq!(held_for_a_second, 10, "600 rounds a minute");
+
+        trigger.cycle();
+        assert_eq!(trigger.mode(), TriggerMode::Burst(3));
+        trigger.fire(1.0, false, false);
+
+        let burst = (0..64)
+            .filter(|step| trigger.fire(STEP, *step == 0, *step == 0))
+            .count();
+        assert_eq!(burst, 3, "the burst finishes once the trigger's let go");
+
+        trigger.cycle();
+        trigger.fire(1.0, false, false);
+
+        let pulls = (0..64)
+            .filter(|step| trigger.fire(STEP, *step % 2 == 0, true))
+            .count();
+        assert_eq!(pulls, 10, "pulls faster than it cycles are dropped");
+    }
+}
@@ -0,0 +1,256 @@
+//! Ziplines.
+//!
+//! A [`Zipline`] is a cable strung between two anchors. Players grab it with the interact key (or
+//! the west face button) when it's within reach of their hands, jumping up to it if need be, and
+//! then slide along it under gravity. While riding, the character controller leaves them to the
+//! line: pushing forward speeds them along the way they're facing, pulling back drags their feet,
+//! and jumping or interacting lets go early. They keep their speed when they drop off, whether
+//! they let go or ran out of cable.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::Player;
+use crate::grapple::Grappling;
+use crate::menu::GameState;
+use crate::movement::{MovementIntent, Suspended};
+use crate::settings::Keybinds;
+use crate::split_screen::PlayerInput;
+use crate::turret::{Manning, man_turrets};
+use crate::vehicle::Driving;
+
+pub struct ZiplinePlugin;
+
+impl Plugin for ZiplinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(let_go)
+            .add_systems(
+                Update,
+                // after turrets, so one press doesn't get a player onto both
+                grab_ziplines
+                    .after(man_turrets)
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(FixedUpdate, ride_ziplines);
+    }
+}
+
+/// A cable from one anchor point to another.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Transform)]
+pub struct Zipline {
+    pub from: Vec3,
+    pub to: Vec3,
+}
+
+impl Zipline {
+    /// How far from a player's hands the cable can be grabbed
+    const REACH: f32 = 1.5;
+    /// From the cable down to the rider's centre
+    const HANG: f32 = 1.4;
+    /// Height of a rider's hands above their centre
+    const HANDS: f32 = 1.0;
+    /// Metres per second per second added pushing along the cable
+    const PUSH: f32 = 3.0;
+    /// Metres per second per second taken off dragging feet
+    const BRAKE: f32 = 8.0;
+    /// Fraction of speed lost per second to the pulley
+    const DRAG: f32 = 0.05;
+    const MAX_SPEED: f32 = 20.0;
+    /// Upwards speed added jumping off
+    const JUMP_OFF: f32 = 5.0;
+    /// How quickly a rider is pulled back under the cable, per second
+    const CATCH_UP: f32 = 10.0;
+
+    pub fn length(&self) -> f32 {
+        self.from.distance(self.to)
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        (self.to - self.from).normalize_or_zero()
+    }
+
+    /// The point `distance` metres along from `from`
+    pub fn point_at(&self, distance: f32) -> Vec3 {
+        self.from + self.direction() * distance
+    }
+
+    /// How far along the cable the closest point to `point` is
+    pub fn closest(&self, point: Vec3) -> f32 {
+        (point - self.from)
+            .dot(self.direction())
+            .clamp(0.0, self.length())
+    }
+}
+
+/// A player riding a [`Zipline`].
+#[derive(Component, Debug)]
+pub struct Ziplining {
+    pub line: Entity,
+    /// Metres along from the line's `from` end
+    pub distance: f32,
+    /// Metres per second towards the line's `to` end, negative going back
+    pub speed: f32,
+    /// The rider's own gravity, handed back when they let go
+    gravity_scale: f32,
+}
+
+fn grab_ziplines(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    keybinds: Res<Keybinds>,
+    gamepads: Query<&Gamepad>,
+    mut left_vehicle: RemovedComponents<Driving>,
+    mut left_turret: RemovedComponents<Manning>,
+    players: Query<
+        (
+            Entity,
+            &PlayerInput,
+            &Transform,
+            &LinearVelocity,
+            Option<&GravityScale>,
+            Has<Ziplining>,
+        ),
+        (
+            With<Player>,
+            Without<Driving>,
+            Without<Manning>,
+            Without<Grappling>,
+        ),
+    >,
+    lines: Query<(Entity, &Zipline)>,
+) {
+    // the same press that got them out of something else
+    let busy: Vec<_> = left_vehicle.read().chain(left_turret.read()).collect();
+
+    for (player, input, transform, velocity, gravity_scale, riding) in players {
+        let keyboard = input.keyboard_mouse && keys.just_pressed(keybinds.interact);
+        let gamepad = input
+            .gamepad(&gamepads)
+            .is_some_and(|gamepad| gamepad.just_pressed(GamepadButton::West));
+
+        if (!keyboard && !gamepad) || busy.contains(&player) {
+            continue;
+        }
+
+        // `let_go` does the rest
+        if riding {
+            commands.entity(player).remove::<Ziplining>();
+            continue;
+        }
+
+        let hands = transform.translation + Vec3::Y * Zipline::HANDS;
+
+        let Some((line, zipline, distance)) = lines
+            .iter()
+            .map(|(line, zipline)| (line, zipline, zipline.closest(hands)))
+            .filter(|(_, zipline, distance)| {
+                zipline.point_at(*distance).distance(hands) <= Zipline::REACH
+            })
+            .min_by(|(_, a, a_distance), (_, b, b_distance)| {
+                let a = a.point_at(*a_distance).distance(hands);
+                let b = b.point_at(*b_distance).distance(hands);
+                a.total_cmp(&b)
+            })
+        else {
+            continue;
+        };
+
+        commands.entity(player).insert((
+            Ziplining {
+                line,
+                distance,
+                // carry on with whatever speed they had along the cable
+                speed: velocity.dot(zipline.direction()),
+                gravity_scale: gravity_scale.map_or(1.0, |scale| scale.0),
+            },
+            Suspended,
+            // the line's slope does the pulling instead
+            GravityScale(0.0),
+        ));
+
+        debug!("{player} grabbed zipline {line}");
+    }
+}
+
+/// Hands a rider back to the character controller, still moving however fast they were going.
+fn let_go(remove: On<Remove, Ziplining>, mut commands: Commands, riders: Query<&Ziplining>) {
+    let Ok(riding) = riders.get(remove.entity) else {
+        return;
+    };
+
+    commands
+        .entity(remove.entity)
+        .try_remove::<Suspended>()
+        .try_insert(GravityScale(riding.gravity_scale));
+
+    debug!("{} let go of zipline {}", remove.entity, riding.line);
+}
+
+fn ride_ziplines(
+    mut commands: Commands,
+    time: Res<Time>,
+    gravity: Res<Gravity>,
+    riders: Query<(
+        Entity,
+        &mut Ziplining,
+        &mut MovementIntent,
+        &Transform,
+        &mut LinearVelocity,
+    )>,
+    lines: Query<&Zipline>,
+) {
+    let delta = time.delta_secs();
+
+    for (rider, mut riding, mut intent, transform, mut velocity) in riders {
+        let Ok(line) = lines.get(riding.line) else {
+            commands.entity(rider).remove::<Ziplining>();
+            continue;
+        };
+
+        let direction = line.direction();
+
+        // downhill pulls harder the steeper the cable
+        let mut acceleration = gravity.0.dot(direction) * riding.gravity_scale;
+
+        let input = intent.direction.y;
+        if input > 0.0 {
+            let facing = transform.forward().dot(direction).signum();
+            acceleration += facing * Zipline::PUSH * input;
+        }
+
+        let mut speed = riding.speed + acceleration * delta;
+
+        if input < 0.0 {
+            let brake = Zipline::BRAKE * -input * delta;
+            speed = speed.signum() * (speed.abs() - brake).max(0.0);
+        }
+
+        speed =
+            (speed * (1.0 - Zipline::DRAG * delta)).clamp(-Zipline::MAX_SPEED, Zipline::MAX_SPEED);
+
+        riding.speed = speed;
+        riding.distance += speed * delta;
+
+        // no dashing on a cable
+        intent.dash = false;
+
+        let jumped = std::mem::take(&mut intent.jump);
+        let run_out = riding.distance <= 0.0 || riding.distance >= line.length();
+
+        if jumped || run_out {
+            velocity.0 = direction * speed;
+
+            if jumped {
+                velocity.y += Zipline::JUMP_OFF;
+            }
+
+            commands.entity(rider).remove::<Ziplining>();
+            continue;
+        }
+
+        // moved by velocity rather than placed, so the controller and physics keep up with it
+        let hanging = line.point_at(riding.distance) - Vec3::Y * Zipline::HANG;
+        velocity.0 = direction * speed + (hanging - transform.translation) * Zipline::CATCH_UP;
+    }
+}